@@ -0,0 +1,97 @@
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use hit::applier;
+use hit::parser::Parser;
+use std::fmt::Write;
+use std::hint::black_box;
+
+fn large_source(lines: usize) -> String {
+  let mut source = String::new();
+  for line in 0..lines {
+    let _ = writeln!(source, "    let value_{0} = compute({0});", line);
+  }
+  source
+}
+
+fn sparse_patch(lines: usize, hunks: usize) -> String {
+  let mut patch = String::new();
+  let _ = writeln!(patch, "--- a/src/large.rs");
+  let _ = writeln!(patch, "+++ b/src/large.rs");
+  let stride = lines / hunks;
+  for hunk in 0..hunks {
+    let line = hunk * stride + 2;
+    let _ = writeln!(patch, "@@ -{0},3 +{0},3 @@", line);
+    let _ = writeln!(patch, "     let value_{0} = compute({0});", line - 1);
+    let _ = writeln!(patch, "-    let value_{0} = compute({0});", line);
+    let _ = writeln!(patch, "+    let value_{0} = recompute({0});", line);
+    let _ = writeln!(patch, "     let value_{0} = compute({0});", line + 1);
+  }
+  patch
+}
+
+fn growing_patch(lines: usize, hunks: usize, added: usize) -> String {
+  let mut patch = String::new();
+  let _ = writeln!(patch, "--- a/src/large.rs");
+  let _ = writeln!(patch, "+++ b/src/large.rs");
+  let stride = lines / hunks;
+  for hunk in 0..hunks {
+    let line = hunk * stride + 1;
+    let _ = writeln!(patch, "@@ -{0},1 +{0},{1} @@", line, added + 1);
+    let _ = writeln!(patch, "     let value_{0} = compute({0});", line - 1);
+    for extra in 0..added {
+      let _ = writeln!(patch, "+    let extra_{}_{} = 0;", line, extra);
+    }
+  }
+  patch
+}
+
+fn apply(c: &mut Criterion) {
+  let source = large_source(200_000);
+  let mut group = c.benchmark_group("apply");
+  group.throughput(Throughput::Bytes(source.len() as u64));
+  for hunks in [1, 500] {
+    let text = sparse_patch(200_000, hunks);
+    let patch = Parser::new(&text)
+      .next()
+      .and_then(Result::ok)
+      .expect("benchmark patch parses");
+    group.bench_function(format!("hunks/{}", hunks), |b| {
+      b.iter(|| applier::apply(black_box(&patch), black_box(&source)))
+    });
+  }
+  let text = growing_patch(200_000, 1_000, 50);
+  let patch = Parser::new(&text)
+    .next()
+    .and_then(Result::ok)
+    .expect("benchmark patch parses");
+  group.bench_function("additions/50000", |b| {
+    b.iter(|| applier::apply(black_box(&patch), black_box(&source)))
+  });
+  group.finish();
+
+  let source = large_source(40);
+  let text = sparse_patch(40, 4);
+  let patch = Parser::new(&text)
+    .next()
+    .and_then(Result::ok)
+    .expect("benchmark patch parses");
+  let mut group = c.benchmark_group("apply/small");
+  group.bench_function("fresh", |b| {
+    b.iter(|| applier::apply(black_box(&patch), black_box(&source)))
+  });
+  let mut reused = applier::Applier::new();
+  let mut output = String::new();
+  group.bench_function("reused", |b| {
+    b.iter(|| {
+      reused
+        .apply_into(black_box(&patch), black_box(&source), &mut output)
+        .map(|results| results.len())
+    })
+  });
+  group.finish();
+}
+
+criterion_group!(benches, apply);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use hit::lexer::Lexer;
+use hit::parser::Parser;
+use std::fmt::Write;
+use std::hint::black_box;
+
+fn large_patch(files: usize, hunks: usize) -> String {
+  let mut patch = String::new();
+  for file in 0..files {
+    let _ =
+      writeln!(patch, "diff --git a/src/file{0}.rs b/src/file{0}.rs", file);
+    let _ = writeln!(patch, "index 1234567..89abcde 100644");
+    let _ = writeln!(patch, "--- a/src/file{}.rs", file);
+    let _ = writeln!(patch, "+++ b/src/file{}.rs", file);
+    for hunk in 0..hunks {
+      let line = hunk * 20 + 1;
+      let _ =
+        writeln!(patch, "@@ -{0},7 +{0},7 @@ fn item_{1}() {{", line, hunk);
+      for context in 0..3 {
+        let _ =
+          writeln!(patch, "     let value_{} = compute({});", context, hunk);
+      }
+      let _ = writeln!(patch, "-    old_call(value_0, value_1);");
+      let _ = writeln!(patch, "+    new_call(value_0, value_1, value_2);");
+      for context in 3..6 {
+        let _ =
+          writeln!(patch, "     let value_{} = compute({});", context, hunk);
+      }
+    }
+  }
+  patch
+}
+
+fn lexer(c: &mut Criterion) {
+  let patch = large_patch(200, 100);
+  let mut group = c.benchmark_group("lexer");
+  group.throughput(Throughput::Bytes(patch.len() as u64));
+  group.bench_function("tokens", |b| {
+    b.iter(|| Lexer::new(black_box(&patch)).count())
+  });
+  group.bench_function("parse", |b| {
+    b.iter(|| Parser::new(black_box(&patch)).count())
+  });
+  group.finish();
+}
+
+criterion_group!(benches, lexer);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+/* Generated with cbindgen from src/ffi.rs (see cbindgen.toml). */
+
+#ifndef HIT_H
+#define HIT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef enum HitStatus {
+  HIT_STATUS_OK = 0,
+  HIT_STATUS_INVALID_ARGUMENT = 1,
+  HIT_STATUS_PARSE = 2,
+  HIT_STATUS_APPLY = 3,
+  HIT_STATUS_IO = 4,
+  HIT_STATUS_UNSUPPORTED = 5,
+  HIT_STATUS_PANIC = 6,
+  HIT_STATUS_SIGNATURE = 7,
+} HitStatus;
+
+typedef enum HitEventKind {
+  HIT_EVENT_KIND_CREATED = 0,
+  HIT_EVENT_KIND_MODIFIED = 1,
+  HIT_EVENT_KIND_DELETED = 2,
+  HIT_EVENT_KIND_RENAMED = 3,
+  HIT_EVENT_KIND_COPIED = 4,
+  HIT_EVENT_KIND_MODE_CHANGED = 5,
+  HIT_EVENT_KIND_SKIPPED = 6,
+  HIT_EVENT_KIND_LOSSY_READ = 7,
+  HIT_EVENT_KIND_DUPLICATE = 8,
+  HIT_EVENT_KIND_MERGED = 9,
+  HIT_EVENT_KIND_REJECTED = 10,
+  HIT_EVENT_KIND_WHITESPACE = 11,
+  HIT_EVENT_KIND_OFFSET = 12,
+  HIT_EVENT_KIND_ALREADY_APPLIED = 13,
+  HIT_EVENT_KIND_REVERSED = 14,
+} HitEventKind;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Applies `patch_text` to the files under `root_dir` (or the current
+ * directory when null). Strings returned by the accessors below stay valid
+ * until the next call to hit_apply on the same thread.
+ */
+HitStatus hit_apply(const char *patch_text, bool reverse, const char *root_dir);
+
+const char *hit_last_error(void);
+
+size_t hit_last_event_count(void);
+
+int32_t hit_last_event_kind(size_t index);
+
+const char *hit_last_event_path(size_t index);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HIT_H */
@@ -0,0 +1,130 @@
+use crate::applier;
+use crate::error::Error;
+use crate::fs::FileSystem;
+use crate::options::ApplyOptions;
+use crate::parser::Line;
+use crate::parser::Patch;
+use std::fmt::Write;
+use std::io;
+
+const DEV_NULL: &str = "/dev/null";
+
+struct Gutter {
+  output: String,
+  width: usize,
+}
+
+impl Gutter {
+  fn line(&mut self, old: Option<usize>, new: Option<usize>, text: &str) {
+    let number = |line: Option<usize>| line.map(|line| line.to_string());
+    let marker = match (old, new) {
+      (Some(_), None) => '-',
+      (None, Some(_)) => '+',
+      _ => ' ',
+    };
+    let _ = writeln!(
+      self.output,
+      "{:>width$} {:>width$} {} {}",
+      number(old).unwrap_or_default(),
+      number(new).unwrap_or_default(),
+      marker,
+      text,
+      width = self.width
+    );
+  }
+}
+
+pub fn annotate(patch: &Patch, source: &str) -> Result<String, Error> {
+  let (patched, _) = applier::apply_detailed(patch, source)?;
+  let source_lines: Vec<&str> = source.lines().collect();
+  let width = source_lines
+    .len()
+    .max(patched.lines().count())
+    .max(1)
+    .ilog10() as usize
+    + 1;
+
+  let mut gutter = Gutter {
+    output: String::new(),
+    width,
+  };
+  let (mut old, mut new) = (1, 1);
+  for hunk in &patch.hunks {
+    let first_line = if hunk.old_span == 0 {
+      hunk.old_line as usize + 1
+    } else {
+      hunk.old_line as usize
+    };
+    while old < first_line {
+      gutter.line(Some(old), Some(new), source_lines[old - 1]);
+      old += 1;
+      new += 1;
+    }
+
+    for line in &hunk.lines {
+      match line {
+        Line::Context(text) => {
+          gutter.line(Some(old), Some(new), text);
+          old += 1;
+          new += 1;
+        }
+        Line::Deletion(text) => {
+          gutter.line(Some(old), None, text);
+          old += 1;
+        }
+        Line::Addition(text) => {
+          gutter.line(None, Some(new), text);
+          new += 1;
+        }
+        Line::NoNewline => {}
+      }
+    }
+  }
+  for text in source_lines.iter().skip(old - 1) {
+    gutter.line(Some(old), Some(new), text);
+    old += 1;
+    new += 1;
+  }
+
+  Ok(gutter.output)
+}
+
+pub fn preview(
+  fs: &impl FileSystem,
+  patch_content: &str,
+  options: &ApplyOptions,
+) -> Result<String, Error> {
+  let mut output = String::new();
+  for patch in options.parser(patch_content) {
+    let patch = patch?;
+    let patch = if options.reverse {
+      patch.invert()
+    } else {
+      patch
+    };
+    let target = if patch.new_file == DEV_NULL {
+      &patch.old_file
+    } else {
+      &patch.new_file
+    };
+    if !options.is_selected(target) || patch.is_binary {
+      continue;
+    }
+
+    let source = if patch.old_file == DEV_NULL {
+      String::new()
+    } else {
+      let path = options
+        .resolve(patch.copy_from.as_deref().unwrap_or(&patch.old_file))?;
+      match fs.read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Error::io_at(e, &path)),
+      }
+    };
+
+    let _ = writeln!(output, "==> {} <==", target);
+    output.push_str(&annotate(&patch, &source)?);
+  }
+  Ok(output)
+}
@@ -1,16 +1,52 @@
+use crate::buf::PatchBuf;
 use crate::error::Error;
+use crate::event::ApplyEvent;
+use crate::event::ApplySink;
+use crate::fs::Contents;
 use crate::fs::FileSystem;
+use crate::merge;
+use crate::merge::Merged;
+use crate::options::ApplyOptions;
+use crate::options::DuplicatePolicy;
+use crate::options::LineEndings;
+use crate::options::Whitespace;
+use crate::options::WhitespacePolicy;
+use crate::parser::BinaryPatch;
 use crate::parser::Hunk;
 use crate::parser::Line;
-use crate::parser::Parser;
 use crate::parser::Patch;
+use crate::render;
+use crate::render::ColorScheme;
+use crate::report::ApplyReport;
+use crate::repository::Eol;
+use crate::telemetry;
+use crate::transaction::Transaction;
+use crate::whitespace;
+use memchr::memchr;
+use memchr::memchr_iter;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
 #[cfg(unix)]
 use std::fs::Permissions;
 use std::io;
+use std::io::BufRead;
+use std::io::BufWriter;
+use std::io::Write;
+use std::iter;
 use std::mem;
+use std::ops::Range;
+use std::ops::RangeInclusive;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
+use std::panic;
 use std::path::Path;
+use std::path::PathBuf;
+use std::str;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
 
 impl<'a> Patch<'a> {
   pub(crate) fn invert(mut self) -> Self {
@@ -18,11 +54,19 @@ impl<'a> Patch<'a> {
     mem::swap(&mut self.rename_from, &mut self.rename_to);
     mem::swap(&mut self.copy_from, &mut self.copy_to);
     mem::swap(&mut self.old_mode, &mut self.new_mode);
+    mem::swap(&mut self.old_hash, &mut self.new_hash);
+    mem::swap(&mut self.old_timestamp, &mut self.new_timestamp);
     if self.new_file == "/dev/null" {
       self.new_mode = self.deleted_file_mode;
     }
 
     self.hunks.iter_mut().for_each(Hunk::invert);
+    self.binary = self.binary.take().and_then(|binary| {
+      Some(BinaryPatch {
+        forward: binary.reverse?,
+        reverse: Some(binary.forward),
+      })
+    });
     self
   }
 }
@@ -32,7 +76,7 @@ impl<'a> Hunk<'a> {
     mem::swap(&mut self.old_line, &mut self.new_line);
     mem::swap(&mut self.old_span, &mut self.new_span);
     self.lines.iter_mut().for_each(|line| {
-      *line = match *line {
+      *line = match mem::replace(line, Line::NoNewline) {
         Line::Addition(s) => Line::Deletion(s),
         Line::Deletion(s) => Line::Addition(s),
         other => other,
@@ -41,157 +85,2179 @@ impl<'a> Hunk<'a> {
   }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkResult {
+  pub position: usize,
+  pub offset: isize,
+  pub fuzz: usize,
+  pub added: usize,
+  pub removed: usize,
+}
+
 pub fn apply<'a>(patch: &Patch<'a>, source: &'a str) -> Result<String, Error> {
-  if patch.hunks.is_empty() {
-    return Ok(source.to_string());
+  apply_detailed(patch, source).map(|(content, _)| content)
+}
+
+pub fn apply_with<'a>(
+  patch: &Patch<'a>,
+  source: &'a str,
+  options: &ApplyOptions,
+) -> Result<String, Error> {
+  let inverted;
+  let patch = if options.reverse {
+    inverted = patch.clone().invert();
+    &inverted
+  } else {
+    patch
+  };
+  let mut content = String::new();
+  Applier::from(options).apply_into(patch, source, &mut content)?;
+  Ok(content)
+}
+
+fn patch_path<'p>(patch: &'p Patch) -> &'p str {
+  if patch.old_file == "/dev/null" {
+    &patch.new_file
+  } else {
+    &patch.old_file
   }
+}
 
-  let source_lines = source.split('\n');
-  let mut result_lines = Vec::new();
-  let mut source_iter = source_lines.peekable();
+pub fn apply_detailed<'a>(
+  patch: &Patch<'a>,
+  source: &'a str,
+) -> Result<(String, Vec<HunkResult>), Error> {
+  let mut content = String::new();
+  let results = Applier::new()
+    .apply_into(patch, source, &mut content)?
+    .to_vec();
+  Ok((content, results))
+}
 
-  let mut current_source_line_num: usize = 1;
-  let mut new_file_should_have_no_newline = false;
+pub fn apply_to_writer(
+  patch: &Patch,
+  reader: impl BufRead,
+  writer: impl io::Write,
+) -> Result<Vec<HunkResult>, Error> {
+  stream(patch, ReadLines::new(reader), Matcher::default(), writer)
+}
 
-  for hunk in &patch.hunks {
-    while current_source_line_num < hunk.old_line as usize {
-      match source_iter.next() {
-        Some(line) => {
-          result_lines.push(line);
-          current_source_line_num += 1;
-        }
-        None => {
-          return Err(Error::Apply(format!(
-            "Unexpected EOF while seeking to line {}",
-            hunk.old_line
-          )));
-        }
-      }
+pub fn apply_bytes_to_writer(
+  patch: &Patch,
+  source: &[u8],
+  writer: impl io::Write,
+) -> Result<Vec<HunkResult>, Error> {
+  stream(patch, SliceLines::new(source), Matcher::default(), writer)
+}
+
+fn stream(
+  patch: &Patch,
+  mut source: impl LineSource,
+  matcher: Matcher,
+  writer: impl io::Write,
+) -> Result<Vec<HunkResult>, Error> {
+  let mut writer = BufWriter::new(writer);
+  if patch.hunks.is_empty() {
+    source.copy_to(&mut writer)?;
+    writer.flush()?;
+    return Ok(Vec::new());
+  }
+
+  let file = patch_path(patch);
+  let mut output = LineWriter::new(&mut writer);
+  let mut results = Vec::with_capacity(patch.hunks.len());
+  let mut no_newline = false;
+  for (hunk_index, hunk) in patch.hunks.iter().enumerate() {
+    let first_line = if hunk.old_span == 0 {
+      hunk.old_line as usize + 1
+    } else {
+      hunk.old_line as usize
+    };
+    while source.number() < first_line {
+      let Some(line) = source.peek()? else {
+        return Err(Error::UnexpectedEof {
+          file: file.to_string(),
+          hunk_index,
+          line: hunk.old_line as usize,
+        });
+      };
+      output.line(line)?;
+      source.advance();
+      no_newline = false;
     }
 
-    let mut in_addition_block = false;
+    let mut result = HunkResult {
+      position: output.lines + 1,
+      offset: 0,
+      fuzz: 0,
+      added: 0,
+      removed: 0,
+    };
+    let mut previous = None;
+    let mut crlf = source.peek()?.is_some_and(is_crlf);
     for line in &hunk.lines {
       match line {
         Line::Addition(text) => {
-          in_addition_block = true;
-          result_lines.push(text);
-          new_file_should_have_no_newline = false;
+          previous = Some(line);
+          result.added += 1;
+          output.added(text, matcher.eol(crlf))?;
+          no_newline = false;
         }
         Line::Context(text) | Line::Deletion(text) => {
-          in_addition_block = false;
-          let source_line = *source_iter.peek().ok_or_else(|| {
-            Error::Apply(format!(
-              "Patch mismatch at line {}. Expected: `{}`, Found: `<EOF>`",
-              current_source_line_num, text
-            ))
-          })?;
-
-          if source_line != *text {
-            return Err(Error::Apply(format!(
-              "Patch mismatch at line {}. Expected: `{}`, Found: `{}`",
-              current_source_line_num, text, source_line
-            )));
+          previous = Some(line);
+          let number = source.number();
+          let found = source.peek()?;
+          let Some(found) =
+            found.filter(|found| matcher.bytes_match(found, text))
+          else {
+            return Err(Error::HunkMismatch {
+              file: file.to_string(),
+              hunk_index,
+              line: number,
+              expected: text.to_string(),
+              found: found
+                .map(|found| String::from_utf8_lossy(found).into_owned()),
+            });
+          };
+          crlf = is_crlf(found);
+          if let Line::Context(_) = line {
+            output.line(found)?;
+            no_newline = false;
+          } else {
+            result.removed += 1;
           }
+          source.advance();
+        }
+        Line::NoNewline => {
+          if !matches!(previous, Some(Line::Addition(_)))
+            && source.peek()?.is_some()
+          {
+            return Err(Error::ExpectedEof {
+              file: file.to_string(),
+              hunk_index,
+              line: source.number(),
+            });
+          }
+          if !matches!(previous, Some(Line::Deletion(_))) {
+            no_newline = true;
+          }
+        }
+      }
+    }
+    results.push(result);
+  }
 
-          let consumed_line = source_iter.next().unwrap();
-          if let Line::Context(_) = line {
-            result_lines.push(consumed_line);
-            new_file_should_have_no_newline = false;
+  while let Some(line) = source.peek()? {
+    output.line(line)?;
+    source.advance();
+    no_newline = false;
+  }
+  output.finish(no_newline)?;
+  writer.flush()?;
+  telemetry::hunks_applied(&results);
+  Ok(results)
+}
+
+trait LineSource {
+  fn number(&self) -> usize;
+  fn peek(&mut self) -> io::Result<Option<&[u8]>>;
+  fn advance(&mut self);
+  fn copy_to(&mut self, writer: &mut impl io::Write) -> io::Result<()>;
+}
+
+struct ReadLines<R> {
+  reader: R,
+  line: Vec<u8>,
+  filled: bool,
+  eof: bool,
+  number: usize,
+}
+
+impl<R: BufRead> ReadLines<R> {
+  fn new(reader: R) -> Self {
+    Self {
+      reader,
+      line: Vec::new(),
+      filled: false,
+      eof: false,
+      number: 1,
+    }
+  }
+}
+
+impl<R: BufRead> LineSource for ReadLines<R> {
+  fn number(&self) -> usize {
+    self.number
+  }
+
+  fn peek(&mut self) -> io::Result<Option<&[u8]>> {
+    if !self.filled {
+      self.line.clear();
+      self.eof = self.reader.read_until(b'\n', &mut self.line)? == 0;
+      self.filled = true;
+    }
+    Ok((!self.eof).then(|| strip_newline(&self.line)))
+  }
+
+  fn advance(&mut self) {
+    self.filled = false;
+    self.number += 1;
+  }
+
+  fn copy_to(&mut self, writer: &mut impl io::Write) -> io::Result<()> {
+    if self.filled {
+      writer.write_all(&self.line)?;
+    }
+    io::copy(&mut self.reader, writer).map(drop)
+  }
+}
+
+struct SliceLines<'a> {
+  source: &'a [u8],
+  offset: usize,
+  number: usize,
+}
+
+impl<'a> SliceLines<'a> {
+  fn new(source: &'a [u8]) -> Self {
+    Self {
+      source,
+      offset: 0,
+      number: 1,
+    }
+  }
+
+  fn line(&self) -> Option<&'a [u8]> {
+    let rest = self
+      .source
+      .get(self.offset..)
+      .filter(|rest| !rest.is_empty())?;
+    Some(match memchr(b'\n', rest) {
+      Some(end) => &rest[..=end],
+      None => rest,
+    })
+  }
+}
+
+impl LineSource for SliceLines<'_> {
+  fn number(&self) -> usize {
+    self.number
+  }
+
+  fn peek(&mut self) -> io::Result<Option<&[u8]>> {
+    Ok(self.line().map(strip_newline))
+  }
+
+  fn advance(&mut self) {
+    self.offset += self.line().map_or(0, <[u8]>::len);
+    self.number += 1;
+  }
+
+  fn copy_to(&mut self, writer: &mut impl io::Write) -> io::Result<()> {
+    writer.write_all(&self.source[self.offset..])
+  }
+}
+
+fn strip_newline(line: &[u8]) -> &[u8] {
+  line.strip_suffix(b"\n").unwrap_or(line)
+}
+
+struct LineWriter<W> {
+  writer: W,
+  lines: usize,
+}
+
+impl<W: io::Write> LineWriter<W> {
+  fn new(writer: W) -> Self {
+    Self { writer, lines: 0 }
+  }
+
+  fn line(&mut self, text: &[u8]) -> io::Result<()> {
+    if self.lines > 0 {
+      self.writer.write_all(b"\n")?;
+    }
+    self.writer.write_all(text)?;
+    self.lines += 1;
+    Ok(())
+  }
+
+  fn added(&mut self, text: &str, eol: Option<Eol>) -> io::Result<()> {
+    let Some(eol) = eol else {
+      return self.line(text.as_bytes());
+    };
+    self.line(text.strip_suffix('\r').unwrap_or(text).as_bytes())?;
+    if eol == Eol::Crlf {
+      self.writer.write_all(b"\r")?;
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self, no_newline: bool) -> io::Result<()> {
+    if self.lines > 0 && !no_newline {
+      self.writer.write_all(b"\n")?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct Applier {
+  starts: Vec<usize>,
+  pieces: Vec<Piece>,
+  results: Vec<HunkResult>,
+  rejected: Vec<usize>,
+  shifted: Vec<(usize, HunkResult)>,
+  matcher: Matcher,
+  fuzz: usize,
+  reject: bool,
+}
+
+impl From<&ApplyOptions> for Applier {
+  fn from(options: &ApplyOptions) -> Self {
+    Self::new()
+      .line_endings(options.line_endings)
+      .whitespace(options.whitespace)
+      .fuzz(options.fuzz)
+      .reject(options.reject)
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Matcher {
+  line_endings: LineEndings,
+  normalize_eol: bool,
+  whitespace: Whitespace,
+}
+
+fn collapse_spaces(text: &str) -> impl Iterator<Item = char> {
+  let mut previous_space = false;
+  text.trim_end().chars().filter_map(move |c| {
+    let space = c.is_whitespace();
+    let skip = space && previous_space;
+    previous_space = space;
+    (!skip).then_some(if space { ' ' } else { c })
+  })
+}
+
+fn is_crlf(line: &[u8]) -> bool {
+  line.ends_with(b"\r")
+}
+
+impl Matcher {
+  fn resolve(self, patch: &Patch, source: &[u8]) -> Self {
+    let normalize_eol = match self.line_endings {
+      LineEndings::Strict => false,
+      LineEndings::IgnoreEol => true,
+      LineEndings::Auto => {
+        let source_crlf = memchr_iter(b'\r', source)
+          .any(|index| source.get(index + 1) == Some(&b'\n'));
+        let patch_crlf = patch.hunks.iter().flat_map(|hunk| &hunk.lines).any(
+          |line| match line {
+            Line::Context(text)
+            | Line::Deletion(text)
+            | Line::Addition(text) => is_crlf(text.as_bytes()),
+            Line::NoNewline => false,
+          },
+        );
+        source_crlf || patch_crlf
+      }
+    };
+    Self {
+      normalize_eol,
+      ..self
+    }
+  }
+
+  fn eol(self, crlf: bool) -> Option<Eol> {
+    self
+      .normalize_eol
+      .then_some(if crlf { Eol::Crlf } else { Eol::Lf })
+  }
+
+  fn bytes_match(self, found: &[u8], expected: &str) -> bool {
+    found == expected.as_bytes()
+      || str::from_utf8(found)
+        .is_ok_and(|found| self.lines_match(found, expected))
+  }
+
+  fn lines_match(self, found: &str, expected: &str) -> bool {
+    if found == expected {
+      return true;
+    }
+    let (found, expected) = if self.normalize_eol {
+      (
+        found.strip_suffix('\r').unwrap_or(found),
+        expected.strip_suffix('\r').unwrap_or(expected),
+      )
+    } else {
+      (found, expected)
+    };
+    match self.whitespace {
+      Whitespace::Exact => found == expected,
+      Whitespace::IgnoreTrailing => found.trim_end() == expected.trim_end(),
+      Whitespace::IgnoreChanges => {
+        collapse_spaces(found).eq(collapse_spaces(expected))
+      }
+      Whitespace::IgnoreAll => found
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .eq(expected.chars().filter(|c| !c.is_whitespace())),
+    }
+  }
+}
+
+fn candidates(
+  origin: usize,
+  range: RangeInclusive<usize>,
+) -> impl Iterator<Item = usize> {
+  let (first, last) = range.into_inner();
+  let origin = origin.clamp(first, last.max(first));
+  let distance = (origin - first).max(last.saturating_sub(origin));
+  (0..=distance)
+    .flat_map(move |distance| {
+      let before = origin.checked_sub(distance).filter(|_| distance > 0);
+      [origin.checked_add(distance), before]
+    })
+    .flatten()
+    .filter(move |start| (first..=last).contains(start))
+}
+
+fn fuzz_trim(hunk: &Hunk, level: usize) -> (usize, usize) {
+  let context = |line: &&Line| matches!(line, Line::Context(_));
+  let lead = hunk.lines.iter().take_while(context).count();
+  let trail = hunk.lines.iter().rev().take_while(context).count();
+  (
+    lead.min(level),
+    trail.min(level).min(hunk.lines.len() - lead.min(level)),
+  )
+}
+
+fn expected_lines<'l>(lines: &'l [Line<'l>]) -> impl Iterator<Item = &'l str> {
+  lines.iter().filter_map(|line| match line {
+    Line::Context(text) | Line::Deletion(text) => Some(text.as_ref()),
+    _ => None,
+  })
+}
+
+impl Applier {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn normalize_eol(mut self, normalize_eol: bool) -> Self {
+    self.matcher.line_endings = if normalize_eol {
+      LineEndings::IgnoreEol
+    } else {
+      LineEndings::Strict
+    };
+    self
+  }
+
+  pub fn line_endings(mut self, line_endings: LineEndings) -> Self {
+    self.matcher.line_endings = line_endings;
+    self
+  }
+
+  pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+    self.matcher.whitespace = whitespace;
+    self
+  }
+
+  pub fn fuzz(mut self, fuzz: usize) -> Self {
+    self.fuzz = fuzz;
+    self
+  }
+
+  pub fn reject(mut self, reject: bool) -> Self {
+    self.reject = reject;
+    self
+  }
+
+  pub fn rejected(&self) -> &[usize] {
+    &self.rejected
+  }
+
+  pub fn is_applied(&self, patch: &Patch, source: &str) -> bool {
+    if patch.new_file == "/dev/null" {
+      return source.is_empty();
+    }
+    if patch.hunks.is_empty() || patch.hunks.iter().any(|h| h.new_span == 0) {
+      return false;
+    }
+
+    self.probe(&patch.clone().invert(), source, 0)
+  }
+
+  pub fn applies(&self, patch: &Patch, source: &str) -> bool {
+    self.probe(patch, source, self.fuzz)
+  }
+
+  fn probe(&self, patch: &Patch, source: &str, fuzz: usize) -> bool {
+    let mut probe = Self {
+      matcher: self.matcher,
+      fuzz,
+      ..Self::default()
+    };
+    probe
+      .splice_quiet(patch, source, Path::new(patch_path(patch)))
+      .is_ok()
+  }
+
+  pub fn apply_into(
+    &mut self,
+    patch: &Patch,
+    source: &str,
+    output: &mut String,
+  ) -> Result<&[HunkResult], Error> {
+    let (spliced, ..) =
+      self.splice(patch, source, Path::new(patch_path(patch)))?;
+    output.clear();
+    spliced.materialize_into(output);
+    Ok(&self.results)
+  }
+
+  fn content_at(
+    &mut self,
+    patch: &Patch,
+    source: &str,
+    file: &Path,
+  ) -> Result<String, Error> {
+    let (spliced, results, rejected) = self.splice(patch, source, file)?;
+    let applied = (0..patch.hunks.len())
+      .filter(|hunk_index| !rejected.contains(hunk_index));
+    let mut shifted = Vec::new();
+    for (hunk_index, result) in applied.zip(results) {
+      if result.offset != 0 || result.fuzz > 0 {
+        shifted.push((hunk_index, result.clone()));
+        log::debug!(
+          path:% = file.display(),
+          hunk = hunk_index + 1,
+          offset = result.offset,
+          fuzz = result.fuzz;
+          "Hunk #{} applied at line {} (offset {} lines, fuzz {})",
+          hunk_index + 1,
+          result.position,
+          result.offset,
+          result.fuzz
+        );
+      }
+    }
+    let mut content = String::new();
+    spliced.materialize_into(&mut content);
+    self.shifted = shifted;
+    Ok(content)
+  }
+
+  fn splice<'a>(
+    &'a mut self,
+    patch: &'a Patch<'a>,
+    source: &'a str,
+    file: &Path,
+  ) -> Result<(Output<'a>, &'a [HunkResult], &'a [usize]), Error> {
+    let spliced = self.splice_quiet(patch, source, file)?;
+    telemetry::hunks_applied(spliced.1);
+    Ok(spliced)
+  }
+
+  fn splice_quiet<'a>(
+    &'a mut self,
+    patch: &'a Patch<'a>,
+    source: &'a str,
+    file: &Path,
+  ) -> Result<(Output<'a>, &'a [HunkResult], &'a [usize]), Error> {
+    let Self {
+      starts,
+      pieces,
+      results,
+      rejected,
+      matcher,
+      fuzz,
+      reject,
+      ..
+    } = self;
+    pieces.clear();
+    results.clear();
+    rejected.clear();
+    let matcher = matcher.resolve(patch, source.as_bytes());
+
+    let mut output = Output {
+      source,
+      hunks: &patch.hunks,
+      pieces,
+      lines: 0,
+      ending: Ending::Verbatim,
+    };
+    if patch.hunks.is_empty() {
+      output.pieces.push(Piece::Source(0..source.len()));
+      return Ok((output, results, rejected));
+    }
+
+    output.pieces.reserve(
+      patch
+        .hunks
+        .iter()
+        .map(|hunk| hunk.new_span as usize + 1)
+        .sum::<usize>()
+        + 1,
+    );
+    results.reserve(patch.hunks.len());
+
+    let consumed: usize = patch
+      .hunks
+      .iter()
+      .flat_map(|hunk| &hunk.lines)
+      .filter(|line| matches!(line, Line::Context(_) | Line::Deletion(_)))
+      .count();
+    let furthest = patch
+      .hunks
+      .iter()
+      .map(|hunk| hunk.old_line as usize)
+      .max()
+      .unwrap_or(0);
+    let mut source_lines =
+      SourceLines::new(source, starts, furthest + consumed);
+
+    let mut new_file_should_have_no_newline = false;
+    let mut drift = 0;
+    for (hunk_index, hunk) in patch.hunks.iter().enumerate() {
+      let first_line = if hunk.old_span == 0 {
+        hunk.old_line as usize + 1
+      } else {
+        hunk.old_line as usize
+      };
+      let declared = first_line.saturating_sub(1);
+      let origin = declared.saturating_add_signed(drift);
+
+      let checkpoint = output.checkpoint();
+      let (next, no_newline) =
+        (source_lines.next, new_file_should_have_no_newline);
+      let mut hunk_splice = HunkSplice {
+        hunk_index,
+        hunk,
+        declared,
+        file,
+        matcher,
+        no_newline: &mut new_file_should_have_no_newline,
+      };
+      let exact = Placement {
+        start: origin,
+        ..Placement::default()
+      };
+      let result =
+        match hunk_splice.apply(&mut output, &mut source_lines, &exact) {
+          Ok(result) => result,
+          Err(error) => {
+            output.rollback(checkpoint.clone());
+            source_lines.next = next;
+            *hunk_splice.no_newline = no_newline;
+            let placed =
+              locate(&mut source_lines, hunk, origin, *fuzz, matcher).and_then(
+                |placement| {
+                  hunk_splice
+                    .apply(&mut output, &mut source_lines, &placement)
+                    .ok()
+                },
+              );
+            match placed {
+              Some(result) => result,
+              None if *reject => {
+                output.rollback(checkpoint);
+                source_lines.next = next;
+                *hunk_splice.no_newline = no_newline;
+                rejected.push(hunk_index);
+                continue;
+              }
+              None => return Err(error),
+            }
           }
+        };
+
+      drift = result.offset;
+      results.push(result);
+    }
+
+    if let Some(rest) = source_lines.rest() {
+      output.keep(rest, 1);
+    }
+
+    output.ending = if new_file_should_have_no_newline {
+      Ending::NoNewline
+    } else {
+      Ending::Newline
+    };
+    Ok((output, results, rejected))
+  }
+}
 
-          current_source_line_num += 1;
+#[derive(Debug, Default)]
+struct Placement {
+  start: usize,
+  level: usize,
+  lead: usize,
+  trail: usize,
+}
+
+fn locate(
+  source_lines: &mut SourceLines,
+  hunk: &Hunk,
+  origin: usize,
+  fuzz: usize,
+  matcher: Matcher,
+) -> Option<Placement> {
+  source_lines.index_all();
+  (0..=fuzz).find_map(|level| {
+    let (lead, trail) = fuzz_trim(hunk, level);
+    let lines = &hunk.lines[lead..hunk.lines.len() - trail];
+    let last = source_lines
+      .len()
+      .checked_sub(expected_lines(lines).count() + lead)?;
+    candidates(origin, source_lines.next..=last)
+      .find(|&start| {
+        source_lines.matches(start + lead, expected_lines(lines), matcher)
+      })
+      .map(|start| Placement {
+        start,
+        level,
+        lead,
+        trail,
+      })
+  })
+}
+
+struct HunkSplice<'h, 'p> {
+  hunk_index: usize,
+  hunk: &'h Hunk<'h>,
+  declared: usize,
+  file: &'p Path,
+  matcher: Matcher,
+  no_newline: &'p mut bool,
+}
+
+impl HunkSplice<'_, '_> {
+  fn apply(
+    &mut self,
+    output: &mut Output,
+    source_lines: &mut SourceLines,
+    placement: &Placement,
+  ) -> Result<HunkResult, Error> {
+    let Self {
+      hunk_index,
+      hunk,
+      file,
+      matcher,
+      ..
+    } = *self;
+    let source = source_lines.source;
+    let first_line = placement.start + placement.lead + 1;
+    let skip = first_line.saturating_sub(source_lines.line_number());
+    if skip > 0 {
+      let Some(skipped) = source_lines.skip(skip) else {
+        return Err(Error::UnexpectedEof {
+          file: file.display().to_string(),
+          hunk_index,
+          line: hunk.old_line as usize,
+        });
+      };
+      output.keep(skipped, skip);
+    }
+
+    let mut result = HunkResult {
+      position: output.lines + 1,
+      offset: placement.start as isize - self.declared as isize,
+      fuzz: placement.level,
+      added: 0,
+      removed: 0,
+    };
+    let mut previous = None;
+    let mut crlf = matcher.normalize_eol
+      && source_lines
+        .peek()
+        .is_some_and(|range| source[range].ends_with('\r'));
+    let lines = hunk.lines.iter().enumerate();
+    let lines = lines
+      .take(hunk.lines.len() - placement.trail)
+      .skip(placement.lead);
+    for (line_index, line) in lines {
+      match line {
+        Line::Addition(_) => {
+          previous = Some(line);
+          result.added += 1;
+          output.insert(hunk_index, line_index, matcher.eol(crlf));
+          *self.no_newline = false;
+        }
+        Line::Context(text) | Line::Deletion(text) => {
+          previous = Some(line);
+          let source_line = source_lines.peek();
+          let found = source_line.clone().map(|range| &source[range]);
+          let Some(source_line) = source_line.filter(|_| {
+            found.is_some_and(|found| matcher.lines_match(found, text))
+          }) else {
+            return Err(Error::HunkMismatch {
+              file: file.display().to_string(),
+              hunk_index,
+              line: source_lines.line_number(),
+              expected: text.to_string(),
+              found: found.map(str::to_string),
+            });
+          };
+
+          source_lines.advance();
+          crlf = matcher.normalize_eol
+            && source[source_line.clone()].ends_with('\r');
+          if let Line::Context(_) = line {
+            output.keep(source_line, 1);
+            *self.no_newline = false;
+          } else {
+            result.removed += 1;
+          }
         }
         Line::NoNewline => {
-          if !in_addition_block && source_iter.peek().is_some() {
-            return Err(Error::Apply(format!(
-              "Patch mismatch at line {}. Expected end of file, Found: ``",
-              current_source_line_num
-            )));
+          if !matches!(previous, Some(Line::Addition(_)))
+            && source_lines.peek().is_some()
+          {
+            return Err(Error::ExpectedEof {
+              file: file.display().to_string(),
+              hunk_index,
+              line: source_lines.line_number(),
+            });
+          }
+          if !matches!(previous, Some(Line::Deletion(_))) {
+            *self.no_newline = true;
+          }
+        }
+      }
+    }
+    Ok(result)
+  }
+}
+
+struct SourceLines<'a> {
+  source: &'a str,
+  starts: &'a mut Vec<usize>,
+  next: usize,
+}
+
+impl<'a> SourceLines<'a> {
+  fn new(source: &'a str, starts: &'a mut Vec<usize>, limit: usize) -> Self {
+    let limit = limit.saturating_add(2);
+    starts.clear();
+    starts.reserve(limit.min(source.len() + 1));
+    starts.extend(
+      iter::once(0)
+        .chain(memchr_iter(b'\n', source.as_bytes()).map(|offset| offset + 1))
+        .take(limit),
+    );
+    Self {
+      source,
+      starts,
+      next: 0,
+    }
+  }
+
+  fn line_number(&self) -> usize {
+    self.next + 1
+  }
+
+  fn len(&self) -> usize {
+    self.starts.len()
+  }
+
+  fn index_all(&mut self) {
+    if let Some(&last) = self.starts.last() {
+      let rest = &self.source.as_bytes()[last..];
+      self
+        .starts
+        .extend(memchr_iter(b'\n', rest).map(|offset| last + offset + 1));
+    }
+  }
+
+  fn matches<'l>(
+    &self,
+    index: usize,
+    expected: impl Iterator<Item = &'l str>,
+    matcher: Matcher,
+  ) -> bool {
+    (index..).zip(expected).all(|(index, expected)| {
+      self.starts.get(index).is_some_and(|&start| {
+        matcher.lines_match(&self.source[start..self.end_of(index)], expected)
+      })
+    })
+  }
+
+  fn end_of(&self, index: usize) -> usize {
+    self
+      .starts
+      .get(index + 1)
+      .map_or(self.source.len(), |next| next - 1)
+  }
+
+  fn peek(&self) -> Option<Range<usize>> {
+    let start = *self.starts.get(self.next)?;
+    Some(start..self.end_of(self.next))
+  }
+
+  fn advance(&mut self) {
+    self.next += 1;
+  }
+
+  fn skip(&mut self, count: usize) -> Option<Range<usize>> {
+    let last = self.next + count - 1;
+    if last >= self.starts.len() {
+      return None;
+    }
+    let skipped = self.starts[self.next]..self.end_of(last);
+    self.next += count;
+    Some(skipped)
+  }
+
+  fn rest(&mut self) -> Option<Range<usize>> {
+    let start = *self.starts.get(self.next)?;
+    self.next = self.starts.len();
+    Some(start..self.source.len())
+  }
+}
+
+#[derive(Debug, Clone)]
+enum Piece {
+  Source(Range<usize>),
+  Addition {
+    hunk: usize,
+    line: usize,
+    eol: Option<Eol>,
+  },
+}
+
+struct Output<'a> {
+  source: &'a str,
+  hunks: &'a [Hunk<'a>],
+  pieces: &'a mut Vec<Piece>,
+  lines: usize,
+  ending: Ending,
+}
+
+impl<'a> Output<'a> {
+  fn checkpoint(&self) -> (usize, usize, Option<Piece>) {
+    (self.lines, self.pieces.len(), self.pieces.last().cloned())
+  }
+
+  fn rollback(&mut self, (lines, len, last): (usize, usize, Option<Piece>)) {
+    self.lines = lines;
+    self.pieces.truncate(len);
+    if let (Some(piece), Some(last)) = (self.pieces.last_mut(), last) {
+      *piece = last;
+    }
+  }
+
+  fn keep(&mut self, line: Range<usize>, count: usize) {
+    self.lines += count;
+    if let Some(Piece::Source(run)) = self.pieces.last_mut()
+      && run.end + 1 == line.start
+    {
+      run.end = line.end;
+      return;
+    }
+    self.pieces.push(Piece::Source(line));
+  }
+
+  fn insert(&mut self, hunk: usize, line: usize, eol: Option<Eol>) {
+    self.lines += 1;
+    self.pieces.push(Piece::Addition { hunk, line, eol });
+  }
+
+  fn piece(&self, piece: &Piece) -> &'a str {
+    match piece {
+      Piece::Source(range) => &self.source[range.clone()],
+      Piece::Addition { hunk, line, eol } => {
+        match &self.hunks[*hunk].lines[*line] {
+          Line::Addition(text) if eol.is_some() => {
+            text.strip_suffix('\r').unwrap_or(text)
+          }
+          Line::Addition(text) => text,
+          _ => "",
+        }
+      }
+    }
+  }
+
+  fn chunks(&self) -> impl Iterator<Item = &'a str> {
+    self.pieces.iter().enumerate().flat_map(|(index, piece)| {
+      let separator = if index > 0 { "\n" } else { "" };
+      let ending = match piece {
+        Piece::Addition {
+          eol: Some(Eol::Crlf),
+          ..
+        } => "\r",
+        _ => "",
+      };
+      [separator, self.piece(piece), ending]
+    })
+  }
+
+  fn len(&self) -> usize {
+    self.chunks().map(str::len).sum()
+  }
+
+  fn materialize_into(&self, output: &mut String) {
+    output.reserve(self.len() + 1);
+    self.chunks().for_each(|chunk| output.push_str(chunk));
+    match self.ending {
+      Ending::Verbatim => {}
+      Ending::Newline => {
+        let terminated = match self.pieces.last() {
+          Some(Piece::Source(range)) => {
+            range.end == self.source.len()
+              && (range.is_empty() || self.source.ends_with('\n'))
+          }
+          Some(Piece::Addition { .. }) => false,
+          None => true,
+        };
+        if !terminated {
+          output.push('\n');
+        }
+      }
+      Ending::NoNewline => {
+        if output.ends_with('\n') {
+          output.pop();
+        }
+      }
+    }
+  }
+}
+
+enum Ending {
+  Verbatim,
+  Newline,
+  NoNewline,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+  UnsafePath {
+    path: String,
+  },
+  Unsupported {
+    path: PathBuf,
+  },
+  MissingFile {
+    path: PathBuf,
+  },
+  TargetExists {
+    path: PathBuf,
+  },
+  Ignored {
+    path: PathBuf,
+  },
+  Unreadable {
+    path: PathBuf,
+    message: String,
+  },
+  HunkUnreachable {
+    path: PathBuf,
+    hunk_index: usize,
+    line: usize,
+  },
+  ContextMismatch {
+    path: PathBuf,
+    hunk_index: usize,
+    line: usize,
+    expected: String,
+    found: Option<String>,
+  },
+  SimilarityMismatch {
+    path: PathBuf,
+    declared: u32,
+    computed: u32,
+  },
+  BinaryMismatch {
+    path: PathBuf,
+    message: String,
+  },
+  AlreadyApplied {
+    path: PathBuf,
+  },
+}
+
+impl fmt::Display for Issue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UnsafePath { path } => {
+        write!(
+          f,
+          "{}: refusing to apply outside the working directory",
+          path
+        )
+      }
+      Self::Unsupported { path } => {
+        write!(f, "{}: binary patch carries no data", path.display())
+      }
+      Self::MissingFile { path } => {
+        write!(f, "{}: does not exist", path.display())
+      }
+      Self::TargetExists { path } => {
+        write!(f, "{}: already exists", path.display())
+      }
+      Self::Ignored { path } => {
+        write!(f, "{}: is ignored by git", path.display())
+      }
+      Self::Unreadable { path, message } => {
+        write!(f, "{}: {}", path.display(), message)
+      }
+      Self::HunkUnreachable {
+        path,
+        hunk_index,
+        line,
+      } => write!(
+        f,
+        "{}: hunk #{} starts at line {} past the end of the file",
+        path.display(),
+        hunk_index + 1,
+        line
+      ),
+      Self::ContextMismatch {
+        path,
+        hunk_index,
+        line,
+        expected,
+        found,
+      } => write!(
+        f,
+        "{}: hunk #{} does not match at line {}. Expected: `{}`, Found: `{}`",
+        path.display(),
+        hunk_index + 1,
+        line,
+        expected.replace('\r', "\\r"),
+        found.as_deref().unwrap_or("<EOF>").replace('\r', "\\r")
+      ),
+      Self::SimilarityMismatch {
+        path,
+        declared,
+        computed,
+      } => write!(
+        f,
+        "{}: rename declares {}% similarity but the result is {}% similar",
+        path.display(),
+        declared,
+        computed
+      ),
+      Self::BinaryMismatch { path, message } => {
+        write!(
+          f,
+          "{}: binary patch does not apply: {}",
+          path.display(),
+          message
+        )
+      }
+      Self::AlreadyApplied { path } => {
+        write!(f, "{}: patch is already applied", path.display())
+      }
+    }
+  }
+}
+
+pub fn check(
+  fs: &impl FileSystem,
+  patch_content: &str,
+  options: &ApplyOptions,
+) -> Result<Vec<Issue>, Error> {
+  let mut issues = Vec::new();
+  let mut base = Staged(fs);
+  let mut staged = Transaction::new(&mut base);
+  for patch_result in options.parser(patch_content) {
+    let patch = patch_result?;
+    let patch = orient(patch, options);
+    if !options.is_selected(target_of(&patch)) {
+      continue;
+    }
+    let found = validate_with(&staged, &patch, options);
+    if found.is_empty() {
+      // Later patches in a series are checked against the earlier ones, and
+      // any failure here resurfaces when the series is actually applied.
+      let mut applier = Applier::from(options);
+      let _ =
+        apply_patch(&mut staged, &mut applier, patch, options, None, &mut ());
+    }
+    issues.extend(found);
+  }
+  Ok(issues)
+}
+
+struct Staged<'f, F>(&'f F);
+
+fn staged_only() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::PermissionDenied,
+    "checks never write to the worktree",
+  )
+}
+
+impl<F: FileSystem> FileSystem for Staged<'_, F> {
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    self.0.read_to_string(path)
+  }
+
+  fn read_contents(&self, path: &Path) -> io::Result<Contents> {
+    self.0.read_contents(path)
+  }
+
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    self.0.read_bytes(path)
+  }
+
+  fn write(&mut self, _: &Path, _: &str) -> io::Result<()> {
+    Err(staged_only())
+  }
+
+  fn remove_file(&mut self, _: &Path) -> io::Result<()> {
+    Err(staged_only())
+  }
+
+  fn create_dir_all(&mut self, _: &Path) -> io::Result<()> {
+    Err(staged_only())
+  }
+
+  #[cfg(unix)]
+  fn set_permissions(&mut self, _: &Path, _: Permissions) -> io::Result<()> {
+    Err(staged_only())
+  }
+
+  #[cfg(unix)]
+  fn get_permissions(&self, path: &Path) -> io::Result<Permissions> {
+    self.0.get_permissions(path)
+  }
+}
+
+pub fn check_many<S: AsRef<str> + Sync>(
+  fs: &(impl FileSystem + Sync),
+  patches: &[S],
+  options: &ApplyOptions,
+  jobs: usize,
+) -> Vec<Result<Vec<Issue>, Error>> {
+  let next = AtomicUsize::new(0);
+  let workers = jobs.clamp(1, patches.len().max(1));
+  let mut results: Vec<_> = thread::scope(|scope| {
+    let handles: Vec<_> = (0..workers)
+      .map(|_| {
+        scope.spawn(|| {
+          let mut done = Vec::new();
+          loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(patch) = patches.get(index) else {
+              break done;
+            };
+            done.push((index, check(fs, patch.as_ref(), options)));
           }
-          new_file_should_have_no_newline = true;
+        })
+      })
+      .collect();
+    handles
+      .into_iter()
+      .flat_map(|handle| {
+        handle
+          .join()
+          .unwrap_or_else(|panic| panic::resume_unwind(panic))
+      })
+      .collect()
+  });
+  results.sort_by_key(|(index, _)| *index);
+  results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub fn validate(fs: &impl FileSystem, patch: &Patch) -> Vec<Issue> {
+  validate_with(fs, patch, &ApplyOptions::default())
+}
+
+pub fn validate_with(
+  fs: &impl FileSystem,
+  patch: &Patch,
+  options: &ApplyOptions,
+) -> Vec<Issue> {
+  let mut issues = Vec::new();
+
+  let mut resolve = |path: &str| match options.resolve(path) {
+    Ok(path) => Some(path),
+    Err(_) => {
+      issues.push(Issue::UnsafePath {
+        path: path.to_string(),
+      });
+      None
+    }
+  };
+  let is_creation = patch.old_file == "/dev/null";
+  let source_path = if is_creation {
+    None
+  } else {
+    resolve(patch.copy_from.as_deref().unwrap_or(&patch.old_file))
+  };
+  let target_path = if patch.new_file == "/dev/null" {
+    None
+  } else {
+    resolve(&patch.new_file)
+  };
+
+  if patch.is_binary && patch.binary.is_none() {
+    issues.push(Issue::Unsupported {
+      path: target_path.or(source_path).unwrap_or_default(),
+    });
+    return issues;
+  }
+
+  if let Some(target) = &target_path {
+    let is_new_path = is_creation
+      || patch.copy_from.is_some()
+      || (patch.rename_from.is_some() && patch.old_file != patch.new_file);
+    if is_new_path && fs.read_to_string(target).is_ok() {
+      issues.push(Issue::TargetExists {
+        path: target.clone(),
+      });
+    }
+    if is_new_path
+      && let Some(repository) = &options.repository
+      && repository.is_ignored(fs, target)
+    {
+      issues.push(Issue::Ignored {
+        path: target.clone(),
+      });
+    }
+  }
+
+  let Some(source_path) = source_path else {
+    return issues;
+  };
+  if let Some(binary) = &patch.binary {
+    match fs.read_bytes(&source_path) {
+      Ok(source) => {
+        if let Err(e) = binary.forward.apply(&source) {
+          issues.push(Issue::BinaryMismatch {
+            path: source_path,
+            message: e.to_string(),
+          });
         }
       }
+      Err(e) if e.kind() == io::ErrorKind::NotFound => {
+        issues.push(Issue::MissingFile { path: source_path });
+      }
+      Err(e) => {
+        issues.push(Issue::Unreadable {
+          path: source_path,
+          message: e.to_string(),
+        });
+      }
     }
+    return issues;
   }
+  let mut widened = None;
+  let source = fs.read_to_string(&source_path).or_else(|e| {
+    if e.kind() != io::ErrorKind::InvalidData {
+      return Err(e);
+    }
+    let bytes = fs.read_bytes(&source_path)?;
+    if options.lossy_utf8 {
+      return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    widened = Some(widen_patch(patch));
+    Ok(widen(&bytes))
+  });
+  let patch = widened.as_ref().unwrap_or(patch);
+  let source = match source {
+    Ok(source) => source,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+      if patch.new_file != "/dev/null" {
+        issues.push(Issue::MissingFile { path: source_path });
+      } else if !options.skip_applied {
+        issues.push(Issue::AlreadyApplied { path: source_path });
+      }
+      return issues;
+    }
+    Err(e) => {
+      issues.push(Issue::Unreadable {
+        path: source_path,
+        message: e.to_string(),
+      });
+      return issues;
+    }
+  };
 
-  result_lines.extend(source_iter);
+  let issues_before_hunks = issues.len();
+  let source_lines: Vec<&str> = source.split('\n').collect();
+  for (hunk_index, hunk) in patch.hunks.iter().enumerate() {
+    let first_line = if hunk.old_span == 0 {
+      hunk.old_line as usize + 1
+    } else {
+      (hunk.old_line as usize).max(1)
+    };
+    let matcher = Matcher {
+      line_endings: options.line_endings,
+      whitespace: options.whitespace,
+      ..Matcher::default()
+    }
+    .resolve(patch, source.as_bytes());
+    let mismatch = |lines, first_line| {
+      first_mismatch(&source_lines, lines, first_line, matcher)
+    };
+    let located = (0..=options.fuzz).any(|level| {
+      let (lead, trail) = fuzz_trim(hunk, level);
+      let lines = &hunk.lines[lead..hunk.lines.len() - trail];
+      let last = source_lines
+        .len()
+        .checked_sub(expected_lines(lines).count());
+      last.is_some_and(|last| {
+        candidates(first_line - 1, 0..=last)
+          .any(|start| mismatch(lines, start + lead + 1).is_none())
+      })
+    });
+    if located {
+      continue;
+    }
+
+    if first_line - 1 + hunk.old_span as usize > source_lines.len() {
+      issues.push(Issue::HunkUnreachable {
+        path: source_path.clone(),
+        hunk_index,
+        line: hunk.old_line as usize,
+      });
+      continue;
+    }
 
-  if result_lines.is_empty() {
-    return Ok(String::new());
+    if let Some((expected, line)) = mismatch(&hunk.lines, first_line) {
+      issues.push(Issue::ContextMismatch {
+        path: source_path.clone(),
+        hunk_index,
+        line,
+        expected: expected.to_string(),
+        found: source_lines.get(line - 1).map(|text| text.to_string()),
+      });
+    }
   }
 
-  let mut final_output = result_lines.join("\n");
+  if issues.len() > issues_before_hunks
+    && Applier::from(options).is_applied(patch, &source)
+  {
+    issues.truncate(issues_before_hunks);
+    if !options.skip_applied {
+      issues.push(Issue::AlreadyApplied { path: source_path });
+    }
+    return issues;
+  }
 
-  if new_file_should_have_no_newline {
-    if final_output.ends_with('\n') {
-      final_output.pop();
+  if let Some(tolerance) = options.similarity_tolerance
+    && let Some(declared) = patch.similarity
+    && patch.rename_from.is_some()
+    && !patch.hunks.is_empty()
+    && issues.len() == issues_before_hunks
+  {
+    let computed = similarity(patch, source.len());
+    if declared.abs_diff(computed) > tolerance {
+      issues.push(Issue::SimilarityMismatch {
+        path: source_path,
+        declared,
+        computed,
+      });
     }
-  } else if !final_output.is_empty() && !final_output.ends_with('\n') {
-    final_output.push('\n');
   }
 
-  Ok(final_output)
+  issues
+}
+
+fn similarity(patch: &Patch, source_len: usize) -> u32 {
+  let (mut deleted, mut added) = (0, 0);
+  for line in patch.hunks.iter().flat_map(|hunk| &hunk.lines) {
+    match line {
+      Line::Deletion(text) => deleted += text.len() + 1,
+      Line::Addition(text) => added += text.len() + 1,
+      _ => {}
+    }
+  }
+
+  let kept = source_len.saturating_sub(deleted);
+  let larger = source_len.max(kept + added);
+  if larger == 0 {
+    return 100;
+  }
+  (kept * 100 / larger) as u32
+}
+
+fn orient<'a>(patch: Patch<'a>, options: &ApplyOptions) -> Patch<'a> {
+  if options.reverse {
+    patch.invert()
+  } else {
+    patch
+  }
+}
+
+fn guess_direction<'a>(
+  fs: &impl FileSystem,
+  applier: &Applier,
+  patch: Patch<'a>,
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<Patch<'a>, Error> {
+  if patch.is_binary || patch.hunks.is_empty() || patch.old_file == "/dev/null"
+  {
+    return Ok(patch);
+  }
+
+  let path =
+    options.resolve(patch.copy_from.as_deref().unwrap_or(&patch.old_file))?;
+  let source = read_source(fs, &path, options, &mut ())?;
+  let source = source.as_str().map_err(|e| Error::io_at(e, &path))?;
+  if applier.applies(&patch, source) || !applier.is_applied(&patch, source) {
+    return Ok(patch);
+  }
+
+  log::warn!(
+    path:% = path.display();
+    "Patch appears to be reversed or already applied, reversing: {}",
+    path.display()
+  );
+  sink.on_event(ApplyEvent::Reversed { path });
+  Ok(patch.invert())
+}
+
+fn police_whitespace(
+  patch: &mut Patch,
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<(), Error> {
+  if options.whitespace_policy == WhitespacePolicy::Nowarn || patch.is_binary {
+    return Ok(());
+  }
+
+  let issues = whitespace::check(patch);
+  let Some(first) = issues.first() else {
+    return Ok(());
+  };
+  let path = PathBuf::from(target_of(patch));
+  if options.whitespace_policy == WhitespacePolicy::Error {
+    return Err(Error::Invalid(
+      format!(
+        "{}:{}: {} ({} whitespace error(s))",
+        path.display(),
+        first.line,
+        first.kind,
+        issues.len()
+      )
+      .into(),
+    ));
+  }
+
+  let fixed = options.whitespace_policy == WhitespacePolicy::Fix;
+  for issue in issues {
+    sink.on_event(ApplyEvent::Whitespace {
+      path: path.clone(),
+      line: issue.line,
+      kind: issue.kind,
+      fixed,
+    });
+  }
+  if fixed {
+    whitespace::fix(patch);
+  }
+  Ok(())
+}
+
+fn target_of<'p>(patch: &'p Patch) -> &'p str {
+  if patch.new_file == "/dev/null" {
+    &patch.old_file
+  } else {
+    &patch.new_file
+  }
+}
+
+fn first_mismatch<'l>(
+  source_lines: &[&str],
+  lines: &'l [Line<'l>],
+  first_line: usize,
+  matcher: Matcher,
+) -> Option<(&'l str, usize)> {
+  expected_lines(lines)
+    .zip(first_line..)
+    .find(|&(text, line)| {
+      !source_lines
+        .get(line - 1)
+        .is_some_and(|found| matcher.lines_match(found, text))
+    })
 }
 
 pub fn patch(
   fs: &mut impl FileSystem,
   patch_content: &str,
-  reverse: bool,
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<ApplyReport, Error> {
+  apply_all(fs, options.parser(patch_content), options, None, sink)
+    .inspect_err(telemetry::failed)
+}
+
+pub fn patch_three_way(
+  fs: &mut impl FileSystem,
+  patch_content: &str,
+  options: &ApplyOptions,
+  pre_images: &impl PreImages,
+  sink: &mut impl ApplySink,
+) -> Result<ApplyReport, Error> {
+  apply_all(
+    fs,
+    options.parser(patch_content),
+    options,
+    Some(pre_images),
+    sink,
+  )
+  .inspect_err(telemetry::failed)
+}
+
+pub fn apply_patches<'a>(
+  fs: &mut impl FileSystem,
+  patches: impl IntoIterator<Item = Patch<'a>>,
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<ApplyReport, Error> {
+  apply_all(fs, patches.into_iter().map(Ok), options, None, sink)
+    .inspect_err(telemetry::failed)
+}
+
+pub fn apply_stream<'a>(
+  fs: &mut impl FileSystem,
+  patches: impl IntoIterator<Item = Result<Patch<'a>, Error>>,
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<ApplyReport, Error> {
+  apply_all(fs, patches.into_iter(), options, None, sink)
+    .inspect_err(telemetry::failed)
+}
+
+pub fn apply_bufs(
+  fs: &mut impl FileSystem,
+  patches: &[PatchBuf],
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<ApplyReport, Error> {
+  apply_patches(fs, patches.iter().map(PatchBuf::as_patch), options, sink)
+}
+
+fn apply_all<'a>(
+  fs: &mut impl FileSystem,
+  patches: impl Iterator<Item = Result<Patch<'a>, Error>>,
+  options: &ApplyOptions,
+  pre_images: Option<&dyn PreImages>,
+  sink: &mut impl ApplySink,
+) -> Result<ApplyReport, Error> {
+  let mut report = ApplyReport::default();
+  let mut sink = |event: ApplyEvent| {
+    report.on_event(event.clone());
+    sink.on_event(event);
+  };
+  if !options.atomic && !options.dry_run {
+    apply_each(fs, patches, options, pre_images, &mut sink)?;
+    return Ok(report);
+  }
+
+  let mut transaction = Transaction::new(fs);
+  let mut events = Vec::new();
+  apply_each(&mut transaction, patches, options, pre_images, &mut events)?;
+  if !options.dry_run {
+    transaction.commit()?;
+  }
+  for event in events {
+    sink.on_event(event);
+  }
+  Ok(report)
+}
+
+fn apply_each<'a>(
+  fs: &mut impl FileSystem,
+  patches: impl Iterator<Item = Result<Patch<'a>, Error>>,
+  options: &ApplyOptions,
+  pre_images: Option<&dyn PreImages>,
+  sink: &mut impl ApplySink,
 ) -> Result<(), Error> {
-  for patch_result in Parser::new(patch_content) {
-    let patch = patch_result?;
-    let patch = if reverse { patch.invert() } else { patch };
+  let mut applier = Applier::from(options);
+  let duplicate = |patch: &Patch| ApplyEvent::Duplicate {
+    path: PathBuf::from(target_of(patch)),
+    policy: options.duplicates,
+  };
+
+  if options.duplicates == DuplicatePolicy::InOrder {
+    let mut seen = HashSet::new();
+    for patch_result in patches {
+      let patch = orient(patch_result?, options);
+      telemetry::patch_parsed();
+      if !seen.insert(target_of(&patch).to_string()) {
+        sink.on_event(duplicate(&patch));
+      }
+      apply_patch(fs, &mut applier, patch, options, pre_images, sink)?;
+    }
+    return Ok(());
+  }
 
-    if patch.is_binary {
-      return Err(Error::Unsupported("Binary files are not supported".into()));
+  let patches = patches
+    .map(|patch_result| {
+      patch_result.map(|patch| {
+        telemetry::patch_parsed();
+        orient(patch, options)
+      })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let mut last = HashMap::new();
+  for (index, patch) in patches.iter().enumerate() {
+    if let Some(previous) = last.insert(target_of(patch), index)
+      && options.duplicates == DuplicatePolicy::Error
+    {
+      return Err(Error::Invalid(
+        format!(
+          "Entries #{} and #{} both target `{}`",
+          previous + 1,
+          index + 1,
+          target_of(patch)
+        )
+        .into(),
+      ));
     }
+  }
+  let last: HashSet<usize> = last.into_values().collect();
 
-    let source_path = Path::new(patch.old_file);
-    let source_content = if patch.old_file == "/dev/null" {
-      String::new()
+  for (index, patch) in patches.into_iter().enumerate() {
+    if last.contains(&index) {
+      apply_patch(fs, &mut applier, patch, options, pre_images, sink)?;
     } else {
-      let path_to_read = patch.copy_from.map_or(source_path, Path::new);
-      match fs.read_to_string(path_to_read) {
-        Ok(content) => content,
-        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
-        Err(e) => return Err(e.into()),
+      sink.on_event(duplicate(&patch));
+    }
+  }
+
+  Ok(())
+}
+
+// Non-UTF-8 files go through the text applier with every byte mapped to the
+// char of the same value, so offsets, fuzz, rejects and merges work on them
+// too and `narrow` restores the exact bytes afterwards.
+fn widen(bytes: &[u8]) -> String {
+  bytes.iter().map(|&byte| char::from(byte)).collect()
+}
+
+fn narrow(text: &str) -> Vec<u8> {
+  text.chars().map(|c| c as u8).collect()
+}
+
+fn widen_patch<'a>(patch: &Patch<'a>) -> Patch<'a> {
+  let mut widened = patch.clone();
+  let lines = widened.hunks.iter_mut().flat_map(|hunk| &mut hunk.lines);
+  for line in lines {
+    if let Line::Context(text) | Line::Deletion(text) | Line::Addition(text) =
+      line
+      && !text.is_ascii()
+    {
+      *text = Cow::Owned(widen(text.as_bytes()));
+    }
+  }
+  widened
+}
+
+struct Widened<'p>(&'p dyn PreImages);
+
+impl PreImages for Widened<'_> {
+  fn pre_image(&self, hash: &str) -> Option<String> {
+    self.0.pre_image_bytes(hash).map(|bytes| widen(&bytes))
+  }
+}
+
+fn read_source(
+  fs: &impl FileSystem,
+  path: &Path,
+  options: &ApplyOptions,
+  sink: &mut impl ApplySink,
+) -> Result<Contents, Error> {
+  match fs.read_contents(path) {
+    Ok(content) => Ok(content),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Contents::default()),
+    Err(e) if e.kind() == io::ErrorKind::InvalidData && options.lossy_utf8 => {
+      let bytes = fs.read_bytes(path).map_err(|e| Error::io_at(e, path))?;
+      log::warn!(
+        path:% = path.display();
+        "Replaced invalid UTF-8 while reading: {}",
+        path.display()
+      );
+      sink.on_event(ApplyEvent::LossyRead {
+        path: path.to_path_buf(),
+      });
+      Ok(Contents::Owned(
+        String::from_utf8_lossy(&bytes).into_owned(),
+      ))
+    }
+    Err(e) => Err(Error::io_at(e, path)),
+  }
+}
+
+pub trait PreImages {
+  fn pre_image(&self, hash: &str) -> Option<String>;
+  fn pre_image_bytes(&self, hash: &str) -> Option<Vec<u8>> {
+    self.pre_image(hash).map(String::into_bytes)
+  }
+}
+
+impl PreImages for HashMap<String, String> {
+  fn pre_image(&self, hash: &str) -> Option<String> {
+    let mut matches = self.iter().filter(|(key, _)| key.starts_with(hash));
+    let (_, content) = matches.next()?;
+    matches.next().is_none().then(|| content.clone())
+  }
+}
+
+fn three_way(
+  applier: &mut Applier,
+  patch: &Patch,
+  ours: &str,
+  path: &Path,
+  pre_images: &dyn PreImages,
+) -> Option<Merged> {
+  let hash = patch.old_hash.as_deref()?;
+  let Some(base) = pre_images.pre_image(hash) else {
+    log::warn!(
+      path:% = path.display(),
+      hash = hash;
+      "Missing pre-image {} for a three-way merge of: {}",
+      hash,
+      path.display()
+    );
+    return None;
+  };
+  let theirs = applier.content_at(patch, &base, path).ok()?;
+
+  let merged = merge::merge(&base, ours, &theirs);
+  if merged.conflicts > 0 {
+    log::warn!(
+      path:% = path.display(),
+      conflicts = merged.conflicts;
+      "Applied with {} conflict{}: {}",
+      merged.conflicts,
+      if merged.conflicts == 1 { "" } else { "s" },
+      path.display()
+    );
+  } else {
+    log::info!(
+      path:% = path.display();
+      "Applied with a three-way merge: {}",
+      path.display()
+    );
+  }
+  Some(merged)
+}
+
+fn rewrite(
+  applier: &mut Applier,
+  patch: &Patch,
+  content: &str,
+  path: &Path,
+  options: &ApplyOptions,
+  pre_images: Option<&dyn PreImages>,
+  sink: &mut impl ApplySink,
+) -> Result<Option<String>, Error> {
+  match applier.content_at(patch, content, path) {
+    Ok(new_content) => Ok(Some(new_content)),
+    Err(_) if applier.is_applied(patch, content) => {
+      if !options.skip_applied {
+        return Err(Error::AlreadyApplied {
+          path: path.display().to_string(),
+        });
       }
-    };
+      log::debug!(
+        path:% = path.display();
+        "Skipped already applied patch: {}",
+        path.display()
+      );
+      sink.on_event(ApplyEvent::AlreadyApplied {
+        path: path.to_path_buf(),
+      });
+      Ok(None)
+    }
+    Err(error) => {
+      let Some(pre_images) = pre_images else {
+        return Err(error);
+      };
+      let merged =
+        three_way(applier, patch, content, path, pre_images).ok_or(error)?;
+      sink.on_event(ApplyEvent::Merged {
+        path: path.to_path_buf(),
+        conflicts: merged.conflicts,
+      });
+      Ok(Some(merged.content))
+    }
+  }
+}
 
-    let new_content = apply(&patch, &source_content)?;
+enum Rewrite {
+  Text(String),
+  Binary(Vec<u8>),
+}
 
-    let output_path = Path::new(patch.new_file);
-    if patch.new_file == "/dev/null" {
-      match fs.remove_file(source_path) {
-        Ok(()) => println!("Deleted file: {}", source_path.display()),
-        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
-        Err(e) => return Err(e.into()),
+fn apply_patch(
+  fs: &mut impl FileSystem,
+  applier: &mut Applier,
+  mut patch: Patch,
+  options: &ApplyOptions,
+  pre_images: Option<&dyn PreImages>,
+  sink: &mut impl ApplySink,
+) -> Result<(), Error> {
+  if !options.is_selected(target_of(&patch)) {
+    sink.on_event(ApplyEvent::Skipped {
+      path: PathBuf::from(target_of(&patch)),
+    });
+    return Ok(());
+  }
+
+  if options.auto_reverse {
+    patch = guess_direction(fs, applier, patch, options, sink)?;
+  }
+  police_whitespace(&mut patch, options, sink)?;
+  let target = target_of(&patch);
+
+  let is_rename =
+    patch.rename_from.is_some() && patch.old_file != patch.new_file;
+  let (source_path, new_content) = if patch.is_binary {
+    let Some(binary) = &patch.binary else {
+      return Err(Error::Unsupported(
+        format!(
+          "Binary files without patch data are not supported: `{}`",
+          target
+        )
+        .into(),
+      ));
+    };
+    let (path, source) = if patch.old_file == "/dev/null" {
+      (options.resolve(&patch.new_file)?, Vec::new())
+    } else {
+      let path = options
+        .resolve(patch.copy_from.as_deref().unwrap_or(&patch.old_file))?;
+      match fs.read_bytes(&path) {
+        Ok(source) => (path, source),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (path, Vec::new()),
+        Err(e) => return Err(Error::io_at(e, &path)),
       }
+    };
+    let new_content = binary.forward.apply(&source)?;
+    (path, Some(Rewrite::Binary(new_content)))
+  } else if patch.old_file == "/dev/null" {
+    let path = options.resolve(&patch.new_file)?;
+    let new_content = applier.content_at(&patch, "", &path)?;
+    (path, Some(Rewrite::Text(new_content)))
+  } else {
+    let path_to_read =
+      options.resolve(patch.copy_from.as_deref().unwrap_or(&patch.old_file))?;
+    if patch.hunks.is_empty() {
+      (path_to_read, None)
     } else {
-      if let Some(parent) = output_path.parent() {
-        fs.create_dir_all(parent)?;
+      match read_source(fs, &path_to_read, options, sink) {
+        Err(Error::Io(io::ErrorKind::InvalidData, _)) => {
+          let source = fs
+            .read_bytes(&path_to_read)
+            .map_err(|e| Error::io_at(e, &path_to_read))?;
+          let pre_images = pre_images.map(Widened);
+          let Some(new_content) = rewrite(
+            applier,
+            &widen_patch(&patch),
+            &widen(&source),
+            &path_to_read,
+            options,
+            pre_images.as_ref().map(|p| p as &dyn PreImages),
+            sink,
+          )?
+          else {
+            return Ok(());
+          };
+          log::debug!(
+            path:% = path_to_read.display();
+            "Applied byte-wise to non-UTF-8 file: {}",
+            path_to_read.display()
+          );
+          (path_to_read, Some(Rewrite::Binary(narrow(&new_content))))
+        }
+        content => {
+          let contents = content?;
+          let content = contents
+            .as_str()
+            .map_err(|e| Error::io_at(e, &path_to_read))?;
+          let Some(new_content) = rewrite(
+            applier,
+            &patch,
+            content,
+            &path_to_read,
+            options,
+            pre_images,
+            sink,
+          )?
+          else {
+            return Ok(());
+          };
+          drop(contents);
+          (path_to_read, Some(Rewrite::Text(new_content)))
+        }
       }
+    }
+  };
+  let rejected = mem::take(&mut applier.rejected);
+  let shifted = mem::take(&mut applier.shifted);
 
-      fs.write(output_path, &new_content)?;
-      println!("Applied patch to: {}", output_path.display());
+  if patch.new_file == "/dev/null" && !rejected.is_empty() {
+    reject_hunks(fs, &patch, rejected, &source_path, sink)?;
+  } else if patch.new_file == "/dev/null" {
+    match fs.remove_file(&source_path) {
+      Ok(()) => {
+        log::debug!(
+          path:% = source_path.display();
+          "Deleted file: {}",
+          source_path.display()
+        );
+        sink.on_event(ApplyEvent::Deleted { path: source_path });
+      }
+      Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+      Err(e) => return Err(Error::io_at(e, &source_path)),
+    }
+  } else {
+    let output_path = options.resolve(&patch.new_file)?;
+    if let Some(parent) = output_path.parent() {
+      fs.create_dir_all(parent)
+        .map_err(|e| Error::io_at(e, parent))?;
+    }
 
-      #[cfg(unix)]
-      {
-        if let Some(mode) = patch.new_mode.or(patch.index_mode) {
-          let perms = Permissions::from_mode(mode);
-          fs.set_permissions(output_path, perms)?;
+    let rewritten = new_content.is_some();
+    match new_content {
+      Some(Rewrite::Text(mut new_content)) => {
+        let eol = options.eol.or_else(|| {
+          let repository = options.repository.as_ref()?;
+          repository.eol(fs, &output_path, &new_content)
+        });
+        if let Some(eol) = eol {
+          new_content = eol.convert(&new_content);
         }
+        fs.write(&output_path, &new_content)
+          .inspect(|_| telemetry::bytes_written(new_content.len()))
       }
+      Some(Rewrite::Binary(new_content)) => fs
+        .write_bytes(&output_path, &new_content)
+        .inspect(|_| telemetry::bytes_written(new_content.len())),
+      None if is_rename => fs.rename(&source_path, &output_path),
+      None if patch.copy_from.is_some() => fs.copy(&source_path, &output_path),
+      None => Ok(()),
+    }
+    .map_err(|e| Error::io_at(e, &output_path))?;
+    log::debug!(
+      path:% = output_path.display(),
+      hunks = patch.hunks.len();
+      "Applied patch to: {}",
+      output_path.display()
+    );
 
-      if patch.rename_from.is_some() && source_path != output_path {
-        match fs.remove_file(source_path) {
-          Ok(()) => {}
-          Err(e) if e.kind() == io::ErrorKind::NotFound => {}
-          Err(e) => return Err(e.into()),
-        }
+    #[cfg(unix)]
+    {
+      if let Some(mode) = patch.new_mode.or(patch.index_mode) {
+        let perms = Permissions::from_mode(mode);
+        fs.set_permissions(&output_path, perms)
+          .map_err(|e| Error::io_at(e, &output_path))?;
+      }
+    }
+
+    if is_rename && rewritten {
+      match fs.remove_file(&source_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(Error::io_at(e, &source_path)),
+      }
+    }
+
+    let is_new_path =
+      patch.old_file == "/dev/null" || patch.copy_from.is_some() || is_rename;
+    if is_new_path
+      && let Some(repository) = &options.repository
+      && repository.is_ignored(fs, &output_path)
+    {
+      log::warn!(
+        path:% = output_path.display();
+        "Created file is ignored by git: {}",
+        output_path.display()
+      );
+    }
+
+    sink.on_event(if patch.old_file == "/dev/null" {
+      ApplyEvent::Created {
+        path: output_path.clone(),
+      }
+    } else if is_rename {
+      ApplyEvent::Renamed {
+        from: source_path,
+        to: output_path.clone(),
+      }
+    } else if patch.copy_from.is_some() {
+      ApplyEvent::Copied {
+        from: source_path,
+        to: output_path.clone(),
       }
+    } else {
+      ApplyEvent::Modified {
+        path: output_path.clone(),
+        hunks: patch.hunks.len(),
+      }
+    });
+
+    if let (Some(_), Some(mode)) = (patch.old_mode, patch.new_mode) {
+      sink.on_event(ApplyEvent::ModeChanged {
+        path: output_path.clone(),
+        mode,
+      });
+    }
+
+    for (hunk_index, result) in shifted {
+      sink.on_event(ApplyEvent::Offset {
+        path: output_path.clone(),
+        hunk: hunk_index + 1,
+        line: result.position,
+        offset: result.offset,
+        fuzz: result.fuzz,
+      });
+    }
+
+    if !rejected.is_empty() {
+      reject_hunks(fs, &patch, rejected, &output_path, sink)?;
     }
   }
 
   Ok(())
 }
+
+fn reject_hunks(
+  fs: &mut impl FileSystem,
+  patch: &Patch,
+  rejected: Vec<usize>,
+  path: &Path,
+  sink: &mut impl ApplySink,
+) -> Result<(), Error> {
+  let name = if patch.new_file == "/dev/null" {
+    &patch.old_file
+  } else {
+    &patch.new_file
+  };
+  let mut content = format!("diff a/{name} b/{name}\t(rejected hunks)\n");
+  for &hunk_index in &rejected {
+    render::write_hunk(
+      &mut content,
+      &ColorScheme::plain(),
+      &patch.hunks[hunk_index],
+    );
+  }
+
+  let mut reject_path = path.as_os_str().to_owned();
+  reject_path.push(".rej");
+  let reject_path = PathBuf::from(reject_path);
+  fs.write(&reject_path, &content)
+    .map_err(|e| Error::io_at(e, &reject_path))?;
+  log::warn!(
+    path:% = path.display(),
+    hunks = rejected.len();
+    "Rejected {} hunk{} of: {}",
+    rejected.len(),
+    if rejected.len() == 1 { "" } else { "s" },
+    path.display()
+  );
+  sink.on_event(ApplyEvent::Rejected {
+    path: path.to_path_buf(),
+    hunks: rejected,
+  });
+  Ok(())
+}
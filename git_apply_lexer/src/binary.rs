@@ -0,0 +1,146 @@
+use crate::error::Error;
+use crate::lexer::BinaryMethod;
+use crate::parser::BinaryHunk;
+use alloc::format;
+use alloc::vec::Vec;
+use miniz_oxide::inflate;
+
+const BASE85: &[u8; 85] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+abcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+fn malformed(what: &str) -> Error {
+  Error::Parse(format!("Malformed binary {}", what).into())
+}
+
+fn decode_line(line: &str, output: &mut Vec<u8>) -> Result<(), Error> {
+  let line = line.strip_suffix('\r').unwrap_or(line).as_bytes();
+  let Some((&length, encoded)) = line.split_first() else {
+    return Err(malformed("data line"));
+  };
+  let length = match length {
+    b'A'..=b'Z' => length - b'A' + 1,
+    b'a'..=b'z' => length - b'a' + 27,
+    _ => return Err(malformed("data line")),
+  } as usize;
+  if encoded.len() != length.div_ceil(4) * 5 {
+    return Err(malformed("data line"));
+  }
+
+  let start = output.len();
+  for chunk in encoded.chunks_exact(5) {
+    let mut value: u32 = 0;
+    for byte in chunk {
+      value = BASE85
+        .iter()
+        .position(|digit| digit == byte)
+        .and_then(|digit| value.checked_mul(85)?.checked_add(digit as u32))
+        .ok_or_else(|| malformed("data line"))?;
+    }
+    output.extend_from_slice(&value.to_be_bytes());
+  }
+  output.truncate(start + length);
+  Ok(())
+}
+
+fn varint(delta: &mut &[u8]) -> Option<usize> {
+  let mut value: usize = 0;
+  let mut shift = 0;
+  loop {
+    let (&byte, rest) = delta.split_first()?;
+    *delta = rest;
+    value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+    shift += 7;
+    if byte & 0x80 == 0 {
+      return Some(value);
+    }
+  }
+}
+
+fn apply_delta(source: &[u8], mut delta: &[u8]) -> Result<Vec<u8>, Error> {
+  let source_size = varint(&mut delta).ok_or_else(|| malformed("delta"))?;
+  let target_size = varint(&mut delta).ok_or_else(|| malformed("delta"))?;
+  if source_size != source.len() {
+    return Err(Error::Invalid(
+      format!(
+        "Binary delta expects {} source bytes, found {}",
+        source_size,
+        source.len()
+      )
+      .into(),
+    ));
+  }
+
+  let mut target =
+    Vec::with_capacity(target_size.min(source.len() + delta.len()));
+  while let Some((&command, rest)) = delta.split_first() {
+    delta = rest;
+    if command & 0x80 != 0 {
+      let mut operand = |flag: u8, bytes: usize| {
+        let mut value = 0;
+        for index in 0..bytes {
+          if command & flag << index != 0 {
+            let (&byte, rest) = delta.split_first()?;
+            delta = rest;
+            value |= (byte as usize) << (8 * index);
+          }
+        }
+        Some(value)
+      };
+      let offset = operand(0x01, 4).ok_or_else(|| malformed("delta"))?;
+      let size = match operand(0x10, 3).ok_or_else(|| malformed("delta"))? {
+        0 => 0x10000,
+        size => size,
+      };
+      let copied = offset
+        .checked_add(size)
+        .and_then(|end| source.get(offset..end))
+        .ok_or_else(|| malformed("delta"))?;
+      target.extend_from_slice(copied);
+    } else if command != 0 {
+      let (inserted, rest) = delta
+        .split_at_checked(command as usize)
+        .ok_or_else(|| malformed("delta"))?;
+      target.extend_from_slice(inserted);
+      delta = rest;
+    } else {
+      return Err(malformed("delta"));
+    }
+  }
+
+  if target.len() != target_size {
+    return Err(malformed("delta"));
+  }
+  Ok(target)
+}
+
+impl<'a> BinaryHunk<'a> {
+  pub fn decode(&self) -> Result<Vec<u8>, Error> {
+    let mut deflated = Vec::new();
+    for line in self.data.lines() {
+      decode_line(line, &mut deflated)?;
+    }
+
+    let inflated =
+      inflate::decompress_to_vec_zlib_with_limit(&deflated, self.size)
+        .map_err(|_| malformed("hunk data"))?;
+    if inflated.len() != self.size {
+      return Err(Error::Parse(
+        format!(
+          "Binary hunk inflates to {} bytes, expected {}",
+          inflated.len(),
+          self.size
+        )
+        .into(),
+      ));
+    }
+    Ok(inflated)
+  }
+
+  pub fn apply(&self, source: &[u8]) -> Result<Vec<u8>, Error> {
+    let data = self.decode()?;
+    match self.method {
+      BinaryMethod::Literal => Ok(data),
+      BinaryMethod::Delta => apply_delta(source, &data),
+    }
+  }
+}
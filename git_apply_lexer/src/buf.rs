@@ -0,0 +1,370 @@
+use crate::lexer::BinaryMethod;
+use crate::parser::BinaryHunk;
+use crate::parser::BinaryPatch;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineBuf {
+  Addition(Range<usize>),
+  Deletion(Range<usize>),
+  Context(Range<usize>),
+  NoNewline,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HunkBuf {
+  pub old_line: u32,
+  pub old_span: u32,
+  pub new_line: u32,
+  pub new_span: u32,
+  heading: Option<Range<usize>>,
+  lines: Vec<LineBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryHunkBuf {
+  pub method: BinaryMethod,
+  pub size: usize,
+  data: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatchBuf {
+  text: String,
+  old_file: Range<usize>,
+  new_file: Range<usize>,
+  rename_from: Option<Range<usize>>,
+  rename_to: Option<Range<usize>>,
+  copy_from: Option<Range<usize>>,
+  copy_to: Option<Range<usize>>,
+  old_hash: Option<Range<usize>>,
+  new_hash: Option<Range<usize>>,
+  old_timestamp: Option<Range<usize>>,
+  new_timestamp: Option<Range<usize>>,
+  pub hunks: Vec<HunkBuf>,
+  pub new_mode: Option<u32>,
+  pub old_mode: Option<u32>,
+  pub deleted_file_mode: Option<u32>,
+  pub similarity: Option<u32>,
+  pub is_binary: bool,
+  pub dissimilarity: Option<u32>,
+  pub index_mode: Option<u32>,
+  binary: Option<(BinaryHunkBuf, Option<BinaryHunkBuf>)>,
+}
+
+struct Arena {
+  text: String,
+  paths: Vec<Range<usize>>,
+}
+
+impl Arena {
+  fn push(&mut self, text: &str) -> Range<usize> {
+    let start = self.text.len();
+    self.text.push_str(text);
+    start..self.text.len()
+  }
+
+  fn intern(&mut self, path: &str) -> Range<usize> {
+    if let Some(range) = self
+      .paths
+      .iter()
+      .find(|range| &self.text[(*range).clone()] == path)
+    {
+      return range.clone();
+    }
+    let range = self.push(path);
+    self.paths.push(range.clone());
+    range
+  }
+}
+
+impl PatchBuf {
+  fn str(&self, range: &Range<usize>) -> &str {
+    &self.text[range.clone()]
+  }
+
+  #[cfg(feature = "cache")]
+  pub(crate) fn is_well_formed(&self) -> bool {
+    let valid = |range: &Range<usize>| self.text.get(range.clone()).is_some();
+    let paths = [
+      Some(&self.old_file),
+      Some(&self.new_file),
+      self.rename_from.as_ref(),
+      self.rename_to.as_ref(),
+      self.copy_from.as_ref(),
+      self.copy_to.as_ref(),
+      self.old_hash.as_ref(),
+      self.new_hash.as_ref(),
+      self.old_timestamp.as_ref(),
+      self.new_timestamp.as_ref(),
+    ];
+    paths.into_iter().flatten().all(valid)
+      && self
+        .hunks
+        .iter()
+        .all(|hunk| hunk.heading.as_ref().is_none_or(valid))
+      && self
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .all(|line| match line {
+          LineBuf::Addition(range)
+          | LineBuf::Deletion(range)
+          | LineBuf::Context(range) => valid(range),
+          LineBuf::NoNewline => true,
+        })
+      && self.binary.iter().all(|(forward, reverse)| {
+        valid(&forward.data)
+          && reverse.as_ref().is_none_or(|reverse| valid(&reverse.data))
+      })
+  }
+
+  pub fn as_patch(&self) -> Patch<'_> {
+    let borrowed = |range: &Range<usize>| Cow::Borrowed(self.str(range));
+    let binary_hunk = |hunk: &BinaryHunkBuf| BinaryHunk {
+      method: hunk.method,
+      size: hunk.size,
+      data: borrowed(&hunk.data),
+    };
+    Patch {
+      old_file: borrowed(&self.old_file),
+      new_file: borrowed(&self.new_file),
+      hunks: self
+        .hunks
+        .iter()
+        .map(|hunk| Hunk {
+          old_line: hunk.old_line,
+          old_span: hunk.old_span,
+          new_line: hunk.new_line,
+          new_span: hunk.new_span,
+          heading: hunk.heading.as_ref().map(borrowed),
+          lines: hunk
+            .lines
+            .iter()
+            .map(|line| match line {
+              LineBuf::Addition(range) => Line::Addition(borrowed(range)),
+              LineBuf::Deletion(range) => Line::Deletion(borrowed(range)),
+              LineBuf::Context(range) => Line::Context(borrowed(range)),
+              LineBuf::NoNewline => Line::NoNewline,
+            })
+            .collect(),
+        })
+        .collect(),
+      rename_from: self.rename_from.as_ref().map(borrowed),
+      rename_to: self.rename_to.as_ref().map(borrowed),
+      new_mode: self.new_mode,
+      old_mode: self.old_mode,
+      deleted_file_mode: self.deleted_file_mode,
+      similarity: self.similarity,
+      is_binary: self.is_binary,
+      copy_from: self.copy_from.as_ref().map(borrowed),
+      copy_to: self.copy_to.as_ref().map(borrowed),
+      dissimilarity: self.dissimilarity,
+      index_mode: self.index_mode,
+      binary: self.binary.as_ref().map(|(forward, reverse)| BinaryPatch {
+        forward: binary_hunk(forward),
+        reverse: reverse.as_ref().map(binary_hunk),
+      }),
+      old_hash: self.old_hash.as_ref().map(borrowed),
+      new_hash: self.new_hash.as_ref().map(borrowed),
+      old_timestamp: self.old_timestamp.as_ref().map(borrowed),
+      new_timestamp: self.new_timestamp.as_ref().map(borrowed),
+    }
+  }
+}
+
+impl From<&Patch<'_>> for PatchBuf {
+  fn from(patch: &Patch<'_>) -> Self {
+    let paths = [
+      Some(&patch.old_file),
+      Some(&patch.new_file),
+      patch.rename_from.as_ref(),
+      patch.rename_to.as_ref(),
+      patch.copy_from.as_ref(),
+      patch.copy_to.as_ref(),
+      patch.old_hash.as_ref(),
+      patch.new_hash.as_ref(),
+      patch.old_timestamp.as_ref(),
+      patch.new_timestamp.as_ref(),
+    ];
+    let capacity = paths.iter().flatten().map(|path| path.len()).sum::<usize>()
+      + patch
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .map(|line| match line {
+          Line::Addition(text) | Line::Deletion(text) | Line::Context(text) => {
+            text.len()
+          }
+          Line::NoNewline => 0,
+        })
+        .sum::<usize>()
+      + patch.binary.as_ref().map_or(0, |binary| {
+        binary.forward.data.len()
+          + binary
+            .reverse
+            .as_ref()
+            .map_or(0, |reverse| reverse.data.len())
+      });
+
+    let mut arena = Arena {
+      text: String::with_capacity(capacity),
+      paths: Vec::new(),
+    };
+    let [
+      old_file,
+      new_file,
+      rename_from,
+      rename_to,
+      copy_from,
+      copy_to,
+      old_hash,
+      new_hash,
+      old_timestamp,
+      new_timestamp,
+    ] = paths.map(|path| path.map(|path| arena.intern(path)));
+
+    let hunks = patch
+      .hunks
+      .iter()
+      .map(|hunk| HunkBuf {
+        old_line: hunk.old_line,
+        old_span: hunk.old_span,
+        new_line: hunk.new_line,
+        new_span: hunk.new_span,
+        heading: hunk.heading.as_deref().map(|text| arena.push(text)),
+        lines: hunk
+          .lines
+          .iter()
+          .map(|line| match line {
+            Line::Addition(text) => LineBuf::Addition(arena.push(text)),
+            Line::Deletion(text) => LineBuf::Deletion(arena.push(text)),
+            Line::Context(text) => LineBuf::Context(arena.push(text)),
+            Line::NoNewline => LineBuf::NoNewline,
+          })
+          .collect(),
+      })
+      .collect();
+    let mut binary_hunk = |hunk: &BinaryHunk| BinaryHunkBuf {
+      method: hunk.method,
+      size: hunk.size,
+      data: arena.push(&hunk.data),
+    };
+    let binary = patch.binary.as_ref().map(|binary| {
+      (
+        binary_hunk(&binary.forward),
+        binary.reverse.as_ref().map(&mut binary_hunk),
+      )
+    });
+
+    Self {
+      text: arena.text,
+      old_file: old_file.unwrap_or_default(),
+      new_file: new_file.unwrap_or_default(),
+      rename_from,
+      rename_to,
+      copy_from,
+      copy_to,
+      old_hash,
+      new_hash,
+      old_timestamp,
+      new_timestamp,
+      hunks,
+      new_mode: patch.new_mode,
+      old_mode: patch.old_mode,
+      deleted_file_mode: patch.deleted_file_mode,
+      similarity: patch.similarity,
+      is_binary: patch.is_binary,
+      dissimilarity: patch.dissimilarity,
+      index_mode: patch.index_mode,
+      binary,
+    }
+  }
+}
+
+impl From<Patch<'_>> for PatchBuf {
+  fn from(patch: Patch<'_>) -> Self {
+    PatchBuf::from(&patch)
+  }
+}
+
+fn owned(text: Cow<'_, str>) -> Cow<'static, str> {
+  Cow::Owned(text.into_owned())
+}
+
+impl Line<'_> {
+  pub fn into_owned(self) -> Line<'static> {
+    match self {
+      Line::Addition(text) => Line::Addition(owned(text)),
+      Line::Deletion(text) => Line::Deletion(owned(text)),
+      Line::Context(text) => Line::Context(owned(text)),
+      Line::NoNewline => Line::NoNewline,
+    }
+  }
+}
+
+impl Hunk<'_> {
+  pub fn into_owned(self) -> Hunk<'static> {
+    Hunk {
+      old_line: self.old_line,
+      old_span: self.old_span,
+      new_line: self.new_line,
+      new_span: self.new_span,
+      heading: self.heading.map(owned),
+      lines: self.lines.into_iter().map(Line::into_owned).collect(),
+    }
+  }
+}
+
+impl BinaryHunk<'_> {
+  pub fn into_owned(self) -> BinaryHunk<'static> {
+    BinaryHunk {
+      method: self.method,
+      size: self.size,
+      data: owned(self.data),
+    }
+  }
+}
+
+impl<'a> Patch<'a> {
+  pub fn to_buf(&self) -> PatchBuf {
+    PatchBuf::from(self)
+  }
+
+  pub fn into_owned(self) -> Patch<'static> {
+    Patch {
+      old_file: owned(self.old_file),
+      new_file: owned(self.new_file),
+      hunks: self.hunks.into_iter().map(Hunk::into_owned).collect(),
+      rename_from: self.rename_from.map(owned),
+      rename_to: self.rename_to.map(owned),
+      new_mode: self.new_mode,
+      old_mode: self.old_mode,
+      deleted_file_mode: self.deleted_file_mode,
+      similarity: self.similarity,
+      is_binary: self.is_binary,
+      copy_from: self.copy_from.map(owned),
+      copy_to: self.copy_to.map(owned),
+      dissimilarity: self.dissimilarity,
+      index_mode: self.index_mode,
+      binary: self.binary.map(|binary| BinaryPatch {
+        forward: binary.forward.into_owned(),
+        reverse: binary.reverse.map(BinaryHunk::into_owned),
+      }),
+      old_hash: self.old_hash.map(owned),
+      new_hash: self.new_hash.map(owned),
+      old_timestamp: self.old_timestamp.map(owned),
+      new_timestamp: self.new_timestamp.map(owned),
+    }
+  }
+}
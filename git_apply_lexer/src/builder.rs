@@ -0,0 +1,175 @@
+use crate::error::Error;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+
+const DEV_NULL: &str = "/dev/null";
+const DEFAULT_MODE: u32 = 0o100644;
+
+#[derive(Debug)]
+pub struct PatchBuilder<'a> {
+  patch: Patch<'a>,
+  hunks: Vec<(u32, Vec<Line<'a>>)>,
+}
+
+impl<'a> PatchBuilder<'a> {
+  pub fn modify(path: impl Into<Cow<'a, str>>) -> Self {
+    let path = path.into();
+    Self::with_files(path.clone(), path)
+  }
+
+  pub fn create(path: impl Into<Cow<'a, str>>) -> Self {
+    let mut builder = Self::with_files(DEV_NULL.into(), path.into());
+    builder.patch.new_mode = Some(DEFAULT_MODE);
+    builder
+  }
+
+  pub fn remove(path: impl Into<Cow<'a, str>>) -> Self {
+    let mut builder = Self::with_files(path.into(), DEV_NULL.into());
+    builder.patch.deleted_file_mode = Some(DEFAULT_MODE);
+    builder
+  }
+
+  pub fn rename(
+    from: impl Into<Cow<'a, str>>,
+    to: impl Into<Cow<'a, str>>,
+  ) -> Self {
+    let (from, to) = (from.into(), to.into());
+    let mut builder = Self::with_files(from.clone(), to.clone());
+    builder.patch.rename_from = Some(from);
+    builder.patch.rename_to = Some(to);
+    builder
+  }
+
+  pub fn copy(
+    from: impl Into<Cow<'a, str>>,
+    to: impl Into<Cow<'a, str>>,
+  ) -> Self {
+    let (from, to) = (from.into(), to.into());
+    let mut builder = Self::with_files(from.clone(), to.clone());
+    builder.patch.copy_from = Some(from);
+    builder.patch.copy_to = Some(to);
+    builder
+  }
+
+  fn with_files(old_file: Cow<'a, str>, new_file: Cow<'a, str>) -> Self {
+    Self {
+      patch: Patch {
+        old_file,
+        new_file,
+        ..Default::default()
+      },
+      hunks: Vec::new(),
+    }
+  }
+
+  pub fn mode(mut self, old_mode: u32, new_mode: u32) -> Self {
+    self.patch.old_mode = Some(old_mode);
+    self.patch.new_mode = Some(new_mode);
+    self
+  }
+
+  pub fn hunk_at(mut self, old_line: u32) -> Self {
+    self.hunks.push((old_line, Vec::new()));
+    self
+  }
+
+  pub fn context(self, text: impl Into<Cow<'a, str>>) -> Self {
+    self.line(Line::Context(text.into()))
+  }
+
+  pub fn delete(self, text: impl Into<Cow<'a, str>>) -> Self {
+    self.line(Line::Deletion(text.into()))
+  }
+
+  #[allow(clippy::should_implement_trait)]
+  pub fn add(self, text: impl Into<Cow<'a, str>>) -> Self {
+    self.line(Line::Addition(text.into()))
+  }
+
+  pub fn no_newline(self) -> Self {
+    self.line(Line::NoNewline)
+  }
+
+  fn line(mut self, line: Line<'a>) -> Self {
+    if self.hunks.is_empty() {
+      self.hunks.push((1, Vec::new()));
+    }
+    if let Some((_, lines)) = self.hunks.last_mut() {
+      lines.push(line);
+    }
+    self
+  }
+
+  pub fn build(self) -> Result<Patch<'a>, Error> {
+    let Self { mut patch, hunks } = self;
+    let is_creation = patch.old_file == DEV_NULL;
+    let is_deletion = patch.new_file == DEV_NULL;
+
+    let mut next_free_line = 1;
+    let mut offset: i64 = 0;
+    for (index, (start, lines)) in hunks.into_iter().enumerate() {
+      let invalid = |reason: &str| {
+        Err(Error::Invalid(
+          format!("Hunk {} {}", index + 1, reason).into(),
+        ))
+      };
+
+      if start == 0 {
+        return invalid("starts at line 0; lines are 1-based");
+      }
+      if start < next_free_line {
+        return invalid("overlaps or precedes the previous hunk");
+      }
+      if lines.iter().all(|line| matches!(line, Line::NoNewline)) {
+        return invalid("has no lines");
+      }
+      if is_creation
+        && lines
+          .iter()
+          .any(|line| matches!(line, Line::Context(_) | Line::Deletion(_)))
+      {
+        return invalid("removes or keeps lines of a file being created");
+      }
+      if is_deletion
+        && lines
+          .iter()
+          .any(|line| matches!(line, Line::Context(_) | Line::Addition(_)))
+      {
+        return invalid("keeps or adds lines to a file being deleted");
+      }
+
+      let (old_span, new_span) =
+        lines.iter().fold((0, 0), |(old, new), line| match line {
+          Line::Context(_) => (old + 1, new + 1),
+          Line::Deletion(_) => (old + 1, new),
+          Line::Addition(_) => (old, new + 1),
+          Line::NoNewline => (old, new),
+        });
+
+      let old_line = if old_span == 0 { start - 1 } else { start };
+      let new_start = start as i64 + offset;
+      let new_line = if new_span == 0 {
+        new_start - 1
+      } else {
+        new_start
+      };
+
+      next_free_line = start + old_span;
+      offset += new_span as i64 - old_span as i64;
+      patch.hunks.push(Hunk {
+        old_line,
+        old_span,
+        new_line: new_line.max(0) as u32,
+        new_span,
+        heading: None,
+        lines,
+      });
+    }
+
+    Ok(patch)
+  }
+}
@@ -0,0 +1,41 @@
+use crate::buf::PatchBuf;
+use crate::error::Error;
+use crate::parser::Parser;
+
+const MAGIC: &[u8] = b"HITC";
+const VERSION: u8 = 5;
+
+pub fn encode(patches: &[PatchBuf]) -> Result<Vec<u8>, Error> {
+  let bytes = [MAGIC, &[VERSION]].concat();
+  postcard::to_extend(patches, bytes)
+    .map_err(|e| Error::Cache(e.to_string().into()))
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<PatchBuf>, Error> {
+  let payload = bytes
+    .strip_prefix(MAGIC)
+    .ok_or(Error::Cache("missing header".into()))?;
+  let payload = match payload.split_first() {
+    Some((&VERSION, payload)) => payload,
+    Some((version, _)) => {
+      return Err(Error::Cache(
+        format!("unsupported version {}", version).into(),
+      ));
+    }
+    None => return Err(Error::Cache("missing version".into())),
+  };
+
+  let patches: Vec<PatchBuf> = postcard::from_bytes(payload)
+    .map_err(|e| Error::Cache(e.to_string().into()))?;
+  if !patches.iter().all(PatchBuf::is_well_formed) {
+    return Err(Error::Cache("ranges outside of the text arena".into()));
+  }
+  Ok(patches)
+}
+
+pub fn compile(patch_content: &str) -> Result<Vec<u8>, Error> {
+  let patches = Parser::new(patch_content)
+    .map(|patch| patch.map(|patch| patch.to_buf()))
+    .collect::<Result<Vec<_>, Error>>()?;
+  encode(&patches)
+}
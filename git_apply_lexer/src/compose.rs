@@ -0,0 +1,344 @@
+use crate::error::Error;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const DEV_NULL: &str = "/dev/null";
+
+#[derive(Debug, Default)]
+struct Slot<'a> {
+  before: Vec<Cow<'a, str>>,
+  line: Option<(bool, Cow<'a, str>)>,
+  start: u32,
+  hunk: usize,
+}
+
+fn first_line(line: u32, span: u32) -> u32 {
+  if span == 0 { line + 1 } else { line }
+}
+
+fn slots<'a>(patch: &Patch<'a>, forward: bool) -> BTreeMap<u32, Slot<'a>> {
+  let mut slots: BTreeMap<u32, Slot<'a>> = BTreeMap::new();
+  for (index, hunk) in patch.hunks.iter().enumerate() {
+    let mut old = first_line(hunk.old_line, hunk.old_span);
+    let mut new = first_line(hunk.new_line, hunk.new_span);
+    for line in &hunk.lines {
+      let (position, start) = if forward { (new, old) } else { (old, new) };
+      let slot = slots.entry(position).or_insert_with(|| Slot {
+        start,
+        hunk: index,
+        ..Default::default()
+      });
+      match (line.clone(), forward) {
+        (Line::Context(text), _) => {
+          slot.line = Some((true, text));
+          old += 1;
+          new += 1;
+        }
+        (Line::Deletion(text), true) => {
+          slot.before.push(text);
+          old += 1;
+        }
+        (Line::Addition(text), true) => {
+          slot.line = Some((false, text));
+          new += 1;
+        }
+        (Line::Deletion(text), false) => {
+          slot.line = Some((false, text));
+          old += 1;
+        }
+        (Line::Addition(text), false) => {
+          slot.before.push(text);
+          new += 1;
+        }
+        (Line::NoNewline, _) => {}
+      }
+    }
+  }
+  slots
+}
+
+fn has_marker(hunk: &Hunk) -> bool {
+  hunk
+    .lines
+    .iter()
+    .any(|line| matches!(line, Line::NoNewline))
+}
+
+fn shifted<'a>(hunk: &Hunk<'a>, old_delta: i64, new_delta: i64) -> Hunk<'a> {
+  Hunk {
+    old_line: (hunk.old_line as i64 + old_delta).max(0) as u32,
+    new_line: (hunk.new_line as i64 + new_delta).max(0) as u32,
+    ..hunk.clone()
+  }
+}
+
+fn flush_run<'a>(run: &mut Vec<Line<'a>>, result: &mut Vec<Line<'a>>) {
+  let deleted = run.iter().filter_map(|line| match line {
+    Line::Deletion(text) => Some(text),
+    _ => None,
+  });
+  let added = run.iter().filter_map(|line| match line {
+    Line::Addition(text) => Some(text),
+    _ => None,
+  });
+  if deleted.clone().eq(added) {
+    let context: Vec<_> = deleted.cloned().map(Line::Context).collect();
+    result.extend(context);
+    run.clear();
+  } else {
+    result.append(run);
+  }
+}
+
+pub(crate) fn cancel_reverted(lines: Vec<Line<'_>>) -> Vec<Line<'_>> {
+  let mut result = Vec::with_capacity(lines.len());
+  let mut run = Vec::new();
+  for line in lines {
+    match line {
+      Line::Deletion(_) | Line::Addition(_) => run.push(line),
+      _ => {
+        flush_run(&mut run, &mut result);
+        result.push(line);
+      }
+    }
+  }
+  flush_run(&mut run, &mut result);
+  result
+}
+
+impl<'a> Patch<'a> {
+  pub fn then(&self, next: &Patch<'a>) -> Result<Patch<'a>, Error> {
+    if self.new_file != next.old_file {
+      return Err(Error::Invalid(
+        format!(
+          "Cannot compose `{}` with a patch to `{}`",
+          self.new_file, next.old_file
+        )
+        .into(),
+      ));
+    }
+    if self.is_binary || next.is_binary {
+      return Err(Error::Unsupported(
+        format!("Binary files cannot be composed: `{}`", self.new_file).into(),
+      ));
+    }
+
+    let mut patch = Patch {
+      old_file: self.old_file.clone(),
+      new_file: next.new_file.clone(),
+      old_mode: self.old_mode.or(next.old_mode),
+      new_mode: next.new_mode.or(self.new_mode),
+      deleted_file_mode: next.deleted_file_mode,
+      index_mode: next.index_mode.or(self.index_mode),
+      hunks: self.compose_hunks(next)?,
+      ..Default::default()
+    };
+    if patch.old_file == DEV_NULL {
+      patch.old_mode = None;
+    } else if patch.old_mode == patch.new_mode {
+      patch.old_mode = None;
+      patch.new_mode = None;
+    }
+    if patch.old_file != DEV_NULL
+      && patch.new_file != DEV_NULL
+      && patch.old_file != patch.new_file
+    {
+      if self.copy_from.is_some() {
+        patch.copy_from = Some(patch.old_file.clone());
+        patch.copy_to = Some(patch.new_file.clone());
+      } else {
+        patch.rename_from = Some(patch.old_file.clone());
+        patch.rename_to = Some(patch.new_file.clone());
+      }
+    }
+
+    Ok(patch)
+  }
+
+  fn compose_hunks(&self, next: &Patch<'a>) -> Result<Vec<Hunk<'a>>, Error> {
+    let first = slots(self, true);
+    let second = slots(next, false);
+    let first_map = self.line_map();
+    let second_map = next.line_map();
+
+    let positions: BTreeSet<u32> =
+      first.keys().chain(second.keys()).copied().collect();
+    let mut regions: Vec<Vec<u32>> = Vec::new();
+    let mut previous: Option<u32> = None;
+    for position in positions {
+      let closed = previous.is_none_or(|previous| {
+        position != previous + 1
+          || (first.get(&previous).is_none_or(|slot| slot.line.is_none())
+            && second.get(&previous).is_none_or(|slot| slot.line.is_none()))
+      });
+      if closed {
+        regions.push(vec![position]);
+      } else if let Some(region) = regions.last_mut() {
+        region.push(position);
+      }
+      previous = Some(position);
+    }
+
+    let mut hunks = Vec::new();
+    for region in regions {
+      let start = region[0];
+      let first_hunks: BTreeSet<usize> = region
+        .iter()
+        .filter_map(|position| first.get(position).map(|slot| slot.hunk))
+        .collect();
+      let second_hunks: BTreeSet<usize> = region
+        .iter()
+        .filter_map(|position| second.get(position).map(|slot| slot.hunk))
+        .collect();
+
+      if second_hunks.is_empty() || first_hunks.is_empty() {
+        let (old_delta, new_delta) = if second_hunks.is_empty() {
+          let mapped = second_map.old_to_new(start).unwrap_or(start);
+          (0, mapped as i64 - start as i64)
+        } else {
+          let mapped = first_map.new_to_old(start).unwrap_or(start);
+          (mapped as i64 - start as i64, 0)
+        };
+        let (source, indices) = if second_hunks.is_empty() {
+          (self, first_hunks)
+        } else {
+          (next, second_hunks)
+        };
+        hunks.extend(
+          indices
+            .into_iter()
+            .map(|index| shifted(&source.hunks[index], old_delta, new_delta)),
+        );
+        continue;
+      }
+
+      if first_hunks
+        .iter()
+        .any(|&index| has_marker(&self.hunks[index]))
+        || second_hunks
+          .iter()
+          .any(|&index| has_marker(&next.hunks[index]))
+      {
+        return Err(Error::Unsupported(
+          format!(
+            "Cannot compose overlapping hunks without trailing newline: `{}`",
+            self.new_file
+          )
+          .into(),
+        ));
+      }
+
+      let old_start = match first.get(&start) {
+        Some(slot) => slot.start,
+        None => first_map.new_to_old(start).unwrap_or(start),
+      };
+      let new_start = match second.get(&start) {
+        Some(slot) => slot.start,
+        None => second_map.old_to_new(start).unwrap_or(start),
+      };
+
+      let mut lines = Vec::new();
+      for position in &region {
+        let first_slot = first.get(position);
+        let second_slot = second.get(position);
+        if let Some(slot) = first_slot {
+          lines.extend(slot.before.iter().cloned().map(Line::Deletion));
+        }
+        if let Some(slot) = second_slot {
+          lines.extend(slot.before.iter().cloned().map(Line::Addition));
+        }
+
+        let first_line = first_slot.and_then(|slot| slot.line.as_ref());
+        let second_line = second_slot.and_then(|slot| slot.line.as_ref());
+        let (original, text) = match (first_line, second_line) {
+          (Some((original, text)), _) => (*original, text.clone()),
+          (None, Some((_, text))) => (true, text.clone()),
+          (None, None) => continue,
+        };
+        let kept = second_line.is_none_or(|(kept, _)| *kept);
+        match (original, kept) {
+          (true, true) => lines.push(Line::Context(text)),
+          (true, false) => lines.push(Line::Deletion(text)),
+          (false, true) => lines.push(Line::Addition(text)),
+          (false, false) => {}
+        }
+      }
+
+      let lines = cancel_reverted(lines);
+      if lines.iter().all(|line| matches!(line, Line::Context(_))) {
+        continue;
+      }
+
+      let (old_span, new_span) =
+        lines.iter().fold((0, 0), |(old, new), line| match line {
+          Line::Context(_) => (old + 1, new + 1),
+          Line::Deletion(_) => (old + 1, new),
+          Line::Addition(_) => (old, new + 1),
+          Line::NoNewline => (old, new),
+        });
+      hunks.push(Hunk {
+        old_line: if old_span == 0 {
+          old_start - 1
+        } else {
+          old_start
+        },
+        old_span,
+        new_line: if new_span == 0 {
+          new_start - 1
+        } else {
+          new_start
+        },
+        new_span,
+        heading: None,
+        lines,
+      });
+    }
+
+    Ok(hunks)
+  }
+}
+
+pub fn compose<'a>(
+  first: &[Patch<'a>],
+  second: &[Patch<'a>],
+) -> Result<Vec<Patch<'a>>, Error> {
+  let mut used = vec![false; second.len()];
+  let mut patches = Vec::new();
+
+  for patch in first {
+    let next = (patch.new_file != DEV_NULL)
+      .then(|| {
+        second.iter().enumerate().position(|(index, next)| {
+          !used[index] && next.old_file == patch.new_file
+        })
+      })
+      .flatten();
+
+    match next {
+      Some(index) => {
+        used[index] = true;
+        let composed = patch.then(&second[index])?;
+        if composed.old_file != DEV_NULL || composed.new_file != DEV_NULL {
+          patches.push(composed);
+        }
+      }
+      None => patches.push(patch.clone()),
+    }
+  }
+
+  patches.extend(
+    second
+      .iter()
+      .zip(used)
+      .filter(|(_, used)| !used)
+      .map(|(patch, _)| patch.clone()),
+  );
+  Ok(patches)
+}
@@ -0,0 +1,78 @@
+use crate::parser::Hunk;
+use crate::parser::Patch;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+const DEV_NULL: &str = "/dev/null";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictRegion {
+  pub file: String,
+  pub lines: Range<u32>,
+  pub hunks: Option<(usize, usize)>,
+}
+
+fn old_range(hunk: &Hunk) -> Range<u32> {
+  if hunk.old_span == 0 {
+    hunk.old_line + 1..hunk.old_line + 1
+  } else {
+    hunk.old_line..hunk.old_line + hunk.old_span
+  }
+}
+
+fn overlaps(a: &Range<u32>, b: &Range<u32>) -> bool {
+  match (a.is_empty(), b.is_empty()) {
+    (false, false) => a.start < b.end && b.start < a.end,
+    (true, false) => b.start < a.start && a.start < b.end,
+    (false, true) => a.start < b.start && b.start < a.end,
+    (true, true) => a.start == b.start,
+  }
+}
+
+pub fn conflicts(a: &Patch, b: &Patch) -> Vec<ConflictRegion> {
+  let whole_file = |file: &str| {
+    vec![ConflictRegion {
+      file: file.to_string(),
+      lines: 0..0,
+      hunks: None,
+    }]
+  };
+
+  if a.old_file == DEV_NULL || b.old_file == DEV_NULL {
+    return if a.old_file == b.old_file && a.new_file == b.new_file {
+      whole_file(&a.new_file)
+    } else {
+      Vec::new()
+    };
+  }
+  if a.old_file != b.old_file {
+    return Vec::new();
+  }
+  if a.new_file == DEV_NULL
+    || b.new_file == DEV_NULL
+    || a.new_file != b.new_file
+    || a.is_binary
+    || b.is_binary
+  {
+    return whole_file(&a.old_file);
+  }
+
+  let mut regions = Vec::new();
+  for (a_index, a_hunk) in a.hunks.iter().enumerate() {
+    let a_range = old_range(a_hunk);
+    for (b_index, b_hunk) in b.hunks.iter().enumerate() {
+      let b_range = old_range(b_hunk);
+      if overlaps(&a_range, &b_range) {
+        regions.push(ConflictRegion {
+          file: a.old_file.to_string(),
+          lines: a_range.start.min(b_range.start)..a_range.end.max(b_range.end),
+          hunks: Some((a_index, b_index)),
+        });
+      }
+    }
+  }
+  regions
+}
@@ -0,0 +1,192 @@
+use crate::applier;
+use crate::error::Error;
+use crate::fs::OsFileSystem;
+use crate::options::ApplyOptions;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+const BEFORE: &str = "before";
+const PATCH: &str = "patch.diff";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+  Outcome {
+    git: Option<String>,
+    hit: Option<String>,
+  },
+  OnlyGit {
+    path: PathBuf,
+  },
+  OnlyHit {
+    path: PathBuf,
+  },
+  Content {
+    path: PathBuf,
+  },
+}
+
+impl fmt::Display for Divergence {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Outcome { git, hit } => write!(
+        f,
+        "git apply {} but hit {}",
+        git
+          .as_deref()
+          .map_or("succeeded".into(), |e| format!("failed: {}", e)),
+        hit
+          .as_deref()
+          .map_or("succeeded".into(), |e| format!("failed: {}", e))
+      ),
+      Self::OnlyGit { path } => {
+        write!(f, "{}: only produced by git apply", path.display())
+      }
+      Self::OnlyHit { path } => {
+        write!(f, "{}: only produced by hit", path.display())
+      }
+      Self::Content { path } => {
+        write!(f, "{}: contents differ", path.display())
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseReport {
+  pub name: String,
+  pub divergences: Vec<Divergence>,
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<(), Error> {
+  fs::create_dir_all(to).map_err(|e| Error::io_at(e, to))?;
+  for entry in fs::read_dir(from).map_err(|e| Error::io_at(e, from))? {
+    let entry = entry?;
+    let target = to.join(entry.file_name());
+    if entry.file_type()?.is_dir() {
+      copy_tree(&entry.path(), &target)?;
+    } else {
+      fs::copy(entry.path(), &target).map_err(|e| Error::io_at(e, &target))?;
+    }
+  }
+  Ok(())
+}
+
+fn snapshot(
+  root: &Path,
+  dir: &Path,
+  files: &mut BTreeMap<PathBuf, Vec<u8>>,
+) -> Result<(), Error> {
+  for entry in fs::read_dir(dir).map_err(|e| Error::io_at(e, dir))? {
+    let entry = entry?;
+    let path = entry.path();
+    if entry.file_type()?.is_dir() {
+      snapshot(root, &path, files)?;
+    } else {
+      let contents = fs::read(&path).map_err(|e| Error::io_at(e, &path))?;
+      let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+      files.insert(relative, contents);
+    }
+  }
+  Ok(())
+}
+
+fn tree(root: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, Error> {
+  let mut files = BTreeMap::new();
+  snapshot(root, root, &mut files)?;
+  Ok(files)
+}
+
+fn git_apply(dir: &Path, patch: &Path) -> Result<Option<String>, Error> {
+  let ceiling = dir.parent().unwrap_or(dir);
+  let output = Command::new("git")
+    .arg("apply")
+    .arg(patch)
+    .current_dir(dir)
+    .env("GIT_CEILING_DIRECTORIES", ceiling)
+    .output()?;
+  Ok(
+    (!output.status.success())
+      .then(|| String::from_utf8_lossy(&output.stderr).trim().to_string()),
+  )
+}
+
+pub fn run_case(case: &Path, work: &Path) -> Result<CaseReport, Error> {
+  let name = case
+    .file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  let patch_path = case.join(PATCH);
+  let patch = fs::read_to_string(&patch_path)
+    .map_err(|e| Error::io_at(e, &patch_path))?;
+  let patch_path = fs::canonicalize(&patch_path)?;
+
+  let work = work.join(&name);
+  let (git_dir, hit_dir) = (work.join("git"), work.join("hit"));
+  if work.exists() {
+    fs::remove_dir_all(&work).map_err(|e| Error::io_at(e, &work))?;
+  }
+  let before = case.join(BEFORE);
+  for dir in [&git_dir, &hit_dir] {
+    if before.is_dir() {
+      copy_tree(&before, dir)?;
+    } else {
+      fs::create_dir_all(dir).map_err(|e| Error::io_at(e, dir))?;
+    }
+  }
+
+  let git = git_apply(&git_dir, &patch_path)?;
+  let hit = applier::patch(
+    &mut OsFileSystem,
+    &patch,
+    &ApplyOptions::new().root(&hit_dir),
+    &mut (),
+  )
+  .err()
+  .map(|e| e.to_string());
+
+  let mut divergences = Vec::new();
+  match (&git, &hit) {
+    (None, None) => {
+      let (git_tree, hit_tree) = (tree(&git_dir)?, tree(&hit_dir)?);
+      for (path, contents) in &git_tree {
+        match hit_tree.get(path) {
+          None => divergences.push(Divergence::OnlyGit { path: path.clone() }),
+          Some(other) if other != contents => {
+            divergences.push(Divergence::Content { path: path.clone() })
+          }
+          Some(_) => {}
+        }
+      }
+      divergences.extend(
+        hit_tree
+          .keys()
+          .filter(|path| !git_tree.contains_key(*path))
+          .map(|path| Divergence::OnlyHit { path: path.clone() }),
+      );
+    }
+    (Some(_), Some(_)) => {}
+    _ => divergences.push(Divergence::Outcome { git, hit }),
+  }
+
+  Ok(CaseReport { name, divergences })
+}
+
+pub fn run_corpus(
+  corpus: &Path,
+  work: &Path,
+) -> Result<Vec<CaseReport>, Error> {
+  let mut cases = Vec::new();
+  for entry in fs::read_dir(corpus).map_err(|e| Error::io_at(e, corpus))? {
+    let entry = entry?;
+    if entry.file_type()?.is_dir() && entry.path().join(PATCH).is_file() {
+      cases.push(entry.path());
+    }
+  }
+  cases.sort();
+
+  cases.iter().map(|case| run_case(case, work)).collect()
+}
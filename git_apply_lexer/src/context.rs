@@ -0,0 +1,233 @@
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use core::str::Lines;
+
+const HUNK_SEPARATOR: &str = "***************";
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<'a> {
+  marker: u8,
+  text: &'a str,
+  no_newline: bool,
+}
+
+pub struct ContextParser<'a> {
+  lines: Peekable<Lines<'a>>,
+  strip: usize,
+}
+
+pub fn is_context_diff(source: &str) -> bool {
+  source.lines().any(|line| line.starts_with(HUNK_SEPARATOR))
+}
+
+fn malformed(line: &str) -> Error {
+  Error::Parse(format!("Malformed context diff line: `{}`", line).into())
+}
+
+fn parse_range(line: &str, marker: &str) -> Option<(u32, u32)> {
+  let range = line
+    .strip_prefix(marker.repeat(3).as_str())?
+    .strip_prefix(' ')?
+    .strip_suffix(marker.repeat(4).as_str())?
+    .trim_end();
+  let (start, end) = match range.split_once(',') {
+    Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+    None => {
+      let line = range.parse().ok()?;
+      (line, line)
+    }
+  };
+  Some((start, end))
+}
+
+fn push_entry<'a>(lines: &mut Vec<Line<'a>>, line: Line<'a>, entry: Entry) {
+  lines.push(line);
+  if entry.no_newline {
+    lines.push(Line::NoNewline);
+  }
+}
+
+fn merge<'a>(
+  old: &[Entry<'a>],
+  new: &[Entry<'a>],
+) -> Result<Vec<Line<'a>>, Error> {
+  let mut lines = Vec::with_capacity(old.len() + new.len());
+  if old.is_empty() || new.is_empty() {
+    for &entry in old.iter().chain(new) {
+      let text = Cow::Borrowed(entry.text);
+      let line = match entry.marker {
+        b'-' => Line::Deletion(text),
+        b'+' => Line::Addition(text),
+        b' ' => Line::Context(text),
+        _ => return Err(malformed(entry.text)),
+      };
+      push_entry(&mut lines, line, entry);
+    }
+    return Ok(lines);
+  }
+
+  let (mut i, mut j) = (0, 0);
+  while i < old.len() || j < new.len() {
+    let (o, n) = (old.get(i), new.get(j));
+    if let Some(&entry) = o.filter(|entry| entry.marker == b'-') {
+      push_entry(&mut lines, Line::Deletion(entry.text.into()), entry);
+      i += 1;
+    } else if let Some(&entry) = n.filter(|entry| entry.marker == b'+') {
+      push_entry(&mut lines, Line::Addition(entry.text.into()), entry);
+      j += 1;
+    } else if o.is_some_and(|entry| entry.marker == b'!')
+      && n.is_some_and(|entry| entry.marker == b'!')
+    {
+      while let Some(&entry) = old.get(i).filter(|entry| entry.marker == b'!') {
+        push_entry(&mut lines, Line::Deletion(entry.text.into()), entry);
+        i += 1;
+      }
+      while let Some(&entry) = new.get(j).filter(|entry| entry.marker == b'!') {
+        push_entry(&mut lines, Line::Addition(entry.text.into()), entry);
+        j += 1;
+      }
+    } else if let (Some(&o), Some(&n)) = (o, n)
+      && o.marker == b' '
+      && n.marker == b' '
+    {
+      lines.push(Line::Context(o.text.into()));
+      if o.no_newline || n.no_newline {
+        lines.push(Line::NoNewline);
+      }
+      i += 1;
+      j += 1;
+    } else {
+      let entry = o.or(n).map_or("", |entry| entry.text);
+      return Err(malformed(entry));
+    }
+  }
+  Ok(lines)
+}
+
+impl<'a> ContextParser<'a> {
+  pub fn new(source: &'a str) -> Self {
+    Self {
+      lines: source.lines().peekable(),
+      strip: 0,
+    }
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = components;
+    self
+  }
+
+  fn file_line(
+    &self,
+    line: &'a str,
+    marker: &str,
+  ) -> Result<(Cow<'a, str>, Option<&'a str>), Error> {
+    let rest = line.strip_prefix(marker).ok_or_else(|| malformed(line))?;
+    Lexer::new("").strip(self.strip).file_line(rest)
+  }
+
+  fn section(
+    &mut self,
+    markers: &[u8],
+    span: u32,
+  ) -> Result<Vec<Entry<'a>>, Error> {
+    let mut entries: Vec<Entry> = Vec::new();
+    while let Some(&line) = self.lines.peek() {
+      if line.starts_with('\\') {
+        let last = entries.last_mut().ok_or_else(|| malformed(line))?;
+        last.no_newline = true;
+      } else if entries.len() < span as usize
+        && let [marker, b' ', ..] | [marker] = line.as_bytes()
+        && markers.contains(marker)
+      {
+        entries.push(Entry {
+          marker: *marker,
+          text: line.get(2..).unwrap_or(""),
+          no_newline: false,
+        });
+      } else {
+        break;
+      }
+      self.lines.next();
+    }
+    Ok(entries)
+  }
+
+  fn parse_hunk(&mut self) -> Result<Hunk<'a>, Error> {
+    let header = self.lines.next().unwrap_or_default();
+    let (old_start, old_end) =
+      parse_range(header, "*").ok_or_else(|| malformed(header))?;
+    let old = self.section(b" -!", old_end + 1 - old_start.min(old_end))?;
+
+    let header = self.lines.next().unwrap_or_default();
+    let (new_start, new_end) =
+      parse_range(header, "-").ok_or_else(|| malformed(header))?;
+    let new = self.section(b" +!", new_end + 1 - new_start.min(new_end))?;
+
+    let lines = merge(&old, &new)?;
+    let count = |kind: fn(&Line) -> bool| {
+      lines.iter().filter(|line| kind(line)).count() as u32
+    };
+    let old_span =
+      count(|line| matches!(line, Line::Context(_) | Line::Deletion(_)));
+    let new_span =
+      count(|line| matches!(line, Line::Context(_) | Line::Addition(_)));
+    Ok(Hunk {
+      old_line: if old_span == 0 { old_end } else { old_start },
+      old_span,
+      new_line: if new_span == 0 { new_end } else { new_start },
+      new_span,
+      heading: None,
+      lines,
+    })
+  }
+
+  fn parse_patch(&mut self) -> Result<Patch<'a>, Error> {
+    let header = self.lines.next().unwrap_or_default();
+    let (old_file, old_timestamp) = self.file_line(header, "*** ")?;
+    let header = self.lines.next().unwrap_or_default();
+    let (new_file, new_timestamp) = self.file_line(header, "--- ")?;
+
+    let mut hunks = Vec::new();
+    while self
+      .lines
+      .next_if(|line| line.starts_with(HUNK_SEPARATOR))
+      .is_some()
+    {
+      hunks.push(self.parse_hunk()?);
+    }
+    Ok(Patch {
+      old_file,
+      new_file,
+      hunks,
+      old_timestamp: old_timestamp.map(Cow::Borrowed),
+      new_timestamp: new_timestamp.map(Cow::Borrowed),
+      ..Default::default()
+    })
+  }
+}
+
+impl<'a> Iterator for ContextParser<'a> {
+  type Item = Result<Patch<'a>, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let line = *self.lines.peek()?;
+      let mut ahead = self.lines.clone();
+      ahead.next();
+      if line.starts_with("*** ")
+        && ahead.next().is_some_and(|next| next.starts_with("--- "))
+      {
+        return Some(self.parse_patch());
+      }
+      self.lines.next();
+    }
+  }
+}
@@ -0,0 +1,158 @@
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::lexer::Token;
+use crate::parser::Parser;
+use miette::Diagnostic;
+use miette::GraphicalReportHandler;
+use miette::GraphicalTheme;
+use miette::NamedSource;
+use miette::SourceSpan;
+use std::ops::Range;
+use thiserror::Error;
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{message}")]
+pub struct Report {
+  message: String,
+  #[source_code]
+  source_code: NamedSource<String>,
+  #[label("{label}")]
+  span: SourceSpan,
+  label: String,
+  #[related]
+  related: Vec<Report>,
+}
+
+impl Report {
+  pub fn render(&self) -> String {
+    let mut output = String::new();
+    let handler =
+      GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor());
+    let _ = handler.render_report(&mut output, self);
+    output
+  }
+}
+
+fn parse_error_span(patch_text: &str) -> Option<Range<usize>> {
+  let mut parser = Parser::new(patch_text);
+  while let Some(result) = parser.next() {
+    if result.is_err() {
+      return Some(parser.span());
+    }
+  }
+  None
+}
+
+fn hunk_span(
+  patch_text: &str,
+  file: &str,
+  hunk_index: usize,
+) -> Option<Range<usize>> {
+  let matches = |name: &str| {
+    !name.is_empty() && (file == name || file.ends_with(&format!("/{}", name)))
+  };
+
+  let mut in_file = false;
+  let mut hunks = 0;
+  for (token, span) in Lexer::new(patch_text).spanned().flatten() {
+    match token {
+      Token::FileHeader { old_file, new_file } => {
+        in_file = matches(&old_file) || matches(&new_file);
+        hunks = 0;
+      }
+      Token::OldFile { path, .. } | Token::NewFile { path, .. }
+        if matches(&path) =>
+      {
+        in_file = true;
+      }
+      Token::HunkHeader { .. } if in_file => {
+        if hunks == hunk_index {
+          return Some(span);
+        }
+        hunks += 1;
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+fn line_span(source: &str, line: usize) -> Range<usize> {
+  let mut offset = 0;
+  for (index, text) in source.split_inclusive('\n').enumerate() {
+    if index + 1 == line {
+      let text = text.strip_suffix('\n').unwrap_or(text);
+      return offset..offset + text.len();
+    }
+    offset += text.len();
+  }
+  source.len()..source.len()
+}
+
+fn patch_source(patch_text: &str) -> NamedSource<String> {
+  NamedSource::new("patch", patch_text.to_string())
+}
+
+pub fn report(
+  error: &Error,
+  patch_text: &str,
+  target: Option<&str>,
+) -> Option<Report> {
+  let (file, hunk_index, line, label) = match error {
+    Error::Parse(_) => {
+      let span = parse_error_span(patch_text)?;
+      return Some(Report {
+        message: error.to_string(),
+        source_code: patch_source(patch_text),
+        span: span.into(),
+        label: "here".to_string(),
+        related: Vec::new(),
+      });
+    }
+    Error::UnexpectedEof {
+      file,
+      hunk_index,
+      line,
+    } => (
+      file,
+      *hunk_index,
+      *line,
+      "file ends before this line".to_string(),
+    ),
+    Error::HunkMismatch {
+      file,
+      hunk_index,
+      line,
+      expected,
+      ..
+    } => (file, *hunk_index, *line, format!("expected `{}`", expected)),
+    Error::ExpectedEof {
+      file,
+      hunk_index,
+      line,
+    } => (file, *hunk_index, *line, "expected end of file".to_string()),
+    _ => return None,
+  };
+
+  let hunk = hunk_span(patch_text, file, hunk_index).map(|span| Report {
+    message: format!("while applying hunk #{}", hunk_index + 1),
+    source_code: patch_source(patch_text),
+    span: span.into(),
+    label: format!("hunk #{}", hunk_index + 1),
+    related: Vec::new(),
+  });
+
+  match target {
+    Some(target) => Some(Report {
+      message: error.to_string(),
+      source_code: NamedSource::new(file, target.to_string()),
+      span: line_span(target, line).into(),
+      label,
+      related: hunk.into_iter().collect(),
+    }),
+    None => hunk.map(|hunk| Report {
+      message: error.to_string(),
+      ..hunk
+    }),
+  }
+}
@@ -0,0 +1,269 @@
+#[cfg(feature = "std")]
+use crate::buf::PatchBuf;
+#[cfg(feature = "std")]
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::fs::FileSystem;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+const DEV_NULL: &str = "/dev/null";
+const DEFAULT_MODE: u32 = 0o100644;
+const DEFAULT_CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+  Equal,
+  Delete,
+  Insert,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Differ {
+  context: usize,
+}
+
+impl Default for Differ {
+  fn default() -> Self {
+    Self {
+      context: DEFAULT_CONTEXT,
+    }
+  }
+}
+
+fn myers(old: &[&str], new: &[&str]) -> Vec<Op> {
+  let (n, m) = (old.len() as isize, new.len() as isize);
+  let max = n + m;
+  let mut v = vec![0isize; 2 * max as usize + 3];
+  let at = |k: isize| (k + max + 1) as usize;
+
+  let mut trace: Vec<Vec<isize>> = Vec::new();
+  'search: for d in 0..=max {
+    for k in (-d..=d).step_by(2) {
+      let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+        v[at(k + 1)]
+      } else {
+        v[at(k - 1)] + 1
+      };
+      let mut y = x - k;
+      while x < n && y < m && old[x as usize] == new[y as usize] {
+        x += 1;
+        y += 1;
+      }
+      v[at(k)] = x;
+      if x >= n && y >= m {
+        trace.push(v[at(-d)..=at(d)].to_vec());
+        break 'search;
+      }
+    }
+    trace.push(v[at(-d)..=at(d)].to_vec());
+  }
+
+  let mut ops = Vec::with_capacity((n + m) as usize);
+  let (mut x, mut y) = (n, m);
+  for d in (1..trace.len() as isize).rev() {
+    let previous = &trace[d as usize - 1];
+    let reached = |k: isize| previous[(k + d - 1) as usize];
+    let k = x - y;
+    let previous_k = if k == -d || (k != d && reached(k - 1) < reached(k + 1)) {
+      k + 1
+    } else {
+      k - 1
+    };
+    let previous_x = reached(previous_k);
+    let previous_y = previous_x - previous_k;
+    while x > previous_x && y > previous_y {
+      ops.push(Op::Equal);
+      x -= 1;
+      y -= 1;
+    }
+    if x == previous_x {
+      ops.push(Op::Insert);
+      y -= 1;
+    } else {
+      ops.push(Op::Delete);
+      x -= 1;
+    }
+  }
+  ops.extend(iter::repeat_n(Op::Equal, x as usize));
+  ops.reverse();
+  ops
+}
+
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Op> {
+  let prefix = old
+    .iter()
+    .zip(new)
+    .take_while(|(old, new)| old == new)
+    .count();
+  let suffix = old[prefix..]
+    .iter()
+    .rev()
+    .zip(new[prefix..].iter().rev())
+    .take_while(|(old, new)| old == new)
+    .count();
+
+  let mut ops = vec![Op::Equal; prefix];
+  ops.extend(myers(
+    &old[prefix..old.len() - suffix],
+    &new[prefix..new.len() - suffix],
+  ));
+  ops.extend(iter::repeat_n(Op::Equal, suffix));
+  ops
+}
+
+fn push_line<'a>(lines: &mut Vec<Line<'a>>, op: Op, text: &'a str) {
+  let (text, no_newline) = match text.strip_suffix('\n') {
+    Some(text) => (text, false),
+    None => (text, true),
+  };
+  lines.push(match op {
+    Op::Equal => Line::Context(text.into()),
+    Op::Delete => Line::Deletion(text.into()),
+    Op::Insert => Line::Addition(text.into()),
+  });
+  if no_newline {
+    lines.push(Line::NoNewline);
+  }
+}
+
+impl Differ {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn context(mut self, context: usize) -> Self {
+    self.context = context;
+    self
+  }
+
+  pub fn diff<'a>(
+    &self,
+    old_file: &'a str,
+    new_file: &'a str,
+    old: &'a str,
+    new: &'a str,
+  ) -> Patch<'a> {
+    let mut patch = Patch {
+      old_file: old_file.into(),
+      new_file: new_file.into(),
+      hunks: self.hunks(old, new),
+      ..Default::default()
+    };
+    if old_file == DEV_NULL {
+      patch.new_mode = Some(DEFAULT_MODE);
+    } else if new_file == DEV_NULL {
+      patch.deleted_file_mode = Some(DEFAULT_MODE);
+    }
+    patch
+  }
+
+  pub fn unified(
+    &self,
+    old_file: &str,
+    new_file: &str,
+    old: &str,
+    new: &str,
+  ) -> String {
+    self.diff(old_file, new_file, old, new).to_string()
+  }
+
+  #[cfg(feature = "std")]
+  pub fn diff_files(
+    &self,
+    fs: &impl FileSystem,
+    old: &Path,
+    new: &Path,
+  ) -> Result<PatchBuf, Error> {
+    let read = |path: &Path| match fs.read_to_string(path) {
+      Ok(content) => Ok(Some(content)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(Error::io_at(e, path)),
+    };
+    let name = |path: &Path, content: &Option<String>| match content {
+      Some(_) => path.to_string_lossy().replace('\\', "/"),
+      None => String::from(DEV_NULL),
+    };
+
+    let (old_content, new_content) = (read(old)?, read(new)?);
+    let (old_name, new_name) =
+      (name(old, &old_content), name(new, &new_content));
+    let patch = self.diff(
+      &old_name,
+      &new_name,
+      old_content.as_deref().unwrap_or_default(),
+      new_content.as_deref().unwrap_or_default(),
+    );
+    Ok(patch.to_buf())
+  }
+
+  fn hunks<'a>(&self, old: &'a str, new: &'a str) -> Vec<Hunk<'a>> {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_lines(&old, &new);
+
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_index, mut new_index) = (0, 0);
+    for op in &ops {
+      positions.push((old_index, new_index));
+      match op {
+        Op::Equal => (old_index, new_index) = (old_index + 1, new_index + 1),
+        Op::Delete => old_index += 1,
+        Op::Insert => new_index += 1,
+      }
+    }
+    positions.push((old_index, new_index));
+
+    let is_change = |index: &usize| ops[*index] != Op::Equal;
+    let mut hunks = Vec::new();
+    let mut cursor = 0;
+    while let Some(start) = (cursor..ops.len()).find(is_change) {
+      let mut end = start;
+      loop {
+        end += ops[end..].iter().take_while(|op| **op != Op::Equal).count();
+        match (end..ops.len()).find(is_change) {
+          Some(next) if next - end <= 2 * self.context => end = next,
+          _ => break,
+        }
+      }
+
+      let from = start.saturating_sub(self.context).max(cursor);
+      let to = (end + self.context).min(ops.len());
+      let mut lines = Vec::new();
+      for (&op, &(old_index, new_index)) in
+        ops[from..to].iter().zip(&positions[from..])
+      {
+        let text = match op {
+          Op::Equal | Op::Delete => old[old_index],
+          Op::Insert => new[new_index],
+        };
+        push_line(&mut lines, op, text);
+      }
+
+      let (old_start, new_start) = positions[from];
+      let (old_end, new_end) = positions[to];
+      let (old_span, new_span) =
+        ((old_end - old_start) as u32, (new_end - new_start) as u32);
+      hunks.push(Hunk {
+        old_line: old_start as u32 + (old_span > 0) as u32,
+        old_span,
+        new_line: new_start as u32 + (new_span > 0) as u32,
+        new_span,
+        heading: None,
+        lines,
+      });
+      cursor = to;
+    }
+    hunks
+  }
+}
@@ -1,27 +1,88 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum Error {
   #[error("Command-line argument error: {0}")]
   Clap(String),
+  #[cfg(feature = "std")]
   #[error("I/O error: {0}")]
   Io(io::ErrorKind, String),
   #[error("Failed to parse patch: {0}")]
   Parse(Cow<'static, str>),
-  #[error("Failed to apply patch: {0}")]
-  Apply(String),
+  #[error(
+    "Failed to apply patch: {file}: hunk #{}: Unexpected EOF while seeking to line {line}",
+    hunk_index + 1
+  )]
+  UnexpectedEof {
+    file: String,
+    hunk_index: usize,
+    line: usize,
+  },
+  #[error(
+    "Failed to apply patch: {file}: hunk #{}: Patch mismatch at line {line}. Expected: `{}`, Found: `{}`",
+    hunk_index + 1,
+    expected.replace('\r', "\\r"),
+    found.as_deref().unwrap_or("<EOF>").replace('\r', "\\r")
+  )]
+  HunkMismatch {
+    file: String,
+    hunk_index: usize,
+    line: usize,
+    expected: String,
+    found: Option<String>,
+  },
+  #[error(
+    "Failed to apply patch: {file}: hunk #{}: Patch mismatch at line {line}. Expected end of file, Found: ``",
+    hunk_index + 1
+  )]
+  ExpectedEof {
+    file: String,
+    hunk_index: usize,
+    line: usize,
+  },
+  #[error(
+    "Failed to apply patch: {path}: Patch appears to be reversed or already applied"
+  )]
+  AlreadyApplied { path: String },
+  #[error(
+    "Failed to apply patch: Refusing to apply outside the working directory: `{path}`"
+  )]
+  UnsafePath { path: String },
+  #[error("{0} requires running inside a git repository")]
+  NoRepository(Cow<'static, str>),
+  #[error("Invalid patch: {0}")]
+  Invalid(Cow<'static, str>),
   #[error("Unsupported patch type: {0}")]
   Unsupported(Cow<'static, str>),
+  #[error("Signature verification failed: {0}")]
+  Signature(Cow<'static, str>),
+  #[error("Invalid checksum manifest: {0}")]
+  Manifest(Cow<'static, str>),
+  #[error("Invalid patch cache: {0}")]
+  Cache(Cow<'static, str>),
 }
 
+#[cfg(feature = "std")]
+impl Error {
+  pub(crate) fn io_at(err: io::Error, path: &Path) -> Self {
+    Self::Io(err.kind(), format!("{}: {}", path.display(), err))
+  }
+}
+
+#[cfg(feature = "std")]
 impl From<io::Error> for Error {
   fn from(err: io::Error) -> Self {
     Self::Io(err.kind(), err.to_string())
   }
 }
 
+#[cfg(feature = "cli")]
 impl From<clap::Error> for Error {
   fn from(err: clap::Error) -> Self {
     Self::Clap(err.to_string())
@@ -0,0 +1,86 @@
+use crate::options::DuplicatePolicy;
+use crate::whitespace::WhitespaceError;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyEvent {
+  Created {
+    path: PathBuf,
+  },
+  Modified {
+    path: PathBuf,
+    hunks: usize,
+  },
+  Deleted {
+    path: PathBuf,
+  },
+  Renamed {
+    from: PathBuf,
+    to: PathBuf,
+  },
+  Copied {
+    from: PathBuf,
+    to: PathBuf,
+  },
+  ModeChanged {
+    path: PathBuf,
+    mode: u32,
+  },
+  Skipped {
+    path: PathBuf,
+  },
+  LossyRead {
+    path: PathBuf,
+  },
+  Duplicate {
+    path: PathBuf,
+    policy: DuplicatePolicy,
+  },
+  Merged {
+    path: PathBuf,
+    conflicts: usize,
+  },
+  Rejected {
+    path: PathBuf,
+    hunks: Vec<usize>,
+  },
+  Whitespace {
+    path: PathBuf,
+    line: usize,
+    kind: WhitespaceError,
+    fixed: bool,
+  },
+  Offset {
+    path: PathBuf,
+    hunk: usize,
+    line: usize,
+    offset: isize,
+    fuzz: usize,
+  },
+  AlreadyApplied {
+    path: PathBuf,
+  },
+  Reversed {
+    path: PathBuf,
+  },
+}
+
+pub trait ApplySink {
+  fn on_event(&mut self, event: ApplyEvent);
+}
+
+impl ApplySink for () {
+  fn on_event(&mut self, _event: ApplyEvent) {}
+}
+
+impl ApplySink for Vec<ApplyEvent> {
+  fn on_event(&mut self, event: ApplyEvent) {
+    self.push(event);
+  }
+}
+
+impl<F: FnMut(ApplyEvent)> ApplySink for F {
+  fn on_event(&mut self, event: ApplyEvent) {
+    self(event);
+  }
+}
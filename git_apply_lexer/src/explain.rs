@@ -0,0 +1,257 @@
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::lexer::Token;
+use crate::parser::Line;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+
+const DEV_NULL: &str = "/dev/null";
+const LOW_CONTEXT: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+  Created,
+  Deleted,
+  Renamed {
+    from: String,
+    similarity: Option<u32>,
+  },
+  Copied {
+    from: String,
+  },
+  Modified,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Risk {
+  LowContext { hunk_index: usize, context: usize },
+  ModeChange { old: u32, new: u32 },
+  PathEscape,
+  Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+  pub path: String,
+  pub change: Change,
+  pub hunks: usize,
+  pub insertions: usize,
+  pub deletions: usize,
+  pub functions: Vec<String>,
+  pub risks: Vec<Risk>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Explanation {
+  pub files: Vec<FileSummary>,
+}
+
+fn escapes(path: &str) -> bool {
+  path != DEV_NULL
+    && (path.starts_with('/') || path.split('/').any(|part| part == ".."))
+}
+
+fn headings(patch_text: &str) -> Vec<String> {
+  Lexer::new(patch_text)
+    .spanned()
+    .map_while(Result::ok)
+    .filter(|(token, _)| matches!(token, Token::HunkHeader { .. }))
+    .map(|(_, span)| {
+      patch_text[span]
+        .splitn(3, "@@")
+        .nth(2)
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+    })
+    .collect()
+}
+
+fn summarize(patch: &Patch, headings: &[String]) -> FileSummary {
+  let stat = patch.stat();
+  let path = if patch.new_file == DEV_NULL {
+    patch.old_file.to_string()
+  } else {
+    patch.new_file.to_string()
+  };
+  let change = if patch.old_file == DEV_NULL {
+    Change::Created
+  } else if patch.new_file == DEV_NULL {
+    Change::Deleted
+  } else if let Some(from) = &patch.rename_from {
+    Change::Renamed {
+      from: from.to_string(),
+      similarity: patch.similarity,
+    }
+  } else if let Some(from) = &patch.copy_from {
+    Change::Copied {
+      from: from.to_string(),
+    }
+  } else {
+    Change::Modified
+  };
+
+  let mut functions = Vec::new();
+  for heading in headings.iter().filter(|heading| !heading.is_empty()) {
+    if !functions.contains(heading) {
+      functions.push(heading.clone());
+    }
+  }
+
+  let mut risks = Vec::new();
+  if [&patch.old_file, &patch.new_file]
+    .into_iter()
+    .any(|path| escapes(path))
+  {
+    risks.push(Risk::PathEscape);
+  }
+  if patch.is_binary {
+    risks.push(Risk::Binary);
+  }
+  if let (Some(old), Some(new)) = (patch.old_mode, patch.new_mode) {
+    risks.push(Risk::ModeChange { old, new });
+  }
+  if change == Change::Modified || matches!(change, Change::Renamed { .. }) {
+    let mut low_context: Vec<_> = patch
+      .hunks
+      .iter()
+      .enumerate()
+      .map(|(hunk_index, hunk)| {
+        let context = hunk
+          .lines
+          .iter()
+          .filter(|line| matches!(line, Line::Context(_)))
+          .count();
+        (hunk_index, context)
+      })
+      .filter(|&(_, context)| context < LOW_CONTEXT)
+      .collect();
+    low_context.sort_by_key(|&(hunk_index, context)| (context, hunk_index));
+    risks.extend(low_context.into_iter().map(|(hunk_index, context)| {
+      Risk::LowContext {
+        hunk_index,
+        context,
+      }
+    }));
+  }
+
+  FileSummary {
+    path,
+    change,
+    hunks: patch.hunks.len(),
+    insertions: stat.insertions,
+    deletions: stat.deletions,
+    functions,
+    risks,
+  }
+}
+
+pub fn explain(patch_text: &str) -> Result<Explanation, Error> {
+  let headings = headings(patch_text);
+  let mut explanation = Explanation::default();
+  let mut next = 0;
+  for patch in Parser::new(patch_text) {
+    let patch = patch?;
+    let end = (next + patch.hunks.len()).min(headings.len());
+    explanation
+      .files
+      .push(summarize(&patch, &headings[next..end]));
+    next = end;
+  }
+  Ok(explanation)
+}
+
+impl fmt::Display for Risk {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::LowContext {
+        hunk_index,
+        context,
+      } => write!(
+        f,
+        "hunk #{} has only {} context line{}",
+        hunk_index + 1,
+        context,
+        if *context == 1 { "" } else { "s" }
+      ),
+      Self::ModeChange { old, new } => {
+        write!(f, "mode changes from {:o} to {:o}", old, new)
+      }
+      Self::PathEscape => write!(f, "path escapes the working directory"),
+      Self::Binary => write!(f, "binary content cannot be reviewed"),
+    }
+  }
+}
+
+impl fmt::Display for Explanation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (insertions, deletions) =
+      self.files.iter().fold((0, 0), |(added, removed), file| {
+        (added + file.insertions, removed + file.deletions)
+      });
+    writeln!(
+      f,
+      "{} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+      self.files.len(),
+      if self.files.len() == 1 { "" } else { "s" },
+      insertions,
+      if insertions == 1 { "" } else { "s" },
+      deletions,
+      if deletions == 1 { "" } else { "s" }
+    )?;
+
+    for file in &self.files {
+      match &file.change {
+        Change::Created => write!(f, "Created {}", file.path)?,
+        Change::Deleted => write!(f, "Deleted {}", file.path)?,
+        Change::Renamed {
+          from,
+          similarity: Some(similarity),
+        } => write!(
+          f,
+          "Renamed {} to {} ({}% similar)",
+          from, file.path, similarity
+        )?,
+        Change::Renamed { from, .. } => {
+          write!(f, "Renamed {} to {}", from, file.path)?
+        }
+        Change::Copied { from } => {
+          write!(f, "Copied {} to {}", from, file.path)?
+        }
+        Change::Modified => write!(f, "Modified {}", file.path)?,
+      }
+      if file.hunks > 0 {
+        write!(
+          f,
+          ": {} hunk{}, +{} -{}",
+          file.hunks,
+          if file.hunks == 1 { "" } else { "s" },
+          file.insertions,
+          file.deletions
+        )?;
+      }
+      writeln!(f)?;
+
+      for function in &file.functions {
+        writeln!(f, "  touches {}", function)?;
+      }
+    }
+
+    let mut risks = self
+      .files
+      .iter()
+      .flat_map(|file| file.risks.iter().map(move |risk| (&file.path, risk)))
+      .peekable();
+    if risks.peek().is_some() {
+      writeln!(f, "Risks:")?;
+      for (path, risk) in risks {
+        writeln!(f, "  {}: {}", path, risk)?;
+      }
+    }
+    Ok(())
+  }
+}
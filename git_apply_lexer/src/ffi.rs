@@ -0,0 +1,251 @@
+use crate::applier;
+use crate::error::Error;
+use crate::event::ApplyEvent;
+use crate::fs::OsFileSystem;
+use crate::options::ApplyOptions;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ffi::c_char;
+use std::panic;
+use std::path::Path;
+use std::ptr;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitStatus {
+  Ok = 0,
+  InvalidArgument = 1,
+  Parse = 2,
+  Apply = 3,
+  Io = 4,
+  Unsupported = 5,
+  Panic = 6,
+  Signature = 7,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitEventKind {
+  Created = 0,
+  Modified = 1,
+  Deleted = 2,
+  Renamed = 3,
+  Copied = 4,
+  ModeChanged = 5,
+  Skipped = 6,
+  LossyRead = 7,
+  Duplicate = 8,
+  Merged = 9,
+  Rejected = 10,
+  Whitespace = 11,
+  Offset = 12,
+  AlreadyApplied = 13,
+  Reversed = 14,
+}
+
+struct Report {
+  error: Option<CString>,
+  events: Vec<(HitEventKind, CString)>,
+}
+
+thread_local! {
+  static LAST_REPORT: RefCell<Report> = const {
+    RefCell::new(Report {
+      error: None,
+      events: Vec::new(),
+    })
+  };
+}
+
+impl From<&Error> for HitStatus {
+  fn from(error: &Error) -> Self {
+    match error {
+      Error::Clap(_)
+      | Error::Invalid(_)
+      | Error::Manifest(_)
+      | Error::NoRepository(_) => Self::InvalidArgument,
+      Error::Io(..) => Self::Io,
+      Error::Parse(_) | Error::Cache(_) => Self::Parse,
+      Error::UnexpectedEof { .. }
+      | Error::HunkMismatch { .. }
+      | Error::ExpectedEof { .. }
+      | Error::AlreadyApplied { .. }
+      | Error::UnsafePath { .. } => Self::Apply,
+      Error::Unsupported(_) => Self::Unsupported,
+      Error::Signature(_) => Self::Signature,
+    }
+  }
+}
+
+fn c_string(text: impl Into<Vec<u8>>) -> CString {
+  CString::new(text).unwrap_or_else(|e| {
+    let mut bytes = e.into_vec();
+    bytes.retain(|&byte| byte != 0);
+    CString::new(bytes).unwrap_or_default()
+  })
+}
+
+fn path_string(path: &Path) -> CString {
+  c_string(path.display().to_string())
+}
+
+fn event_entry(event: ApplyEvent) -> (HitEventKind, CString) {
+  match event {
+    ApplyEvent::Created { path } => (HitEventKind::Created, path_string(&path)),
+    ApplyEvent::Modified { path, .. } => {
+      (HitEventKind::Modified, path_string(&path))
+    }
+    ApplyEvent::Deleted { path } => (HitEventKind::Deleted, path_string(&path)),
+    ApplyEvent::Renamed { to, .. } => (HitEventKind::Renamed, path_string(&to)),
+    ApplyEvent::Copied { to, .. } => (HitEventKind::Copied, path_string(&to)),
+    ApplyEvent::ModeChanged { path, .. } => {
+      (HitEventKind::ModeChanged, path_string(&path))
+    }
+    ApplyEvent::Skipped { path } => (HitEventKind::Skipped, path_string(&path)),
+    ApplyEvent::LossyRead { path } => {
+      (HitEventKind::LossyRead, path_string(&path))
+    }
+    ApplyEvent::Duplicate { path, .. } => {
+      (HitEventKind::Duplicate, path_string(&path))
+    }
+    ApplyEvent::Merged { path, .. } => {
+      (HitEventKind::Merged, path_string(&path))
+    }
+    ApplyEvent::Rejected { path, .. } => {
+      (HitEventKind::Rejected, path_string(&path))
+    }
+    ApplyEvent::Whitespace { path, .. } => {
+      (HitEventKind::Whitespace, path_string(&path))
+    }
+    ApplyEvent::Offset { path, .. } => {
+      (HitEventKind::Offset, path_string(&path))
+    }
+    ApplyEvent::AlreadyApplied { path } => {
+      (HitEventKind::AlreadyApplied, path_string(&path))
+    }
+    ApplyEvent::Reversed { path } => {
+      (HitEventKind::Reversed, path_string(&path))
+    }
+  }
+}
+
+unsafe fn optional_str<'a>(
+  text: *const c_char,
+) -> Result<Option<&'a str>, HitStatus> {
+  if text.is_null() {
+    return Ok(None);
+  }
+  unsafe { CStr::from_ptr(text) }
+    .to_str()
+    .map(Some)
+    .map_err(|_| HitStatus::InvalidArgument)
+}
+
+fn run(
+  patch_text: Option<&str>,
+  reverse: bool,
+  root_dir: Option<&str>,
+) -> (HitStatus, Report) {
+  let mut report = Report {
+    error: None,
+    events: Vec::new(),
+  };
+
+  let Some(patch_text) = patch_text else {
+    report.error = Some(c_string("patch_text must not be null"));
+    return (HitStatus::InvalidArgument, report);
+  };
+
+  let mut options = ApplyOptions::new().reverse(reverse);
+  if let Some(root_dir) = root_dir {
+    options = options.root(root_dir);
+  }
+
+  let mut events = Vec::new();
+  let result =
+    applier::patch(&mut OsFileSystem, patch_text, &options, &mut events);
+  report.events = events.into_iter().map(event_entry).collect();
+
+  match result {
+    Ok(_) => (HitStatus::Ok, report),
+    Err(error) => {
+      report.error = Some(c_string(error.to_string()));
+      (HitStatus::from(&error), report)
+    }
+  }
+}
+
+/// # Safety
+///
+/// `patch_text` must be a valid NUL-terminated string. `root_dir` must be
+/// null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hit_apply(
+  patch_text: *const c_char,
+  reverse: bool,
+  root_dir: *const c_char,
+) -> HitStatus {
+  let arguments = unsafe { (optional_str(patch_text), optional_str(root_dir)) };
+  let (status, report) = match arguments {
+    (Ok(patch_text), Ok(root_dir)) => {
+      panic::catch_unwind(|| run(patch_text, reverse, root_dir)).unwrap_or_else(
+        |_| {
+          let report = Report {
+            error: Some(c_string("panic while applying patch")),
+            events: Vec::new(),
+          };
+          (HitStatus::Panic, report)
+        },
+      )
+    }
+    _ => {
+      let report = Report {
+        error: Some(c_string("arguments must be valid UTF-8")),
+        events: Vec::new(),
+      };
+      (HitStatus::InvalidArgument, report)
+    }
+  };
+
+  LAST_REPORT.with(|last| *last.borrow_mut() = report);
+  status
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn hit_last_error() -> *const c_char {
+  LAST_REPORT.with(|last| {
+    last
+      .borrow()
+      .error
+      .as_ref()
+      .map_or(ptr::null(), |error| error.as_ptr())
+  })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn hit_last_event_count() -> usize {
+  LAST_REPORT.with(|last| last.borrow().events.len())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn hit_last_event_kind(index: usize) -> i32 {
+  LAST_REPORT.with(|last| {
+    last
+      .borrow()
+      .events
+      .get(index)
+      .map_or(-1, |(kind, _)| *kind as i32)
+  })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn hit_last_event_path(index: usize) -> *const c_char {
+  LAST_REPORT.with(|last| {
+    last
+      .borrow()
+      .events
+      .get(index)
+      .map_or(ptr::null(), |(_, path)| path.as_ptr())
+  })
+}
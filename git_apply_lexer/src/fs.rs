@@ -1,3 +1,5 @@
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
 use std::collections::HashMap;
 use std::fs;
 #[cfg(unix)]
@@ -5,11 +7,76 @@ use std::fs::Permissions;
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
+use std::str;
+use std::sync::Arc;
+
+#[cfg(feature = "mmap")]
+const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// File contents returned by [`FileSystem::read_contents`].
+///
+/// With the `mmap` feature, large files are memory-mapped rather than read.
+/// A mapped file must not be truncated or rewritten by another process while
+/// the `Contents` is alive; the map is re-validated as UTF-8 on every access,
+/// but concurrent writers can still hand out inconsistent bytes.
+#[derive(Debug)]
+pub enum Contents {
+  Owned(String),
+  Shared(Arc<str>),
+  #[cfg(feature = "mmap")]
+  Mapped(Mmap),
+}
+
+impl Default for Contents {
+  fn default() -> Self {
+    Self::Owned(String::new())
+  }
+}
+
+impl Contents {
+  pub fn as_str(&self) -> io::Result<&str> {
+    match self {
+      Self::Owned(text) => Ok(text),
+      Self::Shared(text) => Ok(text),
+      #[cfg(feature = "mmap")]
+      Self::Mapped(map) => str::from_utf8(map)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+    }
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    match self {
+      Self::Owned(text) => text.as_bytes(),
+      Self::Shared(text) => text.as_bytes(),
+      #[cfg(feature = "mmap")]
+      Self::Mapped(map) => map,
+    }
+  }
+}
 
 pub trait FileSystem {
   fn read_to_string(&self, path: &Path) -> io::Result<String>;
+  fn read_contents(&self, path: &Path) -> io::Result<Contents> {
+    self.read_to_string(path).map(Contents::Owned)
+  }
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    self.read_to_string(path).map(String::into_bytes)
+  }
   fn write(&mut self, path: &Path, contents: &str) -> io::Result<()>;
+  fn write_bytes(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+    let contents = str::from_utf8(contents)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    self.write(path, contents)
+  }
   fn remove_file(&mut self, path: &Path) -> io::Result<()>;
+  fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    self.copy(from, to)?;
+    self.remove_file(from)
+  }
+  fn copy(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    let contents = self.read_bytes(from)?;
+    self.write_bytes(to, &contents)
+  }
   fn create_dir_all(&mut self, path: &Path) -> io::Result<()>;
   #[cfg(unix)]
   fn set_permissions(
@@ -29,14 +96,45 @@ impl FileSystem for OsFileSystem {
     fs::read_to_string(path)
   }
 
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    fs::read(path)
+  }
+
+  #[cfg(feature = "mmap")]
+  fn read_contents(&self, path: &Path) -> io::Result<Contents> {
+    let file = fs::File::open(path)?;
+    if file.metadata()?.len() < MMAP_THRESHOLD {
+      return io::read_to_string(file).map(Contents::Owned);
+    }
+
+    // SAFETY: the applier drops the map before writing back to the same path,
+    // and `Contents` documents that no other process may modify the file.
+    let map = unsafe { Mmap::map(&file)? };
+    str::from_utf8(&map)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Contents::Mapped(map))
+  }
+
   fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
     fs::write(path, contents)
   }
 
+  fn write_bytes(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+    fs::write(path, contents)
+  }
+
   fn remove_file(&mut self, path: &Path) -> io::Result<()> {
     fs::remove_file(path)
   }
 
+  fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)
+  }
+
+  fn copy(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+  }
+
   fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
     fs::create_dir_all(path)
   }
@@ -56,9 +154,41 @@ impl FileSystem for OsFileSystem {
   }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+  Text(Arc<str>),
+  Bytes(Arc<[u8]>),
+}
+
+impl Entry {
+  fn from_bytes(contents: &[u8]) -> Self {
+    match str::from_utf8(contents) {
+      Ok(text) => Self::Text(text.into()),
+      Err(_) => Self::Bytes(contents.into()),
+    }
+  }
+
+  fn as_bytes(&self) -> &[u8] {
+    match self {
+      Self::Text(text) => text.as_bytes(),
+      Self::Bytes(bytes) => bytes,
+    }
+  }
+
+  fn text(&self) -> io::Result<&Arc<str>> {
+    match self {
+      Self::Text(text) => Ok(text),
+      Self::Bytes(_) => Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+      )),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct MockFileSystem {
-  pub files: HashMap<PathBuf, String>,
+  files: HashMap<PathBuf, Entry>,
   pub created_dirs: Vec<PathBuf>,
   #[cfg(unix)]
   pub file_modes: HashMap<PathBuf, Permissions>,
@@ -68,7 +198,7 @@ pub struct MockFileSystem {
 impl MockFileSystem {
   pub fn new(files: HashMap<PathBuf, String>) -> Self {
     Self {
-      files,
+      files: shared(files),
       ..Default::default()
     }
   }
@@ -79,25 +209,81 @@ impl MockFileSystem {
     #[cfg(unix)] file_modes: HashMap<PathBuf, Permissions>,
   ) -> Self {
     Self {
-      files,
+      files: shared(files),
       created_dirs,
       #[cfg(unix)]
       file_modes,
     }
   }
-}
 
-impl FileSystem for MockFileSystem {
-  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+  pub fn file(&self, path: impl AsRef<Path>) -> Option<&str> {
+    match self.files.get(path.as_ref())? {
+      Entry::Text(text) => Some(text),
+      Entry::Bytes(_) => None,
+    }
+  }
+
+  pub fn file_bytes(&self, path: impl AsRef<Path>) -> Option<&[u8]> {
+    self.files.get(path.as_ref()).map(Entry::as_bytes)
+  }
+
+  pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+    self.files.contains_key(path.as_ref())
+  }
+
+  pub fn paths(&self) -> impl Iterator<Item = &Path> {
+    self.files.keys().map(PathBuf::as_path)
+  }
+
+  pub fn insert(&mut self, path: impl Into<PathBuf>, contents: &str) {
+    self.files.insert(path.into(), Entry::Text(contents.into()));
+  }
+
+  pub fn insert_bytes(&mut self, path: impl Into<PathBuf>, contents: &[u8]) {
+    self.files.insert(path.into(), Entry::from_bytes(contents));
+  }
+
+  fn get(&self, path: &Path) -> io::Result<&Entry> {
     self
       .files
       .get(path)
-      .cloned()
       .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found"))
   }
+}
+
+fn shared(files: HashMap<PathBuf, String>) -> HashMap<PathBuf, Entry> {
+  files
+    .into_iter()
+    .map(|(path, content)| (path, Entry::Text(content.into())))
+    .collect()
+}
+
+impl FileSystem for MockFileSystem {
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    self.get(path)?.text().map(|text| text.to_string())
+  }
+
+  fn read_contents(&self, path: &Path) -> io::Result<Contents> {
+    self.get(path)?.text().cloned().map(Contents::Shared)
+  }
+
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    self.get(path).map(|entry| entry.as_bytes().to_vec())
+  }
 
   fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
-    self.files.insert(path.to_path_buf(), contents.to_string());
+    self.write_bytes(path, contents.as_bytes())
+  }
+
+  fn write_bytes(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+    match self.files.get(path) {
+      Some(existing) if existing.as_bytes() == contents => {}
+      _ => {
+        self
+          .files
+          .insert(path.to_path_buf(), Entry::from_bytes(contents));
+      }
+    }
     Ok(())
   }
 
@@ -109,6 +295,24 @@ impl FileSystem for MockFileSystem {
     }
   }
 
+  fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    self.copy(from, to)?;
+    self.remove_file(from)?;
+    #[cfg(unix)]
+    self.file_modes.remove(from);
+    Ok(())
+  }
+
+  fn copy(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    let contents = self.get(from)?.clone();
+    self.files.insert(to.to_path_buf(), contents);
+    #[cfg(unix)]
+    if let Some(mode) = self.file_modes.get(from).cloned() {
+      self.file_modes.insert(to.to_path_buf(), mode);
+    }
+    Ok(())
+  }
+
   fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
     self.created_dirs.push(path.to_path_buf());
     Ok(())
@@ -0,0 +1,186 @@
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use arbitrary::Arbitrary;
+use arbitrary::Result;
+use arbitrary::Unstructured;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+const PATHS: &[&str] = &[
+  "file.txt",
+  "README.md",
+  "src/lib.rs",
+  "src/main.rs",
+  "docs/guide/intro.md",
+];
+const DEV_NULL: &str = "/dev/null";
+const MAX_HUNKS: usize = 4;
+const MAX_LINES: usize = 8;
+
+fn text<'a>(u: &mut Unstructured<'a>) -> Result<Cow<'a, str>> {
+  let raw = <&'a str>::arbitrary(u)?;
+  let end = raw
+    .char_indices()
+    .find(|&(_, c)| {
+      !(c.is_ascii_alphanumeric() || " _.,;:(){}[]<>=!?*/&|'\"#".contains(c))
+    })
+    .map_or(raw.len(), |(offset, _)| offset);
+  Ok(Cow::Borrowed(&raw[..end]))
+}
+
+impl<'a> Arbitrary<'a> for Line<'a> {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    Ok(match u.int_in_range(0..=2)? {
+      0 => Line::Context(text(u)?),
+      1 => Line::Deletion(text(u)?),
+      _ => Line::Addition(text(u)?),
+    })
+  }
+}
+
+fn hunk<'a>(
+  u: &mut Unstructured<'a>,
+  old_line: u32,
+  new_line: u32,
+  kind: fn(&mut Unstructured<'a>) -> Result<Line<'a>>,
+) -> Result<Hunk<'a>> {
+  let count = u.int_in_range(1..=MAX_LINES)?;
+  let lines = (0..count).map(|_| kind(u)).collect::<Result<Vec<_>>>()?;
+
+  let old_span = lines
+    .iter()
+    .filter(|line| matches!(line, Line::Context(_) | Line::Deletion(_)))
+    .count() as u32;
+  let new_span = lines
+    .iter()
+    .filter(|line| matches!(line, Line::Context(_) | Line::Addition(_)))
+    .count() as u32;
+
+  Ok(Hunk {
+    old_line: if old_span == 0 {
+      old_line - 1
+    } else {
+      old_line
+    },
+    old_span,
+    new_line: if new_span == 0 {
+      new_line - 1
+    } else {
+      new_line
+    },
+    new_span,
+    heading: None,
+    lines,
+  })
+}
+
+impl<'a> Arbitrary<'a> for Hunk<'a> {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let old_line = u.int_in_range(1..=1000)?;
+    let new_line = u.int_in_range(1..=1000)?;
+    hunk(u, old_line, new_line, Line::arbitrary)
+  }
+}
+
+impl<'a> Arbitrary<'a> for Patch<'a> {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let path = *u.choose(PATHS)?;
+    let mut patch = Patch {
+      old_file: path.into(),
+      new_file: path.into(),
+      ..Default::default()
+    };
+
+    let kind: fn(&mut Unstructured<'a>) -> Result<Line<'a>> =
+      match u.int_in_range(0..=2)? {
+        0 => {
+          patch.old_file = DEV_NULL.into();
+          patch.new_mode = Some(0o100644);
+          patch
+            .hunks
+            .push(hunk(u, 1, 1, |u| Ok(Line::Addition(text(u)?)))?);
+          return Ok(patch);
+        }
+        1 => {
+          patch.new_file = DEV_NULL.into();
+          patch.deleted_file_mode = Some(0o100644);
+          patch
+            .hunks
+            .push(hunk(u, 1, 1, |u| Ok(Line::Deletion(text(u)?)))?);
+          return Ok(patch);
+        }
+        _ => Line::arbitrary,
+      };
+
+    let mut old_line = 1;
+    let mut offset: i64 = 0;
+    for _ in 0..u.int_in_range(1..=MAX_HUNKS)? {
+      old_line += u.int_in_range(0..=20)?;
+      let new_line = (old_line as i64 + offset).max(1) as u32;
+      let hunk = hunk(u, old_line, new_line, kind)?;
+      old_line += hunk.old_span + 1;
+      offset += hunk.new_span as i64 - hunk.old_span as i64;
+      patch.hunks.push(hunk);
+    }
+
+    Ok(patch)
+  }
+}
+
+pub fn render(patch: &Patch) -> String {
+  let prefixed = |prefix: &str, path: &str| {
+    if path == DEV_NULL {
+      path.to_string()
+    } else {
+      format!("{}{}", prefix, path)
+    }
+  };
+  let header_old = if patch.old_file == DEV_NULL {
+    &patch.new_file
+  } else {
+    &patch.old_file
+  };
+  let header_new = if patch.new_file == DEV_NULL {
+    &patch.old_file
+  } else {
+    &patch.new_file
+  };
+
+  let mut output = String::new();
+  let _ = writeln!(output, "diff --git a/{} b/{}", header_old, header_new);
+  if let Some(mode) = patch.deleted_file_mode {
+    let _ = writeln!(output, "deleted file mode {:o}", mode);
+  } else if patch.old_file == DEV_NULL
+    && let Some(mode) = patch.new_mode
+  {
+    let _ = writeln!(output, "new file mode {:o}", mode);
+  }
+
+  if !patch.hunks.is_empty() {
+    let _ = writeln!(output, "--- {}", prefixed("a/", &patch.old_file));
+    let _ = writeln!(output, "+++ {}", prefixed("b/", &patch.new_file));
+  }
+
+  for hunk in &patch.hunks {
+    let _ = write!(
+      output,
+      "@@ -{},{} +{},{} @@",
+      hunk.old_line, hunk.old_span, hunk.new_line, hunk.new_span
+    );
+    let _ = match &hunk.heading {
+      Some(heading) => writeln!(output, " {}", heading),
+      None => writeln!(output),
+    };
+    for line in &hunk.lines {
+      let _ = match line {
+        Line::Context(text) => writeln!(output, " {}", text),
+        Line::Deletion(text) => writeln!(output, "-{}", text),
+        Line::Addition(text) => writeln!(output, "+{}", text),
+        Line::NoNewline => writeln!(output, "\\ No newline at end of file"),
+      };
+    }
+  }
+
+  output
+}
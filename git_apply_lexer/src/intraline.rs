@@ -0,0 +1,161 @@
+use crate::parser::Hunk;
+use crate::parser::Line;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntralineChange {
+  pub deletion: usize,
+  pub addition: usize,
+  pub ranges: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl<'a> Hunk<'a> {
+  pub fn intraline(&self) -> Vec<IntralineChange> {
+    let mut changes = Vec::new();
+    let mut deletions = Vec::new();
+    let mut additions = Vec::new();
+
+    for (index, line) in self.lines.iter().enumerate() {
+      match line {
+        Line::Deletion(_) => {
+          if !additions.is_empty() {
+            self.pair(&mut deletions, &mut additions, &mut changes);
+          }
+          deletions.push(index);
+        }
+        Line::Addition(_) => additions.push(index),
+        Line::Context(_) => {
+          self.pair(&mut deletions, &mut additions, &mut changes)
+        }
+        Line::NoNewline => {}
+      }
+    }
+    self.pair(&mut deletions, &mut additions, &mut changes);
+
+    changes
+  }
+
+  fn pair(
+    &self,
+    deletions: &mut Vec<usize>,
+    additions: &mut Vec<usize>,
+    changes: &mut Vec<IntralineChange>,
+  ) {
+    for (&deletion, &addition) in deletions.iter().zip(additions.iter()) {
+      if let (Line::Deletion(old), Line::Addition(new)) =
+        (&self.lines[deletion], &self.lines[addition])
+      {
+        changes.push(IntralineChange {
+          deletion,
+          addition,
+          ranges: diff_line(old, new),
+        });
+      }
+    }
+    deletions.clear();
+    additions.clear();
+  }
+}
+
+pub fn diff_line(old: &str, new: &str) -> Vec<(Range<usize>, Range<usize>)> {
+  let old_tokens = tokenize(old);
+  let new_tokens = tokenize(new);
+  let common = longest_common_subsequence(old, &old_tokens, new, &new_tokens);
+
+  let mut ranges = Vec::new();
+  let (mut old_index, mut new_index) = (0, 0);
+  for (old_match, new_match) in common
+    .into_iter()
+    .chain([(old_tokens.len(), new_tokens.len())])
+  {
+    if old_index < old_match || new_index < new_match {
+      ranges.push((
+        span(&old_tokens, old_index..old_match, old.len()),
+        span(&new_tokens, new_index..new_match, new.len()),
+      ));
+    }
+    old_index = old_match + 1;
+    new_index = new_match + 1;
+  }
+
+  ranges
+}
+
+fn tokenize(text: &str) -> Vec<Range<usize>> {
+  let class = |c: char| {
+    if c.is_alphanumeric() || c == '_' {
+      0
+    } else if c.is_whitespace() {
+      1
+    } else {
+      2
+    }
+  };
+
+  let mut tokens: Vec<Range<usize>> = Vec::new();
+  let mut previous = None;
+  for (offset, c) in text.char_indices() {
+    let current = class(c);
+    match tokens.last_mut() {
+      Some(token) if current != 2 && previous == Some(current) => {
+        token.end = offset + c.len_utf8();
+      }
+      _ => tokens.push(offset..offset + c.len_utf8()),
+    }
+    previous = Some(current);
+  }
+  tokens
+}
+
+fn longest_common_subsequence(
+  old: &str,
+  old_tokens: &[Range<usize>],
+  new: &str,
+  new_tokens: &[Range<usize>],
+) -> Vec<(usize, usize)> {
+  let (rows, cols) = (old_tokens.len(), new_tokens.len());
+  let mut table = vec![0u32; (rows + 1) * (cols + 1)];
+  let at = |i: usize, j: usize| i * (cols + 1) + j;
+
+  for i in (0..rows).rev() {
+    for j in (0..cols).rev() {
+      table[at(i, j)] =
+        if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+          table[at(i + 1, j + 1)] + 1
+        } else {
+          table[at(i + 1, j)].max(table[at(i, j + 1)])
+        };
+    }
+  }
+
+  let mut pairs = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < rows && j < cols {
+    if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+      pairs.push((i, j));
+      i += 1;
+      j += 1;
+    } else if table[at(i + 1, j)] >= table[at(i, j + 1)] {
+      i += 1;
+    } else {
+      j += 1;
+    }
+  }
+  pairs
+}
+
+fn span(
+  tokens: &[Range<usize>],
+  range: Range<usize>,
+  len: usize,
+) -> Range<usize> {
+  let start = tokens.get(range.start).map_or(len, |token| token.start);
+  let end = if range.is_empty() {
+    start
+  } else {
+    tokens[range.end - 1].end
+  };
+  start..end
+}
@@ -1,63 +1,270 @@
 use crate::error::Error;
-use std::iter::Peekable;
-use std::str::Lines;
+use crate::stats;
+use crate::stats::FileStat;
+use crate::stats::StatSummary;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use memchr::memchr;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BinaryMethod {
+  Literal,
+  Delta,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token<'a> {
   FileHeader {
-    old_file: &'a str,
-    new_file: &'a str,
+    old_file: Cow<'a, str>,
+    new_file: Cow<'a, str>,
   },
   Index {
     old_hash: &'a str,
     new_hash: &'a str,
     mode: Option<u32>,
   },
-  OldFile(&'a str),
-  NewFile(&'a str),
+  OldFile {
+    path: Cow<'a, str>,
+    timestamp: Option<&'a str>,
+  },
+  NewFile {
+    path: Cow<'a, str>,
+    timestamp: Option<&'a str>,
+  },
   HunkHeader {
     old_line: u32,
     old_span: u32,
     new_line: u32,
     new_span: u32,
+    heading: Option<&'a str>,
   },
   Addition(&'a str),
   Deletion(&'a str),
   Context(&'a str),
   NoNewline,
-  RenameFrom(&'a str),
-  RenameTo(&'a str),
+  RenameFrom(Cow<'a, str>),
+  RenameTo(Cow<'a, str>),
   Similarity(u32),
   NewFileMode(u32),
   OldFileMode(u32),
   DeletedFileMode(u32),
   BinaryFileDiffer {
-    old_file: &'a str,
-    new_file: &'a str,
+    old_file: Cow<'a, str>,
+    new_file: Cow<'a, str>,
   },
-  CopyFrom(&'a str),
-  CopyTo(&'a str),
+  CopyFrom(Cow<'a, str>),
+  CopyTo(Cow<'a, str>),
   Dissimilarity(u32),
+  GitBinaryPatch,
+  BinaryHunk {
+    method: BinaryMethod,
+    size: usize,
+    data: &'a str,
+  },
+  DiffStat {
+    line: &'a str,
+    stat: FileStat,
+  },
+  DiffStatSummary {
+    line: &'a str,
+    summary: StatSummary,
+  },
+}
+
+fn unquote(s: &str) -> Option<(String, &str)> {
+  let body = s.strip_prefix('"')?;
+  let mut bytes = Vec::new();
+  let mut chars = body.char_indices();
+  while let Some((index, c)) = chars.next() {
+    match c {
+      '"' => {
+        let path = String::from_utf8_lossy(&bytes).into_owned();
+        return Some((path, &body[index + 1..]));
+      }
+      '\\' => {
+        let (_, escaped) = chars.next()?;
+        bytes.push(match escaped {
+          'a' => 0x07,
+          'b' => 0x08,
+          't' => b'\t',
+          'n' => b'\n',
+          'v' => 0x0b,
+          'f' => 0x0c,
+          'r' => b'\r',
+          '"' => b'"',
+          '\\' => b'\\',
+          '0'..='3' => {
+            let mut value = escaped.to_digit(8)?;
+            for _ in 0..2 {
+              value = value * 8 + chars.next()?.1.to_digit(8)?;
+            }
+            value as u8
+          }
+          _ => return None,
+        });
+      }
+      _ => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+    }
+  }
+  None
+}
+
+fn quoted_path(s: &str) -> Result<Cow<'_, str>, Error> {
+  if !s.starts_with('"') {
+    return Ok(Cow::Borrowed(s));
+  }
+  match unquote(s) {
+    Some((path, "")) => Ok(Cow::Owned(path)),
+    _ => Err(Error::Parse(
+      format!("Malformed quoted path: `{}`", s).into(),
+    )),
+  }
+}
+
+fn split_paths(rest: &str) -> Option<(&str, &str)> {
+  if rest.starts_with('"') {
+    let (_, after) = unquote(rest)?;
+    let old = &rest[..rest.len() - after.len()];
+    return Some((old, after.strip_prefix(' ')?));
+  }
+  if let Some((old, _)) = rest.split_once(" \"") {
+    return Some((old, &rest[old.len() + 1..]));
+  }
+
+  let middle = rest.len() / 2;
+  if rest.len() % 2 == 1 && rest.as_bytes()[middle] == b' ' {
+    let (old, new) = (&rest[..middle], &rest[middle + 1..]);
+    if let (Some((_, old_name)), Some((_, new_name))) =
+      (old.split_once('/'), new.split_once('/'))
+      && old_name == new_name
+    {
+      return Some((old, new));
+    }
+  }
+  let mut parts = rest.split_whitespace();
+  Some((parts.next()?, parts.next()?))
 }
 
 pub struct Lexer<'a> {
-  lines: Peekable<Lines<'a>>,
+  source: &'a str,
+  position: usize,
+  strip: Option<usize>,
+  in_diff: bool,
+}
+
+pub struct Spanned<'a> {
+  lexer: Lexer<'a>,
 }
 
 impl<'a> Lexer<'a> {
   pub fn new(source: &'a str) -> Self {
     Lexer {
-      lines: source.lines().peekable(),
+      source,
+      position: 0,
+      strip: None,
+      in_diff: false,
     }
   }
 
-  fn strip_git_prefix(s: &'a str) -> Result<&'a str, Error> {
-    s.strip_prefix("a/")
-      .or_else(|| s.strip_prefix("b/"))
-      .or_else(|| (s == "/dev/null").then_some(s))
-      .ok_or_else(|| {
-        Error::Parse(format!("Malformed file path: `{}`", s).into())
-      })
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = Some(components);
+    self
+  }
+
+  pub fn spanned(self) -> Spanned<'a> {
+    Spanned { lexer: self }
+  }
+
+  fn line_at(&self, start: usize) -> Option<(Range<usize>, usize)> {
+    let rest = self
+      .source
+      .as_bytes()
+      .get(start..)
+      .filter(|rest| !rest.is_empty())?;
+    Some(match memchr(b'\n', rest) {
+      Some(offset) => {
+        let end = start + offset;
+        let end = if rest[..offset].ends_with(b"\r") {
+          end - 1
+        } else {
+          end
+        };
+        (start..end, start + offset + 1)
+      }
+      None => (start..self.source.len(), self.source.len()),
+    })
+  }
+
+  pub(crate) fn source(&self) -> &'a str {
+    self.source
+  }
+
+  pub(crate) fn next_line_span(&mut self) -> Option<Range<usize>> {
+    loop {
+      let (line, next) = self.line_at(self.position)?;
+      if !line.is_empty() {
+        return Some(line);
+      }
+      self.position = next;
+    }
+  }
+
+  fn strip_git_prefix(&self, s: &'a str) -> Result<Cow<'a, str>, Error> {
+    match quoted_path(s)? {
+      Cow::Borrowed(path) => self.strip_components(path).map(Cow::Borrowed),
+      Cow::Owned(path) => {
+        let stripped = self.strip_components(&path)?;
+        Ok(Cow::Owned(stripped.into()))
+      }
+    }
+  }
+
+  pub(crate) fn file_line(
+    &self,
+    rest: &'a str,
+  ) -> Result<(Cow<'a, str>, Option<&'a str>), Error> {
+    let (path, timestamp) = match unquote(rest) {
+      Some((_, after)) => {
+        let path = &rest[..rest.len() - after.len()];
+        (path, after.strip_prefix('\t'))
+      }
+      None => match rest.split_once('\t') {
+        Some((path, timestamp)) => (path, Some(timestamp)),
+        None => (rest, None),
+      },
+    };
+    let timestamp = timestamp.map(str::trim).filter(|t| !t.is_empty());
+    Ok((self.strip_git_prefix(path)?, timestamp))
+  }
+
+  fn strip_components<'s>(&self, s: &'s str) -> Result<&'s str, Error> {
+    let malformed =
+      || Error::Parse(format!("Malformed file path: `{}`", s).into());
+    if s == "/dev/null" {
+      return Ok(s);
+    }
+    let Some(components) = self.strip else {
+      return s
+        .strip_prefix("a/")
+        .or_else(|| s.strip_prefix("b/"))
+        .ok_or_else(malformed);
+    };
+
+    let mut path = s;
+    for _ in 0..components {
+      path = path
+        .split_once('/')
+        .map(|(_, rest)| rest.trim_start_matches('/'))
+        .ok_or_else(malformed)?;
+    }
+    if path.is_empty() {
+      return Err(malformed());
+    }
+    Ok(path)
   }
 
   fn parse_index_line(rest: &'a str) -> Result<Token<'a>, Error> {
@@ -79,10 +286,8 @@ impl<'a> Lexer<'a> {
   }
 
   fn parse_hunk_header(header: &'a str) -> Result<Token<'a>, Error> {
-    let content = header
-      .split(" @@")
-      .next()
-      .ok_or(Error::Parse("Malformed hunk header".into()))?;
+    let (content, heading) = header.split_once(" @@").unwrap_or((header, ""));
+    let heading = heading.strip_prefix(' ').filter(|h| !h.is_empty());
 
     let mut parts = content.split_whitespace();
 
@@ -104,6 +309,7 @@ impl<'a> Lexer<'a> {
       old_span,
       new_line,
       new_span,
+      heading,
     })
   }
 
@@ -143,21 +349,112 @@ impl<'a> Lexer<'a> {
       .map_err(|e| Error::Parse(format!("Invalid file mode: {}", e).into()))
   }
 
-  fn next_token(&mut self) -> Result<Token<'a>, Error> {
-    let line_content = self
-      .lines
-      .next()
+  fn unexpected_line(line: &str) -> Error {
+    Error::Parse(format!("Unexpected line: `{}`", line).into())
+  }
+
+  pub(crate) fn next_token(&mut self) -> Result<Token<'a>, Error> {
+    let (line, next) = self
+      .line_at(self.position)
       .ok_or(Error::Parse("Unexpected EOF".into()))?;
+    self.position = next;
+    let line_content = &self.source[line];
 
+    let token = match line_content.as_bytes().first() {
+      Some(b'+') => match line_content.strip_prefix("+++ ") {
+        Some(rest) => {
+          let (path, timestamp) = self.file_line(rest)?;
+          Ok(Token::NewFile { path, timestamp })
+        }
+        None => Ok(Token::Addition(&line_content[1..])),
+      },
+      Some(b'-') => match line_content.strip_prefix("--- ") {
+        Some(rest) => {
+          let (path, timestamp) = self.file_line(rest)?;
+          Ok(Token::OldFile { path, timestamp })
+        }
+        None => Ok(Token::Deletion(&line_content[1..])),
+      },
+      Some(b' ') => Ok(self.context(&line_content[1..])),
+      Some(b'@') => match line_content.strip_prefix("@@ ") {
+        Some(hunk_header) => Self::parse_hunk_header(hunk_header),
+        None => Err(Self::unexpected_line(line_content)),
+      },
+      None => Ok(Token::Context("")),
+      _ => match Self::binary_method(line_content) {
+        Some((method, size)) => self.binary_hunk(method, size),
+        None => self.header_token(line_content),
+      },
+    };
+    if let Ok(
+      Token::FileHeader { .. }
+      | Token::OldFile { .. }
+      | Token::HunkHeader { .. },
+    ) = token
+    {
+      self.in_diff = true;
+    }
+    token
+  }
+
+  fn context(&self, line: &'a str) -> Token<'a> {
+    if self.in_diff {
+      Token::Context(line)
+    } else if let Some(summary) = stats::parse_summary(line) {
+      Token::DiffStatSummary { line, summary }
+    } else if let Some(stat) = stats::parse_stat_line(line) {
+      Token::DiffStat { line, stat }
+    } else {
+      Token::Context(line)
+    }
+  }
+
+  fn binary_method(line_content: &str) -> Option<(BinaryMethod, &str)> {
+    line_content
+      .strip_prefix("literal ")
+      .map(|size| (BinaryMethod::Literal, size))
+      .or_else(|| {
+        line_content
+          .strip_prefix("delta ")
+          .map(|size| (BinaryMethod::Delta, size))
+      })
+  }
+
+  fn binary_hunk(
+    &mut self,
+    method: BinaryMethod,
+    size: &str,
+  ) -> Result<Token<'a>, Error> {
+    let size = size.parse().map_err(|e| {
+      Error::Parse(
+        format!("Invalid binary hunk size: `{}` - {}", size, e).into(),
+      )
+    })?;
+
+    let start = self.position;
+    let mut end = start;
+    while let Some((line, next)) = self.line_at(self.position)
+      && !line.is_empty()
+    {
+      end = line.end;
+      self.position = next;
+    }
+    Ok(Token::BinaryHunk {
+      method,
+      size,
+      data: &self.source[start..end],
+    })
+  }
+
+  fn header_token(&self, line_content: &'a str) -> Result<Token<'a>, Error> {
     if let Some(rest) = line_content.strip_prefix("diff --git ") {
-      let mut parts = rest.split_whitespace();
-      match (parts.next(), parts.next()) {
-        (Some(old_file_raw), Some(new_file_raw)) => {
-          let old_file = Self::strip_git_prefix(old_file_raw)?;
-          let new_file = Self::strip_git_prefix(new_file_raw)?;
+      match split_paths(rest) {
+        Some((old_file_raw, new_file_raw)) => {
+          let old_file = self.strip_git_prefix(old_file_raw)?;
+          let new_file = self.strip_git_prefix(new_file_raw)?;
           Ok(Token::FileHeader { old_file, new_file })
         }
-        _ => Err(Error::Parse("Invalid file header".into())),
+        None => Err(Error::Parse("Invalid file header".into())),
       }
     } else if let Some(rest) = line_content.strip_prefix("deleted file mode ") {
       let mode = Self::parse_octal_mode(rest)?;
@@ -168,28 +465,14 @@ impl<'a> Lexer<'a> {
       Ok(Token::Dissimilarity(percent))
     } else if let Some(rest) = line_content.strip_prefix("index ") {
       Self::parse_index_line(rest)
-    } else if let Some(stripped) = line_content.strip_prefix("--- ") {
-      Ok(Token::OldFile(Self::strip_git_prefix(stripped)?))
-    } else if let Some(stripped) = line_content.strip_prefix("+++ ") {
-      Ok(Token::NewFile(Self::strip_git_prefix(stripped)?))
-    } else if let Some(stripped) = line_content.strip_prefix('-') {
-      Ok(Token::Deletion(stripped))
-    } else if let Some(stripped) = line_content.strip_prefix('+') {
-      Ok(Token::Addition(stripped))
-    } else if let Some(hunk_header) = line_content.strip_prefix("@@ ") {
-      Self::parse_hunk_header(hunk_header)
-    } else if line_content.starts_with('@') {
-      Err(Error::Parse(
-        format!("Unexpected line: `{}`", line_content).into(),
-      ))
-    } else if line_content.starts_with(' ') {
-      Ok(Token::Context(line_content))
+    } else if line_content == "GIT binary patch" {
+      Ok(Token::GitBinaryPatch)
     } else if line_content == "\\ No newline at end of file" {
       Ok(Token::NoNewline)
     } else if let Some(rest) = line_content.strip_prefix("rename from ") {
-      Ok(Token::RenameFrom(rest))
+      Ok(Token::RenameFrom(quoted_path(rest)?))
     } else if let Some(rest) = line_content.strip_prefix("rename to ") {
-      Ok(Token::RenameTo(rest))
+      Ok(Token::RenameTo(quoted_path(rest)?))
     } else if let Some(rest) = line_content.strip_prefix("similarity index ") {
       let percent = Self::parse_percentage(rest, "Invalid similarity")?;
       Ok(Token::Similarity(percent))
@@ -203,23 +486,23 @@ impl<'a> Lexer<'a> {
       let mode = Self::parse_octal_mode(rest)?;
       Ok(Token::OldFileMode(mode))
     } else if let Some(rest) = line_content.strip_prefix("Binary files ") {
-      let (old_file, rest) = rest
-        .split_once(" and ")
-        .ok_or(Error::Parse("Invalid binary files line".into()))?;
-      let new_file = rest
-        .strip_suffix(" differ")
-        .ok_or(Error::Parse("Invalid binary files line".into()))?;
-      Ok(Token::BinaryFileDiffer { old_file, new_file })
+      let invalid = || Error::Parse("Invalid binary files line".into());
+      let (old_file, rest) = match unquote(rest) {
+        Some((_, after)) => rest.split_at(rest.len() - after.len()),
+        None => rest.split_once(" and ").ok_or_else(invalid)?,
+      };
+      let rest = rest.strip_prefix(" and ").unwrap_or(rest);
+      let new_file = rest.strip_suffix(" differ").ok_or_else(invalid)?;
+      Ok(Token::BinaryFileDiffer {
+        old_file: quoted_path(old_file)?,
+        new_file: quoted_path(new_file)?,
+      })
     } else if let Some(rest) = line_content.strip_prefix("copy from ") {
-      Ok(Token::CopyFrom(rest))
+      Ok(Token::CopyFrom(quoted_path(rest)?))
     } else if let Some(rest) = line_content.strip_prefix("copy to ") {
-      Ok(Token::CopyTo(rest))
-    } else if line_content.is_empty() {
-      Ok(Token::Context(""))
+      Ok(Token::CopyTo(quoted_path(rest)?))
     } else {
-      Err(Error::Parse(
-        format!("Unexpected line: `{}`", line_content).into(),
-      ))
+      Err(Self::unexpected_line(line_content))
     }
   }
 }
@@ -228,10 +511,16 @@ impl<'a> Iterator for Lexer<'a> {
   type Item = Result<Token<'a>, Error>;
 
   fn next(&mut self) -> Option<Self::Item> {
-    while let Some(&"") = self.lines.peek() {
-      self.lines.next();
-    }
-    self.lines.peek()?;
+    self.next_line_span()?;
     Some(self.next_token())
   }
 }
+
+impl<'a> Iterator for Spanned<'a> {
+  type Item = Result<(Token<'a>, Range<usize>), Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let span = self.lexer.next_line_span()?;
+    Some(self.lexer.next_token().map(|token| (token, span)))
+  }
+}
@@ -1,5 +1,68 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod annotate;
+#[cfg(feature = "std")]
 pub mod applier;
+pub mod binary;
+pub mod buf;
+pub mod builder;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod compose;
+pub mod conflict;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod context;
+#[cfg(feature = "pretty-errors")]
+pub mod diagnostic;
+pub mod differ;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod event;
+pub mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
 pub mod fs;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+pub mod intraline;
 pub mod lexer;
+pub mod linemap;
+pub mod mail;
+#[cfg(feature = "manifest")]
+pub mod manifest;
+pub mod merge;
+pub mod minimize;
+pub mod normal;
+#[cfg(feature = "std")]
+pub mod options;
 pub mod parser;
+pub mod rebase;
+pub mod redact;
+pub mod render;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod repository;
+pub mod series;
+#[cfg(feature = "cli")]
+pub mod serve;
+#[cfg(feature = "signature")]
+pub mod signature;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod summary;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "std")]
+pub mod transaction;
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod whitespace;
@@ -0,0 +1,121 @@
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+  old_start: u32,
+  old_end: u32,
+  new_start: u32,
+  new_end: u32,
+  lines: Vec<(Option<u32>, Option<u32>)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineMap {
+  segments: Vec<Segment>,
+}
+
+impl<'a> Patch<'a> {
+  pub fn line_map(&self) -> LineMap {
+    let segments = self
+      .hunks
+      .iter()
+      .map(|hunk| {
+        let old_start = if hunk.old_span == 0 {
+          hunk.old_line + 1
+        } else {
+          hunk.old_line
+        };
+        let new_start = if hunk.new_span == 0 {
+          hunk.new_line + 1
+        } else {
+          hunk.new_line
+        };
+
+        let (mut old, mut new) = (old_start, new_start);
+        let mut lines = Vec::new();
+        for line in &hunk.lines {
+          match line {
+            Line::Context(_) => {
+              lines.push((Some(old), Some(new)));
+              old += 1;
+              new += 1;
+            }
+            Line::Deletion(_) => {
+              lines.push((Some(old), None));
+              old += 1;
+            }
+            Line::Addition(_) => {
+              lines.push((None, Some(new)));
+              new += 1;
+            }
+            Line::NoNewline => {}
+          }
+        }
+
+        Segment {
+          old_start,
+          old_end: old_start + hunk.old_span,
+          new_start,
+          new_end: new_start + hunk.new_span,
+          lines,
+        }
+      })
+      .collect();
+
+    LineMap { segments }
+  }
+}
+
+impl LineMap {
+  pub fn old_to_new(&self, line: u32) -> Option<u32> {
+    self.translate(line, true)
+  }
+
+  pub fn new_to_old(&self, line: u32) -> Option<u32> {
+    self.translate(line, false)
+  }
+
+  fn translate(&self, line: u32, forward: bool) -> Option<u32> {
+    if line == 0 {
+      return None;
+    }
+
+    let shift = |start: u32, target: u32| {
+      u32::try_from(line as i64 + target as i64 - start as i64).ok()
+    };
+
+    let mut offset = (1, 1);
+    for segment in &self.segments {
+      let (from_start, from_end, to_start, to_end) = if forward {
+        (
+          segment.old_start,
+          segment.old_end,
+          segment.new_start,
+          segment.new_end,
+        )
+      } else {
+        (
+          segment.new_start,
+          segment.new_end,
+          segment.old_start,
+          segment.old_end,
+        )
+      };
+
+      if line < from_start {
+        return shift(from_start, to_start);
+      }
+      if line < from_end {
+        return segment.lines.iter().find_map(|&(old, new)| {
+          let (from, to) = if forward { (old, new) } else { (new, old) };
+          (from == Some(line)).then_some(to)?
+        });
+      }
+      offset = (from_end, to_end);
+    }
+
+    shift(offset.0, offset.1)
+  }
+}
@@ -0,0 +1,216 @@
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mail<'a> {
+  pub author: Option<&'a str>,
+  pub date: Option<&'a str>,
+  pub subject: String,
+  pub message: String,
+  pub diff: &'a str,
+}
+
+impl<'a> Mail<'a> {
+  pub fn patches(&self) -> Parser<'a> {
+    Parser::new(self.diff)
+  }
+}
+
+pub struct MailParser<'a> {
+  mails: vec::IntoIter<Mail<'a>>,
+  current: Option<Parser<'a>>,
+  strip: Option<usize>,
+}
+
+pub fn is_mail(source: &str) -> bool {
+  source
+    .lines()
+    .find(|line| !line.trim().is_empty())
+    .is_some_and(|line| {
+      ["From ", "From: ", "Subject: "]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+    })
+}
+
+pub fn split(source: &str) -> Vec<Mail<'_>> {
+  let mut mails = Vec::new();
+  let (mut start, mut offset) = (0, 0);
+  for line in source.split_inclusive('\n') {
+    if line.starts_with("From ") && offset > start {
+      mails.push(parse_mail(&source[start..offset]));
+      start = offset;
+    }
+    offset += line.len();
+  }
+  if start < source.len() {
+    mails.push(parse_mail(&source[start..]));
+  }
+  mails
+}
+
+fn is_header(line: &str) -> bool {
+  ["From: ", "Date: ", "Subject: "]
+    .iter()
+    .any(|prefix| line.starts_with(prefix))
+}
+
+fn is_scissors(line: &str) -> bool {
+  let line = line.trim();
+  line.starts_with('-') && (line.contains(">8") || line.contains("8<"))
+}
+
+fn is_diff_start(line: &str) -> bool {
+  ["diff ", "--- ", "Index: "]
+    .iter()
+    .any(|prefix| line.starts_with(prefix))
+}
+
+fn strip_subject_tags(subject: &str) -> &str {
+  let mut subject = subject.trim();
+  while subject.starts_with('[')
+    && let Some(end) = subject.find(']')
+  {
+    subject = subject[end + 1..].trim_start();
+  }
+  subject
+}
+
+fn headers<'a>(
+  lines: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>,
+  mail: &mut Mail<'a>,
+) {
+  let mut folding = false;
+  for (_, line) in lines.by_ref() {
+    if line.is_empty() {
+      break;
+    }
+    if line.starts_with([' ', '\t']) {
+      if folding {
+        mail.subject.push(' ');
+        mail.subject.push_str(line.trim());
+      }
+      continue;
+    }
+    folding = false;
+    let Some((name, value)) = line.split_once(':') else {
+      continue;
+    };
+    let value = value.trim();
+    if name.eq_ignore_ascii_case("From") {
+      mail.author = Some(value);
+    } else if name.eq_ignore_ascii_case("Date") {
+      mail.date = Some(value);
+    } else if name.eq_ignore_ascii_case("Subject") {
+      mail.subject = value.into();
+      folding = true;
+    }
+  }
+}
+
+fn in_body_headers<'a>(
+  lines: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>,
+  mail: &mut Mail<'a>,
+) {
+  while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+  if lines.peek().is_some_and(|&(_, line)| is_header(line)) {
+    headers(lines, mail);
+  }
+}
+
+fn signature(diff: &str) -> usize {
+  let (mut offset, mut cut) = (0, diff.len());
+  for line in diff.split_inclusive('\n') {
+    let content = line.trim_end_matches(['\n', '\r']);
+    if content == "-- " {
+      cut = offset;
+    } else if content.starts_with(['+', '-', ' ', '@', '\\']) {
+      cut = diff.len();
+    }
+    offset += line.len();
+  }
+  cut
+}
+
+fn parse_mail(text: &str) -> Mail<'_> {
+  let mut mail = Mail::default();
+  let mut position = 0;
+  let mut lines = text
+    .split_inclusive('\n')
+    .map(|line| {
+      let start = position;
+      position += line.len();
+      (start, line.trim_end_matches(['\n', '\r']))
+    })
+    .peekable();
+
+  lines.next_if(|(_, line)| line.starts_with("From "));
+  headers(&mut lines, &mut mail);
+  in_body_headers(&mut lines, &mut mail);
+
+  let mut message = Vec::new();
+  let mut diff_start = text.len();
+  let mut separated = false;
+  while let Some((start, line)) = lines.next() {
+    if is_diff_start(line) {
+      diff_start = start;
+      break;
+    }
+    if separated {
+      continue;
+    }
+    if is_scissors(line) {
+      message.clear();
+      in_body_headers(&mut lines, &mut mail);
+    } else if line == "---" {
+      separated = true;
+    } else {
+      message.push(line);
+    }
+  }
+
+  let diff = &text[diff_start..];
+  mail.diff = &diff[..signature(diff)];
+  mail.subject = strip_subject_tags(&mail.subject).into();
+  mail.message = message.join("\n").trim().into();
+  mail
+}
+
+impl<'a> MailParser<'a> {
+  pub fn new(source: &'a str) -> Self {
+    Self {
+      mails: split(source).into_iter(),
+      current: None,
+      strip: None,
+    }
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = Some(components);
+    self
+  }
+}
+
+impl<'a> Iterator for MailParser<'a> {
+  type Item = Result<Patch<'a>, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(parser) = &mut self.current
+        && let Some(patch) = parser.next()
+      {
+        return Some(patch);
+      }
+      let parser = self.mails.next()?.patches();
+      self.current = Some(match self.strip {
+        Some(components) => parser.strip(components),
+        None => parser,
+      });
+    }
+  }
+}
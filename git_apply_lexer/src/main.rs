@@ -1,44 +1,744 @@
+use clap::Args;
 use clap::CommandFactory;
 use clap::Parser;
+use clap::Subcommand;
+use hit::annotate;
 use hit::applier;
+#[cfg(feature = "pretty-errors")]
+use hit::diagnostic;
 use hit::error::Error;
+use hit::explain;
 use hit::fs::OsFileSystem;
+#[cfg(feature = "manifest")]
+use hit::manifest::Manifest;
+#[cfg(feature = "manifest")]
+use hit::manifest::Side;
+use hit::options::ApplyOptions;
+use hit::options::LineEndings;
+use hit::options::Whitespace;
+use hit::options::WhitespacePolicy;
+use hit::parser;
+use hit::redact;
+use hit::redact::RedactOptions;
+use hit::render;
+use hit::render::ColorScheme;
+use hit::report::ApplyReport;
+use hit::report::FileStatus;
+use hit::repository::Eol;
+use hit::repository::Repository;
+use hit::repository::Revision;
+use hit::serve;
+#[cfg(feature = "signature")]
+use hit::signature;
+use hit::stats;
+use hit::stats::StatFormat;
+use hit::summary;
+use log::Level;
+use log::LevelFilter;
+use log::Log;
+use log::Metadata;
+use log::Record;
+use std::env;
 use std::fs;
 use std::io;
 use std::io::IsTerminal;
 use std::io::Read;
+use std::num::NonZeroUsize;
+#[cfg(feature = "signature")]
+use std::path::Path;
+use std::path::PathBuf;
 use std::process;
+use std::thread;
+
+const STAT_WIDTH: usize = 80;
+const FATAL_EXIT: i32 = 128;
+#[cfg(feature = "server")]
+const TOKEN_VARIABLE: &str = "HIT_SERVE_TOKEN";
+
+struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+  fn enabled(&self, metadata: &Metadata) -> bool {
+    metadata.level() <= Level::Info && metadata.target().starts_with("hit")
+  }
+
+  fn log(&self, record: &Record) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+    match record.level() {
+      Level::Info => println!("{}", record.args()),
+      _ => eprintln!("{}", record.args()),
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+  #[command(subcommand)]
+  command: Option<Command>,
   file: Option<String>,
+  #[command(flatten)]
+  apply: ApplyArgs,
+  #[arg(long)]
+  stat: bool,
+  #[arg(long, conflicts_with_all = [
+    "stat", "annotate", "three_way", "reject", "atomic", "dry_run", "check",
+  ])]
+  numstat: bool,
+  #[arg(long, conflicts_with_all = [
+    "stat", "numstat", "annotate", "three_way", "reject", "atomic", "dry_run",
+    "check",
+  ])]
+  shortstat: bool,
+  #[arg(long, conflicts_with_all = [
+    "annotate", "three_way", "reject", "atomic", "dry_run", "check",
+  ])]
+  summary: bool,
+  #[arg(long, conflicts_with_all = [
+    "stat", "numstat", "shortstat", "summary", "annotate", "three_way",
+    "reject", "atomic", "dry_run", "check",
+  ])]
+  name_only: bool,
+  #[arg(long, conflicts_with_all = [
+    "stat", "numstat", "shortstat", "summary", "name_only", "annotate",
+    "three_way", "reject", "atomic", "dry_run", "check",
+  ])]
+  name_status: bool,
+  #[arg(long, conflicts_with = "stat")]
+  annotate: bool,
+  #[arg(long = "3way", conflicts_with_all = ["stat", "annotate"])]
+  three_way: bool,
+  #[arg(long, conflicts_with_all = ["stat", "annotate"])]
+  reject: bool,
+  #[arg(long, conflicts_with_all = ["stat", "annotate"])]
+  atomic: bool,
+  #[arg(long, conflicts_with_all = ["stat", "annotate"])]
+  dry_run: bool,
+  #[arg(long, conflicts_with_all = [
+    "stat", "annotate", "three_way", "reject", "atomic", "dry_run",
+  ])]
+  check: bool,
+  #[cfg(feature = "manifest")]
+  #[arg(long, value_name = "FILE", conflicts_with = "stat")]
+  manifest: Option<String>,
+  #[cfg(feature = "signature")]
+  #[arg(long, value_name = "KEY")]
+  verify_key: Option<String>,
+  #[cfg(feature = "signature")]
+  #[arg(long, value_name = "FILE", requires = "verify_key")]
+  signature: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct ApplyArgs {
   #[arg(short, long)]
   reverse: bool,
+  #[arg(long)]
+  repo_root: bool,
+  #[arg(short = 'p', value_name = "N")]
+  strip: Option<usize>,
+  #[arg(long, value_name = "ROOT", conflicts_with = "repo_root")]
+  directory: Option<PathBuf>,
+  #[arg(long)]
+  ignore_space_at_eol: bool,
+  #[arg(
+    short = 'b',
+    long,
+    visible_alias = "ignore-whitespace",
+    conflicts_with = "ignore_space_at_eol"
+  )]
+  ignore_space_change: bool,
+  #[arg(
+    short = 'w',
+    long,
+    conflicts_with_all = ["ignore_space_at_eol", "ignore_space_change"]
+  )]
+  ignore_all_space: bool,
+  #[arg(
+    long,
+    value_name = "ACTION",
+    value_parser = ["nowarn", "warn", "fix", "error"]
+  )]
+  whitespace: Option<String>,
+  #[arg(
+    long,
+    value_name = "MODE",
+    value_parser = ["strict", "ignore-eol", "auto"]
+  )]
+  line_endings: Option<String>,
+  #[arg(long, value_name = "EOL", value_parser = ["lf", "crlf"])]
+  eol: Option<String>,
+  #[arg(long)]
+  skip_applied: bool,
+  #[arg(long, conflicts_with_all = ["reverse", "skip_applied"])]
+  auto_reverse: bool,
+  #[arg(long, value_name = "FILE")]
+  target: Option<String>,
+  #[arg(long)]
+  lenient: bool,
 }
 
-fn run() -> Result<(), Error> {
-  let cli = Cli::parse();
+#[derive(Subcommand, Debug)]
+enum Command {
+  Stat {
+    file: Option<String>,
+  },
+  Check {
+    files: Vec<String>,
+    #[arg(long, value_name = "COMMIT")]
+    rev: Option<String>,
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+  },
+  #[cfg(feature = "manifest")]
+  Verify {
+    manifest: String,
+  },
+  Explain {
+    file: Option<String>,
+  },
+  Redact {
+    file: Option<String>,
+    #[arg(long)]
+    hash_paths: bool,
+  },
+  Serve {
+    #[arg(long)]
+    stdio: bool,
+    #[cfg(feature = "server")]
+    #[arg(long, conflicts_with = "stdio")]
+    http: Option<String>,
+    #[cfg(feature = "server")]
+    #[arg(long, default_value = ".")]
+    root: String,
+    #[cfg(feature = "server")]
+    #[arg(long)]
+    token: Option<String>,
+  },
+}
+
+fn read_patch(file: Option<&str>) -> Result<Option<String>, Error> {
+  if let Some(path_str) = file {
+    return Ok(Some(fs::read_to_string(path_str)?));
+  }
+
+  if io::stdin().is_terminal() {
+    Cli::command().print_help().map_err(Error::from)?;
+    return Ok(None);
+  }
+  let mut buffer = String::new();
+  io::stdin().read_to_string(&mut buffer)?;
+  Ok(Some(buffer))
+}
+
+fn print_stat(patch_content: &str, format: StatFormat) -> Result<(), Error> {
+  let stats = parser::Parser::new(patch_content).stats()?;
+  print!("{}", stats::render(&stats, format, STAT_WIDTH));
+  Ok(())
+}
+
+fn print_summary(patch_content: &str) -> Result<(), Error> {
+  let patches =
+    parser::Parser::new(patch_content).collect::<Result<Vec<_>, Error>>()?;
+  print!("{}", summary::render(&patches));
+  Ok(())
+}
+
+fn print_names(patch_content: &str, status: bool) -> Result<(), Error> {
+  let patches =
+    parser::Parser::new(patch_content).collect::<Result<Vec<_>, Error>>()?;
+  if status {
+    print!("{}", summary::render_name_status(&patches));
+  } else {
+    print!("{}", summary::render_name_only(&patches));
+  }
+  Ok(())
+}
 
-  let patch_content = if let Some(path_str) = cli.file {
-    fs::read_to_string(path_str)?
+fn stat_format(cli: &Cli) -> Option<StatFormat> {
+  if cli.numstat {
+    Some(StatFormat::Numstat)
+  } else if cli.shortstat {
+    Some(StatFormat::Shortstat)
   } else {
-    if io::stdin().is_terminal() {
-      Cli::command().print_help().map_err(Error::from)?;
-      return Ok(());
+    cli.stat.then_some(StatFormat::Stat)
+  }
+}
+
+fn apply_options(args: &ApplyArgs) -> Result<ApplyOptions, Error> {
+  let mut options = ApplyOptions::new()
+    .reverse(args.reverse)
+    .repository_root(args.repo_root)
+    .skip_applied(args.skip_applied)
+    .auto_reverse(args.auto_reverse)
+    .lenient(args.lenient);
+  if let Some(components) = args.strip {
+    options = options.strip(components);
+  }
+  if let Some(directory) = &args.directory {
+    options = options.root(directory);
+  }
+  if let Some(target) = &args.target {
+    options = options.target(target);
+  }
+  if args.ignore_space_at_eol {
+    options = options.whitespace(Whitespace::IgnoreTrailing);
+  } else if args.ignore_space_change {
+    options = options.whitespace(Whitespace::IgnoreChanges);
+  } else if args.ignore_all_space {
+    options = options.whitespace(Whitespace::IgnoreAll);
+  }
+  options = options.whitespace_policy(match args.whitespace.as_deref() {
+    Some("warn") => WhitespacePolicy::Warn,
+    Some("fix") => WhitespacePolicy::Fix,
+    Some("error") => WhitespacePolicy::Error,
+    _ => WhitespacePolicy::Nowarn,
+  });
+  options = options.line_endings(match args.line_endings.as_deref() {
+    Some("ignore-eol") => LineEndings::IgnoreEol,
+    Some("auto") => LineEndings::Auto,
+    _ => LineEndings::Strict,
+  });
+  match args.eol.as_deref() {
+    Some("lf") => options = options.eol(Eol::Lf),
+    Some("crlf") => options = options.eol(Eol::Crlf),
+    _ => {}
+  }
+  let repository = Repository::discover(&OsFileSystem, &env::current_dir()?);
+  match repository {
+    Some(repository) => Ok(options.repository(repository)),
+    None if args.repo_root => Err(Error::NoRepository("--repo-root".into())),
+    None => Ok(options),
+  }
+}
+
+fn run(
+  patch_content: &str,
+  annotate: bool,
+  three_way: bool,
+  options: &ApplyOptions,
+) -> Result<bool, Error> {
+  if annotate {
+    print!(
+      "{}",
+      annotate::preview(&OsFileSystem, patch_content, options)?
+    );
+    return Ok(true);
+  }
+  let report = if three_way {
+    let Some(repository) = &options.repository else {
+      return Err(Error::NoRepository("--3way".into()));
+    };
+    applier::patch_three_way(
+      &mut OsFileSystem,
+      patch_content,
+      options,
+      repository,
+      &mut (),
+    )?
+  } else {
+    applier::patch(&mut OsFileSystem, patch_content, options, &mut ())?
+  };
+  print_report(&report, options);
+  Ok(!report.has_rejects())
+}
+
+fn print_report(report: &ApplyReport, options: &ApplyOptions) {
+  let fixed = options.whitespace_policy == WhitespacePolicy::Fix;
+  for file in &report.files {
+    for issue in &file.whitespace {
+      eprintln!(
+        "warning: {}:{}: {}{}",
+        file.path.display(),
+        issue.line,
+        issue.kind,
+        if fixed { " (fixed)" } else { "" }
+      );
+    }
+    match file.status {
+      FileStatus::Skipped => continue,
+      FileStatus::AlreadyApplied => {
+        println!("Already applied: {}", file.path.display());
+      }
+      FileStatus::Deleted => println!("Deleted file: {}", file.path.display()),
+      _ if file.reversed => {
+        println!("Applied reversed patch to: {}", file.path.display());
+      }
+      _ => println!("Applied patch to: {}", file.path.display()),
     }
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer)?;
-    buffer
+    for offset in &file.offsets {
+      println!(
+        "Hunk #{} applied at line {} (offset {} lines, fuzz {})",
+        offset.hunk, offset.line, offset.offset, offset.fuzz
+      );
+    }
+  }
+}
+
+fn needs_whole_patch(cli: &Cli) -> bool {
+  if cli.annotate
+    || cli.summary
+    || cli.name_only
+    || cli.name_status
+    || cli.three_way
+    || cli.reject
+    || cli.atomic
+    || cli.dry_run
+    || cli.check
+  {
+    return true;
+  }
+  #[cfg(feature = "signature")]
+  if cli.verify_key.is_some() {
+    return true;
+  }
+  #[cfg(feature = "manifest")]
+  if cli.manifest.is_some() {
+    return true;
+  }
+  false
+}
+
+fn run_streaming(args: &ApplyArgs) {
+  let options = apply_options(args).unwrap_or_else(|e| fail(&e, None));
+  let mut section = None;
+  let patches = options.reader(io::stdin().lock()).map(|patch| {
+    let patch = patch?.as_patch().into_owned();
+    section = Some(patch.clone());
+    Ok(patch)
+  });
+  match applier::apply_stream(&mut OsFileSystem, patches, &options, &mut ()) {
+    Ok(report) => print_report(&report, &options),
+    Err(e) => {
+      let text =
+        section.map(|patch| render::pretty(&patch, ColorScheme::plain()));
+      fail(&e, text.as_deref())
+    }
+  }
+}
+
+fn check(
+  patch_content: &str,
+  args: &ApplyArgs,
+  rev: Option<&str>,
+) -> Result<bool, Error> {
+  let options = apply_options(args)?;
+  let issues = match revision(rev, &options)? {
+    Some(revision) => applier::check(&revision, patch_content, &options)?,
+    None => applier::check(&OsFileSystem, patch_content, &options)?,
   };
 
-  applier::patch(&mut OsFileSystem, &patch_content, cli.reverse)?;
+  for issue in &issues {
+    println!("{}", issue);
+  }
+  Ok(issues.is_empty())
+}
+
+fn check_files(
+  files: &[String],
+  args: &ApplyArgs,
+  rev: Option<&str>,
+  jobs: Option<NonZeroUsize>,
+) -> Result<bool, Error> {
+  let patches = files
+    .iter()
+    .map(fs::read_to_string)
+    .collect::<Result<Vec<_>, _>>()?;
+  let jobs = jobs
+    .or_else(|| thread::available_parallelism().ok())
+    .map_or(1, NonZeroUsize::get);
+  let options = apply_options(args)?;
+  let results = match revision(rev, &options)? {
+    Some(revision) => applier::check_many(&revision, &patches, &options, jobs),
+    None => applier::check_many(&OsFileSystem, &patches, &options, jobs),
+  };
+
+  let mut clean = true;
+  for (file, result) in files.iter().zip(results) {
+    match result {
+      Ok(issues) => {
+        for issue in &issues {
+          println!("{}: {}", file, issue);
+        }
+        clean &= issues.is_empty();
+      }
+      Err(e) => {
+        println!("{}: {}", file, e);
+        clean = false;
+      }
+    }
+  }
+  Ok(clean)
+}
+
+fn revision(
+  rev: Option<&str>,
+  options: &ApplyOptions,
+) -> Result<Option<Revision>, Error> {
+  match (rev, &options.repository) {
+    (Some(rev), Some(repository)) => {
+      Revision::resolve(repository.clone(), rev).map(Some)
+    }
+    (Some(_), None) => Err(Error::NoRepository("--rev".into())),
+    (None, _) => Ok(None),
+  }
+}
+
+#[cfg(feature = "manifest")]
+fn record_manifest(
+  patch_content: &str,
+  args: &ApplyArgs,
+  output: &str,
+) -> Result<(), Error> {
+  let options = apply_options(args)?;
+  let manifest = Manifest::record(&mut OsFileSystem, patch_content, &options)?;
+  fs::write(output, manifest.to_string())?;
   Ok(())
 }
 
+#[cfg(feature = "manifest")]
+fn verify_manifest(path: &str) -> Result<bool, Error> {
+  let manifest = Manifest::parse(&fs::read_to_string(path)?)?;
+  match manifest.verify(&OsFileSystem)? {
+    Some(Side::Before) => println!("Tree matches the pre-apply checksums"),
+    Some(Side::After) => println!("Tree matches the post-apply checksums"),
+    None => {
+      for path in manifest.mismatches(&OsFileSystem, Side::After)? {
+        println!("Checksum mismatch: {}", path.display());
+      }
+      return Ok(false);
+    }
+  }
+  Ok(true)
+}
+
+#[cfg(feature = "signature")]
+fn verify_signature(
+  patch_content: &str,
+  key: &str,
+  signature: Option<&str>,
+) -> Result<(), Error> {
+  let Some(signature) = signature else {
+    return Err(Error::Signature(
+      "Reading from stdin requires --signature <FILE>".into(),
+    ));
+  };
+  let signature = match fs::read_to_string(signature) {
+    Ok(signature) => signature,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+      return Err(Error::Signature(
+        format!("Missing signature file: `{}`", signature).into(),
+      ));
+    }
+    Err(e) => return Err(e.into()),
+  };
+  let key = if Path::new(key).is_file() {
+    fs::read_to_string(key)?
+  } else {
+    key.to_string()
+  };
+
+  signature::verify(patch_content, &key, &signature)
+}
+
+fn fail(error: &Error, patch_content: Option<&str>) -> ! {
+  fail_with(error, patch_content, 1)
+}
+
+#[cfg_attr(not(feature = "pretty-errors"), allow(unused_variables))]
+fn fail_with(error: &Error, patch_content: Option<&str>, code: i32) -> ! {
+  #[cfg(feature = "pretty-errors")]
+  if let Some(patch_content) = patch_content {
+    let target = match error {
+      Error::UnexpectedEof { file, .. }
+      | Error::HunkMismatch { file, .. }
+      | Error::ExpectedEof { file, .. } => fs::read_to_string(file).ok(),
+      _ => None,
+    };
+    if let Some(report) =
+      diagnostic::report(error, patch_content, target.as_deref())
+    {
+      eprint!("{}", report.render());
+      process::exit(code);
+    }
+  }
+
+  eprintln!("Error: {}", error);
+  process::exit(code);
+}
+
 fn main() {
-  if let Err(e) = run() {
-    eprintln!("Error: {}", e);
-    process::exit(1);
+  if log::set_logger(&LOGGER).is_ok() {
+    log::set_max_level(LevelFilter::Info);
+  }
+
+  let cli = Cli::parse();
+  let needs_whole_patch = needs_whole_patch(&cli);
+  let stat_format = stat_format(&cli);
+  let (file, stat) = match cli.command {
+    Some(Command::Stat { file }) => (file, Some(StatFormat::Stat)),
+    Some(Command::Check { files, rev, jobs }) if files.len() > 1 => {
+      match check_files(&files, &cli.apply, rev.as_deref(), jobs) {
+        Ok(true) => return,
+        Ok(false) => process::exit(1),
+        Err(e) => fail_with(&e, None, FATAL_EXIT),
+      }
+    }
+    Some(Command::Check { files, rev, .. }) => {
+      let patch_content = match read_patch(files.first().map(String::as_str)) {
+        Ok(Some(patch_content)) => patch_content,
+        Ok(None) => return,
+        Err(e) => fail_with(&e, None, FATAL_EXIT),
+      };
+      match check(&patch_content, &cli.apply, rev.as_deref()) {
+        Ok(true) => return,
+        Ok(false) => process::exit(1),
+        Err(e) => fail_with(&e, Some(&patch_content), FATAL_EXIT),
+      }
+    }
+    #[cfg(feature = "manifest")]
+    Some(Command::Verify { manifest }) => match verify_manifest(&manifest) {
+      Ok(true) => return,
+      Ok(false) => process::exit(1),
+      Err(e) => fail(&e, None),
+    },
+    Some(Command::Explain { file }) => {
+      let patch_content = match read_patch(file.as_deref()) {
+        Ok(Some(patch_content)) => patch_content,
+        Ok(None) => return,
+        Err(e) => fail(&e, None),
+      };
+      match explain::explain(&patch_content) {
+        Ok(explanation) => print!("{}", explanation),
+        Err(e) => fail(&e, Some(&patch_content)),
+      }
+      return;
+    }
+    Some(Command::Redact { file, hash_paths }) => {
+      let patch_content = match read_patch(file.as_deref()) {
+        Ok(Some(patch_content)) => patch_content,
+        Ok(None) => return,
+        Err(e) => fail(&e, None),
+      };
+      let options = RedactOptions::new().hash_paths(hash_paths);
+      match redact::redact(&patch_content, options) {
+        Ok(redacted) => print!("{}", redacted),
+        Err(e) => fail(&e, Some(&patch_content)),
+      }
+      return;
+    }
+    Some(Command::Serve { stdio, .. }) if stdio => {
+      log::set_max_level(LevelFilter::Off);
+      let result =
+        serve::serve(&mut OsFileSystem, io::stdin().lock(), io::stdout());
+      if let Err(e) = result {
+        fail(&Error::from(e), None);
+      }
+      return;
+    }
+    #[cfg(feature = "server")]
+    Some(Command::Serve {
+      http: Some(address),
+      root,
+      token,
+      ..
+    }) => {
+      let config = serve::HttpConfig {
+        root: root.into(),
+        token: token.or_else(|| env::var(TOKEN_VARIABLE).ok()),
+      };
+      if let Err(e) = serve::serve_http(&mut OsFileSystem, &address, &config) {
+        fail(&Error::from(e), None);
+      }
+      return;
+    }
+    Some(Command::Serve { .. }) => fail(
+      &Error::Clap("`serve` requires --stdio or --http <addr>".to_string()),
+      None,
+    ),
+    None => (cli.file, stat_format),
+  };
+
+  if file.is_none()
+    && stat.is_none()
+    && !needs_whole_patch
+    && !io::stdin().is_terminal()
+  {
+    run_streaming(&cli.apply);
+    return;
+  }
+
+  let patch_content = match read_patch(file.as_deref()) {
+    Ok(Some(patch_content)) => patch_content,
+    Ok(None) => return,
+    Err(e) => fail(&e, None),
+  };
+
+  #[cfg(feature = "signature")]
+  if let Some(key) = &cli.verify_key {
+    let signature = cli
+      .signature
+      .or_else(|| file.map(|file| format!("{}.minisig", file)));
+    if let Err(e) = verify_signature(&patch_content, key, signature.as_deref())
+    {
+      fail(&e, None);
+    }
+  }
+
+  #[cfg(feature = "manifest")]
+  if let Some(manifest) = &cli.manifest {
+    if let Err(e) = record_manifest(&patch_content, &cli.apply, manifest) {
+      fail(&e, Some(&patch_content));
+    }
+    return;
+  }
+
+  if cli.check {
+    match check(&patch_content, &cli.apply, None) {
+      Ok(true) => return,
+      Ok(false) => process::exit(1),
+      Err(e) => fail_with(&e, Some(&patch_content), FATAL_EXIT),
+    }
+  }
+
+  if cli.name_only || cli.name_status {
+    if let Err(e) = print_names(&patch_content, cli.name_status) {
+      fail(&e, Some(&patch_content));
+    }
+    return;
+  }
+
+  if stat.is_some() || cli.summary {
+    if let Some(format) = stat
+      && let Err(e) = print_stat(&patch_content, format)
+    {
+      fail(&e, Some(&patch_content));
+    }
+    if cli.summary
+      && let Err(e) = print_summary(&patch_content)
+    {
+      fail(&e, Some(&patch_content));
+    }
+    return;
+  }
+
+  let options = match apply_options(&cli.apply) {
+    Ok(options) => options
+      .reject(cli.reject)
+      .atomic(cli.atomic)
+      .dry_run(cli.dry_run),
+    Err(e) => fail(&e, None),
+  };
+  match run(&patch_content, cli.annotate, cli.three_way, &options) {
+    Ok(true) => {}
+    Ok(false) => process::exit(1),
+    Err(e) => fail(&e, Some(&patch_content)),
   }
 }
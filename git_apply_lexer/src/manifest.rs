@@ -0,0 +1,208 @@
+use crate::applier;
+use crate::error::Error;
+use crate::fs::FileSystem;
+use crate::options::ApplyOptions;
+use sha2::Digest;
+use sha2::Sha256;
+use std::fmt;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+const ABSENT: &str = "-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+  Before,
+  After,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+  pub path: PathBuf,
+  pub before: Option<String>,
+  pub after: Option<String>,
+}
+
+impl ManifestEntry {
+  pub fn checksum(&self, side: Side) -> Option<&str> {
+    match side {
+      Side::Before => self.before.as_deref(),
+      Side::After => self.after.as_deref(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+  pub entries: Vec<ManifestEntry>,
+}
+
+pub fn checksum(contents: &str) -> String {
+  Sha256::digest(contents.as_bytes()).iter().fold(
+    String::with_capacity(64),
+    |mut hex, byte| {
+      let _ = write!(hex, "{:02x}", byte);
+      hex
+    },
+  )
+}
+
+fn checksum_at(
+  fs: &impl FileSystem,
+  path: &Path,
+) -> Result<Option<String>, Error> {
+  match fs.read_to_string(path) {
+    Ok(contents) => Ok(Some(checksum(&contents))),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(Error::io_at(e, path)),
+  }
+}
+
+fn touched_paths(
+  patch_content: &str,
+  options: &ApplyOptions,
+) -> Result<Vec<PathBuf>, Error> {
+  let mut paths = Vec::new();
+  for patch in options.parser(patch_content) {
+    let patch = patch?;
+    let patch = if options.reverse {
+      patch.invert()
+    } else {
+      patch
+    };
+
+    let target = if patch.new_file == "/dev/null" {
+      &patch.old_file
+    } else {
+      &patch.new_file
+    };
+    if !options.is_selected(target) {
+      continue;
+    }
+
+    let source = patch.copy_from.as_deref().unwrap_or(&patch.old_file);
+    for file in [source, &patch.new_file] {
+      if file == "/dev/null" {
+        continue;
+      }
+      let path = options.resolve(file)?;
+      if !paths.contains(&path) {
+        paths.push(path);
+      }
+    }
+  }
+  Ok(paths)
+}
+
+impl Manifest {
+  pub fn record(
+    fs: &mut impl FileSystem,
+    patch_content: &str,
+    options: &ApplyOptions,
+  ) -> Result<Self, Error> {
+    let paths = touched_paths(patch_content, options)?;
+    let before = paths
+      .iter()
+      .map(|path| checksum_at(fs, path))
+      .collect::<Result<Vec<_>, Error>>()?;
+
+    applier::patch(fs, patch_content, options, &mut ())?;
+
+    let entries = paths
+      .into_iter()
+      .zip(before)
+      .map(|(path, before)| {
+        let after = checksum_at(fs, &path)?;
+        Ok(ManifestEntry {
+          path,
+          before,
+          after,
+        })
+      })
+      .collect::<Result<_, Error>>()?;
+    Ok(Self { entries })
+  }
+
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    let entries = text
+      .lines()
+      .enumerate()
+      .filter(|(_, line)| !line.trim().is_empty())
+      .map(|(index, line)| {
+        let mut fields = line.splitn(3, ' ');
+        let (Some(before), Some(after), Some(path)) =
+          (fields.next(), fields.next(), fields.next())
+        else {
+          return Err(Error::Manifest(
+            format!("Line {} is not `<before> <after> <path>`", index + 1)
+              .into(),
+          ));
+        };
+        let digest = |field: &str| {
+          if field == ABSENT {
+            Ok(None)
+          } else if field.len() == 64
+            && field.bytes().all(|byte| byte.is_ascii_hexdigit())
+          {
+            Ok(Some(field.to_ascii_lowercase()))
+          } else {
+            Err(Error::Manifest(
+              format!(
+                "Line {} has an invalid checksum: `{}`",
+                index + 1,
+                field
+              )
+              .into(),
+            ))
+          }
+        };
+        Ok(ManifestEntry {
+          path: PathBuf::from(path),
+          before: digest(before)?,
+          after: digest(after)?,
+        })
+      })
+      .collect::<Result<_, Error>>()?;
+    Ok(Self { entries })
+  }
+
+  pub fn mismatches(
+    &self,
+    fs: &impl FileSystem,
+    side: Side,
+  ) -> Result<Vec<&Path>, Error> {
+    let mut mismatches = Vec::new();
+    for entry in &self.entries {
+      if checksum_at(fs, &entry.path)?.as_deref() != entry.checksum(side) {
+        mismatches.push(entry.path.as_path());
+      }
+    }
+    Ok(mismatches)
+  }
+
+  pub fn verify(&self, fs: &impl FileSystem) -> Result<Option<Side>, Error> {
+    for side in [Side::After, Side::Before] {
+      if self.mismatches(fs, side)?.is_empty() {
+        return Ok(Some(side));
+      }
+    }
+    Ok(None)
+  }
+}
+
+impl fmt::Display for Manifest {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for entry in &self.entries {
+      writeln!(
+        f,
+        "{} {} {}",
+        entry.before.as_deref().unwrap_or(ABSENT),
+        entry.after.as_deref().unwrap_or(ABSENT),
+        entry.path.display()
+      )?;
+    }
+    Ok(())
+  }
+}
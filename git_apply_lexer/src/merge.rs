@@ -0,0 +1,182 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+const OURS_MARKER: &str = "<<<<<<< ours\n";
+const SEPARATOR: &str = "=======\n";
+const THEIRS_MARKER: &str = ">>>>>>> theirs\n";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+  pub content: String,
+  pub conflicts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Change {
+  base: Range<usize>,
+  side: Range<usize>,
+}
+
+fn diff(base: &[&str], side: &[&str]) -> Vec<Change> {
+  let prefix = base
+    .iter()
+    .zip(side)
+    .take_while(|(base, side)| base == side)
+    .count();
+  let suffix = base[prefix..]
+    .iter()
+    .rev()
+    .zip(side[prefix..].iter().rev())
+    .take_while(|(base, side)| base == side)
+    .count();
+  let old = &base[prefix..base.len() - suffix];
+  let new = &side[prefix..side.len() - suffix];
+
+  let (rows, cols) = (old.len(), new.len());
+  let mut table = vec![0u32; (rows + 1) * (cols + 1)];
+  let at = |i: usize, j: usize| i * (cols + 1) + j;
+  for i in (0..rows).rev() {
+    for j in (0..cols).rev() {
+      table[at(i, j)] = if old[i] == new[j] {
+        table[at(i + 1, j + 1)] + 1
+      } else {
+        table[at(i + 1, j)].max(table[at(i, j + 1)])
+      };
+    }
+  }
+
+  let mut changes = Vec::new();
+  let mut push = |base: Range<usize>, side: Range<usize>| {
+    if !base.is_empty() || !side.is_empty() {
+      changes.push(Change {
+        base: prefix + base.start..prefix + base.end,
+        side: prefix + side.start..prefix + side.end,
+      });
+    }
+  };
+  let (mut i, mut j) = (0, 0);
+  let (mut start_i, mut start_j) = (0, 0);
+  while i < rows && j < cols {
+    if old[i] == new[j] {
+      push(start_i..i, start_j..j);
+      i += 1;
+      j += 1;
+      (start_i, start_j) = (i, j);
+    } else if table[at(i + 1, j)] >= table[at(i, j + 1)] {
+      i += 1;
+    } else {
+      j += 1;
+    }
+  }
+  push(start_i..rows, start_j..cols);
+  changes
+}
+
+struct Side<'s> {
+  lines: Vec<&'s str>,
+  changes: Vec<Change>,
+  next: usize,
+  offset: isize,
+}
+
+impl<'s> Side<'s> {
+  fn new(base: &[&str], text: &'s str) -> Self {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let changes = diff(base, &lines);
+    Self {
+      lines,
+      changes,
+      next: 0,
+      offset: 0,
+    }
+  }
+
+  fn peek(&self) -> Option<&Change> {
+    self.changes.get(self.next)
+  }
+
+  fn absorb(&mut self, region: &mut Range<usize>) -> bool {
+    let mut absorbed = false;
+    while let Some(change) = self.changes.get(self.next)
+      && change.base.start <= region.end
+    {
+      region.end = region.end.max(change.base.end);
+      self.offset += change.side.len() as isize - change.base.len() as isize;
+      self.next += 1;
+      absorbed = true;
+    }
+    absorbed
+  }
+
+  fn text(&self, region: &Range<usize>, offset: isize) -> &[&'s str] {
+    let start = region.start.saturating_add_signed(offset);
+    let end = region.end.saturating_add_signed(self.offset);
+    &self.lines[start..end]
+  }
+}
+
+fn push_lines(content: &mut String, lines: &[&str]) {
+  for line in lines {
+    content.push_str(line);
+  }
+}
+
+fn push_conflict_side(content: &mut String, lines: &[&str]) {
+  push_lines(content, lines);
+  if !content.ends_with('\n') {
+    content.push('\n');
+  }
+}
+
+pub fn merge(base: &str, ours: &str, theirs: &str) -> Merged {
+  let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+  let mut ours = Side::new(&base_lines, ours);
+  let mut theirs = Side::new(&base_lines, theirs);
+
+  let mut merged = Merged {
+    content: String::with_capacity(base.len()),
+    conflicts: 0,
+  };
+  let mut cursor = 0;
+  loop {
+    let start = match (ours.peek(), theirs.peek()) {
+      (None, None) => break,
+      (Some(change), None) | (None, Some(change)) => change.base.start,
+      (Some(a), Some(b)) => a.base.start.min(b.base.start),
+    };
+    push_lines(&mut merged.content, &base_lines[cursor..start]);
+
+    let (ours_offset, theirs_offset) = (ours.offset, theirs.offset);
+    let mut region = start..start;
+    let (mut ours_changed, mut theirs_changed) = (false, false);
+    loop {
+      let absorbed_ours = ours.absorb(&mut region);
+      let absorbed_theirs = theirs.absorb(&mut region);
+      ours_changed |= absorbed_ours;
+      theirs_changed |= absorbed_theirs;
+      if !absorbed_ours && !absorbed_theirs {
+        break;
+      }
+    }
+
+    let ours_text = ours.text(&region, ours_offset);
+    let theirs_text = theirs.text(&region, theirs_offset);
+    if !theirs_changed || ours_text == theirs_text {
+      push_lines(&mut merged.content, ours_text);
+    } else if !ours_changed {
+      push_lines(&mut merged.content, theirs_text);
+    } else {
+      merged.conflicts += 1;
+      merged.content.push_str(OURS_MARKER);
+      push_conflict_side(&mut merged.content, ours_text);
+      merged.content.push_str(SEPARATOR);
+      push_conflict_side(&mut merged.content, theirs_text);
+      merged.content.push_str(THEIRS_MARKER);
+    }
+    cursor = region.end;
+  }
+  push_lines(&mut merged.content, &base_lines[cursor..]);
+  merged
+}
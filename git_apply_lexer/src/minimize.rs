@@ -0,0 +1,102 @@
+use crate::compose::cancel_reverted;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::vec::Vec;
+
+impl<'a> Patch<'a> {
+  pub fn minimize(&self, context: u32) -> Patch<'a> {
+    Patch {
+      hunks: self
+        .hunks
+        .iter()
+        .flat_map(|hunk| hunk.minimize(context as usize))
+        .collect(),
+      ..self.clone()
+    }
+  }
+}
+
+impl<'a> Hunk<'a> {
+  fn minimize(&self, context: usize) -> Vec<Hunk<'a>> {
+    let lines = cancel_reverted(self.lines.clone());
+    let is_anchor = |index: usize| match lines[index] {
+      Line::Deletion(_) | Line::Addition(_) => true,
+      Line::Context(_) => {
+        matches!(lines.get(index + 1), Some(Line::NoNewline))
+      }
+      Line::NoNewline => false,
+    };
+
+    let anchors: Vec<usize> =
+      (0..lines.len()).filter(|&index| is_anchor(index)).collect();
+    let keep: Vec<bool> = (0..lines.len())
+      .map(|index| {
+        anchors
+          .iter()
+          .any(|&anchor| anchor.abs_diff(index) <= context)
+          || (matches!(lines[index], Line::NoNewline)
+            && index > 0
+            && anchors.contains(&(index - 1)))
+      })
+      .collect();
+
+    let mut old = if self.old_span == 0 {
+      self.old_line + 1
+    } else {
+      self.old_line
+    };
+    let mut new = if self.new_span == 0 {
+      self.new_line + 1
+    } else {
+      self.new_line
+    };
+
+    let mut hunks = Vec::new();
+    let mut current: Option<Hunk<'a>> = None;
+    for (line, keep) in lines.into_iter().zip(keep) {
+      let (old_step, new_step) = match line {
+        Line::Context(_) => (1, 1),
+        Line::Deletion(_) => (1, 0),
+        Line::Addition(_) => (0, 1),
+        Line::NoNewline => (0, 0),
+      };
+
+      if keep {
+        let hunk = current.get_or_insert_with(|| Hunk {
+          old_line: old,
+          new_line: new,
+          ..Default::default()
+        });
+        hunk.old_span += old_step;
+        hunk.new_span += new_step;
+        hunk.lines.push(line);
+      } else if let Some(hunk) = current.take() {
+        hunks.push(hunk);
+      }
+
+      old += old_step;
+      new += new_step;
+    }
+    hunks.extend(current);
+
+    hunks
+      .into_iter()
+      .filter(|hunk| {
+        hunk
+          .lines
+          .iter()
+          .any(|line| matches!(line, Line::Deletion(_) | Line::Addition(_)))
+      })
+      .map(|mut hunk| {
+        if hunk.old_span == 0 {
+          hunk.old_line -= 1;
+        }
+        if hunk.new_span == 0 {
+          hunk.new_line -= 1;
+        }
+        hunk
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,185 @@
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use core::str::Lines;
+
+#[derive(Debug, Clone, Copy)]
+struct Command {
+  old: (u32, u32),
+  action: u8,
+  new: (u32, u32),
+}
+
+pub struct NormalParser<'a> {
+  lines: Peekable<Lines<'a>>,
+  strip: usize,
+  target: Option<Cow<'a, str>>,
+}
+
+pub fn is_normal_diff(source: &str) -> bool {
+  source
+    .lines()
+    .find(|line| !line.starts_with("diff "))
+    .and_then(parse_command)
+    .is_some()
+}
+
+fn malformed(line: &str) -> Error {
+  Error::Parse(format!("Malformed normal diff line: `{}`", line).into())
+}
+
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+  let number = |s: &str| {
+    s.bytes()
+      .all(|byte| byte.is_ascii_digit())
+      .then(|| s.parse().ok())
+      .flatten()
+  };
+  match range.split_once(',') {
+    Some((start, end)) => Some((number(start)?, number(end)?)),
+    None => number(range).map(|line| (line, line)),
+  }
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+  let index = line.find(['a', 'c', 'd'])?;
+  let (old, rest) = line.split_at(index);
+  let command = Command {
+    old: parse_range(old)?,
+    action: rest.as_bytes()[0],
+    new: parse_range(&rest[1..])?,
+  };
+  let single = |(start, end): (u32, u32)| start == end;
+  match command.action {
+    b'a' if !single(command.old) => None,
+    b'd' if !single(command.new) => None,
+    _ => Some(command),
+  }
+}
+
+impl<'a> NormalParser<'a> {
+  pub fn new(source: &'a str) -> Self {
+    Self {
+      lines: source.lines().peekable(),
+      strip: 0,
+      target: None,
+    }
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = components;
+    self
+  }
+
+  pub fn target(mut self, path: impl Into<Cow<'a, str>>) -> Self {
+    self.target = Some(path.into());
+    self
+  }
+
+  fn section(
+    &mut self,
+    marker: char,
+    (start, end): (u32, u32),
+    line: fn(Cow<'a, str>) -> Line<'a>,
+    lines: &mut Vec<Line<'a>>,
+  ) -> Result<u32, Error> {
+    let span = end + 1 - start.min(end);
+    for _ in 0..span {
+      let text = self.lines.next().unwrap_or_default();
+      let body = match text.strip_prefix(marker) {
+        Some("") => "",
+        Some(body) => body.strip_prefix(' ').ok_or_else(|| malformed(text))?,
+        None => return Err(malformed(text)),
+      };
+      lines.push(line(body.into()));
+    }
+    if self.lines.next_if(|text| text.starts_with('\\')).is_some() {
+      lines.push(Line::NoNewline);
+    }
+    Ok(span)
+  }
+
+  fn parse_hunk(&mut self, command: Command) -> Result<Hunk<'a>, Error> {
+    let mut lines = Vec::new();
+    let (mut old_span, mut new_span) = (0, 0);
+    if command.action != b'a' {
+      old_span = self.section('<', command.old, Line::Deletion, &mut lines)?;
+    }
+    if command.action == b'c' {
+      let separator = self.lines.next().unwrap_or_default();
+      if separator != "---" {
+        return Err(malformed(separator));
+      }
+    }
+    if command.action != b'd' {
+      new_span = self.section('>', command.new, Line::Addition, &mut lines)?;
+    }
+    Ok(Hunk {
+      old_line: command.old.0,
+      old_span,
+      new_line: command.new.0,
+      new_span,
+      heading: None,
+      lines,
+    })
+  }
+
+  fn file_names(
+    &self,
+    header: Option<&'a str>,
+  ) -> Result<(Cow<'a, str>, Cow<'a, str>), Error> {
+    if let Some(target) = &self.target {
+      return Ok((target.clone(), target.clone()));
+    }
+    let Some(header) = header else {
+      return Err(Error::Parse(
+        "Normal diff does not name a file to patch".into(),
+      ));
+    };
+    let mut words = header.split_whitespace().rev();
+    let (Some(new), Some(old)) = (words.next(), words.next()) else {
+      return Err(malformed(header));
+    };
+    let lexer = Lexer::new("").strip(self.strip);
+    Ok((lexer.file_line(old)?.0, lexer.file_line(new)?.0))
+  }
+
+  fn parse_patch(&mut self) -> Result<Patch<'a>, Error> {
+    let header = self.lines.next_if(|line| line.starts_with("diff "));
+    let (old_file, new_file) = self.file_names(header)?;
+
+    let mut hunks = Vec::new();
+    while let Some(&line) = self.lines.peek()
+      && !line.starts_with("diff ")
+    {
+      self.lines.next();
+      let command = parse_command(line).ok_or_else(|| malformed(line))?;
+      hunks.push(self.parse_hunk(command)?);
+    }
+    Ok(Patch {
+      old_file,
+      new_file,
+      hunks,
+      ..Default::default()
+    })
+  }
+}
+
+impl<'a> Iterator for NormalParser<'a> {
+  type Item = Result<Patch<'a>, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.lines.peek()?;
+    let patch = self.parse_patch();
+    if patch.is_err() {
+      self.lines.by_ref().for_each(drop);
+    }
+    Some(patch)
+  }
+}
@@ -0,0 +1,289 @@
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::parser::ParserOptions;
+use crate::repository::Eol;
+use crate::repository::Repository;
+use crate::stream::PatchReader;
+use std::io::BufRead;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+  Error,
+  #[default]
+  InOrder,
+  LastWins,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Whitespace {
+  #[default]
+  Exact,
+  IgnoreTrailing,
+  IgnoreChanges,
+  IgnoreAll,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEndings {
+  #[default]
+  Strict,
+  IgnoreEol,
+  Auto,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WhitespacePolicy {
+  #[default]
+  Nowarn,
+  Warn,
+  Fix,
+  Error,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApplyOptions {
+  pub reverse: bool,
+  pub root: Option<PathBuf>,
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+  pub unsafe_paths: bool,
+  pub repository: Option<Repository>,
+  pub repository_root: bool,
+  pub line_endings: LineEndings,
+  pub eol: Option<Eol>,
+  pub lossy_utf8: bool,
+  pub duplicates: DuplicatePolicy,
+  pub similarity_tolerance: Option<u32>,
+  pub fuzz: usize,
+  pub reject: bool,
+  pub atomic: bool,
+  pub strip: Option<usize>,
+  pub dry_run: bool,
+  pub whitespace: Whitespace,
+  pub whitespace_policy: WhitespacePolicy,
+  pub skip_applied: bool,
+  pub auto_reverse: bool,
+  pub target: Option<String>,
+  pub lenient: bool,
+}
+
+impl ApplyOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn reverse(mut self, reverse: bool) -> Self {
+    self.reverse = reverse;
+    self
+  }
+
+  pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+    self.root = Some(root.into());
+    self
+  }
+
+  pub fn include(mut self, pattern: impl Into<String>) -> Self {
+    self.include.push(pattern.into());
+    self
+  }
+
+  pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+    self.exclude.push(pattern.into());
+    self
+  }
+
+  pub fn unsafe_paths(mut self, unsafe_paths: bool) -> Self {
+    self.unsafe_paths = unsafe_paths;
+    self
+  }
+
+  pub fn repository(mut self, repository: Repository) -> Self {
+    self.repository = Some(repository);
+    self
+  }
+
+  pub fn repository_root(mut self, repository_root: bool) -> Self {
+    self.repository_root = repository_root;
+    self
+  }
+
+  pub fn normalize_eol(mut self, normalize_eol: bool) -> Self {
+    self.line_endings = if normalize_eol {
+      LineEndings::IgnoreEol
+    } else {
+      LineEndings::Strict
+    };
+    self
+  }
+
+  pub fn line_endings(mut self, line_endings: LineEndings) -> Self {
+    self.line_endings = line_endings;
+    self
+  }
+
+  pub fn eol(mut self, eol: Eol) -> Self {
+    self.eol = Some(eol);
+    self
+  }
+
+  pub fn lossy_utf8(mut self, lossy_utf8: bool) -> Self {
+    self.lossy_utf8 = lossy_utf8;
+    self
+  }
+
+  pub fn duplicates(mut self, duplicates: DuplicatePolicy) -> Self {
+    self.duplicates = duplicates;
+    self
+  }
+
+  pub fn similarity_tolerance(mut self, tolerance: u32) -> Self {
+    self.similarity_tolerance = Some(tolerance);
+    self
+  }
+
+  pub fn fuzz(mut self, fuzz: usize) -> Self {
+    self.fuzz = fuzz;
+    self
+  }
+
+  pub fn reject(mut self, reject: bool) -> Self {
+    self.reject = reject;
+    self
+  }
+
+  pub fn atomic(mut self, atomic: bool) -> Self {
+    self.atomic = atomic;
+    self
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = Some(components);
+    self
+  }
+
+  pub fn dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+    self
+  }
+
+  pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+    self.whitespace = whitespace;
+    self
+  }
+
+  pub fn whitespace_policy(mut self, policy: WhitespacePolicy) -> Self {
+    self.whitespace_policy = policy;
+    self
+  }
+
+  pub fn skip_applied(mut self, skip_applied: bool) -> Self {
+    self.skip_applied = skip_applied;
+    self
+  }
+
+  pub fn auto_reverse(mut self, auto_reverse: bool) -> Self {
+    self.auto_reverse = auto_reverse;
+    self
+  }
+
+  pub fn target(mut self, path: impl Into<String>) -> Self {
+    self.target = Some(path.into());
+    self
+  }
+
+  pub fn lenient(mut self, lenient: bool) -> Self {
+    self.lenient = lenient;
+    self
+  }
+
+  pub fn parser<'a>(&self, patch_content: &'a str) -> Parser<'a> {
+    let parser = Parser::new(patch_content).options(ParserOptions {
+      lenient: self.lenient,
+    });
+    let parser = match self.strip {
+      Some(components) => parser.strip(components),
+      None => parser,
+    };
+    match &self.target {
+      Some(target) => parser.target(target.clone()),
+      None => parser,
+    }
+  }
+
+  pub fn reader<R: BufRead>(&self, reader: R) -> PatchReader<R> {
+    let reader = PatchReader::new(reader).options(ParserOptions {
+      lenient: self.lenient,
+    });
+    let reader = match self.strip {
+      Some(components) => reader.strip(components),
+      None => reader,
+    };
+    match &self.target {
+      Some(target) => reader.target(target.as_str()),
+      None => reader,
+    }
+  }
+
+  pub fn is_selected(&self, path: &str) -> bool {
+    if self.exclude.iter().any(|pattern| wildmatch(pattern, path)) {
+      return false;
+    }
+
+    self.include.is_empty()
+      || self.include.iter().any(|pattern| wildmatch(pattern, path))
+  }
+
+  pub fn resolve(&self, path: &str) -> Result<PathBuf, Error> {
+    let relative = Path::new(path);
+    if !self.unsafe_paths
+      && relative.components().any(|component| {
+        !matches!(component, Component::Normal(_) | Component::CurDir)
+      })
+    {
+      return Err(Error::UnsafePath {
+        path: path.to_string(),
+      });
+    }
+
+    Ok(match (&self.root, &self.repository) {
+      (Some(root), _) => root.join(relative),
+      (None, Some(repository)) if self.repository_root => {
+        repository.root.join(relative)
+      }
+      _ => relative.to_path_buf(),
+    })
+  }
+}
+
+pub(crate) fn wildmatch(pattern: &str, text: &str) -> bool {
+  let pattern: Vec<char> = pattern.chars().collect();
+  let text: Vec<char> = text.chars().collect();
+
+  let (mut p, mut t) = (0, 0);
+  let mut backtrack = None;
+  while t < text.len() {
+    match pattern.get(p) {
+      Some('*') => {
+        backtrack = Some((p, t));
+        p += 1;
+      }
+      Some(&c) if c == '?' || c == text[t] => {
+        p += 1;
+        t += 1;
+      }
+      _ => match backtrack {
+        Some((star, matched)) => {
+          p = star + 1;
+          t = matched + 1;
+          backtrack = Some((star, matched + 1));
+        }
+        None => return false,
+      },
+    }
+  }
+
+  pattern[p..].iter().all(|&c| c == '*')
+}
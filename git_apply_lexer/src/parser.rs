@@ -1,69 +1,350 @@
+use crate::context;
+use crate::context::ContextParser;
 use crate::error::Error;
+use crate::lexer::BinaryMethod;
 use crate::lexer::Lexer;
 use crate::lexer::Token;
-use std::iter::Peekable;
+use crate::mail;
+use crate::mail::MailParser;
+use crate::normal;
+use crate::normal::NormalParser;
+use crate::series;
+use crate::series::Commit;
+use crate::series::LogParser;
+use crate::series::PatchSeries;
+use crate::stats::DiffStat;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Range;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Line<'a> {
-  Addition(&'a str),
-  Deletion(&'a str),
-  Context(&'a str),
+  Addition(Cow<'a, str>),
+  Deletion(Cow<'a, str>),
+  Context(Cow<'a, str>),
   NoNewline,
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hunk<'a> {
   pub old_line: u32,
   pub old_span: u32,
   pub new_line: u32,
   pub new_span: u32,
+  pub heading: Option<Cow<'a, str>>,
   pub lines: Vec<Line<'a>>,
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryHunk<'a> {
+  pub method: BinaryMethod,
+  pub size: usize,
+  pub data: Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryPatch<'a> {
+  pub forward: BinaryHunk<'a>,
+  pub reverse: Option<BinaryHunk<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Patch<'a> {
-  pub old_file: &'a str,
-  pub new_file: &'a str,
+  pub old_file: Cow<'a, str>,
+  pub new_file: Cow<'a, str>,
   pub hunks: Vec<Hunk<'a>>,
-  pub rename_from: Option<&'a str>,
-  pub rename_to: Option<&'a str>,
+  pub rename_from: Option<Cow<'a, str>>,
+  pub rename_to: Option<Cow<'a, str>>,
   pub new_mode: Option<u32>,
   pub old_mode: Option<u32>,
   pub deleted_file_mode: Option<u32>,
   pub similarity: Option<u32>,
   pub is_binary: bool,
-  pub copy_from: Option<&'a str>,
-  pub copy_to: Option<&'a str>,
+  pub copy_from: Option<Cow<'a, str>>,
+  pub copy_to: Option<Cow<'a, str>>,
   pub dissimilarity: Option<u32>,
   pub index_mode: Option<u32>,
+  pub binary: Option<BinaryPatch<'a>>,
+  pub old_hash: Option<Cow<'a, str>>,
+  pub new_hash: Option<Cow<'a, str>>,
+  pub old_timestamp: Option<Cow<'a, str>>,
+  pub new_timestamp: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LazyHunk<'a> {
+  pub old_line: u32,
+  pub old_span: u32,
+  pub new_line: u32,
+  pub new_span: u32,
+  pub heading: Option<&'a str>,
+  pub body: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LazyPatch<'a> {
+  pub header: Patch<'a>,
+  pub hunks: Vec<LazyHunk<'a>>,
+}
+
+impl<'a> LazyHunk<'a> {
+  pub fn lines(&self) -> Result<Vec<Line<'a>>, Error> {
+    let mut lines = Vec::new();
+    Parser::new(self.body).parse_hunk_lines(Some(&mut lines), None)?;
+    Ok(lines)
+  }
+
+  pub fn hunk(&self) -> Result<Hunk<'a>, Error> {
+    Ok(Hunk {
+      old_line: self.old_line,
+      old_span: self.old_span,
+      new_line: self.new_line,
+      new_span: self.new_span,
+      heading: self.heading.map(Cow::Borrowed),
+      lines: self.lines()?,
+    })
+  }
+}
+
+impl<'a> LazyPatch<'a> {
+  pub fn patch(self) -> Result<Patch<'a>, Error> {
+    let hunks = self
+      .hunks
+      .iter()
+      .map(LazyHunk::hunk)
+      .collect::<Result<_, _>>()?;
+    Ok(Patch {
+      hunks,
+      ..self.header
+    })
+  }
+}
+
+impl<'a> Patch<'a> {
+  pub fn additions(&self) -> impl Iterator<Item = &str> {
+    self.hunks.iter().flat_map(|hunk| {
+      hunk.lines.iter().filter_map(|line| match line {
+        Line::Addition(text) => Some(text.as_ref()),
+        _ => None,
+      })
+    })
+  }
+
+  pub fn deletions(&self) -> impl Iterator<Item = &str> {
+    self.hunks.iter().flat_map(|hunk| {
+      hunk.lines.iter().filter_map(|line| match line {
+        Line::Deletion(text) => Some(text.as_ref()),
+        _ => None,
+      })
+    })
+  }
+}
+
+impl<'a> Hunk<'a> {
+  pub fn changed_old_range(&self) -> Option<Range<u32>> {
+    self.changed_range(true)
+  }
+
+  pub fn changed_new_range(&self) -> Option<Range<u32>> {
+    self.changed_range(false)
+  }
+
+  fn changed_range(&self, old_side: bool) -> Option<Range<u32>> {
+    let mut old = if self.old_span == 0 {
+      self.old_line + 1
+    } else {
+      self.old_line
+    };
+    let mut new = if self.new_span == 0 {
+      self.new_line + 1
+    } else {
+      self.new_line
+    };
+
+    let mut range: Option<Range<u32>> = None;
+    for line in &self.lines {
+      let (position, len) = match line {
+        Line::Context(_) => {
+          old += 1;
+          new += 1;
+          continue;
+        }
+        Line::Deletion(_) => {
+          old += 1;
+          if old_side { (old - 1, 1) } else { (new, 0) }
+        }
+        Line::Addition(_) => {
+          new += 1;
+          if old_side { (old, 0) } else { (new - 1, 1) }
+        }
+        Line::NoNewline => continue,
+      };
+      let end = position + len;
+      range = Some(range.map_or(position..end, |range| {
+        range.start.min(position)..range.end.max(end)
+      }));
+    }
+    range
+  }
+}
+
+type Peeked<'a> = (Result<Token<'a>, Error>, Range<usize>);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+  pub lenient: bool,
+}
+
+enum Front<'a> {
+  Context(ContextParser<'a>),
+  Normal(NormalParser<'a>),
+  Mail(Box<MailParser<'a>>),
+  Log(Box<LogParser<'a>>),
 }
 
 pub struct Parser<'a> {
-  tokens: Peekable<Lexer<'a>>,
+  lexer: Lexer<'a>,
+  peeked: Option<Peeked<'a>>,
+  span: Range<usize>,
+  front: Option<Front<'a>>,
+  options: ParserOptions,
+  diffstat: DiffStat,
 }
 
 impl<'a> Parser<'a> {
   pub fn new(source: &'a str) -> Self {
+    let front = if mail::is_mail(source) {
+      Some(Front::Mail(Box::new(MailParser::new(source))))
+    } else if series::is_log(source) {
+      Some(Front::Log(Box::new(LogParser::new(source))))
+    } else if context::is_context_diff(source) {
+      Some(Front::Context(ContextParser::new(source)))
+    } else if normal::is_normal_diff(source) {
+      Some(Front::Normal(NormalParser::new(source)))
+    } else {
+      None
+    };
     Self {
-      tokens: Lexer::new(source).peekable(),
+      lexer: Lexer::new(source),
+      peeked: None,
+      span: 0..0,
+      front,
+      options: ParserOptions::default(),
+      diffstat: DiffStat::default(),
+    }
+  }
+
+  pub fn options(mut self, options: ParserOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.lexer = self.lexer.strip(components);
+    self.front = self.front.map(|front| match front {
+      Front::Context(context) => Front::Context(context.strip(components)),
+      Front::Normal(normal) => Front::Normal(normal.strip(components)),
+      Front::Mail(mail) => Front::Mail(Box::new(mail.strip(components))),
+      Front::Log(log) => Front::Log(Box::new(log.strip(components))),
+    });
+    self
+  }
+
+  pub fn target(mut self, path: impl Into<Cow<'a, str>>) -> Self {
+    if let Some(Front::Normal(normal)) = self.front {
+      self.front = Some(Front::Normal(normal.target(path)));
+    }
+    self
+  }
+
+  pub fn diffstat(&self) -> &DiffStat {
+    &self.diffstat
+  }
+
+  pub fn span(&self) -> Range<usize> {
+    self.span.clone()
+  }
+
+  fn peek(&mut self) -> Option<Result<Token<'a>, Error>> {
+    if self.peeked.is_none() {
+      let span = self.lexer.next_line_span()?;
+      self.peeked = Some((self.lexer.next_token(), span));
+    }
+    let (token, span) = self.peeked.as_ref()?;
+    self.span = span.clone();
+    Some(token.clone())
+  }
+
+  fn advance(&mut self) {
+    self.peeked = None;
+  }
+
+  fn skip_prologue(&mut self) {
+    while let Some(token) = self.peek() {
+      match token {
+        Ok(Token::DiffStat { stat, .. }) => self.diffstat.files.push(stat),
+        Ok(Token::DiffStatSummary { summary, .. }) => {
+          self.diffstat.summary = Some(summary);
+        }
+        Ok(Token::FileHeader { .. } | Token::OldFile { .. }) => break,
+        _ if self.options.lenient => {}
+        _ => break,
+      }
+      self.advance();
     }
   }
 
   fn parse_patch(&mut self) -> Result<Patch<'a>, Error> {
+    let mut patch = self.parse_header()?;
+    patch.hunks = self
+      .parse_hunks(&patch, false)?
+      .into_iter()
+      .map(|(hunk, lines)| Hunk {
+        old_line: hunk.old_line,
+        old_span: hunk.old_span,
+        new_line: hunk.new_line,
+        new_span: hunk.new_span,
+        heading: hunk.heading.map(Cow::Borrowed),
+        lines,
+      })
+      .collect();
+    Ok(patch)
+  }
+
+  fn parse_lazy_patch(&mut self) -> Result<LazyPatch<'a>, Error> {
+    let header = self.parse_header()?;
+    let hunks = self
+      .parse_hunks(&header, true)?
+      .into_iter()
+      .map(|(hunk, _)| hunk)
+      .collect();
+    Ok(LazyPatch { header, hunks })
+  }
+
+  fn parse_header(&mut self) -> Result<Patch<'a>, Error> {
     let mut patch = Patch::default();
 
     if let Some(Ok(Token::FileHeader {
       old_file: fh_old,
       new_file: fh_new,
-    })) = self.tokens.peek()
+    })) = self.peek()
     {
       patch.old_file = fh_old;
       patch.new_file = fh_new;
-      self.tokens.next();
+      self.advance();
     }
 
-    while let Some(Ok(token)) = self.tokens.peek() {
-      match *token {
+    while let Some(Ok(token)) = self.peek() {
+      match token {
         Token::RenameFrom(from) => patch.rename_from = Some(from),
         Token::RenameTo(to) => patch.rename_to = Some(to),
         Token::NewFileMode(mode) => patch.new_mode = Some(mode),
@@ -71,99 +352,179 @@ impl<'a> Parser<'a> {
         Token::DeletedFileMode(mode) => patch.deleted_file_mode = Some(mode),
         Token::Similarity(percent) => patch.similarity = Some(percent),
         Token::BinaryFileDiffer { .. } => patch.is_binary = true,
-        Token::OldFile(file) => patch.old_file = file,
-        Token::NewFile(file) => patch.new_file = file,
+        Token::OldFile { path, timestamp } => {
+          patch.old_file = path;
+          patch.old_timestamp = timestamp.map(Cow::Borrowed);
+        }
+        Token::NewFile { path, timestamp } => {
+          patch.new_file = path;
+          patch.new_timestamp = timestamp.map(Cow::Borrowed);
+        }
         Token::CopyFrom(from) => patch.copy_from = Some(from),
         Token::CopyTo(to) => patch.copy_to = Some(to),
         Token::Dissimilarity(percent) => patch.dissimilarity = Some(percent),
-        Token::Index { mode, .. } => patch.index_mode = mode,
+        Token::Index {
+          old_hash,
+          new_hash,
+          mode,
+        } => {
+          patch.old_hash = Some(old_hash.into());
+          patch.new_hash = Some(new_hash.into());
+          patch.index_mode = mode;
+        }
+        Token::GitBinaryPatch => patch.is_binary = true,
+        Token::BinaryHunk { method, size, data } => {
+          let hunk = BinaryHunk {
+            method,
+            size,
+            data: data.into(),
+          };
+          match &mut patch.binary {
+            Some(binary) => binary.reverse = Some(hunk),
+            None => {
+              patch.binary = Some(BinaryPatch {
+                forward: hunk,
+                reverse: None,
+              })
+            }
+          }
+        }
         _ => break,
       }
-      self.tokens.next();
+      self.advance();
     }
 
-    if let Some(Err(e)) = self.tokens.peek() {
-      return Err(e.clone());
+    if patch.binary.is_some() {
+      if patch.deleted_file_mode.is_some() {
+        patch.new_file = Cow::Borrowed("/dev/null");
+      } else if patch.new_mode.is_some() && patch.old_mode.is_none() {
+        patch.old_file = Cow::Borrowed("/dev/null");
+      }
     }
 
+    if let Some(Err(e)) = self.peek() {
+      return Err(e);
+    }
+
+    Ok(patch)
+  }
+
+  fn parse_hunks(
+    &mut self,
+    header: &Patch<'a>,
+    lazy: bool,
+  ) -> Result<Vec<(LazyHunk<'a>, Vec<Line<'a>>)>, Error> {
+    let mut hunks = Vec::new();
     loop {
       if self
-        .tokens
         .peek()
         .is_some_and(|t| matches!(t, Ok(Token::HunkHeader { .. })))
       {
-        patch.hunks.push(self.parse_hunk()?);
+        let mut lines = Vec::new();
+        let hunk = self.parse_hunk((!lazy).then_some(&mut lines))?;
+        hunks.push((hunk, lines));
       } else {
         break;
       }
     }
 
-    if patch.hunks.is_empty() {
-      let (lines, old_span, new_span) = self.parse_hunk_lines()?;
-      if !lines.is_empty() {
-        if patch.old_file.is_empty() && patch.new_file.is_empty() {
+    if hunks.is_empty() {
+      let mut lines = Vec::new();
+      let (body, old_span, new_span) =
+        self.parse_hunk_lines((!lazy).then_some(&mut lines), None)?;
+      if !body.is_empty() {
+        if header.old_file.is_empty() && header.new_file.is_empty() {
           return Err(Error::Parse(
             "Patch has hunks but no file information".into(),
           ));
         }
-        patch.hunks.push(Hunk {
+        let hunk = LazyHunk {
           old_line: if old_span > 0 { 1 } else { 0 },
           old_span,
           new_line: if new_span > 0 { 1 } else { 0 },
           new_span,
-          lines,
-        });
+          heading: None,
+          body,
+        };
+        hunks.push((hunk, lines));
       }
     }
 
-    Ok(patch)
+    Ok(hunks)
   }
 
-  fn parse_hunk_lines(&mut self) -> Result<(Vec<Line<'a>>, u32, u32), Error> {
-    let mut lines = Vec::new();
+  fn parse_hunk_lines(
+    &mut self,
+    mut lines: Option<&mut Vec<Line<'a>>>,
+    limit: Option<(u32, u32)>,
+  ) -> Result<(&'a str, u32, u32), Error> {
+    let mut body: Option<Range<usize>> = None;
     let mut old_lines_count = 0;
     let mut new_lines_count = 0;
-    while let Some(Ok(token)) = self.tokens.peek() {
-      let line = match *token {
+    while let Some(Ok(token)) = self.peek() {
+      if limit == Some((old_lines_count, new_lines_count))
+        && token != Token::NoNewline
+      {
+        break;
+      }
+      let line = match token {
         Token::Addition(s) => {
           new_lines_count += 1;
-          Line::Addition(s)
+          Line::Addition(s.into())
         }
         Token::Deletion(s) => {
           old_lines_count += 1;
-          Line::Deletion(s)
+          Line::Deletion(s.into())
         }
         Token::Context(s) => {
           old_lines_count += 1;
           new_lines_count += 1;
-          Line::Context(s)
+          Line::Context(s.into())
         }
         Token::NoNewline => Line::NoNewline,
         _ => break,
       };
-      lines.push(line);
-      self.tokens.next();
+      if let Some(lines) = lines.as_deref_mut() {
+        lines.push(line);
+      }
+      let span = self.span();
+      body = Some(body.map_or(span.clone(), |body| body.start..span.end));
+      self.advance();
     }
 
-    if let Some(Err(e)) = self.tokens.peek() {
-      return Err(e.clone());
+    if limit.is_none()
+      && let Some(Err(e)) = self.peek()
+    {
+      return Err(e);
     }
 
-    Ok((lines, old_lines_count, new_lines_count))
+    let body = body.map_or("", |body| &self.lexer.source()[body]);
+    Ok((body, old_lines_count, new_lines_count))
   }
 
-  fn parse_hunk(&mut self) -> Result<Hunk<'a>, Error> {
+  fn parse_hunk(
+    &mut self,
+    lines: Option<&mut Vec<Line<'a>>>,
+  ) -> Result<LazyHunk<'a>, Error> {
     let Some(Ok(Token::HunkHeader {
       old_line,
       old_span,
       new_line,
       new_span,
-    })) = self.tokens.next()
+      heading,
+    })) = self.peek()
     else {
       return Err(Error::Parse("Expected hunk header".into()));
     };
+    let header_span = self.span();
+    self.advance();
 
-    let (lines, old_lines_count, new_lines_count) = self.parse_hunk_lines()?;
+    let limit = self.options.lenient.then_some((old_span, new_span));
+    let (body, old_lines_count, new_lines_count) =
+      self.parse_hunk_lines(lines, limit)?;
+    if old_lines_count != old_span || new_lines_count != new_span {
+      self.span = header_span;
+    }
 
     if old_lines_count != old_span {
       return Err(Error::Parse(
@@ -185,21 +546,78 @@ impl<'a> Parser<'a> {
       ));
     }
 
-    Ok(Hunk {
+    Ok(LazyHunk {
       old_line,
       old_span,
       new_line,
       new_span,
-      lines,
+      heading,
+      body,
     })
   }
+
+  pub fn lazy(self) -> LazyParser<'a> {
+    LazyParser {
+      parser: self,
+      exhausted: false,
+    }
+  }
+
+  pub(crate) fn is_unified(&self) -> bool {
+    self.front.is_none()
+  }
+
+  pub fn series(mut self) -> Result<PatchSeries<'a>, Error> {
+    let patches = match self.front.take() {
+      Some(Front::Log(log)) => return log.series(),
+      front => {
+        self.front = front;
+        self.collect::<Result<_, _>>()?
+      }
+    };
+    Ok(PatchSeries {
+      commits: vec![Commit {
+        patches,
+        ..Default::default()
+      }],
+    })
+  }
+}
+
+pub struct LazyParser<'a> {
+  parser: Parser<'a>,
+  exhausted: bool,
+}
+
+impl<'a> Iterator for LazyParser<'a> {
+  type Item = Result<LazyPatch<'a>, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let parser = &mut self.parser;
+    if !parser.is_unified() {
+      return (!mem::replace(&mut self.exhausted, true)).then(|| {
+        Err(Error::Unsupported(
+          "Lazy parsing is only supported for unified diffs".into(),
+        ))
+      });
+    }
+    parser.skip_prologue();
+    parser.peek().is_some().then(|| parser.parse_lazy_patch())
+  }
 }
 
 impl<'a> Iterator for Parser<'a> {
   type Item = Result<Patch<'a>, Error>;
 
   fn next(&mut self) -> Option<Self::Item> {
-    self.tokens.peek()?;
-    Some(self.parse_patch())
+    match &mut self.front {
+      Some(Front::Context(context)) => return context.next(),
+      Some(Front::Normal(normal)) => return normal.next(),
+      Some(Front::Mail(mail)) => return mail.next(),
+      Some(Front::Log(log)) => return log.next(),
+      None => {}
+    }
+    self.skip_prologue();
+    self.peek().is_some().then(|| self.parse_patch())
   }
 }
@@ -0,0 +1,99 @@
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error(
+  "Conflict in {file}: hunk #{} overlaps changes at line {line}",
+  hunk_index + 1
+)]
+pub struct Conflict {
+  pub file: String,
+  pub hunk_index: usize,
+  pub line: u32,
+}
+
+impl<'a> Patch<'a> {
+  pub fn rebase_onto(&self, other: &Patch<'a>) -> Result<Patch<'a>, Conflict> {
+    if self.old_file == "/dev/null"
+      || (self.old_file != other.new_file && self.old_file != other.old_file)
+    {
+      return Ok(self.clone());
+    }
+
+    let conflict = |hunk_index: usize, line: u32| Conflict {
+      file: self.old_file.to_string(),
+      hunk_index,
+      line,
+    };
+    if other.new_file == "/dev/null" {
+      return Err(conflict(0, 0));
+    }
+
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+    for hunk in &other.hunks {
+      let mut line = if hunk.old_span == 0 {
+        hunk.old_line + 1
+      } else {
+        hunk.old_line
+      };
+      for hunk_line in &hunk.lines {
+        match hunk_line {
+          Line::Context(_) => line += 1,
+          Line::Deletion(_) => {
+            deleted.push(line);
+            line += 1;
+          }
+          Line::Addition(_) => inserted.push(line),
+          Line::NoNewline => {}
+        }
+      }
+    }
+
+    let map = other.line_map();
+    let mut rebased = self.clone();
+    if self.old_file == other.old_file && other.old_file != other.new_file {
+      rebased.old_file = other.new_file.clone();
+      if self.new_file == self.old_file {
+        rebased.new_file = other.new_file.clone();
+      }
+    }
+
+    for (hunk_index, hunk) in rebased.hunks.iter_mut().enumerate() {
+      let (anchor, target) = if hunk.old_span == 0 {
+        let insertion = hunk.old_line + 1;
+        if deleted.contains(&hunk.old_line) || inserted.contains(&insertion) {
+          return Err(conflict(hunk_index, hunk.old_line));
+        }
+        let target = match hunk.old_line {
+          0 => Some(0),
+          line => map.old_to_new(line),
+        };
+        (hunk.old_line, target)
+      } else {
+        let range = hunk.old_line..hunk.old_line + hunk.old_span;
+        let overlaps = deleted.iter().any(|line| range.contains(line))
+          || inserted
+            .iter()
+            .any(|&line| line > range.start && line < range.end);
+        if overlaps {
+          return Err(conflict(hunk_index, hunk.old_line));
+        }
+        (hunk.old_line, map.old_to_new(hunk.old_line))
+      };
+
+      let Some(target) = target else {
+        return Err(conflict(hunk_index, anchor));
+      };
+      let delta = target as i64 - anchor as i64;
+      hunk.old_line = target;
+      hunk.new_line = (hunk.new_line as i64 + delta).max(0) as u32;
+    }
+
+    Ok(rebased)
+  }
+}
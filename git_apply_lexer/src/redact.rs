@@ -0,0 +1,115 @@
+use crate::error::Error;
+use crate::parser::Line;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use crate::render;
+use crate::render::ColorScheme;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const DEV_NULL: &str = "/dev/null";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RedactOptions {
+  pub hash_paths: bool,
+}
+
+impl RedactOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn hash_paths(mut self, hash_paths: bool) -> Self {
+    self.hash_paths = hash_paths;
+    self
+  }
+}
+
+fn fnv1a(text: &str) -> u32 {
+  text.bytes().fold(0x811c_9dc5, |hash, byte| {
+    (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+  })
+}
+
+fn hash_path(path: &str) -> String {
+  if path == DEV_NULL {
+    return String::from(path);
+  }
+
+  path
+    .split('/')
+    .map(|component| match component.rsplit_once('.') {
+      Some((stem, extension)) if !stem.is_empty() => {
+        format!("{:08x}.{}", fnv1a(stem), extension)
+      }
+      _ => format!("{:08x}", fnv1a(component)),
+    })
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+struct Redactor<'a> {
+  options: RedactOptions,
+  placeholders: BTreeMap<Cow<'a, str>, usize>,
+}
+
+impl<'a> Redactor<'a> {
+  fn text(&mut self, text: &Cow<'a, str>) -> Cow<'a, str> {
+    if text.trim().is_empty() {
+      return text.clone();
+    }
+
+    let next = self.placeholders.len() + 1;
+    let id = *self.placeholders.entry(text.clone()).or_insert(next);
+    Cow::Owned(format!("line {}", id))
+  }
+
+  fn path(&self, path: &mut Cow<'a, str>) {
+    if self.options.hash_paths {
+      *path = Cow::Owned(hash_path(path));
+    }
+  }
+
+  fn patch(&mut self, mut patch: Patch<'a>) -> Patch<'a> {
+    for path in [&mut patch.old_file, &mut patch.new_file]
+      .into_iter()
+      .chain(patch.rename_from.as_mut())
+      .chain(patch.rename_to.as_mut())
+      .chain(patch.copy_from.as_mut())
+      .chain(patch.copy_to.as_mut())
+    {
+      self.path(path);
+    }
+    patch.binary = None;
+
+    for line in patch.hunks.iter_mut().flat_map(|hunk| &mut hunk.lines) {
+      match line {
+        Line::Context(text) | Line::Deletion(text) | Line::Addition(text) => {
+          *text = self.text(text);
+        }
+        Line::NoNewline => {}
+      }
+    }
+    patch
+  }
+}
+
+pub fn redact(
+  patch_text: &str,
+  options: RedactOptions,
+) -> Result<String, Error> {
+  let mut redactor = Redactor {
+    options,
+    placeholders: BTreeMap::new(),
+  };
+
+  let mut output = String::new();
+  for patch in Parser::new(patch_text) {
+    let patch = redactor.patch(patch?);
+    output.push_str(&render::pretty(&patch, ColorScheme::plain()));
+  }
+  Ok(output)
+}
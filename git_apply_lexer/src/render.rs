@@ -0,0 +1,313 @@
+use crate::lexer::BinaryMethod;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter;
+use core::ops::Range;
+
+const DEV_NULL: &str = "/dev/null";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorScheme {
+  pub meta: &'static str,
+  pub fragment: &'static str,
+  pub deletion: &'static str,
+  pub addition: &'static str,
+  pub context: &'static str,
+  pub deletion_highlight: &'static str,
+  pub addition_highlight: &'static str,
+  pub reset: &'static str,
+  pub intraline: bool,
+}
+
+impl Default for ColorScheme {
+  fn default() -> Self {
+    Self {
+      meta: "\x1b[1m",
+      fragment: "\x1b[36m",
+      deletion: "\x1b[31m",
+      addition: "\x1b[32m",
+      context: "",
+      deletion_highlight: "\x1b[7;31m",
+      addition_highlight: "\x1b[7;32m",
+      reset: "\x1b[0m",
+      intraline: true,
+    }
+  }
+}
+
+impl ColorScheme {
+  pub fn plain() -> Self {
+    Self {
+      meta: "",
+      fragment: "",
+      deletion: "",
+      addition: "",
+      context: "",
+      deletion_highlight: "",
+      addition_highlight: "",
+      reset: "",
+      intraline: false,
+    }
+  }
+
+  fn paint(&self, output: &mut String, color: &str, text: &str) {
+    if color.is_empty() {
+      output.push_str(text);
+    } else {
+      output.push_str(color);
+      output.push_str(text);
+      output.push_str(self.reset);
+    }
+  }
+
+  fn paint_line(
+    &self,
+    output: &mut String,
+    marker: char,
+    text: &str,
+    (color, highlight): (&str, &str),
+    ranges: &[Range<usize>],
+  ) {
+    output.push_str(color);
+    output.push(marker);
+    let mut position = 0;
+    for range in ranges.iter().filter(|range| !range.is_empty()) {
+      output.push_str(&text[position..range.start]);
+      output.push_str(highlight);
+      output.push_str(&text[range.clone()]);
+      output.push_str(self.reset);
+      output.push_str(color);
+      position = range.end;
+    }
+    output.push_str(&text[position..]);
+    if !color.is_empty() {
+      output.push_str(self.reset);
+    }
+    output.push('\n');
+  }
+}
+
+pub fn quote_path(path: &str) -> Cow<'_, str> {
+  let needs_quoting =
+    |byte: u8| !(0x20..0x7f).contains(&byte) || byte == b'"' || byte == b'\\';
+  if !path.bytes().any(needs_quoting) {
+    return Cow::Borrowed(path);
+  }
+
+  let mut quoted = String::from("\"");
+  for byte in path.bytes() {
+    match byte {
+      0x07 => quoted.push_str("\\a"),
+      0x08 => quoted.push_str("\\b"),
+      b'\t' => quoted.push_str("\\t"),
+      b'\n' => quoted.push_str("\\n"),
+      0x0b => quoted.push_str("\\v"),
+      0x0c => quoted.push_str("\\f"),
+      b'\r' => quoted.push_str("\\r"),
+      b'"' => quoted.push_str("\\\""),
+      b'\\' => quoted.push_str("\\\\"),
+      byte if needs_quoting(byte) => {
+        quoted.push_str(&format!("\\{:03o}", byte));
+      }
+      byte => quoted.push(byte as char),
+    }
+  }
+  quoted.push('"');
+  Cow::Owned(quoted)
+}
+
+fn header(output: &mut String, scheme: &ColorScheme, patch: &Patch) {
+  let mut meta = |line: &str| {
+    if !line.is_empty() {
+      scheme.paint(output, scheme.meta, line);
+    }
+    output.push('\n');
+  };
+  let prefixed = |prefix: &str, path: &str| {
+    if path == DEV_NULL {
+      String::from(path)
+    } else {
+      quote_path(&format!("{}{}", prefix, path)).into_owned()
+    }
+  };
+
+  let old_name = if patch.old_file == DEV_NULL {
+    &patch.new_file
+  } else {
+    &patch.old_file
+  };
+  let new_name = if patch.new_file == DEV_NULL {
+    &patch.old_file
+  } else {
+    &patch.new_file
+  };
+  meta(&format!(
+    "diff --git {} {}",
+    prefixed("a/", old_name),
+    prefixed("b/", new_name)
+  ));
+
+  if let Some(mode) = patch.deleted_file_mode {
+    meta(&format!("deleted file mode {:o}", mode));
+  } else if patch.old_file == DEV_NULL
+    && let Some(mode) = patch.new_mode
+  {
+    meta(&format!("new file mode {:o}", mode));
+  } else if let (Some(old_mode), Some(new_mode)) =
+    (patch.old_mode, patch.new_mode)
+  {
+    meta(&format!("old mode {:o}", old_mode));
+    meta(&format!("new mode {:o}", new_mode));
+  }
+  if let Some(percent) = patch.similarity {
+    meta(&format!("similarity index {}%", percent));
+  }
+  if let Some(percent) = patch.dissimilarity {
+    meta(&format!("dissimilarity index {}%", percent));
+  }
+  if let (Some(from), Some(to)) = (&patch.rename_from, &patch.rename_to) {
+    meta(&format!("rename from {}", quote_path(from)));
+    meta(&format!("rename to {}", quote_path(to)));
+  }
+  if let (Some(from), Some(to)) = (&patch.copy_from, &patch.copy_to) {
+    meta(&format!("copy from {}", quote_path(from)));
+    meta(&format!("copy to {}", quote_path(to)));
+  }
+  if let (Some(old_hash), Some(new_hash)) = (&patch.old_hash, &patch.new_hash) {
+    match patch.index_mode {
+      Some(mode) => {
+        meta(&format!("index {}..{} {:o}", old_hash, new_hash, mode))
+      }
+      None => meta(&format!("index {}..{}", old_hash, new_hash)),
+    }
+  }
+
+  if let Some(binary) = &patch.binary {
+    meta("GIT binary patch");
+    for hunk in iter::once(&binary.forward).chain(&binary.reverse) {
+      let method = match hunk.method {
+        BinaryMethod::Literal => "literal",
+        BinaryMethod::Delta => "delta",
+      };
+      meta(&format!("{} {}", method, hunk.size));
+      for line in hunk.data.lines() {
+        meta(line);
+      }
+      meta("");
+    }
+  } else if patch.is_binary {
+    meta(&format!(
+      "Binary files {} and {} differ",
+      prefixed("a/", &patch.old_file),
+      prefixed("b/", &patch.new_file)
+    ));
+  } else if !patch.hunks.is_empty() {
+    let stamped = |path: String, timestamp: &Option<Cow<str>>| match timestamp {
+      Some(timestamp) => format!("{}\t{}", path, timestamp),
+      None => path,
+    };
+    meta(&format!(
+      "--- {}",
+      stamped(prefixed("a/", &patch.old_file), &patch.old_timestamp)
+    ));
+    meta(&format!(
+      "+++ {}",
+      stamped(prefixed("b/", &patch.new_file), &patch.new_timestamp)
+    ));
+  }
+}
+
+pub(crate) fn write_hunk(
+  output: &mut String,
+  scheme: &ColorScheme,
+  hunk: &Hunk,
+) {
+  let mut highlights: Vec<Vec<Range<usize>>> =
+    vec![Vec::new(); hunk.lines.len()];
+  if scheme.intraline {
+    for change in hunk.intraline() {
+      let (old, new): (Vec<_>, Vec<_>) = change.ranges.into_iter().unzip();
+      highlights[change.deletion] = old;
+      highlights[change.addition] = new;
+    }
+  }
+
+  let fragment = format!(
+    "@@ -{},{} +{},{} @@",
+    hunk.old_line, hunk.old_span, hunk.new_line, hunk.new_span
+  );
+  scheme.paint(output, scheme.fragment, &fragment);
+  if let Some(heading) = &hunk.heading {
+    output.push(' ');
+    output.push_str(heading);
+  }
+  output.push('\n');
+
+  for (line, ranges) in hunk.lines.iter().zip(&highlights) {
+    match line {
+      Line::Deletion(text) => scheme.paint_line(
+        output,
+        '-',
+        text,
+        (scheme.deletion, scheme.deletion_highlight),
+        ranges,
+      ),
+      Line::Addition(text) => scheme.paint_line(
+        output,
+        '+',
+        text,
+        (scheme.addition, scheme.addition_highlight),
+        ranges,
+      ),
+      Line::Context(text) => scheme.paint_line(
+        output,
+        ' ',
+        text,
+        (scheme.context, scheme.context),
+        ranges,
+      ),
+      Line::NoNewline => output.push_str("\\ No newline at end of file\n"),
+    }
+  }
+}
+
+pub fn pretty(patch: &Patch, scheme: ColorScheme) -> String {
+  let mut output = String::new();
+  header(&mut output, &scheme, patch);
+  for hunk in &patch.hunks {
+    write_hunk(&mut output, &scheme, hunk);
+  }
+  output
+}
+
+impl fmt::Display for Patch<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&pretty(self, ColorScheme::plain()))
+  }
+}
+
+impl fmt::Display for Hunk<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut output = String::new();
+    write_hunk(&mut output, &ColorScheme::plain(), self);
+    f.write_str(&output)
+  }
+}
+
+impl fmt::Display for Line<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Line::Addition(text) => write!(f, "+{}", text),
+      Line::Deletion(text) => write!(f, "-{}", text),
+      Line::Context(text) => write!(f, " {}", text),
+      Line::NoNewline => f.write_str("\\ No newline at end of file"),
+    }
+  }
+}
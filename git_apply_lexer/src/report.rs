@@ -0,0 +1,146 @@
+use crate::event::ApplyEvent;
+use crate::event::ApplySink;
+use crate::whitespace::WhitespaceIssue;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FileStatus {
+  Created,
+  #[default]
+  Modified,
+  Deleted,
+  Renamed {
+    from: PathBuf,
+  },
+  Copied {
+    from: PathBuf,
+  },
+  Skipped,
+  AlreadyApplied,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HunkOffset {
+  pub hunk: usize,
+  pub line: usize,
+  pub offset: isize,
+  pub fuzz: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileReport {
+  pub path: PathBuf,
+  pub status: FileStatus,
+  pub hunks: usize,
+  pub mode: Option<u32>,
+  pub offsets: Vec<HunkOffset>,
+  pub rejected: Vec<usize>,
+  pub conflicts: usize,
+  pub whitespace: Vec<WhitespaceIssue>,
+  pub lossy: bool,
+  pub reversed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApplyReport {
+  pub files: Vec<FileReport>,
+}
+
+impl ApplyReport {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn file(&self, path: &Path) -> Option<&FileReport> {
+    self.files.iter().find(|file| file.path == path)
+  }
+
+  pub fn has_rejects(&self) -> bool {
+    self.files.iter().any(|file| !file.rejected.is_empty())
+  }
+
+  pub fn has_conflicts(&self) -> bool {
+    self.files.iter().any(|file| file.conflicts > 0)
+  }
+
+  fn entry(&mut self, path: PathBuf) -> &mut FileReport {
+    let index = match self.files.iter().position(|file| file.path == path) {
+      Some(index) => index,
+      None => {
+        self.files.push(FileReport {
+          path,
+          ..Default::default()
+        });
+        self.files.len() - 1
+      }
+    };
+    &mut self.files[index]
+  }
+}
+
+impl ApplySink for ApplyReport {
+  fn on_event(&mut self, event: ApplyEvent) {
+    match event {
+      ApplyEvent::Created { path } => {
+        self.entry(path).status = FileStatus::Created;
+      }
+      ApplyEvent::Modified { path, hunks } => {
+        let file = self.entry(path);
+        file.status = FileStatus::Modified;
+        file.hunks = hunks;
+      }
+      ApplyEvent::Deleted { path } => {
+        self.entry(path).status = FileStatus::Deleted;
+      }
+      ApplyEvent::Renamed { from, to } => {
+        self.entry(to).status = FileStatus::Renamed { from };
+      }
+      ApplyEvent::Copied { from, to } => {
+        self.entry(to).status = FileStatus::Copied { from };
+      }
+      ApplyEvent::ModeChanged { path, mode } => {
+        self.entry(path).mode = Some(mode);
+      }
+      ApplyEvent::Skipped { path } => {
+        self.entry(path).status = FileStatus::Skipped;
+      }
+      ApplyEvent::AlreadyApplied { path } => {
+        self.entry(path).status = FileStatus::AlreadyApplied;
+      }
+      ApplyEvent::Reversed { path } => {
+        self.entry(path).reversed = true;
+      }
+      ApplyEvent::LossyRead { path } => {
+        self.entry(path).lossy = true;
+      }
+      ApplyEvent::Duplicate { .. } => {}
+      ApplyEvent::Merged { path, conflicts } => {
+        self.entry(path).conflicts += conflicts;
+      }
+      ApplyEvent::Rejected { path, hunks } => {
+        self.entry(path).rejected.extend(hunks);
+      }
+      ApplyEvent::Whitespace {
+        path, line, kind, ..
+      } => {
+        self
+          .entry(path)
+          .whitespace
+          .push(WhitespaceIssue { line, kind });
+      }
+      ApplyEvent::Offset {
+        path,
+        hunk,
+        line,
+        offset,
+        fuzz,
+      } => self.entry(path).offsets.push(HunkOffset {
+        hunk,
+        line,
+        offset,
+        fuzz,
+      }),
+    }
+  }
+}
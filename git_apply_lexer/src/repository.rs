@@ -0,0 +1,353 @@
+use crate::applier::PreImages;
+use crate::error::Error;
+use crate::fs::FileSystem;
+use crate::options::wildmatch;
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eol {
+  Lf,
+  Crlf,
+}
+
+impl Eol {
+  pub fn convert(self, content: &str) -> String {
+    let normalized = content.replace("\r\n", "\n");
+    match self {
+      Self::Lf => normalized,
+      Self::Crlf => normalized.replace('\n', "\r\n"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Text {
+  Set,
+  Unset,
+  Auto,
+}
+
+#[derive(Debug, Default)]
+struct Attributes {
+  text: Option<Text>,
+  eol: Option<Eol>,
+}
+
+impl Attributes {
+  fn parse(&mut self, attributes: &[&str]) {
+    for attribute in attributes {
+      match *attribute {
+        "text" | "crlf" => self.text = Some(Text::Set),
+        "-text" | "-crlf" | "binary" => self.text = Some(Text::Unset),
+        "text=auto" | "crlf=input" => self.text = Some(Text::Auto),
+        "!text" | "!crlf" => self.text = None,
+        "eol=lf" => self.eol = Some(Eol::Lf),
+        "eol=crlf" => self.eol = Some(Eol::Crlf),
+        "-eol" | "!eol" => self.eol = None,
+        _ => {}
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Repository {
+  pub root: PathBuf,
+  pub prefix: PathBuf,
+  common_dir: PathBuf,
+  autocrlf: bool,
+  core_eol: Option<Eol>,
+}
+
+fn find_git_dir(fs: &impl FileSystem, dir: &Path) -> Option<PathBuf> {
+  let dot_git = dir.join(".git");
+  if fs.read_to_string(&dot_git.join("HEAD")).is_ok() {
+    return Some(dot_git);
+  }
+
+  let pointer = fs.read_to_string(&dot_git).ok()?;
+  let git_dir = pointer.trim().strip_prefix("gitdir:")?.trim();
+  Some(dir.join(git_dir))
+}
+
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+  if !pattern.contains('/') {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    return wildmatch(pattern, name);
+  }
+
+  let pattern = pattern.trim_start_matches('/');
+  wildmatch(pattern, path)
+    || pattern
+      .strip_prefix("**/")
+      .is_some_and(|rest| wildmatch(rest, path))
+}
+
+impl Repository {
+  pub fn discover(fs: &impl FileSystem, start: &Path) -> Option<Self> {
+    let (root, git_dir) = start
+      .ancestors()
+      .find_map(|dir| find_git_dir(fs, dir).map(|git_dir| (dir, git_dir)))?;
+
+    let common_dir = match fs.read_to_string(&git_dir.join("commondir")) {
+      Ok(common_dir) => git_dir.join(common_dir.trim()),
+      Err(_) => git_dir,
+    };
+    let config = fs.read_to_string(&common_dir.join("config"));
+    let mut repository = Self {
+      root: root.to_path_buf(),
+      prefix: start.strip_prefix(root).unwrap_or(start).to_path_buf(),
+      common_dir,
+      ..Self::default()
+    };
+    if let Ok(config) = config {
+      repository.read_config(&config);
+    }
+    Some(repository)
+  }
+
+  fn read_config(&mut self, config: &str) {
+    let mut in_core = false;
+    for line in config.lines().map(str::trim) {
+      if line.starts_with('[') {
+        in_core = line.eq_ignore_ascii_case("[core]");
+        continue;
+      }
+      let Some((key, value)) = line.split_once('=').filter(|_| in_core) else {
+        continue;
+      };
+
+      let value = value.trim().to_ascii_lowercase();
+      match key.trim().to_ascii_lowercase().as_str() {
+        "autocrlf" => self.autocrlf = value == "true",
+        "eol" => {
+          self.core_eol = match value.as_str() {
+            "crlf" => Some(Eol::Crlf),
+            "lf" => Some(Eol::Lf),
+            _ => None,
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+
+  pub fn relative_path(&self, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+      path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    } else {
+      self.prefix.join(path)
+    }
+  }
+
+  fn rule_files(
+    &self,
+    fs: &impl FileSystem,
+    path: &str,
+    file_name: &str,
+  ) -> Vec<(String, usize)> {
+    let dirs = path.match_indices('/').map(|(index, _)| &path[..index]);
+    [""]
+      .into_iter()
+      .chain(dirs)
+      .filter_map(|dir| {
+        let rules = fs.read_to_string(&self.root.join(dir).join(file_name));
+        rules.ok().map(|rules| (rules, dir.len()))
+      })
+      .collect()
+  }
+
+  fn attributes(&self, fs: &impl FileSystem, path: &Path) -> Attributes {
+    let mut attributes = Attributes::default();
+    let path = path.to_string_lossy().replace('\\', "/");
+
+    for (rules, dir_len) in self.rule_files(fs, &path, ".gitattributes") {
+      let within = path[dir_len..].trim_start_matches('/');
+      for line in rules.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+          continue;
+        }
+        let mut fields = line.split_whitespace();
+        let Some(pattern) = fields.next() else {
+          continue;
+        };
+        if !pattern.ends_with('/') && matches_pattern(pattern, within) {
+          attributes.parse(&fields.collect::<Vec<_>>());
+        }
+      }
+    }
+    attributes
+  }
+
+  pub fn is_ignored(&self, fs: &impl FileSystem, path: &Path) -> bool {
+    let path = self.relative_path(path);
+    let path = path.to_string_lossy().replace('\\', "/");
+    let exclude = fs
+      .read_to_string(&self.common_dir.join("info").join("exclude"))
+      .unwrap_or_default();
+    let mut sources = vec![(exclude, 0)];
+    sources.extend(self.rule_files(fs, &path, ".gitignore"));
+
+    let dirs = path
+      .match_indices('/')
+      .map(|(index, _)| (&path[..index], true));
+    for (candidate, is_dir) in dirs.chain([(path.as_str(), false)]) {
+      let mut ignored = false;
+      for (rules, dir_len) in &sources {
+        if *dir_len > 0 && *dir_len >= candidate.len() {
+          continue;
+        }
+        let within = candidate[*dir_len..].trim_start_matches('/');
+
+        for line in rules.lines().map(str::trim_end) {
+          if line.is_empty() || line.starts_with('#') {
+            continue;
+          }
+          let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line),
+          };
+          let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+          };
+          if (is_dir || !dir_only) && matches_pattern(pattern, within) {
+            ignored = !negated;
+          }
+        }
+      }
+      if ignored {
+        return true;
+      }
+    }
+    false
+  }
+
+  pub fn eol(
+    &self,
+    fs: &impl FileSystem,
+    path: &Path,
+    content: &str,
+  ) -> Option<Eol> {
+    let attributes = self.attributes(fs, &self.relative_path(path));
+    let native = if self.autocrlf {
+      Eol::Crlf
+    } else {
+      self.core_eol.unwrap_or(Eol::Lf)
+    };
+
+    match (attributes.text, attributes.eol) {
+      (Some(Text::Unset), _) => None,
+      (_, Some(eol)) => Some(eol),
+      (Some(Text::Set), None) => Some(native),
+      (text, None) => {
+        let automatic = text == Some(Text::Auto) || self.autocrlf;
+        (automatic && !content.contains('\0')).then_some(native)
+      }
+    }
+  }
+}
+
+impl PreImages for Repository {
+  fn pre_image(&self, hash: &str) -> Option<String> {
+    String::from_utf8(self.pre_image_bytes(hash)?).ok()
+  }
+
+  fn pre_image_bytes(&self, hash: &str) -> Option<Vec<u8>> {
+    let output = Command::new("git")
+      .arg("-C")
+      .arg(&self.root)
+      .args(["cat-file", "blob", hash])
+      .output()
+      .ok()?;
+    output.status.success().then_some(output.stdout)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+  repository: Repository,
+  commit: String,
+}
+
+fn read_only() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::PermissionDenied,
+    "git revisions are read-only",
+  )
+}
+
+impl Revision {
+  pub fn resolve(repository: Repository, rev: &str) -> Result<Self, Error> {
+    let output = Command::new("git")
+      .arg("-C")
+      .arg(&repository.root)
+      .args(["rev-parse", "--verify", "--quiet"])
+      .arg(format!("{}^{{commit}}", rev))
+      .output()?;
+    if !output.status.success() {
+      return Err(Error::Invalid(
+        format!("Unknown git revision: `{}`", rev).into(),
+      ));
+    }
+
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(Self { repository, commit })
+  }
+
+  pub fn commit(&self) -> &str {
+    &self.commit
+  }
+}
+
+impl FileSystem for Revision {
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    let path = self.repository.relative_path(path);
+    let path = path.to_string_lossy().replace('\\', "/");
+    let output = Command::new("git")
+      .arg("-C")
+      .arg(&self.repository.root)
+      .args(["cat-file", "blob"])
+      .arg(format!("{}:{}", self.commit, path))
+      .output()?;
+    if !output.status.success() {
+      return Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist in {}", path, self.commit),
+      ));
+    }
+
+    String::from_utf8(output.stdout)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  fn write(&mut self, _: &Path, _: &str) -> io::Result<()> {
+    Err(read_only())
+  }
+
+  fn remove_file(&mut self, _: &Path) -> io::Result<()> {
+    Err(read_only())
+  }
+
+  fn create_dir_all(&mut self, _: &Path) -> io::Result<()> {
+    Err(read_only())
+  }
+
+  #[cfg(unix)]
+  fn set_permissions(&mut self, _: &Path, _: Permissions) -> io::Result<()> {
+    Err(read_only())
+  }
+
+  #[cfg(unix)]
+  fn get_permissions(&self, _: &Path) -> io::Result<Permissions> {
+    Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "git revisions do not expose permissions",
+    ))
+  }
+}
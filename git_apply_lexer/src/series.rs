@@ -0,0 +1,162 @@
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Commit<'a> {
+  pub sha: &'a str,
+  pub author: Option<&'a str>,
+  pub date: Option<&'a str>,
+  pub message: String,
+  pub patches: Vec<Patch<'a>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatchSeries<'a> {
+  pub commits: Vec<Commit<'a>>,
+}
+
+impl<'a> PatchSeries<'a> {
+  pub fn patches(&self) -> impl Iterator<Item = &Patch<'a>> {
+    self.commits.iter().flat_map(|commit| &commit.patches)
+  }
+
+  pub fn into_patches(self) -> impl Iterator<Item = Patch<'a>> {
+    self.commits.into_iter().flat_map(|commit| commit.patches)
+  }
+}
+
+pub struct LogParser<'a> {
+  commits: vec::IntoIter<(Commit<'a>, &'a str)>,
+  current: Option<Parser<'a>>,
+  strip: Option<usize>,
+}
+
+fn commit_sha(line: &str) -> Option<&str> {
+  let sha = line.strip_prefix("commit ")?.split_whitespace().next()?;
+  (sha.len() >= 7 && sha.bytes().all(|byte| byte.is_ascii_hexdigit()))
+    .then_some(sha)
+}
+
+pub fn is_log(source: &str) -> bool {
+  source
+    .lines()
+    .find(|line| !line.trim().is_empty())
+    .and_then(commit_sha)
+    .is_some()
+}
+
+fn is_diff_start(line: &str) -> bool {
+  ["diff ", "--- ", "Index: "]
+    .iter()
+    .any(|prefix| line.starts_with(prefix))
+}
+
+fn parse_commit(text: &str) -> (Commit<'_>, &str) {
+  let mut commit = Commit::default();
+  let mut position = 0;
+  let mut lines = text.split_inclusive('\n').map(|line| {
+    let start = position;
+    position += line.len();
+    (start, line.trim_end_matches(['\n', '\r']))
+  });
+
+  if let Some((_, line)) = lines.next() {
+    commit.sha = commit_sha(line).unwrap_or_default();
+  }
+  for (_, line) in lines.by_ref() {
+    let Some((name, value)) = line.split_once(':') else {
+      break;
+    };
+    match name {
+      "Author" => commit.author = Some(value.trim()),
+      "Date" => commit.date = Some(value.trim()),
+      _ => {}
+    }
+  }
+
+  let mut message = Vec::new();
+  let mut diff_start = text.len();
+  for (start, line) in lines {
+    if is_diff_start(line) {
+      diff_start = start;
+      break;
+    }
+    if let Some(line) = line.strip_prefix("    ") {
+      message.push(line);
+    } else if line.trim().is_empty() {
+      message.push("");
+    }
+  }
+  commit.message = message.join("\n").trim().into();
+  (commit, &text[diff_start..])
+}
+
+fn parser(diff: &str, strip: Option<usize>) -> Parser<'_> {
+  match strip {
+    Some(components) => Parser::new(diff).strip(components),
+    None => Parser::new(diff),
+  }
+}
+
+pub fn split(source: &str) -> Vec<(Commit<'_>, &str)> {
+  let mut commits = Vec::new();
+  let mut start = None;
+  let mut offset = 0;
+  for line in source.split_inclusive('\n') {
+    if commit_sha(line).is_some() {
+      if let Some(start) = start {
+        commits.push(parse_commit(&source[start..offset]));
+      }
+      start = Some(offset);
+    }
+    offset += line.len();
+  }
+  if let Some(start) = start {
+    commits.push(parse_commit(&source[start..]));
+  }
+  commits
+}
+
+impl<'a> LogParser<'a> {
+  pub fn new(source: &'a str) -> Self {
+    Self {
+      commits: split(source).into_iter(),
+      current: None,
+      strip: None,
+    }
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = Some(components);
+    self
+  }
+
+  pub fn series(self) -> Result<PatchSeries<'a>, Error> {
+    let mut series = PatchSeries::default();
+    for (mut commit, diff) in self.commits {
+      commit.patches = parser(diff, self.strip).collect::<Result<_, _>>()?;
+      series.commits.push(commit);
+    }
+    Ok(series)
+  }
+}
+
+impl<'a> Iterator for LogParser<'a> {
+  type Item = Result<Patch<'a>, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(parser) = &mut self.current
+        && let Some(patch) = parser.next()
+      {
+        return Some(patch);
+      }
+      let (_, diff) = self.commits.next()?;
+      self.current = Some(parser(diff, self.strip));
+    }
+  }
+}
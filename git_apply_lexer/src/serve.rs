@@ -0,0 +1,465 @@
+use crate::applier;
+use crate::applier::Issue;
+use crate::error::Error;
+use crate::event::ApplyEvent;
+use crate::fs::FileSystem;
+use crate::options::ApplyOptions;
+use crate::options::DuplicatePolicy;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use crate::whitespace::WhitespaceError;
+use serde_json::Value;
+use serde_json::json;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+#[cfg(feature = "server")]
+use std::path::PathBuf;
+#[cfg(feature = "server")]
+use tiny_http::Header;
+#[cfg(feature = "server")]
+use tiny_http::Response;
+#[cfg(feature = "server")]
+use tiny_http::Server;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const APPLY_ERROR: i64 = -32000;
+const METHODS: &[&str] = &["parse", "stat", "check", "apply"];
+
+struct Failure {
+  code: i64,
+  message: String,
+}
+
+impl From<Error> for Failure {
+  fn from(error: Error) -> Self {
+    Self {
+      code: APPLY_ERROR,
+      message: error.to_string(),
+    }
+  }
+}
+
+fn unknown_method(method: &str) -> Failure {
+  Failure {
+    code: METHOD_NOT_FOUND,
+    message: format!("Unknown method: `{}`", method),
+  }
+}
+
+fn invalid_params(message: &str) -> Failure {
+  Failure {
+    code: INVALID_PARAMS,
+    message: message.to_string(),
+  }
+}
+
+fn path(path: &Path) -> Value {
+  Value::String(path.display().to_string())
+}
+
+fn line_to_json(line: &Line) -> Value {
+  match line {
+    Line::Context(text) => json!({ "kind": "context", "text": text }),
+    Line::Deletion(text) => json!({ "kind": "deletion", "text": text }),
+    Line::Addition(text) => json!({ "kind": "addition", "text": text }),
+    Line::NoNewline => json!({ "kind": "noNewline" }),
+  }
+}
+
+fn hunk_to_json(hunk: &Hunk) -> Value {
+  json!({
+    "oldLine": hunk.old_line,
+    "oldSpan": hunk.old_span,
+    "newLine": hunk.new_line,
+    "newSpan": hunk.new_span,
+    "lines": hunk.lines.iter().map(line_to_json).collect::<Vec<_>>(),
+  })
+}
+
+fn patch_to_json(patch: &Patch) -> Value {
+  json!({
+    "oldFile": patch.old_file,
+    "newFile": patch.new_file,
+    "renameFrom": patch.rename_from,
+    "renameTo": patch.rename_to,
+    "copyFrom": patch.copy_from,
+    "copyTo": patch.copy_to,
+    "oldMode": patch.old_mode,
+    "newMode": patch.new_mode,
+    "deletedFileMode": patch.deleted_file_mode,
+    "indexMode": patch.index_mode,
+    "similarity": patch.similarity,
+    "dissimilarity": patch.dissimilarity,
+    "isBinary": patch.is_binary,
+    "hunks": patch.hunks.iter().map(hunk_to_json).collect::<Vec<_>>(),
+  })
+}
+
+fn event_to_json(event: &ApplyEvent) -> Value {
+  match event {
+    ApplyEvent::Created { path: file } => {
+      json!({ "kind": "created", "path": path(file) })
+    }
+    ApplyEvent::Modified { path: file, hunks } => {
+      json!({ "kind": "modified", "path": path(file), "hunks": hunks })
+    }
+    ApplyEvent::Deleted { path: file } => {
+      json!({ "kind": "deleted", "path": path(file) })
+    }
+    ApplyEvent::Renamed { from, to } => {
+      json!({ "kind": "renamed", "from": path(from), "to": path(to) })
+    }
+    ApplyEvent::Copied { from, to } => {
+      json!({ "kind": "copied", "from": path(from), "to": path(to) })
+    }
+    ApplyEvent::ModeChanged { path: file, mode } => {
+      json!({ "kind": "modeChanged", "path": path(file), "mode": mode })
+    }
+    ApplyEvent::Skipped { path: file } => {
+      json!({ "kind": "skipped", "path": path(file) })
+    }
+    ApplyEvent::LossyRead { path: file } => {
+      json!({ "kind": "lossyRead", "path": path(file) })
+    }
+    ApplyEvent::Duplicate { path: file, policy } => {
+      let policy = match policy {
+        DuplicatePolicy::Error => "error",
+        DuplicatePolicy::InOrder => "inOrder",
+        DuplicatePolicy::LastWins => "lastWins",
+      };
+      json!({ "kind": "duplicate", "path": path(file), "policy": policy })
+    }
+    ApplyEvent::Merged {
+      path: file,
+      conflicts,
+    } => {
+      json!({ "kind": "merged", "path": path(file), "conflicts": conflicts })
+    }
+    ApplyEvent::Rejected { path: file, hunks } => {
+      json!({ "kind": "rejected", "path": path(file), "hunks": hunks })
+    }
+    ApplyEvent::Whitespace {
+      path: file,
+      line,
+      kind,
+      fixed,
+    } => {
+      let error = match kind {
+        WhitespaceError::TrailingSpace => "trailingSpace",
+        WhitespaceError::SpaceBeforeTab => "spaceBeforeTab",
+        WhitespaceError::BlankAtEof => "blankAtEof",
+      };
+      json!({
+        "kind": "whitespace",
+        "path": path(file),
+        "line": line,
+        "error": error,
+        "fixed": fixed,
+      })
+    }
+    ApplyEvent::Offset {
+      path: file,
+      hunk,
+      line,
+      offset,
+      fuzz,
+    } => json!({
+      "kind": "offset",
+      "path": path(file),
+      "hunk": hunk,
+      "line": line,
+      "offset": offset,
+      "fuzz": fuzz,
+    }),
+    ApplyEvent::AlreadyApplied { path: file } => {
+      json!({ "kind": "alreadyApplied", "path": path(file) })
+    }
+    ApplyEvent::Reversed { path: file } => {
+      json!({ "kind": "reversed", "path": path(file) })
+    }
+  }
+}
+
+fn issue_to_json(issue: &Issue) -> Value {
+  match issue {
+    Issue::UnsafePath { path } => json!({ "kind": "unsafePath", "path": path }),
+    Issue::Unsupported { path: file } => {
+      json!({ "kind": "unsupported", "path": path(file) })
+    }
+    Issue::MissingFile { path: file } => {
+      json!({ "kind": "missingFile", "path": path(file) })
+    }
+    Issue::TargetExists { path: file } => {
+      json!({ "kind": "targetExists", "path": path(file) })
+    }
+    Issue::Ignored { path: file } => {
+      json!({ "kind": "ignored", "path": path(file) })
+    }
+    Issue::Unreadable {
+      path: file,
+      message,
+    } => {
+      json!({ "kind": "unreadable", "path": path(file), "message": message })
+    }
+    Issue::HunkUnreachable {
+      path: file,
+      hunk_index,
+      line,
+    } => json!({
+      "kind": "hunkUnreachable",
+      "path": path(file),
+      "hunk": hunk_index,
+      "line": line,
+    }),
+    Issue::ContextMismatch {
+      path: file,
+      hunk_index,
+      line,
+      expected,
+      found,
+    } => json!({
+      "kind": "contextMismatch",
+      "path": path(file),
+      "hunk": hunk_index,
+      "line": line,
+      "expected": expected,
+      "found": found,
+    }),
+    Issue::SimilarityMismatch {
+      path: file,
+      declared,
+      computed,
+    } => json!({
+      "kind": "similarityMismatch",
+      "path": path(file),
+      "declared": declared,
+      "computed": computed,
+    }),
+    Issue::BinaryMismatch {
+      path: file,
+      message,
+    } => json!({
+      "kind": "binaryMismatch",
+      "path": path(file),
+      "message": message,
+    }),
+    Issue::AlreadyApplied { path: file } => {
+      json!({ "kind": "alreadyApplied", "path": path(file) })
+    }
+  }
+}
+
+fn parse_all(text: &str) -> Result<Vec<Patch<'_>>, Error> {
+  Parser::new(text).collect()
+}
+
+fn options(params: &Value) -> ApplyOptions {
+  let mut options =
+    ApplyOptions::new().reverse(params["reverse"].as_bool().unwrap_or(false));
+  if let Some(root) = params["root"].as_str() {
+    options = options.root(root);
+  }
+  options
+}
+
+fn call(
+  fs: &mut impl FileSystem,
+  method: &str,
+  patch: &str,
+  options: &ApplyOptions,
+) -> Result<Value, Failure> {
+  match method {
+    "parse" => {
+      let patches = parse_all(patch)?;
+      Ok(patches.iter().map(patch_to_json).collect())
+    }
+    "stat" => {
+      let stats = Parser::new(patch).stats()?;
+      Ok(
+        stats
+          .into_iter()
+          .map(|stat| {
+            json!({
+              "path": stat.path,
+              "insertions": stat.insertions,
+              "deletions": stat.deletions,
+              "isBinary": stat.is_binary,
+            })
+          })
+          .collect(),
+      )
+    }
+    "check" => {
+      let issues = applier::check(fs, patch, options)?;
+      Ok(json!({
+        "ok": issues.is_empty(),
+        "issues": issues.iter().map(issue_to_json).collect::<Vec<_>>(),
+      }))
+    }
+    "apply" => {
+      let mut events = Vec::new();
+      applier::patch(fs, patch, options, &mut events)?;
+      Ok(json!({
+        "events": events.iter().map(event_to_json).collect::<Vec<_>>(),
+      }))
+    }
+    _ => Err(unknown_method(method)),
+  }
+}
+
+pub fn handle(fs: &mut impl FileSystem, request: &str) -> Value {
+  let respond = |id: Value, result: Result<Value, Failure>| match result {
+    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+    Err(Failure { code, message }) => json!({
+      "jsonrpc": "2.0",
+      "id": id,
+      "error": { "code": code, "message": message },
+    }),
+  };
+
+  let request: Value = match serde_json::from_str(request) {
+    Ok(request) => request,
+    Err(e) => {
+      return respond(
+        Value::Null,
+        Err(Failure {
+          code: PARSE_ERROR,
+          message: e.to_string(),
+        }),
+      );
+    }
+  };
+
+  let id = request["id"].clone();
+  let Some(method) = request["method"].as_str() else {
+    return respond(
+      id,
+      Err(Failure {
+        code: INVALID_REQUEST,
+        message: "`method` must be a string".to_string(),
+      }),
+    );
+  };
+
+  let params = &request["params"];
+  let result = if !METHODS.contains(&method) {
+    Err(unknown_method(method))
+  } else if let Some(patch) = params["patch"].as_str() {
+    call(fs, method, patch, &options(params))
+  } else {
+    Err(invalid_params("`patch` must be a string"))
+  };
+  respond(id, result)
+}
+
+pub fn serve(
+  fs: &mut impl FileSystem,
+  input: impl BufRead,
+  mut output: impl Write,
+) -> io::Result<()> {
+  for line in input.lines() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let response = handle(fs, &line);
+    writeln!(output, "{}", response)?;
+    output.flush()?;
+  }
+  Ok(())
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpConfig {
+  pub root: PathBuf,
+  pub token: Option<String>,
+}
+
+#[cfg(feature = "server")]
+pub fn respond(
+  fs: &mut impl FileSystem,
+  config: &HttpConfig,
+  http_method: &str,
+  url: &str,
+  authorization: Option<&str>,
+  body: &str,
+) -> (u16, Value) {
+  let error =
+    |status: u16, message: &str| (status, json!({ "error": message }));
+
+  if let Some(token) = &config.token
+    && authorization.and_then(|value| value.strip_prefix("Bearer "))
+      != Some(token.as_str())
+  {
+    return error(401, "Missing or invalid bearer token");
+  }
+
+  let (path, query) = url.split_once('?').unwrap_or((url, ""));
+  let method = path.trim_start_matches('/');
+  if !matches!(method, "check" | "apply") {
+    return error(404, &format!("No such endpoint: `{}`", path));
+  }
+  if http_method != "POST" {
+    return error(405, "Only POST is supported");
+  }
+
+  let reverse = query
+    .split('&')
+    .any(|pair| matches!(pair, "reverse" | "reverse=1" | "reverse=true"));
+  let options = ApplyOptions::new().root(&config.root).reverse(reverse);
+  match call(fs, method, body, &options) {
+    Ok(result) => (200, result),
+    Err(Failure { message, .. }) => error(422, &message),
+  }
+}
+
+#[cfg(feature = "server")]
+pub fn serve_http(
+  fs: &mut impl FileSystem,
+  address: &str,
+  config: &HttpConfig,
+) -> io::Result<()> {
+  let server = Server::http(address).map_err(io::Error::other)?;
+  log::info!("Listening on http://{}", address);
+
+  for mut request in server.incoming_requests() {
+    let mut body = String::new();
+    let (status, value) = match request.as_reader().read_to_string(&mut body) {
+      Ok(_) => {
+        let authorization = request
+          .headers()
+          .iter()
+          .find(|header| header.field.equiv("Authorization"))
+          .map(|header| header.value.as_str());
+        respond(
+          fs,
+          config,
+          request.method().as_str(),
+          request.url(),
+          authorization,
+          &body,
+        )
+      }
+      Err(e) => (400, json!({ "error": e.to_string() })),
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+      .expect("static header is valid");
+    let response = Response::from_string(value.to_string())
+      .with_status_code(status)
+      .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+      log::warn!("Failed to send response: {}", e);
+    }
+  }
+
+  Ok(())
+}
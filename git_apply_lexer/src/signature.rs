@@ -0,0 +1,26 @@
+use crate::error::Error;
+use minisign_verify::PublicKey;
+use minisign_verify::Signature;
+
+fn rejected(error: minisign_verify::Error) -> Error {
+  Error::Signature(error.to_string().into())
+}
+
+pub fn verify(
+  patch_text: &str,
+  public_key: &str,
+  signature: &str,
+) -> Result<(), Error> {
+  let public_key = public_key.trim();
+  let public_key = if public_key.contains('\n') {
+    PublicKey::decode(public_key)
+  } else {
+    PublicKey::from_base64(public_key)
+  }
+  .map_err(rejected)?;
+  let signature = Signature::decode(signature).map_err(rejected)?;
+
+  public_key
+    .verify(patch_text.as_bytes(), &signature, false)
+    .map_err(rejected)
+}
@@ -0,0 +1,349 @@
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::lexer::Token;
+use crate::parser::LazyPatch;
+use crate::parser::Line;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use crate::series::Commit;
+use crate::series::PatchSeries;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileStat {
+  pub path: String,
+  pub insertions: usize,
+  pub deletions: usize,
+  pub is_binary: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatSummary {
+  pub files: usize,
+  pub insertions: usize,
+  pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatFormat {
+  #[default]
+  Stat,
+  Numstat,
+  Shortstat,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffStat {
+  pub files: Vec<FileStat>,
+  pub summary: Option<StatSummary>,
+}
+
+impl StatSummary {
+  pub fn of(stats: &[FileStat]) -> Self {
+    Self {
+      files: stats.len(),
+      insertions: stats.iter().map(|stat| stat.insertions).sum(),
+      deletions: stats.iter().map(|stat| stat.deletions).sum(),
+    }
+  }
+}
+
+impl fmt::Display for StatSummary {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+
+    write!(f, " {} file{} changed", self.files, plural(self.files))?;
+    if self.insertions > 0 || self.deletions == 0 {
+      let insertions = self.insertions;
+      write!(f, ", {} insertion{}(+)", insertions, plural(insertions))?;
+    }
+    if self.deletions > 0 || self.insertions == 0 {
+      let deletions = self.deletions;
+      write!(f, ", {} deletion{}(-)", deletions, plural(deletions))?;
+    }
+    Ok(())
+  }
+}
+
+pub fn parse_stat_line(line: &str) -> Option<FileStat> {
+  let (path, change) = line.rsplit_once(" | ")?;
+  let path = path.trim();
+  let mut words = change.split_whitespace();
+  let count = words.next()?;
+  if path.is_empty() {
+    return None;
+  }
+  if count == "Bin" {
+    return Some(FileStat {
+      path: path.to_string(),
+      insertions: 0,
+      deletions: 0,
+      is_binary: true,
+    });
+  }
+
+  let count: usize = count.parse().ok()?;
+  let graph = words.next().unwrap_or_default();
+  if words.next().is_some() || !graph.trim_matches(['+', '-']).is_empty() {
+    return None;
+  }
+  let added = graph.bytes().filter(|&byte| byte == b'+').count();
+  let insertions = match graph.len() {
+    0 => 0,
+    length => (count * added + length / 2) / length,
+  };
+  Some(FileStat {
+    path: path.to_string(),
+    insertions,
+    deletions: count - insertions,
+    is_binary: false,
+  })
+}
+
+pub fn parse_summary(line: &str) -> Option<StatSummary> {
+  let mut parts = line.trim().split(", ");
+  let (files, rest) = parts.next()?.split_once(' ')?;
+  if rest != "file changed" && rest != "files changed" {
+    return None;
+  }
+  let mut summary = StatSummary {
+    files: files.parse().ok()?,
+    ..Default::default()
+  };
+  for part in parts {
+    let (count, kind) = part.split_once(' ')?;
+    let count = count.parse().ok()?;
+    match kind {
+      "insertion(+)" | "insertions(+)" => summary.insertions = count,
+      "deletion(-)" | "deletions(-)" => summary.deletions = count,
+      _ => return None,
+    }
+  }
+  Some(summary)
+}
+
+impl<'a> Patch<'a> {
+  pub fn stat(&self) -> FileStat {
+    let (insertions, deletions) = self
+      .hunks
+      .iter()
+      .flat_map(|hunk| &hunk.lines)
+      .fold((0, 0), |(added, removed), line| match line {
+        Line::Addition(_) => (added + 1, removed),
+        Line::Deletion(_) => (added, removed + 1),
+        _ => (added, removed),
+      });
+
+    FileStat {
+      path: self.display_path(),
+      insertions,
+      deletions,
+      is_binary: self.is_binary,
+    }
+  }
+
+  fn display_path(&self) -> String {
+    let old_file = self
+      .rename_from
+      .as_deref()
+      .or(self.copy_from.as_deref())
+      .unwrap_or(&self.old_file);
+    let new_file = self
+      .rename_to
+      .as_deref()
+      .or(self.copy_to.as_deref())
+      .unwrap_or(&self.new_file);
+
+    if new_file == "/dev/null" {
+      old_file.to_string()
+    } else if old_file == "/dev/null" || old_file == new_file {
+      new_file.to_string()
+    } else {
+      format!("{} => {}", old_file, new_file)
+    }
+  }
+}
+
+impl<'a> Commit<'a> {
+  pub fn stats(&self) -> Vec<FileStat> {
+    self.patches.iter().map(Patch::stat).collect()
+  }
+}
+
+impl<'a> PatchSeries<'a> {
+  pub fn stats(&self) -> Vec<FileStat> {
+    self.patches().map(Patch::stat).collect()
+  }
+
+  pub fn summary(&self) -> StatSummary {
+    StatSummary::of(&self.stats())
+  }
+}
+
+impl<'a> LazyPatch<'a> {
+  pub fn stat(&self) -> FileStat {
+    let (insertions, deletions) = self
+      .hunks
+      .iter()
+      .flat_map(|hunk| Lexer::new(hunk.body))
+      .fold((0, 0), |(added, removed), token| match token {
+        Ok(Token::Addition(_)) => (added + 1, removed),
+        Ok(Token::Deletion(_)) => (added, removed + 1),
+        _ => (added, removed),
+      });
+
+    FileStat {
+      insertions,
+      deletions,
+      ..self.header.stat()
+    }
+  }
+}
+
+impl Parser<'_> {
+  pub fn stats(self) -> Result<Vec<FileStat>, Error> {
+    if self.is_unified() {
+      self
+        .lazy()
+        .map(|patch| patch.map(|patch| patch.stat()))
+        .collect()
+    } else {
+      self.map(|patch| patch.map(|patch| patch.stat())).collect()
+    }
+  }
+}
+
+pub fn render_diffstat(patches: &[Patch], width: usize) -> String {
+  let stats: Vec<FileStat> = patches.iter().map(Patch::stat).collect();
+  render_stats(&stats, width)
+}
+
+pub fn render_stats(stats: &[FileStat], width: usize) -> String {
+  let max_change = stats
+    .iter()
+    .map(|stat| stat.insertions + stat.deletions)
+    .max()
+    .unwrap_or(0);
+  let count_width = stats
+    .iter()
+    .map(|stat| {
+      if stat.is_binary {
+        3
+      } else {
+        (stat.insertions + stat.deletions).to_string().len()
+      }
+    })
+    .max()
+    .unwrap_or(1);
+  let name_width = stats
+    .iter()
+    .map(|stat| stat.path.chars().count())
+    .max()
+    .unwrap_or(0)
+    .min(width.saturating_sub(count_width + 16).max(10));
+  let graph_width = width
+    .saturating_sub(name_width + count_width + 5)
+    .clamp(1, max_change.max(1));
+
+  let mut output = String::new();
+  for stat in stats {
+    let name = truncate_name(&stat.path, name_width);
+    if stat.is_binary {
+      let _ =
+        writeln!(output, " {:<name_width$} | {:>count_width$}", name, "Bin");
+      continue;
+    }
+
+    let (added, removed) =
+      scale(stat.insertions, stat.deletions, graph_width, max_change);
+    let graph = format!("{}{}", "+".repeat(added), "-".repeat(removed));
+    let _ = writeln!(
+      output,
+      " {:<name_width$} | {:>count_width$}{}{}",
+      name,
+      stat.insertions + stat.deletions,
+      if graph.is_empty() { "" } else { " " },
+      graph
+    );
+  }
+
+  let _ = writeln!(output, "{}", StatSummary::of(stats));
+
+  output
+}
+
+pub fn render_numstat(stats: &[FileStat]) -> String {
+  let mut output = String::new();
+  for stat in stats {
+    let _ = if stat.is_binary {
+      writeln!(output, "-\t-\t{}", stat.path)
+    } else {
+      writeln!(
+        output,
+        "{}\t{}\t{}",
+        stat.insertions, stat.deletions, stat.path
+      )
+    };
+  }
+  output
+}
+
+pub fn render_shortstat(stats: &[FileStat]) -> String {
+  format!("{}\n", StatSummary::of(stats))
+}
+
+pub fn render(stats: &[FileStat], format: StatFormat, width: usize) -> String {
+  match format {
+    StatFormat::Stat => render_stats(stats, width),
+    StatFormat::Numstat => render_numstat(stats),
+    StatFormat::Shortstat => render_shortstat(stats),
+  }
+}
+
+fn scale(
+  insertions: usize,
+  deletions: usize,
+  width: usize,
+  max_change: usize,
+) -> (usize, usize) {
+  if max_change <= width {
+    return (insertions, deletions);
+  }
+
+  let linear = |count: usize| {
+    if count == 0 {
+      0
+    } else {
+      1 + count * (width - 1) / max_change
+    }
+  };
+
+  let mut total = linear(insertions + deletions);
+  if total < 2 && insertions > 0 && deletions > 0 {
+    total = 2;
+  }
+
+  if insertions < deletions {
+    let added = linear(insertions);
+    (added, total - added)
+  } else {
+    let removed = linear(deletions);
+    (total - removed, removed)
+  }
+}
+
+fn truncate_name(name: &str, width: usize) -> String {
+  let length = name.chars().count();
+  if length <= width {
+    return name.to_string();
+  }
+
+  let keep = width.saturating_sub(3);
+  let suffix: String = name.chars().skip(length - keep).collect();
+  format!("...{}", suffix)
+}
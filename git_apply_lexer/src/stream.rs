@@ -0,0 +1,174 @@
+use crate::buf::PatchBuf;
+use crate::error::Error;
+use crate::mail;
+use crate::parser::Parser;
+use crate::parser::ParserOptions;
+use crate::series;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::mem;
+
+/// Yields patches one file section at a time, so memory stays bounded by
+/// the largest section rather than the whole input.
+///
+/// Input is split before each `diff ` header (or a `--- ` header following a
+/// hunk) and every section is parsed on its own, which assumes plain git or
+/// unified diffs. Mail and `git log -p` input carries metadata between
+/// sections, so it is detected from the start of the stream and read whole.
+pub struct PatchReader<R> {
+  reader: R,
+  pending: String,
+  queue: VecDeque<Result<PatchBuf, Error>>,
+  done: bool,
+  started: bool,
+  strip: Option<usize>,
+  target: Option<String>,
+  options: ParserOptions,
+}
+
+fn hunk_counts(header: &str) -> Option<(u32, u32)> {
+  let mut ranges = header.strip_prefix("@@ -")?.split(' ');
+  let count = |range: &str| match range.split_once(',') {
+    Some((_, count)) => count.parse().ok(),
+    None => Some(1),
+  };
+  let old = count(ranges.next()?)?;
+  let new = count(ranges.next()?.strip_prefix('+')?)?;
+  Some((old, new))
+}
+
+impl<R: BufRead> PatchReader<R> {
+  pub fn new(reader: R) -> Self {
+    Self {
+      reader,
+      pending: String::new(),
+      queue: VecDeque::new(),
+      done: false,
+      started: false,
+      strip: None,
+      target: None,
+      options: ParserOptions::default(),
+    }
+  }
+
+  pub fn strip(mut self, components: usize) -> Self {
+    self.strip = Some(components);
+    self
+  }
+
+  pub fn target(mut self, path: impl Into<String>) -> Self {
+    self.target = Some(path.into());
+    self
+  }
+
+  pub fn options(mut self, options: ParserOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  fn stop(&mut self) {
+    self.done = true;
+    self.pending.clear();
+  }
+
+  fn read_rest(&mut self, chunk: &mut String) -> Result<(), Error> {
+    chunk.push_str(&mem::take(&mut self.pending));
+    self.reader.read_to_string(chunk)?;
+    self.done = true;
+    Ok(())
+  }
+
+  fn read_chunk(&mut self) -> Result<String, Error> {
+    let mut chunk = mem::take(&mut self.pending);
+    let mut has_hunk = false;
+    let (mut old, mut new): (u32, u32) = (0, 0);
+
+    loop {
+      let mut line = String::new();
+      if self.reader.read_line(&mut line)? == 0 {
+        self.done = true;
+        return Ok(chunk);
+      }
+
+      if old > 0 || new > 0 {
+        match line.as_bytes().first() {
+          Some(b' ' | b'\n' | b'\r') => {
+            old = old.saturating_sub(1);
+            new = new.saturating_sub(1);
+          }
+          Some(b'-') => old = old.saturating_sub(1),
+          Some(b'+') => new = new.saturating_sub(1),
+          Some(b'\\') => {}
+          _ => (old, new) = (0, 0),
+        }
+      } else if let Some(counts) = hunk_counts(&line) {
+        (old, new) = counts;
+        has_hunk = true;
+      } else if !chunk.is_empty()
+        && (line.starts_with("diff ") || (has_hunk && line.starts_with("--- ")))
+      {
+        self.pending = line;
+        return Ok(chunk);
+      }
+      chunk.push_str(&line);
+    }
+  }
+}
+
+impl<R: BufRead> Iterator for PatchReader<R> {
+  type Item = Result<PatchBuf, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while self.queue.is_empty() {
+      if self.done && self.pending.is_empty() {
+        return None;
+      }
+      let mut chunk = match self.read_chunk() {
+        Ok(chunk) => chunk,
+        Err(e) => {
+          self.stop();
+          return Some(Err(e));
+        }
+      };
+      if !mem::replace(&mut self.started, true)
+        && (mail::is_mail(&chunk) || series::is_log(&chunk))
+        && let Err(e) = self.read_rest(&mut chunk)
+      {
+        self.stop();
+        return Some(Err(e));
+      }
+      let parser = Parser::new(&chunk).options(self.options);
+      let parser = match self.strip {
+        Some(components) => parser.strip(components),
+        None => parser,
+      };
+      let parser = match &self.target {
+        Some(target) => parser.target(target.as_str()),
+        None => parser,
+      };
+      for patch in parser {
+        match patch {
+          Ok(patch) => self.queue.push_back(Ok(patch.to_buf())),
+          Err(e) => {
+            self.stop();
+            self.queue.push_back(Err(e));
+            break;
+          }
+        }
+      }
+    }
+    self.queue.pop_front()
+  }
+}
+
+impl Parser<'_> {
+  pub fn from_reader<R: BufRead>(reader: R) -> PatchReader<R> {
+    PatchReader::new(reader)
+  }
+
+  pub fn from_read<R: Read>(reader: R) -> PatchReader<BufReader<R>> {
+    PatchReader::new(BufReader::new(reader))
+  }
+}
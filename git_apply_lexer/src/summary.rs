@@ -0,0 +1,250 @@
+use crate::parser::Patch;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+
+const DEFAULT_MODE: u32 = 0o100644;
+const TYPE_MASK: u32 = 0o170000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+  Added,
+  Modified,
+  Deleted,
+  Renamed,
+  Copied,
+  TypeChanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameStatus {
+  pub status: Status,
+  pub path: String,
+  pub from: Option<String>,
+  pub similarity: Option<u32>,
+}
+
+impl Status {
+  pub fn letter(self) -> char {
+    match self {
+      Status::Added => 'A',
+      Status::Modified => 'M',
+      Status::Deleted => 'D',
+      Status::Renamed => 'R',
+      Status::Copied => 'C',
+      Status::TypeChanged => 'T',
+    }
+  }
+}
+
+impl fmt::Display for NameStatus {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.status.letter())?;
+    if let Some(from) = &self.from {
+      write!(f, "{:03}\t{}", self.similarity.unwrap_or_default(), from)?;
+    }
+    write!(f, "\t{}", self.path)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+  Create {
+    path: String,
+    mode: u32,
+  },
+  Delete {
+    path: String,
+    mode: u32,
+  },
+  Rename {
+    from: String,
+    to: String,
+    similarity: Option<u32>,
+  },
+  Copy {
+    from: String,
+    to: String,
+    similarity: Option<u32>,
+  },
+  ModeChange {
+    path: String,
+    old: u32,
+    new: u32,
+  },
+}
+
+impl fmt::Display for Change {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let similar =
+      |f: &mut fmt::Formatter<'_>, similarity: &Option<u32>| match similarity {
+        Some(percent) => write!(f, " ({}%)", percent),
+        None => Ok(()),
+      };
+    match self {
+      Change::Create { path, mode } => {
+        write!(f, " create mode {:06o} {}", mode, path)
+      }
+      Change::Delete { path, mode } => {
+        write!(f, " delete mode {:06o} {}", mode, path)
+      }
+      Change::Rename {
+        from,
+        to,
+        similarity,
+      } => {
+        write!(f, " rename {}", rename_path(from, to))?;
+        similar(f, similarity)
+      }
+      Change::Copy {
+        from,
+        to,
+        similarity,
+      } => {
+        write!(f, " copy {}", rename_path(from, to))?;
+        similar(f, similarity)
+      }
+      Change::ModeChange { path, old, new } => {
+        write!(f, " mode change {:06o} => {:06o} {}", old, new, path)
+      }
+    }
+  }
+}
+
+pub fn rename_path(from: &str, to: &str) -> String {
+  let (a, b) = (from.as_bytes(), to.as_bytes());
+  let mut prefix = 0;
+  for (index, (x, y)) in a.iter().zip(b).enumerate() {
+    if x != y {
+      break;
+    }
+    if *x == b'/' {
+      prefix = index + 1;
+    }
+  }
+
+  let mut suffix = 0;
+  let (mut i, mut j) = (a.len(), b.len());
+  while i > prefix && j > prefix && a[i - 1] == b[j - 1] {
+    i -= 1;
+    j -= 1;
+    if a[i] == b'/' {
+      suffix = a.len() - i;
+    }
+  }
+
+  if prefix + suffix == 0 {
+    return format!("{} => {}", from, to);
+  }
+  format!(
+    "{}{{{} => {}}}{}",
+    &from[..prefix],
+    &from[prefix..from.len() - suffix],
+    &to[prefix..to.len() - suffix],
+    &from[from.len() - suffix..]
+  )
+}
+
+pub fn changes(patch: &Patch) -> Vec<Change> {
+  let mut changes = Vec::new();
+  let created = patch.old_file == "/dev/null";
+  let deleted = patch.new_file == "/dev/null";
+  if created {
+    changes.push(Change::Create {
+      path: patch.new_file.to_string(),
+      mode: patch.new_mode.or(patch.index_mode).unwrap_or(DEFAULT_MODE),
+    });
+  } else if deleted {
+    changes.push(Change::Delete {
+      path: patch.old_file.to_string(),
+      mode: patch
+        .deleted_file_mode
+        .or(patch.index_mode)
+        .unwrap_or(DEFAULT_MODE),
+    });
+  } else if let (Some(from), Some(to)) = (&patch.rename_from, &patch.rename_to)
+  {
+    changes.push(Change::Rename {
+      from: from.to_string(),
+      to: to.to_string(),
+      similarity: patch.similarity,
+    });
+  } else if let (Some(from), Some(to)) = (&patch.copy_from, &patch.copy_to) {
+    changes.push(Change::Copy {
+      from: from.to_string(),
+      to: to.to_string(),
+      similarity: patch.similarity,
+    });
+  }
+
+  if !created
+    && !deleted
+    && let (Some(old), Some(new)) = (patch.old_mode, patch.new_mode)
+    && old != new
+  {
+    changes.push(Change::ModeChange {
+      path: patch.new_file.to_string(),
+      old,
+      new,
+    });
+  }
+  changes
+}
+
+pub fn name_status(patch: &Patch) -> NameStatus {
+  let mut name = NameStatus {
+    status: Status::Modified,
+    path: patch.new_file.to_string(),
+    from: None,
+    similarity: None,
+  };
+  if patch.old_file == "/dev/null" {
+    name.status = Status::Added;
+  } else if patch.new_file == "/dev/null" {
+    name.status = Status::Deleted;
+    name.path = patch.old_file.to_string();
+  } else if let (Some(from), Some(to)) = (&patch.rename_from, &patch.rename_to)
+  {
+    name.status = Status::Renamed;
+    name.path = to.to_string();
+    name.from = Some(from.to_string());
+    name.similarity = patch.similarity;
+  } else if let (Some(from), Some(to)) = (&patch.copy_from, &patch.copy_to) {
+    name.status = Status::Copied;
+    name.path = to.to_string();
+    name.from = Some(from.to_string());
+    name.similarity = patch.similarity;
+  } else if let (Some(old), Some(new)) = (patch.old_mode, patch.new_mode)
+    && old & TYPE_MASK != new & TYPE_MASK
+  {
+    name.status = Status::TypeChanged;
+  }
+  name
+}
+
+pub fn render_name_only(patches: &[Patch]) -> String {
+  let mut output = String::new();
+  for name in patches.iter().map(name_status) {
+    let _ = writeln!(output, "{}", name.path);
+  }
+  output
+}
+
+pub fn render_name_status(patches: &[Patch]) -> String {
+  let mut output = String::new();
+  for name in patches.iter().map(name_status) {
+    let _ = writeln!(output, "{}", name);
+  }
+  output
+}
+
+pub fn render(patches: &[Patch]) -> String {
+  let mut output = String::new();
+  for change in patches.iter().flat_map(changes) {
+    let _ = writeln!(output, "{}", change);
+  }
+  output
+}
@@ -0,0 +1,80 @@
+use crate::applier::HunkResult;
+use crate::error::Error;
+
+pub const PATCHES_PARSED: &str = "hit_patches_parsed_total";
+pub const HUNKS_APPLIED: &str = "hit_hunks_applied_total";
+pub const HUNKS_FUZZED: &str = "hit_hunks_fuzzed_total";
+pub const HUNK_OFFSET: &str = "hit_hunk_offset_lines";
+pub const FAILURES: &str = "hit_failures_total";
+pub const BYTES_WRITTEN: &str = "hit_bytes_written_total";
+
+pub fn describe() {
+  #[cfg(feature = "metrics")]
+  {
+    metrics::describe_counter!(PATCHES_PARSED, "Patches parsed for applying");
+    metrics::describe_counter!(HUNKS_APPLIED, "Hunks spliced into files");
+    metrics::describe_counter!(HUNKS_FUZZED, "Hunks applied with fuzz");
+    metrics::describe_histogram!(
+      HUNK_OFFSET,
+      "Lines each hunk moved from its declared position"
+    );
+    metrics::describe_counter!(FAILURES, "Failed applies by error kind");
+    metrics::describe_counter!(
+      BYTES_WRITTEN,
+      metrics::Unit::Bytes,
+      "Bytes written to patched files"
+    );
+  }
+}
+
+pub fn failure_kind(error: &Error) -> &'static str {
+  match error {
+    Error::Clap(_) => "usage",
+    Error::Io(..) => "io",
+    Error::Parse(_) => "parse",
+    Error::UnexpectedEof { .. } => "unexpected_eof",
+    Error::HunkMismatch { .. } => "hunk_mismatch",
+    Error::ExpectedEof { .. } => "expected_eof",
+    Error::AlreadyApplied { .. } => "already_applied",
+    Error::UnsafePath { .. } => "unsafe_path",
+    Error::NoRepository(_) => "no_repository",
+    Error::Invalid(_) => "invalid",
+    Error::Unsupported(_) => "unsupported",
+    Error::Signature(_) => "signature",
+    Error::Manifest(_) => "manifest",
+    Error::Cache(_) => "cache",
+  }
+}
+
+pub(crate) fn patch_parsed() {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(PATCHES_PARSED).increment(1);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn hunks_applied(results: &[HunkResult]) {
+  #[cfg(feature = "metrics")]
+  {
+    metrics::counter!(HUNKS_APPLIED).increment(results.len() as u64);
+    let fuzzed = results.iter().filter(|result| result.fuzz > 0).count();
+    if fuzzed > 0 {
+      metrics::counter!(HUNKS_FUZZED).increment(fuzzed as u64);
+    }
+    let offset = metrics::histogram!(HUNK_OFFSET);
+    for result in results {
+      offset.record(result.offset.unsigned_abs() as f64);
+    }
+  }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn failed(error: &Error) {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(FAILURES, "kind" => failure_kind(error)).increment(1);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn bytes_written(bytes: usize) {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(BYTES_WRITTEN).increment(bytes as u64);
+}
@@ -0,0 +1,260 @@
+use crate::error::Error;
+use crate::fs::Contents;
+use crate::fs::FileSystem;
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug)]
+enum Operation {
+  Write(PathBuf, Arc<[u8]>),
+  Remove(PathBuf),
+  Rename(PathBuf, PathBuf),
+  Copy(PathBuf, PathBuf),
+  CreateDir(PathBuf),
+  #[cfg(unix)]
+  SetPermissions(PathBuf, Permissions),
+}
+
+#[derive(Debug)]
+struct Backup {
+  path: PathBuf,
+  contents: Option<Vec<u8>>,
+  #[cfg(unix)]
+  permissions: Option<Permissions>,
+}
+
+#[derive(Debug)]
+pub struct Transaction<'f, F: FileSystem> {
+  inner: &'f mut F,
+  files: HashMap<PathBuf, Option<Arc<[u8]>>>,
+  #[cfg(unix)]
+  permissions: HashMap<PathBuf, Permissions>,
+  operations: Vec<Operation>,
+}
+
+fn not_found() -> io::Error {
+  io::Error::new(io::ErrorKind::NotFound, "file not found")
+}
+
+impl<'f, F: FileSystem> Transaction<'f, F> {
+  pub fn new(inner: &'f mut F) -> Self {
+    Self {
+      inner,
+      files: HashMap::new(),
+      #[cfg(unix)]
+      permissions: HashMap::new(),
+      operations: Vec::new(),
+    }
+  }
+
+  pub fn commit(self) -> Result<(), Error> {
+    let mut backups = Vec::new();
+    for operation in self.operations {
+      if let Err((e, path)) = Self::replay(self.inner, &mut backups, operation)
+      {
+        Self::restore(self.inner, backups);
+        return Err(Error::io_at(e, &path));
+      }
+    }
+    Ok(())
+  }
+
+  fn replay(
+    inner: &mut F,
+    backups: &mut Vec<Backup>,
+    operation: Operation,
+  ) -> Result<(), (io::Error, PathBuf)> {
+    let mut backup = |path: &Path, inner: &F| {
+      if backups.iter().any(|backup| backup.path == path) {
+        return;
+      }
+      backups.push(Backup {
+        path: path.to_path_buf(),
+        contents: inner.read_bytes(path).ok(),
+        #[cfg(unix)]
+        permissions: inner.get_permissions(path).ok(),
+      });
+    };
+    let result = match &operation {
+      Operation::Write(path, contents) => {
+        backup(path, inner);
+        inner.write_bytes(path, contents)
+      }
+      Operation::Remove(path) => {
+        backup(path, inner);
+        inner.remove_file(path)
+      }
+      Operation::Rename(from, to) => {
+        backup(from, inner);
+        backup(to, inner);
+        inner.rename(from, to)
+      }
+      Operation::Copy(from, to) => {
+        backup(to, inner);
+        inner.copy(from, to)
+      }
+      Operation::CreateDir(path) => inner.create_dir_all(path),
+      #[cfg(unix)]
+      Operation::SetPermissions(path, permissions) => {
+        backup(path, inner);
+        inner.set_permissions(path, permissions.clone())
+      }
+    };
+    result.map_err(|e| {
+      let path = match operation {
+        Operation::Write(path, _)
+        | Operation::Remove(path)
+        | Operation::Rename(path, _)
+        | Operation::Copy(path, _)
+        | Operation::CreateDir(path) => path,
+        #[cfg(unix)]
+        Operation::SetPermissions(path, _) => path,
+      };
+      (e, path)
+    })
+  }
+
+  fn shared(&self, path: &Path) -> io::Result<Arc<[u8]>> {
+    match self.files.get(path) {
+      Some(contents) => contents.clone().ok_or_else(not_found),
+      None => self.inner.read_bytes(path).map(Arc::from),
+    }
+  }
+
+  fn restore(inner: &mut F, backups: Vec<Backup>) {
+    for backup in backups.into_iter().rev() {
+      let restored = match &backup.contents {
+        Some(contents) => inner.write_bytes(&backup.path, contents),
+        None => match inner.remove_file(&backup.path) {
+          Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+          result => result,
+        },
+      };
+      #[cfg(unix)]
+      let restored = restored.and_then(|()| match backup.permissions {
+        Some(permissions) => inner.set_permissions(&backup.path, permissions),
+        None => Ok(()),
+      });
+      if let Err(e) = restored {
+        log::error!(
+          path:% = backup.path.display();
+          "Failed to roll back: {}: {}",
+          backup.path.display(),
+          e
+        );
+      }
+    }
+  }
+}
+
+impl<F: FileSystem> FileSystem for Transaction<'_, F> {
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    match self.files.get(path) {
+      Some(Some(contents)) => String::from_utf8(contents.to_vec())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+      Some(None) => Err(not_found()),
+      None => self.inner.read_to_string(path),
+    }
+  }
+
+  fn read_contents(&self, path: &Path) -> io::Result<Contents> {
+    match self.files.get(path) {
+      Some(_) => self.read_to_string(path).map(Contents::Owned),
+      None => self.inner.read_contents(path),
+    }
+  }
+
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    match self.files.get(path) {
+      Some(Some(contents)) => Ok(contents.to_vec()),
+      Some(None) => Err(not_found()),
+      None => self.inner.read_bytes(path),
+    }
+  }
+
+  fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+    self.write_bytes(path, contents.as_bytes())
+  }
+
+  fn write_bytes(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+    let contents: Arc<[u8]> = contents.into();
+    self
+      .files
+      .insert(path.to_path_buf(), Some(contents.clone()));
+    self
+      .operations
+      .push(Operation::Write(path.to_path_buf(), contents));
+    Ok(())
+  }
+
+  fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+    self.read_bytes(path)?;
+    self.files.insert(path.to_path_buf(), None);
+    #[cfg(unix)]
+    self.permissions.remove(path);
+    self.operations.push(Operation::Remove(path.to_path_buf()));
+    Ok(())
+  }
+
+  fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    let contents = self.shared(from)?;
+    #[cfg(unix)]
+    if let Ok(permissions) = self.get_permissions(from) {
+      self.permissions.remove(from);
+      self.permissions.insert(to.to_path_buf(), permissions);
+    }
+    self.files.insert(from.to_path_buf(), None);
+    self.files.insert(to.to_path_buf(), Some(contents));
+    self
+      .operations
+      .push(Operation::Rename(from.to_path_buf(), to.to_path_buf()));
+    Ok(())
+  }
+
+  fn copy(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+    let contents = self.shared(from)?;
+    #[cfg(unix)]
+    if let Ok(permissions) = self.get_permissions(from) {
+      self.permissions.insert(to.to_path_buf(), permissions);
+    }
+    self.files.insert(to.to_path_buf(), Some(contents));
+    self
+      .operations
+      .push(Operation::Copy(from.to_path_buf(), to.to_path_buf()));
+    Ok(())
+  }
+
+  fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+    self
+      .operations
+      .push(Operation::CreateDir(path.to_path_buf()));
+    Ok(())
+  }
+
+  #[cfg(unix)]
+  fn set_permissions(
+    &mut self,
+    path: &Path,
+    perm: Permissions,
+  ) -> io::Result<()> {
+    self.permissions.insert(path.to_path_buf(), perm.clone());
+    self
+      .operations
+      .push(Operation::SetPermissions(path.to_path_buf(), perm));
+    Ok(())
+  }
+
+  #[cfg(unix)]
+  fn get_permissions(&self, path: &Path) -> io::Result<Permissions> {
+    match (self.permissions.get(path), self.files.get(path)) {
+      (Some(permissions), _) => Ok(permissions.clone()),
+      (None, Some(None)) => Err(not_found()),
+      (None, _) => self.inner.get_permissions(path),
+    }
+  }
+}
@@ -0,0 +1,123 @@
+use crate::applier;
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use crate::render;
+use crate::render::ColorScheme;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::slice;
+
+const DEV_NULL: &str = "/dev/null";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+  Render {
+    index: usize,
+    rendered: String,
+  },
+  MissingFile {
+    index: usize,
+    path: String,
+  },
+  Apply {
+    index: usize,
+    error: Error,
+  },
+  Revert {
+    index: usize,
+    error: Error,
+  },
+  Restore {
+    index: usize,
+    expected: String,
+    found: String,
+  },
+}
+
+impl fmt::Display for Mismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Render { index, .. } => write!(
+        f,
+        "patch #{}: parsing the rendered patch does not give it back",
+        index + 1
+      ),
+      Self::MissingFile { index, path } => {
+        write!(f, "patch #{}: {}: no such file", index + 1, path)
+      }
+      Self::Apply { index, error } => {
+        write!(f, "patch #{}: {}", index + 1, error)
+      }
+      Self::Revert { index, error } => {
+        write!(f, "patch #{}: inverted patch fails: {}", index + 1, error)
+      }
+      Self::Restore { index, .. } => write!(
+        f,
+        "patch #{}: inverted patch does not restore the original",
+        index + 1
+      ),
+    }
+  }
+}
+
+fn render_roundtrip(index: usize, patch: &Patch) -> Option<Mismatch> {
+  let rendered = render::pretty(patch, ColorScheme::plain());
+  let parsed = Parser::new(&rendered).collect::<Result<Vec<_>, _>>();
+  match parsed {
+    Ok(parsed) if parsed == slice::from_ref(patch) => None,
+    _ => Some(Mismatch::Render { index, rendered }),
+  }
+}
+
+fn apply_roundtrip(
+  index: usize,
+  patch: &Patch,
+  files: &HashMap<PathBuf, String>,
+) -> Option<Mismatch> {
+  let source = if patch.old_file == DEV_NULL {
+    ""
+  } else {
+    let path = patch.copy_from.as_deref().unwrap_or(&patch.old_file);
+    match files.get(&PathBuf::from(path)) {
+      Some(source) => source.as_str(),
+      None => {
+        return Some(Mismatch::MissingFile {
+          index,
+          path: path.to_string(),
+        });
+      }
+    }
+  };
+
+  let applied = match applier::apply(patch, source) {
+    Ok(applied) => applied,
+    Err(error) => return Some(Mismatch::Apply { index, error }),
+  };
+  match applier::apply(&patch.clone().invert(), &applied) {
+    Ok(restored) if restored == source => None,
+    Ok(found) => Some(Mismatch::Restore {
+      index,
+      expected: source.to_string(),
+      found,
+    }),
+    Err(error) => Some(Mismatch::Revert { index, error }),
+  }
+}
+
+pub fn roundtrip(
+  patch_text: &str,
+  files: &HashMap<PathBuf, String>,
+) -> Result<Vec<Mismatch>, Error> {
+  let patches = Parser::new(patch_text).collect::<Result<Vec<_>, _>>()?;
+
+  let mut mismatches = Vec::new();
+  for (index, patch) in patches.iter().enumerate() {
+    mismatches.extend(render_roundtrip(index, patch));
+    if !patch.is_binary {
+      mismatches.extend(apply_roundtrip(index, patch, files));
+    }
+  }
+  Ok(mismatches)
+}
@@ -0,0 +1,112 @@
+use crate::applier;
+use crate::error::Error;
+use crate::fs::MockFileSystem;
+use crate::options::ApplyOptions;
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Parser;
+use crate::parser::Patch;
+use js_sys::Array;
+use js_sys::Map;
+use js_sys::Object;
+use js_sys::Reflect;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use wasm_bindgen::JsError;
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+  let _ = Reflect::set(object, &JsValue::from_str(key), &value.into());
+}
+
+fn line_to_js(line: &Line) -> Object {
+  let object = Object::new();
+  let (kind, text) = match line {
+    Line::Context(text) => ("context", Some(text)),
+    Line::Deletion(text) => ("deletion", Some(text)),
+    Line::Addition(text) => ("addition", Some(text)),
+    Line::NoNewline => ("noNewline", None),
+  };
+  set(&object, "kind", kind);
+  if let Some(text) = text {
+    set(&object, "text", text.as_ref());
+  }
+  object
+}
+
+fn hunk_to_js(hunk: &Hunk) -> Object {
+  let object = Object::new();
+  set(&object, "oldLine", hunk.old_line);
+  set(&object, "oldSpan", hunk.old_span);
+  set(&object, "newLine", hunk.new_line);
+  set(&object, "newSpan", hunk.new_span);
+  set(
+    &object,
+    "lines",
+    hunk.lines.iter().map(line_to_js).collect::<Array>(),
+  );
+  object
+}
+
+fn patch_to_js(patch: &Patch) -> Object {
+  let object = Object::new();
+  set(&object, "oldFile", patch.old_file.as_ref());
+  set(&object, "newFile", patch.new_file.as_ref());
+  set(&object, "renameFrom", patch.rename_from.as_deref());
+  set(&object, "renameTo", patch.rename_to.as_deref());
+  set(&object, "copyFrom", patch.copy_from.as_deref());
+  set(&object, "copyTo", patch.copy_to.as_deref());
+  set(&object, "oldMode", patch.old_mode);
+  set(&object, "newMode", patch.new_mode);
+  set(&object, "deletedFileMode", patch.deleted_file_mode);
+  set(&object, "indexMode", patch.index_mode);
+  set(&object, "similarity", patch.similarity);
+  set(&object, "dissimilarity", patch.dissimilarity);
+  set(&object, "isBinary", patch.is_binary);
+  set(
+    &object,
+    "hunks",
+    patch.hunks.iter().map(hunk_to_js).collect::<Array>(),
+  );
+  object
+}
+
+#[wasm_bindgen(js_name = parsePatch)]
+pub fn parse_patch(text: &str) -> Result<JsValue, JsError> {
+  let patches = Parser::new(text).collect::<Result<Vec<_>, Error>>()?;
+  Ok(patches.iter().map(patch_to_js).collect::<Array>().into())
+}
+
+#[wasm_bindgen(js_name = applyToMap)]
+pub fn apply_to_map(files: &Map, patch: &str) -> Result<Map, JsError> {
+  let mut contents = HashMap::new();
+  let mut invalid_entry = false;
+  files.for_each(
+    &mut |value, key| match (key.as_string(), value.as_string()) {
+      (Some(path), Some(content)) => {
+        contents.insert(PathBuf::from(path), content);
+      }
+      _ => invalid_entry = true,
+    },
+  );
+  if invalid_entry {
+    return Err(JsError::new(
+      "files must map string paths to string contents",
+    ));
+  }
+
+  let mut fs = MockFileSystem::new(contents);
+  applier::patch(&mut fs, patch, &ApplyOptions::default(), &mut ())?;
+
+  let result = Map::new();
+  for path in fs.paths() {
+    result.set(
+      &JsValue::from_str(&path.to_string_lossy()),
+      &JsValue::from_str(&String::from_utf8_lossy(
+        fs.file_bytes(path).unwrap_or_default(),
+      )),
+    );
+  }
+  Ok(result)
+}
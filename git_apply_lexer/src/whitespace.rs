@@ -0,0 +1,155 @@
+use crate::parser::Hunk;
+use crate::parser::Line;
+use crate::parser::Patch;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhitespaceError {
+  TrailingSpace,
+  SpaceBeforeTab,
+  BlankAtEof,
+}
+
+impl fmt::Display for WhitespaceError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::TrailingSpace => "trailing whitespace",
+      Self::SpaceBeforeTab => "space before tab in indent",
+      Self::BlankAtEof => "new blank line at EOF",
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhitespaceIssue {
+  pub line: usize,
+  pub kind: WhitespaceError,
+}
+
+fn content(text: &str) -> &str {
+  text.strip_suffix('\r').unwrap_or(text)
+}
+
+fn indent(text: &str) -> &str {
+  let end = text.find(|c| c != ' ' && c != '\t').unwrap_or(text.len());
+  &text[..end]
+}
+
+fn is_blank(text: &str) -> bool {
+  content(text).trim_matches([' ', '\t']).is_empty()
+}
+
+pub fn line_errors(text: &str) -> impl Iterator<Item = WhitespaceError> {
+  let text = content(text);
+  let trailing = text.ends_with([' ', '\t']);
+  let before_tab = indent(text).contains(" \t");
+  [
+    trailing.then_some(WhitespaceError::TrailingSpace),
+    before_tab.then_some(WhitespaceError::SpaceBeforeTab),
+  ]
+  .into_iter()
+  .flatten()
+}
+
+pub fn fix_line(text: &str) -> Cow<'_, str> {
+  if line_errors(text).next().is_none() {
+    return Cow::Borrowed(text);
+  }
+
+  let cr = if text.ends_with('\r') { "\r" } else { "" };
+  let body = content(text).trim_end_matches([' ', '\t']);
+  let indent = indent(body);
+  let mut fixed = String::with_capacity(text.len());
+  let mut spaces = 0;
+  for c in indent.chars() {
+    match c {
+      ' ' => spaces += 1,
+      _ => {
+        spaces = 0;
+        fixed.push('\t');
+      }
+    }
+  }
+  fixed.extend(iter::repeat_n(' ', spaces));
+  fixed.push_str(&body[indent.len()..]);
+  fixed.push_str(cr);
+  Cow::Owned(fixed)
+}
+
+fn blank_tail(hunk: &Hunk) -> usize {
+  let lines = match hunk.lines.last() {
+    Some(Line::NoNewline) => &hunk.lines[..hunk.lines.len() - 1],
+    _ => &hunk.lines[..],
+  };
+  if lines
+    .last()
+    .is_some_and(|line| matches!(line, Line::Context(_)))
+  {
+    return 0;
+  }
+  lines
+    .iter()
+    .rev()
+    .filter(|line| !matches!(line, Line::Deletion(_)))
+    .take_while(|line| matches!(line, Line::Addition(text) if is_blank(text)))
+    .count()
+}
+
+pub fn check(patch: &Patch) -> Vec<WhitespaceIssue> {
+  let mut issues = Vec::new();
+  for hunk in &patch.hunks {
+    let mut line = hunk.new_line.max(1) as usize;
+    for hunk_line in &hunk.lines {
+      match hunk_line {
+        Line::Addition(text) => {
+          issues.extend(
+            line_errors(text).map(|kind| WhitespaceIssue { line, kind }),
+          );
+          line += 1;
+        }
+        Line::Context(_) => line += 1,
+        Line::Deletion(_) | Line::NoNewline => {}
+      }
+    }
+  }
+
+  if let Some(hunk) = patch.hunks.last() {
+    let blank = blank_tail(hunk);
+    let end = (hunk.new_line + hunk.new_span) as usize;
+    issues.extend((end - blank..end).map(|line| WhitespaceIssue {
+      line,
+      kind: WhitespaceError::BlankAtEof,
+    }));
+  }
+  issues
+}
+
+pub fn fix(patch: &mut Patch) {
+  for hunk in &mut patch.hunks {
+    for line in &mut hunk.lines {
+      if let Line::Addition(text) = line
+        && let Cow::Owned(fixed) = fix_line(text)
+      {
+        *text = Cow::Owned(fixed);
+      }
+    }
+  }
+
+  let Some(hunk) = patch.hunks.last_mut() else {
+    return;
+  };
+  let mut blank = blank_tail(hunk);
+  let mut index = hunk.lines.len();
+  while blank > 0 && index > 0 {
+    index -= 1;
+    if let Line::Addition(_) = hunk.lines[index] {
+      hunk.lines.remove(index);
+      hunk.new_span -= 1;
+      blank -= 1;
+    }
+  }
+}
@@ -0,0 +1,69 @@
+use hit::annotate;
+use hit::error::Error;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIFF: &str = r#"--- a/file.txt
++++ b/file.txt
+@@ -2,3 +2,3 @@
+ two
+-three
++THREE
+ four
+@@ -9,2 +9,3 @@
+ nine
+ ten
++eleven
+"#;
+
+fn source() -> String {
+  [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    "ten",
+  ]
+  .map(|line| format!("{}\n", line))
+  .concat()
+}
+
+#[test]
+fn annotate_marks_changes_with_both_line_numbers() {
+  let patch = Parser::new(DIFF).next().unwrap().unwrap();
+  assert_eq!(
+    annotate::annotate(&patch, &source()).unwrap(),
+    " 1  1   one
+ 2  2   two
+ 3    - three
+    3 + THREE
+ 4  4   four
+ 5  5   five
+ 6  6   six
+ 7  7   seven
+ 8  8   eight
+ 9  9   nine
+10 10   ten
+   11 + eleven
+"
+  );
+}
+
+#[test]
+fn preview_annotates_without_writing() {
+  let fs =
+    MockFileSystem::new(HashMap::from([(PathBuf::from("file.txt"), source())]));
+
+  let preview = annotate::preview(&fs, DIFF, &ApplyOptions::new()).unwrap();
+  assert!(preview.starts_with("==> file.txt <==\n 1  1   one\n"));
+  assert_eq!(fs.file("file.txt").unwrap(), source());
+
+  let fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "changed\n".to_string(),
+  )]));
+  assert!(matches!(
+    annotate::preview(&fs, DIFF, &ApplyOptions::new()),
+    Err(Error::HunkMismatch { .. } | Error::UnexpectedEof { .. })
+  ));
+}
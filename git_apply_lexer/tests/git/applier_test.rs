@@ -1,28 +1,35 @@
 use hit::applier;
+use hit::applier::PreImages;
 use hit::error::Error;
+use hit::event::ApplyEvent;
 use hit::fs::FileSystem;
 use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
 use hit::parser::Hunk;
 use hit::parser::Line;
+use hit::parser::Parser;
 use hit::parser::Patch;
 use std::collections::HashMap;
+use std::io::BufReader;
 use std::path::PathBuf;
+use std::ptr;
 
 #[test]
 fn apply_simple_patch() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 3,
       new_line: 1,
       new_span: 3,
+      heading: None,
       lines: vec![
-        Line::Context("  context 1"),
-        Line::Deletion("old line"),
-        Line::Addition("new line"),
-        Line::Context("  context 2"),
+        Line::Context("  context 1".into()),
+        Line::Deletion("old line".into()),
+        Line::Addition("new line".into()),
+        Line::Context("  context 2".into()),
       ],
     }],
     ..Default::default()
@@ -37,18 +44,19 @@ fn apply_simple_patch() {
 #[test]
 fn apply_removes_trailing_newline() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 2,
       new_line: 1,
       new_span: 2,
+      heading: None,
       lines: vec![
-        Line::Deletion("line1"),
-        Line::Deletion("line2"),
-        Line::Addition("Line1_Changed"),
-        Line::Addition("line2"),
+        Line::Deletion("line1".into()),
+        Line::Deletion("line2".into()),
+        Line::Addition("Line1_Changed".into()),
+        Line::Addition("line2".into()),
         Line::NoNewline,
       ],
     }],
@@ -62,17 +70,18 @@ fn apply_removes_trailing_newline() {
 #[test]
 fn apply_adds_trailing_newline() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 1,
       new_line: 1,
       new_span: 2,
+      heading: None,
       lines: vec![
-        Line::Deletion("hello"),
-        Line::Addition("hello"),
-        Line::Addition("world"),
+        Line::Deletion("hello".into()),
+        Line::Addition("hello".into()),
+        Line::Addition("world".into()),
       ],
     }],
     ..Default::default()
@@ -85,20 +94,21 @@ fn apply_adds_trailing_newline() {
 #[test]
 fn apply_preserves_and_adds_trailing_newline() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 2,
       new_line: 1,
       new_span: 3,
+      heading: None,
       lines: vec![
-        Line::Deletion("line1"),
-        Line::Deletion("line2"),
+        Line::Deletion("line1".into()),
+        Line::Deletion("line2".into()),
         Line::NoNewline,
-        Line::Addition("line1"),
-        Line::Addition("line2"),
-        Line::Addition("line3"),
+        Line::Addition("line1".into()),
+        Line::Addition("line2".into()),
+        Line::Addition("line3".into()),
       ],
     }],
     ..Default::default()
@@ -111,17 +121,18 @@ fn apply_preserves_and_adds_trailing_newline() {
 #[test]
 fn apply_mismatch_on_unexpected_trailing_newline() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 1,
       new_line: 1,
       new_span: 1,
+      heading: None,
       lines: vec![
-        Line::Deletion("hello"),
+        Line::Deletion("hello".into()),
         Line::NoNewline,
-        Line::Addition("world"),
+        Line::Addition("world".into()),
       ],
     }],
     ..Default::default()
@@ -130,13 +141,13 @@ fn apply_mismatch_on_unexpected_trailing_newline() {
   let result = applier::apply(&patch, source);
   assert!(result.is_err());
   match result.unwrap_err() {
-    Error::Apply(msg) => {
+    e @ Error::ExpectedEof { line: 2, .. } => {
       assert_eq!(
-        msg,
-        "Patch mismatch at line 2. Expected end of file, Found: ``"
+        e.to_string(),
+        "Failed to apply patch: file.txt: hunk #1: Patch mismatch at line 2. Expected end of file, Found: ``"
       );
     }
-    _ => panic!("Expected Apply error"),
+    e => panic!("Expected ExpectedEof error, got {:?}", e),
   }
 }
 
@@ -157,9 +168,9 @@ rename to new_name.txt
   files.insert(PathBuf::from("old_name.txt"), "file content\n".to_string());
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(!fs.files.contains_key(&PathBuf::from("old_name.txt")));
-  assert!(fs.files.contains_key(&PathBuf::from("new_name.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(!fs.contains("old_name.txt"));
+  assert!(fs.contains("new_name.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("new_name.txt")).unwrap(),
     "new file content\n"
@@ -178,9 +189,9 @@ rename to new_metadata.txt
   files.insert(PathBuf::from("old_metadata.txt"), "content".to_string());
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(!fs.files.contains_key(&PathBuf::from("old_metadata.txt")));
-  assert!(fs.files.contains_key(&PathBuf::from("new_metadata.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(!fs.contains("old_metadata.txt"));
+  assert!(fs.contains("new_metadata.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("new_metadata.txt"))
       .unwrap(),
@@ -191,14 +202,15 @@ rename to new_metadata.txt
 #[test]
 fn apply_patch_mismatch() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 1,
       new_line: 1,
       new_span: 1,
-      lines: vec![Line::Context("expected line")],
+      heading: None,
+      lines: vec![Line::Context("expected line".into())],
     }],
     ..Default::default()
   };
@@ -206,15 +218,16 @@ fn apply_patch_mismatch() {
   let source = "different line";
   let result = applier::apply(&patch, source);
   assert!(result.is_err());
-  match result.unwrap_err() {
-    Error::Apply(msg) => {
-      assert_eq!(
-        msg,
-        "Patch mismatch at line 1. Expected: `expected line`, Found: `different line`"
-      );
+  assert_eq!(
+    result.unwrap_err(),
+    Error::HunkMismatch {
+      file: "file.txt".to_string(),
+      hunk_index: 0,
+      line: 1,
+      expected: "expected line".to_string(),
+      found: Some("different line".to_string()),
     }
-    _ => panic!("Expected Apply error"),
-  }
+  );
 }
 
 #[test]
@@ -231,8 +244,8 @@ index 0000000..abcdef0
 
   let mut fs = MockFileSystem::new(HashMap::new());
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(fs.files.contains_key(&PathBuf::from("new_file.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(fs.contains("new_file.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("new_file.txt")).unwrap(),
     "line 1\nline 2\n"
@@ -257,8 +270,8 @@ index abcdef0..0000000
   );
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(!fs.files.contains_key(&PathBuf::from("file_to_delete.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(!fs.contains("file_to_delete.txt"));
 }
 
 #[test]
@@ -286,13 +299,71 @@ index abcdef0..abcdef0
   );
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
   assert_eq!(
     fs.read_to_string(&PathBuf::from("file.txt")).unwrap(),
     "new line 1\nnew line 2\nline 3\nnew line 4\nnew line 5\n"
   );
 }
 
+#[test]
+fn patch_shares_untouched_contents_with_clones() {
+  let diff = r#"--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-old
++new
+"#;
+
+  let mut fs = MockFileSystem::new(HashMap::from([
+    (PathBuf::from("a.txt"), "old\n".to_string()),
+    (PathBuf::from("b.txt"), "untouched\n".to_string()),
+  ]));
+  let snapshot = fs.clone();
+
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert_eq!(fs.file("a.txt").unwrap(), "new\n");
+  assert_eq!(snapshot.file("a.txt").unwrap(), "old\n");
+  assert!(ptr::eq(
+    fs.file("b.txt").unwrap(),
+    snapshot.file("b.txt").unwrap(),
+  ));
+}
+
+#[test]
+fn patch_metadata_only_keeps_contents_untouched() {
+  let diff = r#"diff --git a/old.bin b/new.bin
+similarity index 100%
+rename from old.bin
+rename to new.bin
+diff --git a/copied.bin b/copy.bin
+similarity index 100%
+copy from copied.bin
+copy to copy.bin
+diff --git a/script.sh b/script.sh
+old mode 100644
+new mode 100755
+"#;
+
+  let mut fs = MockFileSystem::new(HashMap::from([
+    (PathBuf::from("old.bin"), "renamed\n".to_string()),
+    (PathBuf::from("copied.bin"), "copied\n".to_string()),
+    (PathBuf::from("script.sh"), "#!/bin/sh\n".to_string()),
+  ]));
+  let snapshot = fs.clone();
+
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(!fs.contains("old.bin"));
+  for (from, to) in [
+    ("old.bin", "new.bin"),
+    ("copied.bin", "copied.bin"),
+    ("copied.bin", "copy.bin"),
+    ("script.sh", "script.sh"),
+  ] {
+    assert!(ptr::eq(snapshot.file(from).unwrap(), fs.file(to).unwrap(),));
+  }
+}
+
 #[test]
 fn patch_copy_file() {
   let diff = r#"diff --git a/old_file.txt b/new_file.txt
@@ -304,9 +375,9 @@ copy to new_file.txt
   files.insert(PathBuf::from("old_file.txt"), "content".to_string());
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(fs.files.contains_key(&PathBuf::from("old_file.txt")));
-  assert!(fs.files.contains_key(&PathBuf::from("new_file.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(fs.contains("old_file.txt"));
+  assert!(fs.contains("new_file.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("new_file.txt")).unwrap(),
     "content"
@@ -326,8 +397,8 @@ new mode 100755
   files.insert(PathBuf::from("file.txt"), "hello\n".to_string());
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(fs.files.contains_key(&PathBuf::from("file.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(fs.contains("file.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("file.txt")).unwrap(),
     "hello\n"
@@ -350,7 +421,7 @@ new mode 100755
   let mut files = HashMap::new();
   files.insert(PathBuf::from("file.txt"), "hello\n".to_string());
   let mut fs = MockFileSystem::new(files);
-  applier::patch(&mut fs, diff, false).unwrap();
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
   assert_eq!(
     fs.read_to_string(&PathBuf::from("file.txt")).unwrap(),
     "hello\n"
@@ -360,20 +431,21 @@ new mode 100755
 #[test]
 fn apply_empty_lines() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 5,
       new_line: 1,
       new_span: 5,
+      heading: None,
       lines: vec![
-        Line::Context(" line 1"),
-        Line::Context(" "),
-        Line::Context(" line 3"),
-        Line::Deletion("line 4"),
-        Line::Addition("new line 4"),
-        Line::Context(" line 5"),
+        Line::Context(" line 1".into()),
+        Line::Context(" ".into()),
+        Line::Context(" line 3".into()),
+        Line::Deletion("line 4".into()),
+        Line::Addition("new line 4".into()),
+        Line::Context(" line 5".into()),
       ],
     }],
     ..Default::default()
@@ -398,17 +470,17 @@ fn patch_whitespace_context_mismatch() {
   let mut files = HashMap::new();
   files.insert(
     PathBuf::from("file.txt"),
-    "  context line\ndeletion line\n".to_string(),
+    " context line\ndeletion line\n".to_string(),
   );
   let mut fs = MockFileSystem::new(files);
-  let result = applier::patch(&mut fs, diff, false);
+  let result = applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ());
   assert!(result.is_err());
   match result.unwrap_err() {
-    Error::Apply(msg) => assert_eq!(
-      msg,
-      "Patch mismatch at line 1. Expected: `   context line`, Found: `  context line`"
+    e @ Error::HunkMismatch { .. } => assert_eq!(
+      e.to_string(),
+      "Failed to apply patch: file.txt: hunk #1: Patch mismatch at line 1. Expected: `  context line`, Found: ` context line`"
     ),
-    e => panic!("Expected Apply error, got {:?}", e),
+    e => panic!("Expected HunkMismatch error, got {:?}", e),
   }
 }
 
@@ -425,17 +497,17 @@ fn patch_whitespace_deletion_mismatch() {
   let mut files = HashMap::new();
   files.insert(
     PathBuf::from("file.txt"),
-    " context line\n   deletion line\n".to_string(),
+    "context line\n   deletion line\n".to_string(),
   );
   let mut fs = MockFileSystem::new(files);
-  let result = applier::patch(&mut fs, diff, false);
+  let result = applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ());
   assert!(result.is_err());
   match result.unwrap_err() {
-    Error::Apply(msg) => assert_eq!(
-      msg,
-      "Patch mismatch at line 2. Expected: `  deletion line`, Found: `   deletion line`"
+    e @ Error::HunkMismatch { .. } => assert_eq!(
+      e.to_string(),
+      "Failed to apply patch: file.txt: hunk #1: Patch mismatch at line 2. Expected: `  deletion line`, Found: `   deletion line`"
     ),
-    e => panic!("Expected Apply error, got {:?}", e),
+    e => panic!("Expected HunkMismatch error, got {:?}", e),
   }
 }
 
@@ -452,13 +524,13 @@ fn patch_whitespace_match() {
   let mut files = HashMap::new();
   files.insert(
     PathBuf::from("file.txt"),
-    "  context line\n  deletion line\n".to_string(),
+    " context line\n  deletion line\n".to_string(),
   );
   let mut fs = MockFileSystem::new(files);
-  applier::patch(&mut fs, diff, false).unwrap();
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
   assert_eq!(
     fs.read_to_string(&PathBuf::from("file.txt")).unwrap(),
-    "  context line\n  addition line\n"
+    " context line\n  addition line\n"
   );
 }
 
@@ -472,11 +544,14 @@ Binary files /dev/null and b/image.png differ
 
   let mut fs = MockFileSystem::new(HashMap::new());
 
-  let result = applier::patch(&mut fs, diff, false);
+  let result = applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ());
   assert!(result.is_err());
   match result.unwrap_err() {
     Error::Unsupported(msg) => {
-      assert_eq!(msg, "Binary files are not supported");
+      assert_eq!(
+        msg,
+        "Binary files without patch data are not supported: `image.png`"
+      );
     }
     err => panic!("Expected Unsupported error, got {:?}", err),
   }
@@ -494,14 +569,15 @@ index 1234567..abcdefg
 +new line
   context 2
 "#;
-  let initial_content = "  context 1\nnew line\n  context 2\n";
-  let expected_content = "  context 1\nold line\n  context 2\n";
+  let initial_content = " context 1\nnew line\n context 2\n";
+  let expected_content = " context 1\nold line\n context 2\n";
 
   let mut files = HashMap::new();
   files.insert(PathBuf::from("file.txt"), initial_content.to_string());
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, true).unwrap();
+  applier::patch(&mut fs, diff, &ApplyOptions::new().reverse(true), &mut ())
+    .unwrap();
 
   assert_eq!(
     fs.read_to_string(&PathBuf::from("file.txt")).unwrap(),
@@ -522,8 +598,8 @@ index 0000000..abcdef0
 
   let mut fs = MockFileSystem::new(HashMap::new());
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(fs.files.contains_key(&PathBuf::from("new/dir/file.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(fs.contains("new/dir/file.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("new/dir/file.txt"))
       .unwrap(),
@@ -552,33 +628,155 @@ fn patch_with_offset_line_numbers() {
   files.insert(PathBuf::from("file.txt"), source.to_string());
   let mut fs = MockFileSystem::new(files);
 
-  let result = applier::patch(&mut fs, diff, false);
-  assert!(result.is_err());
-  match result.unwrap_err() {
-    Error::Apply(msg) => {
-      assert_eq!(
-        msg,
-        "Patch mismatch at line 10. Expected: ` some context`, Found: `line 10`"
-      );
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  assert_eq!(applier::validate(&fs, &patch), vec![]);
+  let (_, results) = applier::apply_detailed(&patch, source).unwrap();
+  assert_eq!(results[0].offset, -7);
+  assert_eq!(results[0].position, 3);
+
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert_eq!(
+    fs.file("file.txt").unwrap(),
+    source.replace("the line to remove", "the new line to add")
+  );
+}
+
+#[test]
+fn apply_carries_offset_to_later_hunks() {
+  let hunk = |old_line: u32, from: &'static str, to: &'static str| Hunk {
+    old_line,
+    old_span: 2,
+    new_line: old_line,
+    new_span: 2,
+    heading: None,
+    lines: vec![
+      Line::Context("anchor".into()),
+      Line::Deletion(from.into()),
+      Line::Addition(to.into()),
+    ],
+  };
+  let patch = Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![hunk(1, "a", "A"), hunk(4, "b", "B")],
+    ..Default::default()
+  };
+  let source = "x\nx\nanchor\na\nx\nanchor\nb\nanchor\na\n";
+
+  let (output, results) = applier::apply_detailed(&patch, source).unwrap();
+  assert_eq!(output, "x\nx\nanchor\nA\nx\nanchor\nB\nanchor\na\n");
+  assert_eq!(
+    results.iter().map(|r| r.offset).collect::<Vec<_>>(),
+    vec![2, 2]
+  );
+
+  let missing = Patch {
+    hunks: vec![hunk(1, "z", "Z")],
+    ..patch
+  };
+  assert!(matches!(
+    applier::apply(&missing, source),
+    Err(Error::HunkMismatch { line: 1, .. })
+  ));
+}
+
+#[test]
+fn patch_three_way_merges_drifted_file() {
+  let diff = r#"diff --git a/file.txt b/file.txt
+index 1a2b3c4..5d6e7f8 100644
+--- a/file.txt
++++ b/file.txt
+@@ -2,7 +2,7 @@
+ 2
+ 3
+ 4
+-5
++five
+ 6
+ 7
+ 8
+"#;
+  let base = "1\n2\n3\n4\n5\n6\n7\n8\n";
+  let pre_images = HashMap::from([("1a2b3c4d".to_string(), base.to_string())]);
+  let path = PathBuf::from("file.txt");
+  let files = |source: &str| {
+    MockFileSystem::new(HashMap::from([(path.clone(), source.to_string())]))
+  };
+
+  let mut fs = files("1\ntwo\n3\n4\n5\n6\n7\n8\n");
+  assert!(
+    applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).is_err()
+  );
+  let mut events = Vec::new();
+  applier::patch_three_way(
+    &mut fs,
+    diff,
+    &ApplyOptions::default(),
+    &pre_images,
+    &mut events,
+  )
+  .unwrap();
+  assert_eq!(fs.file(&path).unwrap(), "1\ntwo\n3\n4\nfive\n6\n7\n8\n");
+  assert_eq!(
+    events[0],
+    ApplyEvent::Merged {
+      path: path.clone(),
+      conflicts: 0
     }
-    e => panic!("Expected Apply error, got {:?}", e),
-  }
+  );
+
+  let mut fs = files("1\n2\n3\n4\nFIVE\n6\n7\n8\n");
+  applier::patch_three_way(
+    &mut fs,
+    diff,
+    &ApplyOptions::default(),
+    &pre_images,
+    &mut (),
+  )
+  .unwrap();
+  assert_eq!(
+    fs.file(&path).unwrap(),
+    "1\n2\n3\n4\n<<<<<<< ours\nFIVE\n=======\nfive\n>>>>>>> theirs\n6\n7\n8\n"
+  );
+
+  let mut fs = files("1\ntwo\n3\n4\n5\n6\n7\n8\n");
+  let result = applier::patch_three_way(
+    &mut fs,
+    diff,
+    &ApplyOptions::default(),
+    &HashMap::new(),
+    &mut (),
+  );
+  assert!(matches!(result, Err(Error::HunkMismatch { .. })));
+}
+
+#[test]
+fn pre_images_match_unambiguous_extensions_of_the_hash() {
+  let pre_images = HashMap::from([
+    ("1a2b3c4d".to_string(), "one".to_string()),
+    ("1a2b3c5e".to_string(), "two".to_string()),
+  ]);
+  assert_eq!(pre_images.pre_image("1a2b3c4"), Some("one".to_string()));
+  assert_eq!(pre_images.pre_image("1a2b3c4d"), Some("one".to_string()));
+  assert_eq!(pre_images.pre_image("1a2b3c"), None);
+  assert_eq!(pre_images.pre_image("1a2b3c4d5e"), None);
 }
 
 #[test]
 fn apply_only_context_lines() {
   let patch = Patch {
-    old_file: "file.txt",
-    new_file: "file.txt",
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
     hunks: vec![Hunk {
       old_line: 1,
       old_span: 3,
       new_line: 1,
       new_span: 3,
+      heading: None,
       lines: vec![
-        Line::Context("  context 1"),
-        Line::Context("  context 2"),
-        Line::Context("  context 3"),
+        Line::Context("  context 1".into()),
+        Line::Context("  context 2".into()),
+        Line::Context("  context 3".into()),
       ],
     }],
     ..Default::default()
@@ -604,10 +802,560 @@ index 0000000..abcdef0 100644
   files.insert(PathBuf::from("empty.txt"), "".to_string());
   let mut fs = MockFileSystem::new(files);
 
-  applier::patch(&mut fs, diff, false).unwrap();
-  assert!(fs.files.contains_key(&PathBuf::from("empty.txt")));
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ()).unwrap();
+  assert!(fs.contains("empty.txt"));
   assert_eq!(
     fs.read_to_string(&PathBuf::from("empty.txt")).unwrap(),
     "line 1\nline 2\n"
   );
 }
+
+#[test]
+fn apply_reports_eof_inside_hunk() {
+  let patch = Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![Hunk {
+      old_line: 2,
+      old_span: 1,
+      new_line: 2,
+      new_span: 1,
+      heading: None,
+      lines: vec![Line::Deletion("second".into())],
+    }],
+    ..Default::default()
+  };
+
+  let error = applier::apply(&patch, "first").unwrap_err();
+  assert_eq!(
+    error,
+    Error::HunkMismatch {
+      file: "file.txt".to_string(),
+      hunk_index: 0,
+      line: 2,
+      expected: "second".to_string(),
+      found: None,
+    }
+  );
+  assert_eq!(
+    error.to_string(),
+    "Failed to apply patch: file.txt: hunk #1: Patch mismatch at line 2. Expected: `second`, Found: `<EOF>`"
+  );
+}
+
+#[test]
+fn apply_reports_eof_while_seeking() {
+  let patch = Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![Hunk {
+      old_line: 5,
+      old_span: 1,
+      new_line: 5,
+      new_span: 1,
+      heading: None,
+      lines: vec![Line::Deletion("fifth".into())],
+    }],
+    ..Default::default()
+  };
+
+  let error = applier::apply(&patch, "one\ntwo\n").unwrap_err();
+  assert_eq!(
+    error,
+    Error::UnexpectedEof {
+      file: "file.txt".to_string(),
+      hunk_index: 0,
+      line: 5,
+    }
+  );
+  assert_eq!(
+    error.to_string(),
+    "Failed to apply patch: file.txt: hunk #1: Unexpected EOF while seeking to line 5"
+  );
+}
+
+#[test]
+fn validate_reports_each_failing_hunk() {
+  let patch = Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![
+      Hunk {
+        old_line: 1,
+        old_span: 1,
+        new_line: 1,
+        new_span: 1,
+        heading: None,
+        lines: vec![Line::Deletion("one".into()), Line::Addition("uno".into())],
+      },
+      Hunk {
+        old_line: 2,
+        old_span: 1,
+        new_line: 2,
+        new_span: 1,
+        heading: None,
+        lines: vec![
+          Line::Deletion("deux".into()),
+          Line::Addition("dos".into()),
+        ],
+      },
+      Hunk {
+        old_line: 9,
+        old_span: 1,
+        new_line: 9,
+        new_span: 1,
+        heading: None,
+        lines: vec![Line::Deletion("nine".into())],
+      },
+    ],
+    ..Default::default()
+  };
+  let mut files = HashMap::new();
+  files.insert(PathBuf::from("file.txt"), "one\ntwo\n".to_string());
+  let fs = MockFileSystem::new(files);
+
+  assert_eq!(
+    applier::validate(&fs, &patch),
+    vec![
+      applier::Issue::ContextMismatch {
+        path: PathBuf::from("file.txt"),
+        hunk_index: 1,
+        line: 2,
+        expected: "deux".to_string(),
+        found: Some("two".to_string()),
+      },
+      applier::Issue::HunkUnreachable {
+        path: PathBuf::from("file.txt"),
+        hunk_index: 2,
+        line: 9,
+      },
+    ]
+  );
+}
+
+#[test]
+fn validate_checks_files_and_paths() {
+  let mut files = HashMap::new();
+  files.insert(PathBuf::from("new.txt"), "taken\n".to_string());
+  let fs = MockFileSystem::new(files);
+
+  let rename = Patch {
+    old_file: "old.txt".into(),
+    new_file: "new.txt".into(),
+    rename_from: Some("old.txt".into()),
+    rename_to: Some("new.txt".into()),
+    ..Default::default()
+  };
+  assert_eq!(
+    applier::validate(&fs, &rename),
+    vec![
+      applier::Issue::TargetExists {
+        path: PathBuf::from("new.txt"),
+      },
+      applier::Issue::MissingFile {
+        path: PathBuf::from("old.txt"),
+      },
+    ]
+  );
+
+  let escape = Patch {
+    old_file: "/dev/null".into(),
+    new_file: "../outside.txt".into(),
+    ..Default::default()
+  };
+  assert_eq!(
+    applier::validate(&fs, &escape),
+    vec![applier::Issue::UnsafePath {
+      path: "../outside.txt".to_string(),
+    }]
+  );
+}
+
+#[test]
+fn apply_detailed_reports_hunk_results() {
+  let patch = Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![
+      Hunk {
+        old_line: 1,
+        old_span: 1,
+        new_line: 1,
+        new_span: 2,
+        heading: None,
+        lines: vec![Line::Addition("zero".into()), Line::Context("one".into())],
+      },
+      Hunk {
+        old_line: 3,
+        old_span: 2,
+        new_line: 4,
+        new_span: 1,
+        heading: None,
+        lines: vec![
+          Line::Deletion("three".into()),
+          Line::Deletion("four".into()),
+          Line::Addition("3-4".into()),
+        ],
+      },
+    ],
+    ..Default::default()
+  };
+
+  let (content, results) =
+    applier::apply_detailed(&patch, "one\ntwo\nthree\nfour\n").unwrap();
+  assert_eq!(content, "zero\none\ntwo\n3-4\n");
+  assert_eq!(
+    results,
+    vec![
+      applier::HunkResult {
+        position: 1,
+        offset: 0,
+        fuzz: 0,
+        added: 1,
+        removed: 0,
+      },
+      applier::HunkResult {
+        position: 4,
+        offset: 0,
+        fuzz: 0,
+        added: 1,
+        removed: 2,
+      },
+    ]
+  );
+}
+
+#[test]
+fn apply_detailed_seeks_many_sparse_hunks() {
+  let source: String = (1..=1000).map(|line| format!("{}\n", line)).collect();
+  let hunks = (0..100)
+    .map(|index| {
+      let line = index * 10 + 5;
+      Hunk {
+        old_line: line,
+        old_span: 1,
+        new_line: line,
+        new_span: 1,
+        heading: None,
+        lines: vec![
+          Line::Deletion(line.to_string().into()),
+          Line::Addition(format!("{}!", line).into()),
+        ],
+      }
+    })
+    .collect();
+  let patch = Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks,
+    ..Default::default()
+  };
+
+  let (content, results) = applier::apply_detailed(&patch, &source).unwrap();
+  let expected: String = (1..=1000)
+    .map(|line| match line % 10 {
+      5 => format!("{}!\n", line),
+      _ => format!("{}\n", line),
+    })
+    .collect();
+  assert_eq!(content, expected);
+  assert_eq!(results.len(), 100);
+  assert!(
+    results
+      .iter()
+      .enumerate()
+      .all(|(index, result)| result.position == index * 10 + 5)
+  );
+}
+
+#[test]
+fn apply_to_writer_matches_apply() {
+  let hunk = |lines: Vec<Line<'static>>| Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![Hunk {
+      old_line: 2,
+      old_span: 1,
+      new_line: 2,
+      new_span: 1,
+      heading: None,
+      lines,
+    }],
+    ..Default::default()
+  };
+  let cases = [
+    (
+      hunk(vec![
+        Line::Deletion("two".into()),
+        Line::Addition("2".into()),
+      ]),
+      "one\ntwo\nthree\n",
+    ),
+    (
+      hunk(vec![
+        Line::Deletion("two".into()),
+        Line::Addition("2".into()),
+        Line::NoNewline,
+      ]),
+      "one\ntwo\n",
+    ),
+    (
+      hunk(vec![
+        Line::Deletion("two".into()),
+        Line::Addition("2".into()),
+      ]),
+      "one\ntwo",
+    ),
+    (Patch::default(), "untouched\n"),
+  ];
+
+  for (patch, source) in &cases {
+    let mut written = Vec::new();
+    let results =
+      applier::apply_to_writer(patch, source.as_bytes(), &mut written).unwrap();
+    let (expected, expected_results) =
+      applier::apply_detailed(patch, source).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+    assert_eq!(results, expected_results);
+  }
+}
+
+#[test]
+fn apply_to_writer_streams_from_reader() {
+  let source: String = (1..=1000).map(|n| format!("line {}\n", n)).collect();
+  let diff = "--- a/big.txt
++++ b/big.txt
+@@ -2,3 +2,3 @@
+ line 2
+-line 3
++three
+ line 4
+@@ -998,3 +998,4 @@
+ line 998
+ line 999
+ line 1000
++line 1001
+";
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  let reader = BufReader::with_capacity(16, source.as_bytes());
+  let mut written = Vec::new();
+  let results = applier::apply_to_writer(&patch, reader, &mut written).unwrap();
+
+  assert_eq!(
+    String::from_utf8(written).unwrap(),
+    applier::apply(&patch, &source).unwrap()
+  );
+  assert_eq!(results[1].position, 998);
+
+  let mut written = Vec::new();
+  let error = applier::apply_to_writer(
+    &patch,
+    "line 1\nline 2\n".as_bytes(),
+    &mut written,
+  )
+  .unwrap_err();
+  assert!(matches!(error, Error::HunkMismatch { line: 3, .. }));
+}
+
+#[test]
+fn apply_to_writer_keeps_a_lone_empty_line() {
+  let cases = [
+    ("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n+\n", "a\n"),
+    ("--- /dev/null\n+++ b/f\n@@ -0,0 +1 @@\n+\n", ""),
+    ("--- a/f\n+++ b/f\n@@ -1 +0,0 @@\n-a\n", "a\n"),
+  ];
+
+  for (diff, source) in cases {
+    let patch = Parser::new(diff).next().unwrap().unwrap();
+    let expected = applier::apply(&patch, source).unwrap();
+    let mut written = Vec::new();
+    applier::apply_to_writer(&patch, source.as_bytes(), &mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+    let mut written = Vec::new();
+    applier::apply_bytes_to_writer(&patch, source.as_bytes(), &mut written)
+      .unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+  }
+}
+
+#[test]
+fn no_newline_marker_after_deletion_keeps_new_newline() {
+  let marker = "\\ No newline at end of file";
+  let cases = [
+    (
+      format!("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n{marker}\n+a\n"),
+      "a",
+      "a\n",
+    ),
+    (
+      format!("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n x\n-a\n{marker}\n+b\n"),
+      "x\na",
+      "x\nb\n",
+    ),
+    (
+      format!("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n{marker}\n+b\n{marker}\n"),
+      "a",
+      "b",
+    ),
+  ];
+
+  for (diff, source, expected) in cases {
+    let patch = Parser::new(&diff).next().unwrap().unwrap();
+    assert_eq!(applier::apply(&patch, source).unwrap(), expected);
+    let mut written = Vec::new();
+    applier::apply_to_writer(&patch, source.as_bytes(), &mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+  }
+}
+
+#[test]
+fn applier_reuses_buffers_across_patches() {
+  let patch = |from: &'static str, to: &'static str| Patch {
+    old_file: "file.txt".into(),
+    new_file: "file.txt".into(),
+    hunks: vec![Hunk {
+      old_line: 2,
+      old_span: 1,
+      new_line: 2,
+      new_span: 1,
+      heading: None,
+      lines: vec![Line::Deletion(from.into()), Line::Addition(to.into())],
+    }],
+    ..Default::default()
+  };
+
+  let mut applier = applier::Applier::new();
+  let mut output = String::from("stale");
+  let results = applier
+    .apply_into(&patch("two", "2"), "one\ntwo\n", &mut output)
+    .unwrap();
+  assert_eq!(results.len(), 1);
+  assert_eq!(output, "one\n2\n");
+
+  assert!(
+    applier
+      .apply_into(&patch("missing", "2"), "one\ntwo\n", &mut output)
+      .is_err()
+  );
+
+  let results = applier
+    .apply_into(&patch("b", "B"), "a\nb\nc", &mut output)
+    .unwrap()
+    .to_vec();
+  assert_eq!(output, "a\nB\nc\n");
+  assert_eq!(
+    (output, results),
+    applier::apply_detailed(&patch("b", "B"), "a\nb\nc").unwrap()
+  );
+}
+
+#[test]
+fn check_validates_selected_patches() {
+  let diff = r#"diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-old
++new
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-old
++new
+"#;
+  let mut files = HashMap::new();
+  files.insert(PathBuf::from("a.txt"), "new\n".to_string());
+  let fs = MockFileSystem::new(files);
+
+  let issues = applier::check(&fs, diff, &ApplyOptions::default()).unwrap();
+  assert_eq!(issues.len(), 2);
+  assert_eq!(issues[1].to_string(), "b.txt: does not exist");
+
+  let options = ApplyOptions::new().reverse(true).include("a.txt");
+  assert!(applier::check(&fs, diff, &options).unwrap().is_empty());
+}
+
+#[test]
+fn check_stages_earlier_patches_in_a_series() {
+  let mbox = r#"From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 1/2] Insert a line
+
+---
+diff --git a/f.txt b/f.txt
+--- a/f.txt
++++ b/f.txt
+@@ -1,3 +1,4 @@
+ one
++inserted
+ two
+ three
+-- 
+2.39.5
+
+From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 2/2] Edit the new line
+
+---
+diff --git a/f.txt b/f.txt
+--- a/f.txt
++++ b/f.txt
+@@ -2,3 +2,3 @@
+-inserted
++edited
+ two
+ three
+-- 
+2.39.5
+"#;
+  let fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("f.txt"),
+    "one\ntwo\nthree\n".to_string(),
+  )]));
+
+  let options = ApplyOptions::default();
+  assert_eq!(applier::check(&fs, mbox, &options).unwrap(), vec![]);
+  assert_eq!(fs.file("f.txt"), Some("one\ntwo\nthree\n"));
+
+  let second = &mbox[mbox.find("\nFrom 2222").unwrap() + 1..];
+  let issues = applier::check(&fs, second, &options).unwrap();
+  assert!(matches!(
+    &issues[..],
+    [applier::Issue::ContextMismatch { .. }]
+  ));
+}
+
+#[test]
+fn check_many_reports_each_patch_in_order() {
+  let patch = |from: &str, to: &str| {
+    format!(
+      "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-{}\n+{}\n",
+      from, to
+    )
+  };
+  let patches = vec![
+    patch("old", "new"),
+    patch("missing", "new"),
+    "--- a/file.txt\n+++ b/file.txt\n@@ -1,2 +1 @@\n-old\n+new\n".to_string(),
+    patch("old", "newer"),
+  ];
+  let fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "old\n".to_string(),
+  )]));
+
+  for jobs in [1, 3, 16] {
+    let results =
+      applier::check_many(&fs, &patches, &ApplyOptions::default(), jobs);
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0], Ok(Vec::new()));
+    assert!(matches!(
+      results[1].as_deref(),
+      Ok([applier::Issue::ContextMismatch { .. }])
+    ));
+    assert!(results[2].is_err());
+    assert_eq!(results[3], Ok(Vec::new()));
+  }
+}
@@ -0,0 +1,195 @@
+use hit::applier;
+use hit::applier::Issue;
+use hit::error::Error;
+use hit::fs::FileSystem;
+use hit::fs::MockFileSystem;
+use hit::fs::OsFileSystem;
+use hit::lexer::BinaryMethod;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use hit::parser::Patch;
+use hit::render;
+use hit::render::ColorScheme;
+use std::collections::HashMap;
+use std::fs;
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+const CREATED: &str = r#"diff --git a/image.bin b/image.bin
+new file mode 100644
+index 0000000000000000000000000000000000000000..f99426828efa0755a692bdf91bb14ef6bea82411
+GIT binary patch
+literal 14
+VcmWIWb7x>=V$Mj-$;to!4*(fA1pNR2
+
+literal 0
+HcmV?d00001
+
+"#;
+
+const MODIFIED: &str = r#"diff --git a/blob.bin b/blob.bin
+index c8b49c8cd518e58491924bfc364ff26e01a85009..6b29e331c828018edbe66b4cede48b860e470c5d 100644
+GIT binary patch
+delta 22
+dcmZqRXyDlJg^`B=2!8zh_507?fBzRS0RU&*3qSw>
+
+delta 13
+ScmZqRXy91H#I%T+5r_a7Spx$A
+
+"#;
+
+const IMAGE: &[u8] = b"PNG\x00\x01\x02\x03hello\xff\xfe";
+
+fn blob() -> Vec<u8> {
+  (0..4).flat_map(|_| 0..=255).collect()
+}
+
+fn parse(diff: &str) -> Patch<'_> {
+  Parser::new(diff).next().unwrap().unwrap()
+}
+
+#[test]
+fn parse_git_binary_patch_keeps_both_directions() {
+  let patch = parse(CREATED);
+  assert!(patch.is_binary);
+  let binary = patch.binary.as_ref().unwrap();
+  assert_eq!(binary.forward.method, BinaryMethod::Literal);
+  assert_eq!(binary.forward.decode().unwrap(), IMAGE);
+  assert_eq!(binary.reverse.as_ref().unwrap().decode().unwrap(), b"");
+
+  let text = render::pretty(&patch, ColorScheme::plain());
+  assert_eq!(parse(&text), patch);
+}
+
+#[test]
+fn binary_literal_creates_and_reverse_deletes_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("image.bin");
+  let options = ApplyOptions::new().root(dir.path());
+
+  applier::patch(&mut OsFileSystem, CREATED, &options, &mut ()).unwrap();
+  assert_eq!(fs::read(&path).unwrap(), IMAGE);
+
+  let options = options.reverse(true);
+  applier::patch(&mut OsFileSystem, CREATED, &options, &mut ()).unwrap();
+  assert!(!path.exists());
+}
+
+#[test]
+fn binary_delta_rewrites_and_restores_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("blob.bin");
+  fs::write(&path, blob()).unwrap();
+  let options = ApplyOptions::new().root(dir.path());
+
+  applier::patch(&mut OsFileSystem, MODIFIED, &options, &mut ()).unwrap();
+  let mut expected = blob();
+  expected[500..504].fill(0);
+  assert_eq!(fs::read(&path).unwrap(), expected);
+
+  let options = options.reverse(true);
+  applier::patch(&mut OsFileSystem, MODIFIED, &options, &mut ()).unwrap();
+  assert_eq!(fs::read(&path).unwrap(), blob());
+}
+
+#[test]
+fn binary_delta_rejects_different_source() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("blob.bin");
+  fs::write(&path, IMAGE).unwrap();
+  let options = ApplyOptions::new().root(dir.path());
+
+  let issues =
+    applier::validate_with(&OsFileSystem, &parse(MODIFIED), &options);
+  assert!(
+    matches!(&issues[..], [Issue::BinaryMismatch { path: found, .. }] if *found == path)
+  );
+
+  let error =
+    applier::patch(&mut OsFileSystem, MODIFIED, &options, &mut ()).unwrap_err();
+  assert!(matches!(error, Error::Invalid(_)));
+  assert_eq!(fs::read(&path).unwrap(), IMAGE);
+}
+
+#[test]
+fn binary_patches_apply_in_memory() {
+  let mut fs = MockFileSystem::default();
+  applier::patch(&mut fs, CREATED, &ApplyOptions::new(), &mut ()).unwrap();
+  assert_eq!(fs.file_bytes("image.bin"), Some(IMAGE));
+  assert_eq!(fs.file("image.bin"), None);
+
+  let mut fs = MockFileSystem::default();
+  fs.insert_bytes("blob.bin", &blob());
+  applier::patch(&mut fs, MODIFIED, &ApplyOptions::new(), &mut ()).unwrap();
+  let mut expected = blob();
+  expected[500..504].fill(0);
+  assert_eq!(fs.file_bytes("blob.bin"), Some(&expected[..]));
+}
+
+#[derive(Default)]
+struct ByteFileSystem {
+  files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl FileSystem for ByteFileSystem {
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    String::from_utf8(self.read_bytes(path)?)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    self
+      .files
+      .get(path)
+      .cloned()
+      .ok_or(io::ErrorKind::NotFound.into())
+  }
+
+  fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+    self.write_bytes(path, contents.as_bytes())
+  }
+
+  fn write_bytes(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+    self.files.insert(path.to_path_buf(), contents.to_vec());
+    Ok(())
+  }
+
+  fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+    self
+      .files
+      .remove(path)
+      .map(|_| ())
+      .ok_or(io::ErrorKind::NotFound.into())
+  }
+
+  fn create_dir_all(&mut self, _: &Path) -> io::Result<()> {
+    Ok(())
+  }
+
+  #[cfg(unix)]
+  fn set_permissions(&mut self, _: &Path, _: Permissions) -> io::Result<()> {
+    Ok(())
+  }
+
+  #[cfg(unix)]
+  fn get_permissions(&self, _: &Path) -> io::Result<Permissions> {
+    Err(io::ErrorKind::Unsupported.into())
+  }
+}
+
+#[test]
+fn default_rename_and_copy_keep_binary_contents() {
+  let bytes = vec![0xff, 0x00, 0xfe];
+  let mut fs = ByteFileSystem::default();
+  fs.write_bytes(Path::new("a.bin"), &bytes).unwrap();
+
+  fs.copy(Path::new("a.bin"), Path::new("b.bin")).unwrap();
+  fs.rename(Path::new("a.bin"), Path::new("c.bin")).unwrap();
+
+  assert!(!fs.files.contains_key(Path::new("a.bin")));
+  assert_eq!(fs.files[Path::new("b.bin")], bytes);
+  assert_eq!(fs.files[Path::new("c.bin")], bytes);
+}
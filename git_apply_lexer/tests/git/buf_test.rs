@@ -0,0 +1,69 @@
+use hit::applier;
+use hit::buf::PatchBuf;
+use hit::builder::PatchBuilder;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[test]
+fn patch_buf_round_trips_parsed_patch() {
+  let diff = r#"diff --git a/old.txt b/new.txt
+similarity index 90%
+rename from old.txt
+rename to new.txt
+--- a/old.txt
++++ b/new.txt
+@@ -1,2 +1,2 @@
+ keep
+-before
++after
+\ No newline at end of file
+"#;
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+
+  let buf = patch.to_buf();
+  assert_eq!(buf.as_patch(), patch);
+  assert_eq!(buf.clone().as_patch(), patch);
+}
+
+#[test]
+fn patch_buf_outlives_owned_source() {
+  let buf: PatchBuf = {
+    let path = String::from("file.txt");
+    let patch = PatchBuilder::modify(path.clone())
+      .hunk_at(1)
+      .delete(String::from("old"))
+      .add(String::from("new"))
+      .build()
+      .unwrap();
+    PatchBuf::from(&patch)
+  };
+
+  let patch = buf.as_patch();
+  assert_eq!(patch.old_file, "file.txt");
+  assert_eq!(patch.additions().collect::<Vec<_>>(), vec!["new"]);
+  assert_eq!(patch.deletions().collect::<Vec<_>>(), vec!["old"]);
+}
+
+#[test]
+fn owned_patches_outlive_source_and_apply() {
+  let (owned, buf) = {
+    let diff =
+      String::from("--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n");
+    let patch = Parser::new(&diff).next().unwrap().unwrap();
+    (patch.clone().into_owned(), PatchBuf::from(patch))
+  };
+  assert_eq!(owned, buf.as_patch());
+
+  let handle = std::thread::spawn(move || buf);
+  let buf = handle.join().unwrap();
+
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "old\n".to_string(),
+  )]));
+  applier::apply_bufs(&mut fs, &[buf], &ApplyOptions::new(), &mut ()).unwrap();
+  assert_eq!(fs.file("file.txt").unwrap(), "new\n");
+}
@@ -0,0 +1,138 @@
+use hit::applier;
+use hit::builder::PatchBuilder;
+use hit::error::Error;
+use hit::parser::Hunk;
+use hit::parser::Line;
+
+#[test]
+fn build_modify_patch() {
+  let patch = PatchBuilder::modify("src/lib.rs")
+    .hunk_at(10)
+    .delete("old")
+    .add("new")
+    .build()
+    .unwrap();
+
+  assert_eq!(patch.old_file, "src/lib.rs");
+  assert_eq!(patch.new_file, "src/lib.rs");
+  assert_eq!(
+    patch.hunks,
+    vec![Hunk {
+      old_line: 10,
+      old_span: 1,
+      new_line: 10,
+      new_span: 1,
+      heading: None,
+      lines: vec![Line::Deletion("old".into()), Line::Addition("new".into())],
+    }]
+  );
+}
+
+#[test]
+fn build_tracks_new_line_offsets_across_hunks() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .context("b")
+    .add("b2")
+    .add("b3")
+    .hunk_at(5)
+    .delete("e")
+    .build()
+    .unwrap();
+
+  assert_eq!(patch.hunks[0].new_line, 2);
+  assert_eq!(patch.hunks[0].new_span, 3);
+  assert_eq!(patch.hunks[1].old_line, 5);
+  assert_eq!(patch.hunks[1].new_line, 6);
+  assert_eq!(patch.hunks[1].new_span, 0);
+
+  let source = "a\nb\nc\nd\ne\nf\n";
+  assert_eq!(
+    applier::apply(&patch, source).unwrap(),
+    "a\nb\nb2\nb3\nc\nd\nf\n"
+  );
+}
+
+#[test]
+fn build_pure_insertion_uses_preceding_line() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .add("inserted")
+    .build()
+    .unwrap();
+
+  assert_eq!(patch.hunks[0].old_line, 2);
+  assert_eq!(patch.hunks[0].old_span, 0);
+  assert_eq!(patch.hunks[0].new_line, 3);
+  assert_eq!(
+    applier::apply(&patch, "a\nb\nc\n").unwrap(),
+    "a\nb\ninserted\nc\n"
+  );
+}
+
+#[test]
+fn build_create_and_remove() {
+  let created = PatchBuilder::create("new.txt")
+    .add("hello")
+    .no_newline()
+    .build()
+    .unwrap();
+  assert_eq!(created.old_file, "/dev/null");
+  assert_eq!(created.new_mode, Some(0o100644));
+  assert_eq!(created.hunks[0].old_line, 0);
+  assert_eq!(created.hunks[0].new_line, 1);
+  assert_eq!(applier::apply(&created, "").unwrap(), "hello");
+
+  let removed = PatchBuilder::remove("old.txt")
+    .delete("bye")
+    .build()
+    .unwrap();
+  assert_eq!(removed.new_file, "/dev/null");
+  assert_eq!(removed.hunks[0].new_line, 0);
+  assert_eq!(removed.hunks[0].new_span, 0);
+}
+
+#[test]
+fn build_rejects_overlapping_hunks() {
+  let result = PatchBuilder::modify("file.txt")
+    .hunk_at(5)
+    .context("a")
+    .delete("b")
+    .hunk_at(6)
+    .delete("c")
+    .build();
+
+  assert_eq!(
+    result,
+    Err(Error::Invalid(
+      "Hunk 2 overlaps or precedes the previous hunk".into()
+    ))
+  );
+}
+
+#[test]
+fn build_rejects_invalid_hunks() {
+  let result = PatchBuilder::modify("file.txt").hunk_at(0).add("x").build();
+  assert!(matches!(result, Err(Error::Invalid(_))));
+
+  let result = PatchBuilder::modify("file.txt").hunk_at(1).build();
+  assert!(matches!(result, Err(Error::Invalid(_))));
+
+  let result = PatchBuilder::create("file.txt").context("x").build();
+  assert!(matches!(result, Err(Error::Invalid(_))));
+}
+
+#[test]
+fn builder_accepts_owned_strings() {
+  let patch = {
+    let path = format!("src/{}.rs", "generated");
+    let line = format!("pub const VERSION: u32 = {};", 2);
+    PatchBuilder::create(path).add(line).build().unwrap()
+  };
+
+  assert_eq!(patch.new_file, "src/generated.rs");
+  assert_eq!(
+    patch.hunks[0].lines,
+    vec![Line::Addition("pub const VERSION: u32 = 2;".into())]
+  );
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "cache")]
+
+use hit::applier;
+use hit::cache;
+use hit::error::Error;
+use hit::fs::FileSystem;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+const DIFF: &str = "diff --git a/old.txt b/new.txt
+similarity index 50%
+rename from old.txt
+rename to new.txt
+--- a/old.txt
++++ b/new.txt
+@@ -1,2 +1,2 @@
+ keep
+-old
++new
+\\ No newline at end of file
+";
+
+#[test]
+fn compile_round_trips_patches() {
+  let bytes = cache::compile(DIFF).unwrap();
+  let patches = cache::decode(&bytes).unwrap();
+  let parsed = Parser::new(DIFF)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+
+  assert_eq!(patches.len(), 1);
+  assert_eq!(patches[0].as_patch(), parsed[0]);
+  assert_eq!(cache::encode(&patches).unwrap(), bytes);
+}
+
+#[test]
+fn decode_rejects_foreign_bytes() {
+  let bytes = cache::compile(DIFF).unwrap();
+
+  assert!(matches!(cache::decode(b"nope"), Err(Error::Cache(_))));
+  let mut future = bytes.clone();
+  future[4] = 99;
+  assert!(matches!(cache::decode(&future), Err(Error::Cache(_))));
+  assert!(matches!(
+    cache::decode(&bytes[..bytes.len() - 3]),
+    Err(Error::Cache(_))
+  ));
+}
+
+#[test]
+fn apply_patches_from_cache() {
+  let patches = cache::decode(&cache::compile(DIFF).unwrap()).unwrap();
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("old.txt"),
+    "keep\nold\n".to_string(),
+  )]));
+
+  applier::apply_patches(
+    &mut fs,
+    patches.iter().map(|patch| patch.as_patch()),
+    &ApplyOptions::default(),
+    &mut (),
+  )
+  .unwrap();
+  assert_eq!(
+    fs.read_to_string(Path::new("new.txt")).unwrap(),
+    "keep\nnew"
+  );
+  assert!(fs.read_to_string(Path::new("old.txt")).is_err());
+}
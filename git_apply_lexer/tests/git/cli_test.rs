@@ -0,0 +1,352 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::process::Output;
+use std::process::Stdio;
+
+fn hit_stdin(dir: &Path, input: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_hit"))
+    .current_dir(dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(input.as_bytes())
+    .unwrap();
+  child.wait_with_output().unwrap()
+}
+
+#[test]
+fn stdin_applies_mbox_series() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("greeting.txt"), "hello\n").unwrap();
+  let mbox =
+    "From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 1/2] Capitalize the greeting
+
+---
+ greeting.txt | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1 @@
+-hello
++Hello
+-- 
+2.39.5
+
+From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 2/2] Add a farewell
+
+---
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1,2 @@
+ Hello
++Goodbye
+-- 
+2.39.5
+";
+
+  let output = hit_stdin(dir.path(), mbox);
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  assert_eq!(
+    String::from_utf8(output.stdout).unwrap(),
+    "Applied patch to: greeting.txt\n"
+  );
+  assert_eq!(
+    fs::read_to_string(dir.path().join("greeting.txt")).unwrap(),
+    "Hello\nGoodbye\n"
+  );
+}
+
+#[cfg(feature = "pretty-errors")]
+#[test]
+fn stdin_failure_points_at_the_hunk() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("greeting.txt"), "hello\n").unwrap();
+  let output = hit_stdin(
+    dir.path(),
+    "--- a/greeting.txt\n+++ b/greeting.txt\n@@ -1 +1 @@\n-nope\n+yes\n",
+  );
+
+  assert_eq!(output.status.code(), Some(1));
+  let stderr = String::from_utf8(output.stderr).unwrap();
+  assert!(stderr.contains("expected `nope`"), "{}", stderr);
+  assert!(stderr.contains("hunk #1"), "{}", stderr);
+}
+
+#[test]
+fn stdin_applies_git_log_output() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+  fs::write(dir.path().join("b.txt"), "two\n").unwrap();
+  let log = "commit 1111111111111111111111111111111111111111
+Author: Ada Lovelace <ada@example.com>
+Date:   Mon Jan 1 10:00:00 2024 +0000
+
+    Touch both files
+
+diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-one
++ONE
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-two
++TWO
+
+commit 2222222222222222222222222222222222222222
+Author: Ada Lovelace <ada@example.com>
+Date:   Tue Jan 2 10:00:00 2024 +0000
+
+    Extend a
+
+diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1,2 @@
+ ONE
++1
+";
+
+  let output = hit_stdin(dir.path(), log);
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  assert_eq!(
+    fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+    "ONE\n1\n"
+  );
+  assert_eq!(
+    fs::read_to_string(dir.path().join("b.txt")).unwrap(),
+    "TWO\n"
+  );
+}
+
+fn hit_stat(input: &str) -> Output {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("change.patch"), input).unwrap();
+  Command::new(env!("CARGO_BIN_EXE_hit"))
+    .args(["--stat", "change.patch"])
+    .current_dir(dir.path())
+    .output()
+    .unwrap()
+}
+
+#[test]
+fn stat_summarizes_mbox_series() {
+  let output = hit_stat(
+    "From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 1/2] Capitalize the greeting
+
+---
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1 @@
+-hello
++Hello
+-- 
+2.39.5
+
+From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 2/2] Add a farewell
+
+---
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1,2 @@
+ Hello
++Goodbye
+-- 
+2.39.5
+",
+  );
+
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  assert_eq!(
+    String::from_utf8(output.stdout).unwrap(),
+    " greeting.txt | 2 +-
+ greeting.txt | 1 +
+ 2 files changed, 2 insertions(+), 1 deletion(-)
+"
+  );
+}
+
+#[test]
+fn stat_summarizes_context_diff() {
+  let output = hit_stat(
+    "*** file.txt
+--- file.txt
+***************
+*** 1,2 ****
+! one
+  two
+--- 1,3 ----
+! ONE
+  two
++ three
+",
+  );
+
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  assert_eq!(
+    String::from_utf8(output.stdout).unwrap(),
+    " file.txt | 3 ++-
+ 1 file changed, 2 insertions(+), 1 deletion(-)
+"
+  );
+}
+
+#[test]
+fn repo_root_outside_repository_fails() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(
+    dir.path().join("change.patch"),
+    "--- a/file.txt\n+++ b/file.txt\n@@ -0,0 +1 @@\n+new\n",
+  )
+  .unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_hit"))
+    .arg("--repo-root")
+    .arg("change.patch")
+    .current_dir(dir.path())
+    .output()
+    .unwrap();
+
+  assert!(!output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap(),
+    "Error: --repo-root requires running inside a git repository\n"
+  );
+  assert!(!dir.path().join("file.txt").exists());
+}
+
+#[test]
+fn check_rev_outside_repository_fails() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(
+    dir.path().join("change.patch"),
+    "--- a/file.txt\n+++ b/file.txt\n@@ -0,0 +1 @@\n+new\n",
+  )
+  .unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_hit"))
+    .args(["check", "--rev", "HEAD", "change.patch"])
+    .current_dir(dir.path())
+    .output()
+    .unwrap();
+
+  assert_eq!(output.status.code(), Some(128));
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap(),
+    "Error: --rev requires running inside a git repository\n"
+  );
+}
+
+#[test]
+fn check_exits_128_on_fatal_errors() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("file.txt"), "old\n").unwrap();
+  fs::write(
+    dir.path().join("change.patch"),
+    "--- a/file.txt\n+++ b/file.txt\n@@ -1,2 +1 @@\n-old\n+new\n",
+  )
+  .unwrap();
+
+  for args in [&["--check", "change.patch"], &["check", "change.patch"]] {
+    let output = Command::new(env!("CARGO_BIN_EXE_hit"))
+      .args(args)
+      .current_dir(dir.path())
+      .output()
+      .unwrap();
+    assert_eq!(output.status.code(), Some(128), "{:?}", args);
+  }
+}
+
+#[test]
+fn three_way_outside_repository_fails() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(
+    dir.path().join("change.patch"),
+    "--- a/file.txt\n+++ b/file.txt\n@@ -0,0 +1 @@\n+new\n",
+  )
+  .unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_hit"))
+    .args(["--3way", "change.patch"])
+    .current_dir(dir.path())
+    .output()
+    .unwrap();
+
+  assert!(!output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap(),
+    "Error: --3way requires running inside a git repository\n"
+  );
+}
+
+#[test]
+fn warnings_go_to_stderr() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+  fs::write(
+    dir.path().join("change.patch"),
+    "--- a/file.txt
++++ b/file.txt
+@@ -1 +1 @@
+-one
++ONE
+@@ -2 +2 @@
+-missing
++MISSING
+",
+  )
+  .unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_hit"))
+    .args(["--reject", "change.patch"])
+    .current_dir(dir.path())
+    .output()
+    .unwrap();
+
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  let stderr = String::from_utf8(output.stderr).unwrap();
+  assert!(!stdout.contains("Rejected"));
+  assert!(stderr.contains("Rejected 1 hunk of: file.txt"));
+  assert_eq!(
+    fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+    "ONE\ntwo\n"
+  );
+}
@@ -0,0 +1,106 @@
+use hit::applier;
+use hit::builder::PatchBuilder;
+use hit::compose;
+
+const SOURCE: &str = "a\nb\nc\nd\ne\nf\ng\n";
+
+#[test]
+fn then_merges_overlapping_hunks() {
+  let first = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .delete("b")
+    .add("B")
+    .context("c")
+    .build()
+    .unwrap();
+  let second = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .delete("B")
+    .add("BB")
+    .context("c")
+    .delete("d")
+    .build()
+    .unwrap();
+
+  let composed = first.then(&second).unwrap();
+  let intermediate = applier::apply(&first, SOURCE).unwrap();
+  assert_eq!(
+    applier::apply(&composed, SOURCE).unwrap(),
+    applier::apply(&second, &intermediate).unwrap()
+  );
+  assert_eq!(composed.hunks.len(), 1);
+  assert_eq!(
+    (composed.hunks[0].old_line, composed.hunks[0].old_span),
+    (2, 3)
+  );
+}
+
+#[test]
+fn then_shifts_disjoint_hunks() {
+  let first = PatchBuilder::modify("file.txt")
+    .hunk_at(1)
+    .add("x")
+    .add("y")
+    .context("a")
+    .build()
+    .unwrap();
+  let second = PatchBuilder::modify("file.txt")
+    .hunk_at(7)
+    .context("e")
+    .delete("f")
+    .build()
+    .unwrap();
+
+  let composed = first.then(&second).unwrap();
+  let intermediate = applier::apply(&first, SOURCE).unwrap();
+  assert_eq!(
+    applier::apply(&composed, SOURCE).unwrap(),
+    applier::apply(&second, &intermediate).unwrap()
+  );
+  assert_eq!(composed.hunks.len(), 2);
+  assert_eq!(composed.hunks[1].old_line, 5);
+  assert_eq!(composed.hunks[1].new_line, 7);
+}
+
+#[test]
+fn then_drops_changes_that_cancel_out() {
+  let first = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .delete("c")
+    .add("C")
+    .build()
+    .unwrap();
+  let second = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .delete("C")
+    .add("c")
+    .build()
+    .unwrap();
+
+  assert!(first.then(&second).unwrap().hunks.is_empty());
+}
+
+#[test]
+fn compose_chains_renames_and_cancels_created_files() {
+  let first = vec![
+    PatchBuilder::rename("old.txt", "mid.txt").build().unwrap(),
+    PatchBuilder::create("tmp.txt")
+      .add("scratch")
+      .build()
+      .unwrap(),
+  ];
+  let second = vec![
+    PatchBuilder::rename("mid.txt", "new.txt").build().unwrap(),
+    PatchBuilder::remove("tmp.txt")
+      .delete("scratch")
+      .build()
+      .unwrap(),
+  ];
+
+  let composed = compose::compose(&first, &second).unwrap();
+  assert_eq!(composed.len(), 1);
+  assert_eq!(composed[0].old_file, "old.txt");
+  assert_eq!(composed[0].new_file, "new.txt");
+  assert_eq!(composed[0].rename_from.as_deref(), Some("old.txt"));
+  assert_eq!(composed[0].rename_to.as_deref(), Some("new.txt"));
+}
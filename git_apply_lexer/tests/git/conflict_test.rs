@@ -0,0 +1,78 @@
+use hit::builder::PatchBuilder;
+use hit::conflict;
+use hit::conflict::ConflictRegion;
+
+#[test]
+fn conflicts_reports_overlapping_hunks() {
+  let a = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .context("b")
+    .delete("c")
+    .context("d")
+    .hunk_at(20)
+    .delete("t")
+    .build()
+    .unwrap();
+  let b = PatchBuilder::modify("file.txt")
+    .hunk_at(4)
+    .context("d")
+    .add("new")
+    .context("e")
+    .build()
+    .unwrap();
+
+  assert_eq!(
+    conflict::conflicts(&a, &b),
+    vec![ConflictRegion {
+      file: "file.txt".to_string(),
+      lines: 2..6,
+      hunks: Some((0, 0)),
+    }]
+  );
+}
+
+#[test]
+fn conflicts_ignores_disjoint_edits_and_other_files() {
+  let a = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .delete("b")
+    .build()
+    .unwrap();
+  let b = PatchBuilder::modify("file.txt")
+    .hunk_at(5)
+    .delete("e")
+    .build()
+    .unwrap();
+  let other = PatchBuilder::modify("other.txt")
+    .hunk_at(2)
+    .delete("b")
+    .build()
+    .unwrap();
+
+  assert!(conflict::conflicts(&a, &b).is_empty());
+  assert!(conflict::conflicts(&a, &other).is_empty());
+}
+
+#[test]
+fn conflicts_reports_whole_file_changes() {
+  let modify = PatchBuilder::modify("file.txt")
+    .hunk_at(1)
+    .delete("a")
+    .build()
+    .unwrap();
+  let remove = PatchBuilder::remove("file.txt")
+    .delete("a")
+    .build()
+    .unwrap();
+  let rename = PatchBuilder::rename("file.txt", "moved.txt")
+    .build()
+    .unwrap();
+
+  let expected = vec![ConflictRegion {
+    file: "file.txt".to_string(),
+    lines: 0..0,
+    hunks: None,
+  }];
+  assert_eq!(conflict::conflicts(&modify, &remove), expected);
+  assert_eq!(conflict::conflicts(&modify, &rename), expected);
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "conformance")]
+
+use hit::conformance;
+use hit::conformance::Divergence;
+use std::fs;
+use std::path::Path;
+
+fn write_case(corpus: &Path, name: &str, before: &[(&str, &str)], patch: &str) {
+  let case = corpus.join(name);
+  for (path, contents) in before {
+    let path = case.join("before").join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+  fs::create_dir_all(&case).unwrap();
+  fs::write(case.join("patch.diff"), patch).unwrap();
+}
+
+#[test]
+fn run_corpus_compares_against_git_apply() {
+  let corpus = tempfile::tempdir().unwrap();
+  let work = tempfile::tempdir().unwrap();
+  write_case(
+    corpus.path(),
+    "modify",
+    &[("src/lib.rs", "one\ntwo\nthree\n")],
+    r#"diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+"#,
+  );
+  write_case(
+    corpus.path(),
+    "create",
+    &[],
+    r#"diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++hello
+"#,
+  );
+  write_case(
+    corpus.path(),
+    "mismatch",
+    &[("file.txt", "changed\n")],
+    r#"diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1 +1 @@
+-old
++new
+"#,
+  );
+
+  let reports = conformance::run_corpus(corpus.path(), work.path()).unwrap();
+  let names: Vec<_> = reports.iter().map(|r| r.name.as_str()).collect();
+  assert_eq!(names, ["create", "mismatch", "modify"]);
+  for report in &reports {
+    assert_eq!(report.divergences, vec![], "{}", report.name);
+  }
+  assert_eq!(
+    fs::read_to_string(work.path().join("modify/hit/src/lib.rs")).unwrap(),
+    "one\nTWO\nthree\n"
+  );
+}
+
+#[test]
+fn divergences_describe_the_difference() {
+  let divergence = Divergence::Outcome {
+    git: None,
+    hit: Some("bad hunk".to_string()),
+  };
+  assert_eq!(
+    divergence.to_string(),
+    "git apply succeeded but hit failed: bad hunk"
+  );
+}
@@ -0,0 +1,93 @@
+use hit::applier;
+use hit::context;
+use hit::error::Error;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Line;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIFF: &str = "*** file.txt\t2024-01-01 10:00:00.000000000 +0000
+--- file.txt\t2024-01-02 10:00:00.000000000 +0000
+***************
+*** 1,5 ****
+  one
+! two
+  three
+- four
+  five
+--- 1,5 ----
+  one
+! TWO
+  three
+  five
++ six
+***************
+*** 9 ****
+--- 10,11 ----
+  nine
++ ten
+";
+
+#[test]
+fn parse_context_diff_into_unified_hunks() {
+  assert!(context::is_context_diff(DIFF));
+  let patches = Parser::new(DIFF)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+
+  assert_eq!(patches.len(), 1);
+  let patch = &patches[0];
+  assert_eq!(patch.old_file, "file.txt");
+  assert_eq!(patch.new_file, "file.txt");
+  assert_eq!(
+    patch.old_timestamp.as_deref(),
+    Some("2024-01-01 10:00:00.000000000 +0000")
+  );
+  assert_eq!(patch.hunks.len(), 2);
+
+  let hunk = &patch.hunks[0];
+  assert_eq!((hunk.old_line, hunk.old_span), (1, 5));
+  assert_eq!((hunk.new_line, hunk.new_span), (1, 5));
+  assert_eq!(
+    hunk.lines,
+    vec![
+      Line::Context("one".into()),
+      Line::Deletion("two".into()),
+      Line::Addition("TWO".into()),
+      Line::Context("three".into()),
+      Line::Deletion("four".into()),
+      Line::Context("five".into()),
+      Line::Addition("six".into()),
+    ]
+  );
+
+  let hunk = &patch.hunks[1];
+  assert_eq!((hunk.old_line, hunk.old_span), (9, 1));
+  assert_eq!((hunk.new_line, hunk.new_span), (10, 2));
+  assert_eq!(
+    hunk.lines,
+    vec![Line::Context("nine".into()), Line::Addition("ten".into())]
+  );
+}
+
+#[test]
+fn apply_context_diff() {
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "one\ntwo\nthree\nfour\nfive\n0\n0\n0\nnine\n".to_string(),
+  )]));
+  applier::patch(&mut fs, DIFF, &ApplyOptions::new(), &mut ()).unwrap();
+  assert_eq!(
+    fs.file("file.txt").unwrap(),
+    "one\nTWO\nthree\nfive\nsix\n0\n0\n0\nnine\nten\n"
+  );
+}
+
+#[test]
+fn reject_malformed_context_hunk() {
+  let diff = "*** a.txt\n--- a.txt\n***************\n*** 1 ****\n";
+  let result = Parser::new(diff).next().unwrap();
+  assert!(matches!(result, Err(Error::Parse(_))));
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "pretty-errors")]
+
+use hit::applier;
+use hit::diagnostic;
+use hit::parser::Parser;
+
+const PATCH: &str = "diff --git a/f.txt b/f.txt
+--- a/f.txt
++++ b/f.txt
+@@ -2,1 +2,1 @@
+-TWO
++2
+";
+
+#[test]
+fn report_points_at_malformed_hunk() {
+  let text = PATCH.replace("@@ -2,1", "@@ -2,2");
+  let error = Parser::new(&text).next().unwrap().unwrap_err();
+
+  let rendered = diagnostic::report(&error, &text, None).unwrap().render();
+  assert!(rendered.contains("Hunk line count mismatch for old file"));
+  assert!(rendered.contains("4 │ @@ -2,2 +2,1 @@"));
+  assert!(rendered.contains("╰── here"));
+}
+
+#[test]
+fn report_shows_target_and_hunk_for_mismatch() {
+  let source = "one\ntwo\nthree\n";
+  let patch = Parser::new(PATCH).next().unwrap().unwrap();
+  let error = applier::apply(&patch, source).unwrap_err();
+
+  let rendered = diagnostic::report(&error, PATCH, Some(source))
+    .unwrap()
+    .render();
+  assert!(rendered.contains("[f.txt:2:1]"));
+  assert!(rendered.contains("╰── expected `TWO`"));
+  assert!(rendered.contains("╰── hunk #1"));
+}
@@ -0,0 +1,135 @@
+use hit::applier;
+use hit::differ::Differ;
+use hit::fs::MockFileSystem;
+use hit::parser::Line;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+fn lines(range: std::ops::Range<u32>) -> String {
+  range.map(|line| format!("line {}\n", line)).collect()
+}
+
+fn round_trip(differ: &Differ, old: &str, new: &str) {
+  let text = differ.unified("file.txt", "file.txt", old, new);
+  let patch = Parser::new(&text).next().unwrap().unwrap();
+  assert_eq!(applier::apply(&patch, old).unwrap(), new, "{}", text);
+}
+
+#[test]
+fn unified_matches_git_output() {
+  let old = "one\ntwo\nthree\nfour\n";
+  let new = "one\nTWO\nthree\nfour\nfive\n";
+
+  assert_eq!(
+    Differ::new().unified("file.txt", "file.txt", old, new),
+    "diff --git a/file.txt b/file.txt\n\
+     --- a/file.txt\n\
+     +++ b/file.txt\n\
+     @@ -1,4 +1,5 @@\n \
+     one\n\
+     -two\n\
+     +TWO\n \
+     three\n \
+     four\n\
+     +five\n"
+  );
+}
+
+#[test]
+fn context_radius_splits_distant_changes() {
+  let old = lines(1..21);
+  let new = old
+    .replace("line 2\n", "line two\n")
+    .replace("line 18\n", "");
+
+  let patch = Differ::new().diff("file.txt", "file.txt", &old, &new);
+  assert_eq!(patch.hunks.len(), 2);
+  assert_eq!((patch.hunks[1].old_line, patch.hunks[1].old_span), (15, 6));
+  assert_eq!((patch.hunks[1].new_line, patch.hunks[1].new_span), (15, 5));
+
+  let patch = Differ::new()
+    .context(8)
+    .diff("file.txt", "file.txt", &old, &new);
+  assert_eq!(patch.hunks.len(), 1);
+
+  let patch = Differ::new()
+    .context(0)
+    .diff("file.txt", "file.txt", &old, &new);
+  assert!(patch.hunks.iter().all(|hunk| {
+    hunk
+      .lines
+      .iter()
+      .all(|line| !matches!(line, Line::Context(_)))
+  }));
+}
+
+#[test]
+fn diff_round_trips_through_applier() {
+  let old = lines(1..40);
+  let new = old
+    .replace("line 3\n", "")
+    .replace("line 10\n", "line ten\nline ten and a half\n")
+    .replace("line 30\n", "line 31\nline 30\n");
+
+  for context in [0, 1, 3, 10] {
+    let differ = Differ::new().context(context);
+    round_trip(&differ, &old, &new);
+    round_trip(&differ, &new, &old);
+  }
+  round_trip(&Differ::new(), "a\nb", "a\nc\n");
+  round_trip(&Differ::new(), "a\nb\n", "a\nb");
+  round_trip(&Differ::new(), "", "created\n");
+  round_trip(&Differ::new(), "same\n", "same\n");
+}
+
+#[test]
+fn diff_files_marks_missing_side_as_dev_null() {
+  let fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("src/new.rs"),
+    "fn main() {}\n".to_string(),
+  )]));
+
+  let patch = Differ::new()
+    .diff_files(&fs, Path::new("src/old.rs"), Path::new("src/new.rs"))
+    .unwrap();
+  let patch = patch.as_patch();
+  assert_eq!(patch.old_file, "/dev/null");
+  assert_eq!(patch.new_file, "src/new.rs");
+  assert_eq!(patch.new_mode, Some(0o100644));
+  assert_eq!(
+    patch.hunks[0].lines,
+    vec![Line::Addition("fn main() {}".into())]
+  );
+}
+
+#[test]
+fn diff_round_trips_with_and_without_final_newline() {
+  let mut files = vec![String::new()];
+  for count in 1..=3 {
+    for mut choice in 0..3usize.pow(count) {
+      let mut text = String::new();
+      for _ in 0..count {
+        text.push_str(["a", "b", ""][choice % 3]);
+        text.push('\n');
+        choice /= 3;
+      }
+      files.push(text.clone());
+      text.pop();
+      files.push(text);
+    }
+  }
+  files.dedup();
+
+  let differ = Differ::new();
+  for old in &files {
+    for new in &files {
+      round_trip(&differ, old, new);
+      let patch = differ.diff("file.txt", "file.txt", old, new);
+      let mut written = Vec::new();
+      applier::apply_to_writer(&patch, old.as_bytes(), &mut written).unwrap();
+      assert_eq!(String::from_utf8(written).unwrap(), *new);
+    }
+  }
+}
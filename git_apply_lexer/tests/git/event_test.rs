@@ -0,0 +1,194 @@
+use hit::applier;
+use hit::error::Error;
+use hit::event::ApplyEvent;
+use hit::fs::MockFileSystem;
+use hit::fs::OsFileSystem;
+use hit::options::ApplyOptions;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn patch_emits_events_per_file() {
+  let diff = r#"diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/old.txt b/renamed.txt
+similarity index 100%
+rename from old.txt
+rename to renamed.txt
+diff --git a/edit.txt b/edit.txt
+old mode 100644
+new mode 100755
+--- a/edit.txt
++++ b/edit.txt
+@@ -1 +1 @@
+-a
++b
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([
+    (PathBuf::from("gone.txt"), "bye\n".to_string()),
+    (PathBuf::from("old.txt"), "same\n".to_string()),
+    (PathBuf::from("edit.txt"), "a\n".to_string()),
+  ]));
+
+  let mut events = Vec::new();
+  applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut events).unwrap();
+
+  assert_eq!(
+    events,
+    vec![
+      ApplyEvent::Created {
+        path: PathBuf::from("new.txt")
+      },
+      ApplyEvent::Deleted {
+        path: PathBuf::from("gone.txt")
+      },
+      ApplyEvent::Renamed {
+        from: PathBuf::from("old.txt"),
+        to: PathBuf::from("renamed.txt")
+      },
+      ApplyEvent::Modified {
+        path: PathBuf::from("edit.txt"),
+        hunks: 1
+      },
+      ApplyEvent::ModeChanged {
+        path: PathBuf::from("edit.txt"),
+        mode: 0o100755
+      },
+    ]
+  );
+}
+
+#[test]
+fn patch_reports_skipped_and_copied_files_to_closure_sink() {
+  let diff = r#"diff --git a/a.txt b/b.txt
+copy from a.txt
+copy to b.txt
+diff --git a/docs/readme.md b/docs/readme.md
+--- a/docs/readme.md
++++ b/docs/readme.md
+@@ -1 +1 @@
+-x
++y
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("a.txt"),
+    "content\n".to_string(),
+  )]));
+
+  let mut seen = Vec::new();
+  let mut sink = |event: ApplyEvent| seen.push(event);
+  applier::patch(
+    &mut fs,
+    diff,
+    &ApplyOptions::new().exclude("docs/*"),
+    &mut sink,
+  )
+  .unwrap();
+
+  assert_eq!(
+    seen,
+    vec![
+      ApplyEvent::Copied {
+        from: PathBuf::from("a.txt"),
+        to: PathBuf::from("b.txt")
+      },
+      ApplyEvent::Skipped {
+        path: PathBuf::from("docs/readme.md")
+      },
+    ]
+  );
+}
+
+#[test]
+fn patch_preserves_invalid_utf8_unless_lossy() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("notes.txt");
+  fs::write(&path, b"// caf\xe9\nold\n").unwrap();
+  let diff = r#"--- a/notes.txt
++++ b/notes.txt
+@@ -2 +2 @@
+-old
++new
+"#;
+
+  let options = ApplyOptions::new().root(dir.path());
+  let mut events = Vec::new();
+  applier::patch(&mut OsFileSystem, diff, &options, &mut events).unwrap();
+  assert_eq!(
+    events,
+    vec![ApplyEvent::Modified {
+      path: path.clone(),
+      hunks: 1
+    }]
+  );
+  assert_eq!(fs::read(&path).unwrap(), b"// caf\xe9\nnew\n");
+
+  fs::write(&path, b"// caf\xe9\nold\n").unwrap();
+  let mut events = Vec::new();
+  let options = options.lossy_utf8(true);
+  applier::patch(&mut OsFileSystem, diff, &options, &mut events).unwrap();
+  assert_eq!(events[0], ApplyEvent::LossyRead { path: path.clone() });
+  assert_eq!(fs::read_to_string(&path).unwrap(), "// caf\u{fffd}\nnew\n");
+
+  fs::write(&path, b"// caf\xe9\nother\n").unwrap();
+  let options = ApplyOptions::new().root(dir.path());
+  let error =
+    applier::patch(&mut OsFileSystem, diff, &options, &mut ()).unwrap_err();
+  assert!(matches!(error, Error::HunkMismatch { line: 2, .. }));
+}
+
+#[test]
+fn patch_preserves_invalid_utf8_in_memory() {
+  let mut fs = MockFileSystem::default();
+  fs.insert_bytes("notes.txt", b"// caf\xe9\nold\n");
+  let diff = r#"--- a/notes.txt
++++ b/notes.txt
+@@ -2 +2 @@
+-old
++new
+"#;
+
+  applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).unwrap();
+  assert_eq!(fs.file_bytes("notes.txt"), Some(&b"// caf\xe9\nnew\n"[..]));
+}
+
+#[test]
+fn invalid_utf8_hunks_apply_at_offset() {
+  let mut fs = MockFileSystem::default();
+  fs.insert_bytes("notes.txt", b"// caf\xe9\nextra\n\nold\n");
+  let diff = r#"--- a/notes.txt
++++ b/notes.txt
+@@ -2,2 +2,2 @@
+ 
+-old
++café
+"#;
+
+  let options = ApplyOptions::new();
+  assert_eq!(applier::check(&fs, diff, &options).unwrap(), vec![]);
+
+  let mut events = Vec::new();
+  applier::patch(&mut fs, diff, &options, &mut events).unwrap();
+  assert!(events.contains(&ApplyEvent::Offset {
+    path: PathBuf::from("notes.txt"),
+    hunk: 1,
+    line: 3,
+    offset: 1,
+    fuzz: 0,
+  }));
+  assert_eq!(
+    fs.file_bytes("notes.txt"),
+    Some(&b"// caf\xe9\nextra\n\ncaf\xc3\xa9\n"[..])
+  );
+}
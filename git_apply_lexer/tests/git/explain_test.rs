@@ -0,0 +1,103 @@
+use hit::explain;
+use hit::explain::Change;
+use hit::explain::Risk;
+
+const PATCH: &str = r#"diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,7 +10,7 @@ fn parse(input: &str) -> Patch {
+ let a = 1;
+ let b = 2;
+ let c = 3;
+-let d = 4;
++let d = 5;
+ let e = 6;
+ let f = 7;
+ let g = 8;
+@@ -40,2 +40,2 @@ impl Applier {
+ }
++fn extra() {}
+-fn gone() {}
+diff --git a/docs/new.md b/docs/new.md
+new file mode 100644
+--- /dev/null
++++ b/docs/new.md
+@@ -0,0 +1,2 @@
++# Title
++Body
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/old.txt b/new.txt
+similarity index 90%
+rename from old.txt
+rename to new.txt
+"#;
+
+#[test]
+fn explain_summarizes_each_file() {
+  let explanation = explain::explain(PATCH).unwrap();
+  let files = &explanation.files;
+  assert_eq!(files.len(), 4);
+
+  assert_eq!(files[0].path, "src/lib.rs");
+  assert_eq!(files[0].change, Change::Modified);
+  assert_eq!(
+    files[0].functions,
+    ["fn parse(input: &str) -> Patch {", "impl Applier {"]
+  );
+  assert_eq!(
+    files[0].risks,
+    vec![Risk::LowContext {
+      hunk_index: 1,
+      context: 1
+    }]
+  );
+  assert_eq!(files[1].change, Change::Created);
+  assert!(files[1].functions.is_empty());
+  assert_eq!(
+    files[2].risks,
+    vec![Risk::ModeChange {
+      old: 0o100644,
+      new: 0o100755
+    }]
+  );
+  assert_eq!(
+    files[3].change,
+    Change::Renamed {
+      from: "old.txt".to_string(),
+      similarity: Some(90)
+    }
+  );
+}
+
+#[test]
+fn explain_renders_a_narrative() {
+  let explanation = explain::explain(PATCH).unwrap();
+  assert_eq!(
+    explanation.to_string(),
+    "4 files changed, 4 insertions(+), 2 deletions(-)
+Modified src/lib.rs: 2 hunks, +2 -2
+  touches fn parse(input: &str) -> Patch {
+  touches impl Applier {
+Created docs/new.md: 1 hunk, +2 -0
+Modified run.sh
+Renamed old.txt to new.txt (90% similar)
+Risks:
+  src/lib.rs: hunk #2 has only 1 context line
+  run.sh: mode changes from 100644 to 100755
+"
+  );
+}
+
+#[test]
+fn explain_flags_paths_escaping_the_tree() {
+  let diff = r#"--- a/../secrets.txt
++++ b/../secrets.txt
+@@ -1 +1 @@
+-old
++new
+"#;
+  let explanation = explain::explain(diff).unwrap();
+  assert!(explanation.files[0].risks.contains(&Risk::PathEscape));
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "ffi")]
+
+use hit::ffi;
+use hit::ffi::HitEventKind;
+use hit::ffi::HitStatus;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::fs;
+use std::ptr;
+
+const DIFF: &str = r#"diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1 +1 @@
+-old
++new
+"#;
+
+fn last_error() -> Option<String> {
+  let error = ffi::hit_last_error();
+  (!error.is_null()).then(|| {
+    unsafe { CStr::from_ptr(error) }
+      .to_string_lossy()
+      .into_owned()
+  })
+}
+
+#[test]
+fn hit_apply_patches_files_under_root() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("file.txt"), "old\n").unwrap();
+  let patch = CString::new(DIFF).unwrap();
+  let root = CString::new(dir.path().to_str().unwrap()).unwrap();
+
+  let status = unsafe { ffi::hit_apply(patch.as_ptr(), false, root.as_ptr()) };
+
+  assert_eq!(status, HitStatus::Ok);
+  assert_eq!(last_error(), None);
+  assert_eq!(
+    fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+    "new\n"
+  );
+  assert_eq!(ffi::hit_last_event_count(), 1);
+  assert_eq!(ffi::hit_last_event_kind(0), HitEventKind::Modified as i32);
+  let path = unsafe { CStr::from_ptr(ffi::hit_last_event_path(0)) };
+  assert!(path.to_str().unwrap().ends_with("file.txt"));
+  assert_eq!(ffi::hit_last_event_kind(1), -1);
+  assert!(ffi::hit_last_event_path(1).is_null());
+}
+
+#[test]
+fn hit_apply_reports_structured_failures() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("file.txt"), "changed\n").unwrap();
+  let patch = CString::new(DIFF).unwrap();
+  let root = CString::new(dir.path().to_str().unwrap()).unwrap();
+
+  let status = unsafe { ffi::hit_apply(patch.as_ptr(), false, root.as_ptr()) };
+
+  assert_eq!(status, HitStatus::Apply);
+  assert!(last_error().unwrap().contains("Patch mismatch at line 1"));
+  assert_eq!(ffi::hit_last_event_count(), 0);
+
+  let status = unsafe { ffi::hit_apply(ptr::null(), false, ptr::null()) };
+  assert_eq!(status, HitStatus::InvalidArgument);
+  assert_eq!(last_error().as_deref(), Some("patch_text must not be null"));
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "fuzzing")]
+
+use arbitrary::Arbitrary;
+use arbitrary::Unstructured;
+use hit::applier;
+use hit::error::Error;
+use hit::fuzzing;
+use hit::parser::Parser;
+use hit::parser::Patch;
+use hit::verify;
+use std::collections::HashMap;
+
+fn seeds() -> impl Iterator<Item = Vec<u8>> {
+  (0u32..200).map(|seed| {
+    let mut state = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+    (0..512)
+      .map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        (state % 96 + 32) as u8
+      })
+      .collect()
+  })
+}
+
+#[test]
+fn arbitrary_patches_round_trip_through_text() {
+  for data in seeds() {
+    let mut u = Unstructured::new(&data);
+    let Ok(patch) = Patch::arbitrary(&mut u) else {
+      continue;
+    };
+
+    let text = fuzzing::render(&patch);
+    let parsed = Parser::new(&text)
+      .collect::<Result<Vec<_>, Error>>()
+      .unwrap_or_else(|e| panic!("{}\n{}", e, text));
+    assert_eq!(parsed, vec![patch], "{}", text);
+  }
+}
+
+#[test]
+fn arbitrary_created_files_apply_to_empty_source() {
+  for data in seeds() {
+    let mut u = Unstructured::new(&data);
+    let Ok(patch) = Patch::arbitrary(&mut u) else {
+      continue;
+    };
+    if patch.old_file != "/dev/null" {
+      continue;
+    }
+
+    let applied = applier::apply(&patch, "").unwrap();
+    assert_eq!(applied.lines().count(), patch.hunks[0].lines.len());
+  }
+}
+
+#[test]
+fn arbitrary_created_files_satisfy_roundtrip_invariants() {
+  for data in seeds() {
+    let mut u = Unstructured::new(&data);
+    let Ok(patch) = Patch::arbitrary(&mut u) else {
+      continue;
+    };
+    if patch.old_file != "/dev/null" {
+      continue;
+    }
+
+    let text = fuzzing::render(&patch);
+    let mismatches = verify::roundtrip(&text, &HashMap::new()).unwrap();
+    assert_eq!(mismatches, vec![], "{}", text);
+  }
+}
@@ -0,0 +1,65 @@
+use hit::intraline;
+use hit::parser::Hunk;
+use hit::parser::Line;
+
+#[test]
+fn diff_line_reports_changed_word() {
+  let ranges = intraline::diff_line("let x = 1;", "let y = 1;");
+  assert_eq!(ranges, vec![(4..5, 4..5)]);
+}
+
+#[test]
+fn diff_line_reports_pure_insertion() {
+  let ranges = intraline::diff_line("foo(a)", "foo(a, b)");
+  assert_eq!(ranges, vec![(5..5, 5..8)]);
+}
+
+#[test]
+fn diff_line_identical_lines_have_no_ranges() {
+  assert!(intraline::diff_line("same", "same").is_empty());
+}
+
+#[test]
+fn hunk_intraline_pairs_deletions_with_additions() {
+  let hunk = Hunk {
+    old_line: 1,
+    old_span: 3,
+    new_line: 1,
+    new_span: 3,
+    heading: None,
+    lines: vec![
+      Line::Context("fn main() {".into()),
+      Line::Deletion("  old_call();".into()),
+      Line::Addition("  new_call();".into()),
+      Line::Context("}".into()),
+    ],
+  };
+
+  let changes = hunk.intraline();
+  assert_eq!(changes.len(), 1);
+  assert_eq!(changes[0].deletion, 1);
+  assert_eq!(changes[0].addition, 2);
+  assert_eq!(changes[0].ranges, vec![(2..10, 2..10)]);
+}
+
+#[test]
+fn hunk_intraline_skips_unpaired_lines() {
+  let hunk = Hunk {
+    old_line: 1,
+    old_span: 2,
+    new_line: 1,
+    new_span: 1,
+    heading: None,
+    lines: vec![
+      Line::Deletion("a b".into()),
+      Line::Deletion("removed".into()),
+      Line::Addition("a c".into()),
+    ],
+  };
+
+  let changes = hunk.intraline();
+  assert_eq!(changes.len(), 1);
+  assert_eq!(changes[0].deletion, 0);
+  assert_eq!(changes[0].addition, 2);
+  assert_eq!(changes[0].ranges, vec![(2..3, 2..3)]);
+}
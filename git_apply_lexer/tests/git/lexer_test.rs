@@ -1,4 +1,5 @@
 use hit::error::Error;
+use hit::lexer::BinaryMethod;
 use hit::lexer::Lexer;
 use hit::lexer::Token;
 
@@ -18,8 +19,8 @@ index 1234567..abcdefg 100644
   assert_eq!(
     lexer.next(),
     Some(Ok(Token::FileHeader {
-      old_file: "file.txt",
-      new_file: "file.txt"
+      old_file: "file.txt".into(),
+      new_file: "file.txt".into()
     }))
   );
   assert_eq!(
@@ -30,20 +31,33 @@ index 1234567..abcdefg 100644
       mode: Some(0o100644)
     }))
   );
-  assert_eq!(lexer.next(), Some(Ok(Token::OldFile("file.txt"))));
-  assert_eq!(lexer.next(), Some(Ok(Token::NewFile("file.txt"))));
+  assert_eq!(
+    lexer.next(),
+    Some(Ok(Token::OldFile {
+      path: "file.txt".into(),
+      timestamp: None,
+    }))
+  );
+  assert_eq!(
+    lexer.next(),
+    Some(Ok(Token::NewFile {
+      path: "file.txt".into(),
+      timestamp: None,
+    }))
+  );
   assert_eq!(
     lexer.next(),
     Some(Ok(Token::HunkHeader {
       old_line: 1,
       old_span: 2,
       new_line: 1,
-      new_span: 2
+      new_span: 2,
+      heading: None
     }))
   );
   assert_eq!(lexer.next(), Some(Ok(Token::Deletion("hello world"))));
   assert_eq!(lexer.next(), Some(Ok(Token::Addition("Hello, world!"))));
-  assert_eq!(lexer.next(), Some(Ok(Token::Context("   context"))));
+  assert_eq!(lexer.next(), Some(Ok(Token::Context("  context"))));
   assert!(lexer.next().is_none());
 }
 
@@ -73,7 +87,8 @@ index 1234567..abcdefg 100644
       old_line: 1,
       old_span: 2,
       new_line: 1,
-      new_span: 2
+      new_span: 2,
+      heading: None
     }))
   );
 
@@ -97,7 +112,8 @@ fn lex_hunk_header_zero_span() {
       old_line: 0,
       old_span: 0,
       new_line: 1,
-      new_span: 3
+      new_span: 3,
+      heading: None
     }))
   );
 }
@@ -114,14 +130,48 @@ fn lex_malformed_git_prefix() {
   }
 }
 
+#[test]
+fn lex_strips_leading_components() {
+  let diff = "--- project-1.2/src/main.c\n+++ project-1.3//src/main.c\n";
+  let tokens: Vec<_> = Lexer::new(diff).strip(1).flatten().collect();
+  assert_eq!(
+    tokens,
+    vec![
+      Token::OldFile {
+        path: "src/main.c".into(),
+        timestamp: None,
+      },
+      Token::NewFile {
+        path: "src/main.c".into(),
+        timestamp: None,
+      }
+    ]
+  );
+
+  let tokens: Vec<_> = Lexer::new(diff).strip(0).flatten().collect();
+  assert_eq!(
+    tokens[0],
+    Token::OldFile {
+      path: "project-1.2/src/main.c".into(),
+      timestamp: None,
+    }
+  );
+
+  let mut lexer = Lexer::new("--- main.c\n").strip(1);
+  match lexer.next().unwrap().unwrap_err() {
+    Error::Parse(msg) => assert_eq!(msg, "Malformed file path: `main.c`"),
+    _ => panic!("Expected Parse error"),
+  }
+}
+
 #[test]
 fn lex_rename_file() {
   let diff = r#"rename from old.txt
 rename to new.txt
 "#;
   let mut lexer = Lexer::new(diff);
-  assert_eq!(lexer.next(), Some(Ok(Token::RenameFrom("old.txt"))));
-  assert_eq!(lexer.next(), Some(Ok(Token::RenameTo("new.txt"))));
+  assert_eq!(lexer.next(), Some(Ok(Token::RenameFrom("old.txt".into()))));
+  assert_eq!(lexer.next(), Some(Ok(Token::RenameTo("new.txt".into()))));
   assert!(lexer.next().is_none());
 }
 
@@ -131,8 +181,8 @@ fn lex_copy_file() {
 copy to new.txt
 "#;
   let mut lexer = Lexer::new(diff);
-  assert_eq!(lexer.next(), Some(Ok(Token::CopyFrom("old.txt"))));
-  assert_eq!(lexer.next(), Some(Ok(Token::CopyTo("new.txt"))));
+  assert_eq!(lexer.next(), Some(Ok(Token::CopyFrom("old.txt".into()))));
+  assert_eq!(lexer.next(), Some(Ok(Token::CopyTo("new.txt".into()))));
   assert!(lexer.next().is_none());
 }
 
@@ -159,9 +209,172 @@ fn lex_binary_files_differ() {
   assert_eq!(
     lexer.next(),
     Some(Ok(Token::BinaryFileDiffer {
-      old_file: "a/old.bin",
-      new_file: "b/new.bin"
+      old_file: "a/old.bin".into(),
+      new_file: "b/new.bin".into()
     }))
   );
   assert!(lexer.next().is_none());
 }
+
+#[test]
+fn lex_git_binary_patch_consumes_data_lines() {
+  let diff = "GIT binary patch\nliteral 14\nVcmWIWb7x>=V$Mj-$;to!4*(fA1pNR2\n\nliteral 0\nHcmV?d00001\n\n";
+  let tokens: Vec<_> = Lexer::new(diff).collect::<Result<_, _>>().unwrap();
+  assert_eq!(
+    tokens,
+    vec![
+      Token::GitBinaryPatch,
+      Token::BinaryHunk {
+        method: BinaryMethod::Literal,
+        size: 14,
+        data: "VcmWIWb7x>=V$Mj-$;to!4*(fA1pNR2",
+      },
+      Token::BinaryHunk {
+        method: BinaryMethod::Literal,
+        size: 0,
+        data: "HcmV?d00001",
+      },
+    ]
+  );
+}
+
+#[test]
+fn lex_spanned_reports_line_ranges() {
+  let diff = "--- a/file.txt\n+++ b/file.txt\n\n@@ -1 +1 @@\r\n-old\n+new";
+  let tokens: Vec<_> = Lexer::new(diff)
+    .spanned()
+    .collect::<Result<_, _>>()
+    .unwrap();
+
+  assert_eq!(
+    tokens,
+    vec![
+      (
+        Token::OldFile {
+          path: "file.txt".into(),
+          timestamp: None,
+        },
+        0..14
+      ),
+      (
+        Token::NewFile {
+          path: "file.txt".into(),
+          timestamp: None,
+        },
+        15..29
+      ),
+      (
+        Token::HunkHeader {
+          old_line: 1,
+          old_span: 1,
+          new_line: 1,
+          new_span: 1,
+          heading: None,
+        },
+        31..42,
+      ),
+      (Token::Deletion("old"), 44..48),
+      (Token::Addition("new"), 49..53),
+    ]
+  );
+  assert_eq!(&diff[31..42], "@@ -1 +1 @@");
+}
+
+#[test]
+fn lex_unquotes_c_style_paths() {
+  let diff = r#"diff --git "a/caf\303\251 \"menu\".txt" "b/tab\there.txt"
+rename from "caf\303\251 \"menu\".txt"
+rename to "tab\there.txt"
+diff --git a/fo o.txt b/fo o.txt
+--- "a/back\\slash.txt"
++++ b/plain.txt
+"#;
+  let tokens: Vec<_> = Lexer::new(diff).collect::<Result<_, _>>().unwrap();
+
+  assert_eq!(
+    tokens,
+    vec![
+      Token::FileHeader {
+        old_file: "café \"menu\".txt".into(),
+        new_file: "tab\there.txt".into(),
+      },
+      Token::RenameFrom("café \"menu\".txt".into()),
+      Token::RenameTo("tab\there.txt".into()),
+      Token::FileHeader {
+        old_file: "fo o.txt".into(),
+        new_file: "fo o.txt".into(),
+      },
+      Token::OldFile {
+        path: "back\\slash.txt".into(),
+        timestamp: None,
+      },
+      Token::NewFile {
+        path: "plain.txt".into(),
+        timestamp: None,
+      },
+    ]
+  );
+  assert!(
+    Lexer::new("rename from \"open.txt\n")
+      .next()
+      .unwrap()
+      .is_err()
+  );
+}
+
+#[test]
+fn lex_splits_traditional_timestamps() {
+  let diff = "--- old/file.txt\t2024-01-01 10:00:00.000000000 +0000\n\
+              +++ \"new/caf\\303\\251.txt\"\t2024-01-02 11:30:00.000000000 +0000\n";
+  let tokens: Vec<_> = Lexer::new(diff).strip(1).flatten().collect();
+  assert_eq!(
+    tokens,
+    vec![
+      Token::OldFile {
+        path: "file.txt".into(),
+        timestamp: Some("2024-01-01 10:00:00.000000000 +0000"),
+      },
+      Token::NewFile {
+        path: "café.txt".into(),
+        timestamp: Some("2024-01-02 11:30:00.000000000 +0000"),
+      },
+    ]
+  );
+}
+
+#[test]
+fn lex_diffstat_only_before_the_diff() {
+  let diff = " a.txt | 1 +\n 1 file changed, 1 insertion(+)\n--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,3 @@\n a.txt | 1 +\n 1 file changed, 1 insertion(+)\n+new\n";
+  let tokens: Vec<_> = Lexer::new(diff).collect::<Result<_, _>>().unwrap();
+
+  assert!(matches!(tokens[0], Token::DiffStat { .. }));
+  assert!(matches!(tokens[1], Token::DiffStatSummary { .. }));
+  assert_eq!(tokens[5], Token::Context("a.txt | 1 +"));
+  assert_eq!(tokens[6], Token::Context("1 file changed, 1 insertion(+)"));
+}
+
+#[test]
+fn lex_hunk_header_keeps_section_heading() {
+  let mut lexer = Lexer::new("@@ -1,2 +1,2 @@  fn main() {\n@@ -4 +4 @@\n");
+
+  assert_eq!(
+    lexer.next(),
+    Some(Ok(Token::HunkHeader {
+      old_line: 1,
+      old_span: 2,
+      new_line: 1,
+      new_span: 2,
+      heading: Some(" fn main() {"),
+    }))
+  );
+  assert_eq!(
+    lexer.next(),
+    Some(Ok(Token::HunkHeader {
+      old_line: 4,
+      old_span: 1,
+      new_line: 4,
+      new_span: 1,
+      heading: None,
+    }))
+  );
+}
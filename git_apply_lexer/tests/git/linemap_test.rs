@@ -0,0 +1,61 @@
+use hit::builder::PatchBuilder;
+
+#[test]
+fn line_map_shifts_lines_around_hunks() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .context("c")
+    .delete("d")
+    .add("x")
+    .add("y")
+    .context("e")
+    .build()
+    .unwrap();
+  let map = patch.line_map();
+
+  assert_eq!(map.old_to_new(1), Some(1));
+  assert_eq!(map.old_to_new(3), Some(3));
+  assert_eq!(map.old_to_new(4), None);
+  assert_eq!(map.old_to_new(5), Some(6));
+  assert_eq!(map.old_to_new(10), Some(11));
+  assert_eq!(map.old_to_new(0), None);
+}
+
+#[test]
+fn line_map_translates_new_lines_back() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .delete("b")
+    .delete("c")
+    .context("d")
+    .hunk_at(10)
+    .add("inserted")
+    .context("j")
+    .build()
+    .unwrap();
+  let map = patch.line_map();
+
+  assert_eq!(map.new_to_old(1), Some(1));
+  assert_eq!(map.new_to_old(2), Some(4));
+  assert_eq!(map.new_to_old(5), Some(7));
+  assert_eq!(map.new_to_old(8), None);
+  assert_eq!(map.new_to_old(9), Some(10));
+  assert_eq!(map.new_to_old(20), Some(21));
+  assert_eq!(map.old_to_new(2), None);
+  assert_eq!(map.old_to_new(10), Some(9));
+}
+
+#[test]
+fn line_map_handles_pure_insertion() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .add("new")
+    .build()
+    .unwrap();
+  let map = patch.line_map();
+
+  assert_eq!(map.old_to_new(2), Some(2));
+  assert_eq!(map.old_to_new(3), Some(4));
+  assert_eq!(map.new_to_old(3), None);
+  assert_eq!(map.new_to_old(4), Some(3));
+}
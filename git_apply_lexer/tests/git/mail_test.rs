@@ -0,0 +1,91 @@
+use hit::applier;
+use hit::error::Error;
+use hit::fs::MockFileSystem;
+use hit::mail;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const MBOX: &str =
+  "From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Date: Mon, 1 Jan 2024 10:00:00 +0000
+Subject: [PATCH 1/2] Capitalize the
+ greeting
+
+Say hello properly.
+---
+ greeting.txt | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1 @@
+-hello
++Hello
+-- 
+2.43.0
+
+From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+From: Ada Lovelace <ada@example.com>
+Subject: [PATCH 2/2] Add a farewell
+
+Ignored discussion.
+
+-- >8 --
+From: Charles Babbage <charles@example.com>
+Subject: Add a farewell line
+
+Wave goodbye.
+---
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1,2 @@
+ Hello
++Goodbye
+-- 
+2.43.0
+";
+
+#[test]
+fn split_extracts_metadata_and_diffs() {
+  assert!(mail::is_mail(MBOX));
+  let mails = mail::split(MBOX);
+  assert_eq!(mails.len(), 2);
+
+  assert_eq!(mails[0].author, Some("Ada Lovelace <ada@example.com>"));
+  assert_eq!(mails[0].date, Some("Mon, 1 Jan 2024 10:00:00 +0000"));
+  assert_eq!(mails[0].subject, "Capitalize the greeting");
+  assert_eq!(mails[0].message, "Say hello properly.");
+  assert!(mails[0].diff.starts_with("diff --git"));
+  assert!(mails[0].diff.ends_with("+Hello\n"));
+
+  assert_eq!(
+    mails[1].author,
+    Some("Charles Babbage <charles@example.com>")
+  );
+  assert_eq!(mails[1].subject, "Add a farewell line");
+  assert_eq!(mails[1].message, "Wave goodbye.");
+}
+
+#[test]
+fn parser_reads_patches_from_every_mail() {
+  let patches = Parser::new(MBOX)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches.len(), 2);
+  assert!(patches.iter().all(|patch| patch.new_file == "greeting.txt"));
+}
+
+#[test]
+fn apply_mbox_series() {
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("greeting.txt"),
+    "hello\n".to_string(),
+  )]));
+  applier::patch(&mut fs, MBOX, &ApplyOptions::new(), &mut ()).unwrap();
+  assert_eq!(fs.file("greeting.txt").unwrap(), "Hello\nGoodbye\n");
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "manifest")]
+
+use hit::error::Error;
+use hit::fs::FileSystem;
+use hit::fs::MockFileSystem;
+use hit::manifest;
+use hit::manifest::Manifest;
+use hit::manifest::Side;
+use hit::options::ApplyOptions;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+const DIFF: &str = "diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1 +1 @@
+-old
++new
+diff --git a/added.txt b/added.txt
+new file mode 100644
+--- /dev/null
++++ b/added.txt
+@@ -0,0 +1 @@
++hello
+";
+
+fn mock() -> MockFileSystem {
+  let mut files = HashMap::new();
+  files.insert(PathBuf::from("file.txt"), "old\n".to_string());
+  MockFileSystem::new(files)
+}
+
+#[test]
+fn checksum_is_sha256_hex() {
+  assert_eq!(
+    manifest::checksum("abc"),
+    "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+  );
+}
+
+#[test]
+fn record_captures_both_sides() {
+  let mut fs = mock();
+  let manifest =
+    Manifest::record(&mut fs, DIFF, &ApplyOptions::default()).unwrap();
+
+  assert_eq!(manifest.entries.len(), 2);
+  let file = &manifest.entries[0];
+  assert_eq!(file.path, PathBuf::from("file.txt"));
+  assert_eq!(file.before.as_deref(), Some(&*manifest::checksum("old\n")));
+  assert_eq!(file.after.as_deref(), Some(&*manifest::checksum("new\n")));
+  let added = &manifest.entries[1];
+  assert_eq!(added.before, None);
+  assert_eq!(
+    added.after.as_deref(),
+    Some(&*manifest::checksum("hello\n"))
+  );
+
+  assert_eq!(Manifest::parse(&manifest.to_string()).unwrap(), manifest);
+}
+
+#[test]
+fn verify_detects_either_side() {
+  let mut fs = mock();
+  let manifest =
+    Manifest::record(&mut fs, DIFF, &ApplyOptions::default()).unwrap();
+  assert_eq!(manifest.verify(&fs).unwrap(), Some(Side::After));
+
+  let mut before = mock();
+  assert_eq!(manifest.verify(&before).unwrap(), Some(Side::Before));
+
+  before.write(Path::new("file.txt"), "tampered\n").unwrap();
+  assert_eq!(manifest.verify(&before).unwrap(), None);
+  assert_eq!(
+    manifest.mismatches(&before, Side::Before).unwrap(),
+    vec![Path::new("file.txt")]
+  );
+}
+
+#[test]
+fn parse_rejects_malformed_lines() {
+  assert!(matches!(Manifest::parse("- -"), Err(Error::Manifest(_))));
+  assert!(matches!(
+    Manifest::parse("xyz - file.txt"),
+    Err(Error::Manifest(_))
+  ));
+}
@@ -0,0 +1,35 @@
+use hit::merge;
+
+const BASE: &str = "one\ntwo\nthree\nfour\nfive\n";
+
+#[test]
+fn merge_combines_separate_changes() {
+  let ours = "one\nTWO\nthree\nfour\nfive\n";
+  let theirs = "one\ntwo\nthree\nfour\nFIVE\nsix\n";
+
+  let merged = merge::merge(BASE, ours, theirs);
+  assert_eq!(merged.content, "one\nTWO\nthree\nfour\nFIVE\nsix\n");
+  assert_eq!(merged.conflicts, 0);
+}
+
+#[test]
+fn merge_accepts_identical_changes() {
+  let both = "one\ntwo\n3\nfour\nfive\n";
+
+  let merged = merge::merge(BASE, both, both);
+  assert_eq!(merged.content, both);
+  assert_eq!(merged.conflicts, 0);
+}
+
+#[test]
+fn merge_marks_overlapping_changes() {
+  let ours = "one\ntwo\nours\nfour\nfive\n";
+  let theirs = "one\ntwo\ntheirs\nfour\nfive\n";
+
+  let merged = merge::merge(BASE, ours, theirs);
+  assert_eq!(
+    merged.content,
+    "one\ntwo\n<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\nfour\nfive\n"
+  );
+  assert_eq!(merged.conflicts, 1);
+}
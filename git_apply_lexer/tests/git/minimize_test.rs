@@ -0,0 +1,71 @@
+use hit::applier;
+use hit::builder::PatchBuilder;
+use hit::parser::Line;
+
+const SOURCE: &str = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+
+#[test]
+fn minimize_trims_context_and_splits_hunks() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(1)
+    .context("a")
+    .context("b")
+    .delete("c")
+    .add("C")
+    .context("d")
+    .context("e")
+    .context("f")
+    .context("g")
+    .delete("h")
+    .context("i")
+    .context("j")
+    .build()
+    .unwrap();
+
+  let minimized = patch.minimize(1);
+  assert_eq!(minimized.hunks.len(), 2);
+  assert_eq!(
+    minimized.hunks[0].lines,
+    vec![
+      Line::Context("b".into()),
+      Line::Deletion("c".into()),
+      Line::Addition("C".into()),
+      Line::Context("d".into()),
+    ]
+  );
+  assert_eq!(
+    (minimized.hunks[1].old_line, minimized.hunks[1].old_span),
+    (7, 3)
+  );
+  assert_eq!(
+    (minimized.hunks[1].new_line, minimized.hunks[1].new_span),
+    (7, 2)
+  );
+  assert_eq!(
+    applier::apply(&minimized, SOURCE).unwrap(),
+    applier::apply(&patch, SOURCE).unwrap()
+  );
+}
+
+#[test]
+fn minimize_drops_no_op_changes() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .context("b")
+    .delete("c")
+    .add("c")
+    .context("d")
+    .hunk_at(8)
+    .delete("h")
+    .build()
+    .unwrap();
+
+  let minimized = patch.minimize(0);
+  assert_eq!(minimized.hunks.len(), 1);
+  assert_eq!(minimized.hunks[0].old_line, 8);
+  assert_eq!(minimized.hunks[0].new_line, 7);
+  assert_eq!(
+    applier::apply(&minimized, SOURCE).unwrap(),
+    applier::apply(&patch, SOURCE).unwrap()
+  );
+}
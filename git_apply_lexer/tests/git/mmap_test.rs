@@ -0,0 +1,82 @@
+#![cfg(feature = "mmap")]
+
+use hit::applier;
+use hit::fs::Contents;
+use hit::fs::FileSystem;
+use hit::fs::OsFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use std::fs;
+use std::io;
+
+#[test]
+fn read_contents_maps_large_files() {
+  let dir = tempfile::tempdir().unwrap();
+  let small = dir.path().join("small.txt");
+  let large = dir.path().join("large.txt");
+  let text = "line\n".repeat(300_000);
+  fs::write(&small, "small\n").unwrap();
+  fs::write(&large, &text).unwrap();
+
+  let contents = OsFileSystem.read_contents(&small).unwrap();
+  assert!(matches!(contents, Contents::Owned(_)));
+  let contents = OsFileSystem.read_contents(&large).unwrap();
+  assert!(matches!(contents, Contents::Mapped(_)));
+  assert_eq!(contents.as_str().unwrap(), text);
+
+  let mut bytes = text.into_bytes();
+  bytes[0] = 0xff;
+  fs::write(&large, bytes).unwrap();
+  let error = OsFileSystem.read_contents(&large).unwrap_err();
+  assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn patch_rewrites_mapped_file_in_place() {
+  let dir = tempfile::tempdir().unwrap();
+  let mut text = "line\n".repeat(300_000);
+  text.insert_str(0, "first\n");
+  fs::write(dir.path().join("large.txt"), &text).unwrap();
+
+  let diff = "diff --git a/large.txt b/large.txt
+--- a/large.txt
++++ b/large.txt
+@@ -1,2 +1,2 @@
+-first
++changed
+ line
+";
+  let options = ApplyOptions::new().root(dir.path());
+  applier::patch(&mut OsFileSystem, diff, &options, &mut ()).unwrap();
+
+  let patched = fs::read_to_string(dir.path().join("large.txt")).unwrap();
+  assert_eq!(patched.len(), text.len() + 2);
+  assert!(patched.starts_with("changed\nline\n"));
+}
+
+#[test]
+fn apply_bytes_to_writer_patches_mapped_slices() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("large.txt");
+  let text = "line\n".repeat(300_000);
+  fs::write(&path, &text).unwrap();
+
+  let diff = "--- a/large.txt
++++ b/large.txt
+@@ -299999,2 +299999,3 @@
+ line
+ line
++appended
+";
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  let contents = OsFileSystem.read_contents(&path).unwrap();
+  assert!(matches!(contents, Contents::Mapped(_)));
+  let mut written = Vec::new();
+  let results =
+    applier::apply_bytes_to_writer(&patch, contents.as_bytes(), &mut written)
+      .unwrap();
+
+  assert_eq!(results[0].position, 299_999);
+  assert_eq!(written.len(), text.len() + "appended\n".len());
+  assert!(written.ends_with(b"line\nappended\n"));
+}
@@ -1,3 +1,44 @@
+mod annotate_test;
 mod applier_test;
+mod binary_test;
+mod buf_test;
+mod builder_test;
+mod cache_test;
+mod cli_test;
+mod compose_test;
+mod conflict_test;
+mod conformance_test;
+mod context_test;
+mod diagnostic_test;
+mod differ_test;
+mod event_test;
+mod explain_test;
+mod ffi_test;
+mod fuzzing_test;
+mod intraline_test;
 mod lexer_test;
+mod linemap_test;
+mod mail_test;
+mod manifest_test;
+mod merge_test;
+mod minimize_test;
+mod mmap_test;
+mod normal_test;
+mod options_test;
 mod parser_test;
+mod rebase_test;
+mod redact_test;
+mod render_test;
+mod report_test;
+mod repository_test;
+mod serde_test;
+mod series_test;
+mod serve_test;
+mod signature_test;
+mod stats_test;
+mod stream_test;
+mod summary_test;
+mod telemetry_test;
+mod transaction_test;
+mod verify_test;
+mod whitespace_test;
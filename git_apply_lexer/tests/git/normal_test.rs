@@ -0,0 +1,87 @@
+use hit::applier;
+use hit::error::Error;
+use hit::fs::MockFileSystem;
+use hit::normal;
+use hit::options::ApplyOptions;
+use hit::parser::Line;
+use hit::parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIFF: &str = "2c2
+< two
+---
+> TWO
+4d3
+< four
+5a5,6
+> six
+> seven
+";
+
+#[test]
+fn parse_normal_diff_commands() {
+  assert!(normal::is_normal_diff(DIFF));
+  assert!(!normal::is_normal_diff("--- a/file.txt\n+++ b/file.txt\n"));
+
+  let patches = Parser::new(DIFF)
+    .target("file.txt")
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches.len(), 1);
+  let patch = &patches[0];
+  assert_eq!(patch.old_file, "file.txt");
+  assert_eq!(patch.new_file, "file.txt");
+
+  let spans = patch
+    .hunks
+    .iter()
+    .map(|hunk| (hunk.old_line, hunk.old_span, hunk.new_line, hunk.new_span))
+    .collect::<Vec<_>>();
+  assert_eq!(spans, vec![(2, 1, 2, 1), (4, 1, 3, 0), (5, 0, 5, 2)]);
+  assert_eq!(
+    patch.hunks[0].lines,
+    vec![Line::Deletion("two".into()), Line::Addition("TWO".into())]
+  );
+}
+
+#[test]
+fn parse_normal_diff_file_headers() {
+  let diff = "diff -r old/a.txt new/a.txt\n1c1\n< a\n---\n> A\n\\ No newline at end of file\ndiff -r old/b.txt new/b.txt\n0a1\n> b\n";
+  let patches = Parser::new(diff)
+    .strip(1)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches.len(), 2);
+  assert_eq!(patches[0].new_file, "a.txt");
+  assert_eq!(
+    patches[0].hunks[0].lines,
+    vec![
+      Line::Deletion("a".into()),
+      Line::Addition("A".into()),
+      Line::NoNewline,
+    ]
+  );
+  assert_eq!(patches[1].old_file, "b.txt");
+  assert_eq!(patches[1].hunks[0].old_line, 0);
+}
+
+#[test]
+fn normal_diff_requires_a_target() {
+  let result = Parser::new(DIFF).next().unwrap();
+  assert!(matches!(result, Err(Error::Parse(_))));
+}
+
+#[test]
+fn apply_normal_diff() {
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "one\ntwo\nthree\nfour\nfive\n".to_string(),
+  )]));
+  let options = ApplyOptions::new().target("file.txt");
+  applier::patch(&mut fs, DIFF, &options, &mut ()).unwrap();
+  assert_eq!(
+    fs.file("file.txt").unwrap(),
+    "one\nTWO\nthree\nfive\nsix\nseven\n"
+  );
+}
@@ -0,0 +1,705 @@
+use hit::applier;
+use hit::applier::Issue;
+use hit::error::Error;
+use hit::event::ApplyEvent;
+use hit::fs::MockFileSystem;
+use hit::fs::OsFileSystem;
+use hit::options::ApplyOptions;
+use hit::options::DuplicatePolicy;
+use hit::options::LineEndings;
+use hit::options::Whitespace;
+use hit::options::WhitespacePolicy;
+use hit::parser::Parser;
+use hit::report::FileStatus;
+use hit::repository::Eol;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+const TWO_FILES: &str = r#"diff --git a/src/main.rs b/src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-old
++new
+diff --git a/docs/guide.md b/docs/guide.md
+--- a/docs/guide.md
++++ b/docs/guide.md
+@@ -1 +1 @@
+-old
++new
+"#;
+
+fn two_file_fs(prefix: &str) -> MockFileSystem {
+  MockFileSystem::new(HashMap::from([
+    (
+      PathBuf::from(prefix).join("src/main.rs"),
+      "old\n".to_string(),
+    ),
+    (
+      PathBuf::from(prefix).join("docs/guide.md"),
+      "old\n".to_string(),
+    ),
+  ]))
+}
+
+#[test]
+fn builder_sets_fields() {
+  let options = ApplyOptions::new()
+    .reverse(true)
+    .root("project")
+    .include("src/*")
+    .exclude("*.md")
+    .unsafe_paths(true)
+    .normalize_eol(true)
+    .duplicates(DuplicatePolicy::LastWins)
+    .fuzz(2)
+    .reject(true)
+    .atomic(true)
+    .strip(2)
+    .dry_run(true)
+    .whitespace(Whitespace::IgnoreAll)
+    .whitespace_policy(WhitespacePolicy::Fix)
+    .skip_applied(true)
+    .auto_reverse(true)
+    .lenient(true);
+
+  assert!(options.reverse);
+  assert_eq!(options.root, Some(PathBuf::from("project")));
+  assert_eq!(options.include, vec!["src/*".to_string()]);
+  assert_eq!(options.exclude, vec!["*.md".to_string()]);
+  assert!(options.unsafe_paths);
+  assert_eq!(options.line_endings, LineEndings::IgnoreEol);
+  assert_eq!(options.duplicates, DuplicatePolicy::LastWins);
+  assert_eq!(options.fuzz, 2);
+  assert!(options.reject);
+  assert!(options.atomic);
+  assert_eq!(options.strip, Some(2));
+  assert!(options.dry_run);
+  assert_eq!(options.whitespace, Whitespace::IgnoreAll);
+  assert_eq!(options.whitespace_policy, WhitespacePolicy::Fix);
+  assert!(options.skip_applied);
+  assert!(options.auto_reverse);
+  assert!(options.lenient);
+}
+
+#[test]
+fn selection_honors_include_and_exclude() {
+  let options = ApplyOptions::new().include("src/*").exclude("*_test.rs");
+
+  assert!(options.is_selected("src/lib.rs"));
+  assert!(!options.is_selected("src/lib_test.rs"));
+  assert!(!options.is_selected("docs/guide.md"));
+  assert!(ApplyOptions::new().is_selected("anything"));
+}
+
+#[test]
+fn patch_resolves_paths_under_root() {
+  let mut fs = two_file_fs("project");
+
+  applier::patch(
+    &mut fs,
+    TWO_FILES,
+    &ApplyOptions::new().root("project"),
+    &mut (),
+  )
+  .unwrap();
+
+  assert_eq!(fs.file("project/src/main.rs").unwrap(), "new\n");
+  assert_eq!(fs.file("project/docs/guide.md").unwrap(), "new\n");
+  assert!(fs.created_dirs.contains(&PathBuf::from("project/src")));
+}
+
+#[test]
+fn patch_creates_parent_dirs_under_root() {
+  let dir = tempfile::tempdir().unwrap();
+  let diff = r#"diff --git a/nested/deeper/new.txt b/nested/deeper/new.txt
+new file mode 100644
+--- /dev/null
++++ b/nested/deeper/new.txt
+@@ -0,0 +1 @@
++hello
+"#;
+
+  let options = ApplyOptions::new().root(dir.path());
+  applier::patch(&mut OsFileSystem, diff, &options, &mut ()).unwrap();
+  assert_eq!(
+    fs::read_to_string(dir.path().join("nested/deeper/new.txt")).unwrap(),
+    "hello\n"
+  );
+  assert!(!PathBuf::from("nested").exists());
+}
+
+#[test]
+fn patch_skips_excluded_files() {
+  let mut fs = two_file_fs("");
+
+  applier::patch(
+    &mut fs,
+    TWO_FILES,
+    &ApplyOptions::new().exclude("docs/*"),
+    &mut (),
+  )
+  .unwrap();
+
+  assert_eq!(fs.file("src/main.rs").unwrap(), "new\n");
+  assert_eq!(fs.file("docs/guide.md").unwrap(), "old\n");
+}
+
+#[test]
+fn patch_rejects_paths_escaping_root() {
+  let diff = r#"diff --git a/../outside.txt b/../outside.txt
+--- a/../outside.txt
++++ b/../outside.txt
+@@ -1 +1 @@
+-old
++new
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("../outside.txt"),
+    "old\n".to_string(),
+  )]));
+
+  let result = applier::patch(&mut fs, diff, &ApplyOptions::default(), &mut ());
+  assert_eq!(
+    result,
+    Err(Error::UnsafePath {
+      path: "../outside.txt".to_string()
+    })
+  );
+
+  applier::patch(
+    &mut fs,
+    diff,
+    &ApplyOptions::new().unsafe_paths(true),
+    &mut (),
+  )
+  .unwrap();
+  assert_eq!(fs.file("../outside.txt").unwrap(), "new\n");
+}
+
+#[test]
+fn patch_errors_name_the_resolved_file_and_hunk() {
+  let diff = r#"diff --git a/src/main.rs b/src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-old
++new
+@@ -3 +3 @@
+-third
++THIRD
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("project/src/main.rs"),
+    "old\nsecond\nchanged\n".to_string(),
+  )]));
+
+  let error = applier::patch(
+    &mut fs,
+    diff,
+    &ApplyOptions::new().root("project"),
+    &mut (),
+  )
+  .unwrap_err();
+  assert_eq!(
+    error,
+    Error::HunkMismatch {
+      file: "project/src/main.rs".to_string(),
+      hunk_index: 1,
+      line: 3,
+      expected: "third".to_string(),
+      found: Some("changed".to_string()),
+    }
+  );
+  assert_eq!(
+    error.to_string(),
+    "Failed to apply patch: project/src/main.rs: hunk #2: Patch mismatch at line 3. Expected: `third`, Found: `changed`"
+  );
+}
+
+#[test]
+fn normalize_eol_keeps_each_line_ending() {
+  let diff = r#"--- a/mixed.txt
++++ b/mixed.txt
+@@ -1,4 +1,5 @@
+ one
+-two
++TWO
++two and a half
+ three
+-four
++FOUR
+"#;
+  let source = "one\r\ntwo\r\nthree\nfour\n";
+  let mixed_fs = || {
+    MockFileSystem::new(HashMap::from([(
+      PathBuf::from("mixed.txt"),
+      source.to_string(),
+    )]))
+  };
+
+  let mut fs = mixed_fs();
+  assert!(
+    applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).is_err()
+  );
+
+  let options = ApplyOptions::new().normalize_eol(true);
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  assert!(applier::validate_with(&fs, &patch, &options).is_empty());
+
+  let mut fs = mixed_fs();
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(
+    fs.file("mixed.txt").unwrap(),
+    "one\r\nTWO\r\ntwo and a half\r\nthree\nFOUR\n"
+  );
+}
+
+#[test]
+fn line_endings_auto_matches_across_conventions() {
+  let diff = r#"--- a/dos.txt
++++ b/dos.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+"#;
+  let dos_fs = || {
+    MockFileSystem::new(HashMap::from([(
+      PathBuf::from("dos.txt"),
+      "one\r\ntwo\r\nthree\r\n".to_string(),
+    )]))
+  };
+
+  let mut fs = dos_fs();
+  let options = ApplyOptions::new().line_endings(LineEndings::Strict);
+  assert!(applier::patch(&mut fs, diff, &options, &mut ()).is_err());
+
+  let options = ApplyOptions::new().line_endings(LineEndings::Auto);
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  assert!(applier::validate_with(&fs, &patch, &options).is_empty());
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(fs.file("dos.txt").unwrap(), "one\r\nTWO\r\nthree\r\n");
+
+  let mut fs = dos_fs();
+  let options = options.eol(Eol::Lf);
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(fs.file("dos.txt").unwrap(), "one\nTWO\nthree\n");
+}
+
+#[test]
+fn line_endings_auto_handles_mixed_files() {
+  let diff = r#"--- a/mixed.txt
++++ b/mixed.txt
+@@ -1,3 +1,3 @@
+ a
+-b
++B
+ c
+"#;
+  let mixed_fs = || {
+    MockFileSystem::new(HashMap::from([(
+      PathBuf::from("mixed.txt"),
+      "a\nb\r\nc\n".to_string(),
+    )]))
+  };
+
+  let mut fs = mixed_fs();
+  let error =
+    applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).unwrap_err();
+  assert!(error.to_string().ends_with("Expected: `b`, Found: `b\\r`"));
+
+  let options = ApplyOptions::new().line_endings(LineEndings::Auto);
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(fs.file("mixed.txt").unwrap(), "a\nB\r\nc\n");
+}
+
+#[test]
+fn duplicate_entries_follow_the_policy() {
+  let diff = r#"diff --git a/notes.txt b/notes.txt
+deleted file mode 100644
+--- a/notes.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-old
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++fresh
+"#;
+  let notes_fs = || {
+    MockFileSystem::new(HashMap::from([(
+      PathBuf::from("notes.txt"),
+      "old\n".to_string(),
+    )]))
+  };
+  let duplicate = |policy| ApplyEvent::Duplicate {
+    path: PathBuf::from("notes.txt"),
+    policy,
+  };
+
+  let mut fs = notes_fs();
+  let mut events = Vec::new();
+  applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut events).unwrap();
+  assert_eq!(fs.file("notes.txt").unwrap(), "fresh\n");
+  assert!(events.contains(&duplicate(DuplicatePolicy::InOrder)));
+  assert!(events.contains(&ApplyEvent::Deleted {
+    path: PathBuf::from("notes.txt")
+  }));
+
+  let mut fs = notes_fs();
+  let mut events = Vec::new();
+  let options = ApplyOptions::new().duplicates(DuplicatePolicy::LastWins);
+  applier::patch(&mut fs, diff, &options, &mut events).unwrap();
+  assert_eq!(
+    events,
+    vec![
+      duplicate(DuplicatePolicy::LastWins),
+      ApplyEvent::Created {
+        path: PathBuf::from("notes.txt")
+      },
+    ]
+  );
+
+  let mut fs = notes_fs();
+  let options = ApplyOptions::new().duplicates(DuplicatePolicy::Error);
+  assert_eq!(
+    applier::patch(&mut fs, diff, &options, &mut ()),
+    Err(Error::Invalid(
+      "Entries #1 and #2 both target `notes.txt`".into()
+    ))
+  );
+  assert_eq!(fs.file("notes.txt").unwrap(), "old\n");
+}
+
+#[test]
+fn similarity_tolerance_flags_suspicious_renames() {
+  let rename = |similarity: u32| {
+    format!(
+      "diff --git a/old.txt b/new.txt
+similarity index {}%
+rename from old.txt
+rename to new.txt
+--- a/old.txt
++++ b/new.txt
+@@ -3,2 +3,2 @@
+ c
+-d
++e
+",
+      similarity
+    )
+  };
+  let fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("old.txt"),
+    "a\nb\nc\nd\n".to_string(),
+  )]));
+  let options = ApplyOptions::new().similarity_tolerance(20);
+
+  assert!(
+    applier::check(&fs, &rename(75), &options)
+      .unwrap()
+      .is_empty()
+  );
+  assert!(
+    applier::check(&fs, &rename(10), &ApplyOptions::new())
+      .unwrap()
+      .is_empty()
+  );
+  assert_eq!(
+    applier::check(&fs, &rename(10), &options).unwrap(),
+    vec![applier::Issue::SimilarityMismatch {
+      path: PathBuf::from("old.txt"),
+      declared: 10,
+      computed: 75,
+    }]
+  );
+}
+
+#[test]
+fn fuzz_ignores_drifted_edge_context() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,5 +1,5 @@
+ one
+ two
+-three
++THREE
+ four
+ five drifted
+"#;
+  let source = "one\ntwo\nthree\nfour\nfive\n";
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  let fs = || {
+    MockFileSystem::new(HashMap::from([(
+      PathBuf::from("file.txt"),
+      source.to_string(),
+    )]))
+  };
+
+  assert!(applier::apply(&patch, source).is_err());
+  assert_eq!(applier::validate(&fs(), &patch).len(), 1);
+
+  let mut applier = applier::Applier::new().fuzz(1);
+  let mut output = String::new();
+  let results = applier.apply_into(&patch, source, &mut output).unwrap();
+  assert_eq!(output, "one\ntwo\nTHREE\nfour\nfive\n");
+  assert_eq!(results[0].fuzz, 1);
+  assert_eq!(results[0].position, 2);
+
+  let options = ApplyOptions::new().fuzz(2);
+  assert!(applier::validate_with(&fs(), &patch, &options).is_empty());
+  let mut fs = fs();
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(
+    fs.file("file.txt").unwrap(),
+    "one\ntwo\nTHREE\nfour\nfive\n"
+  );
+}
+
+#[test]
+fn reject_writes_failed_hunks_and_applies_the_rest() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+@@ -5,3 +5,3 @@ fn  section()
+ five
+-missing
++SIX
+ seven
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "one\ntwo\nthree\nfour\nfive\nsix\nseven\n".to_string(),
+  )]));
+
+  let options = ApplyOptions::new().reject(true);
+  let mut events = Vec::new();
+  applier::patch(&mut fs, diff, &options, &mut events).unwrap();
+
+  assert_eq!(
+    fs.file("file.txt").unwrap(),
+    "one\nTWO\nthree\nfour\nfive\nsix\nseven\n"
+  );
+  assert_eq!(
+    fs.file("file.txt.rej").unwrap(),
+    "diff a/file.txt b/file.txt\t(rejected hunks)\n\
+     @@ -5,3 +5,3 @@ fn  section()\n five\n-missing\n+SIX\n seven\n"
+  );
+  assert!(events.contains(&ApplyEvent::Rejected {
+    path: PathBuf::from("file.txt"),
+    hunks: vec![1],
+  }));
+}
+
+#[test]
+fn reject_names_renamed_files_by_their_new_path() {
+  let diff = r#"diff --git a/old.txt b/new.txt
+similarity index 50%
+rename from old.txt
+rename to new.txt
+--- a/old.txt
++++ b/new.txt
+@@ -1 +1 @@
+-missing
++new
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("old.txt"),
+    "old\n".to_string(),
+  )]));
+
+  let options = ApplyOptions::new().reject(true);
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+
+  assert_eq!(
+    fs.file("new.txt.rej").unwrap(),
+    "diff a/new.txt b/new.txt\t(rejected hunks)\n\
+     @@ -1,1 +1,1 @@\n-missing\n+new\n"
+  );
+}
+
+#[test]
+fn strip_applies_traditional_prefixes() {
+  let diff = r#"--- project-1.2/src/main.c
++++ project-1.3/src/main.c
+@@ -1 +1 @@
+-old
++new
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("src/main.c"),
+    "old\n".to_string(),
+  )]));
+
+  assert!(
+    applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).is_err()
+  );
+  let options = ApplyOptions::new().strip(1);
+  assert!(applier::check(&fs, diff, &options).unwrap().is_empty());
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(fs.file("src/main.c").unwrap(), "new\n");
+}
+
+#[test]
+fn dry_run_reports_events_without_writing() {
+  let mut fs = two_file_fs("");
+  let mut events = Vec::new();
+
+  let options = ApplyOptions::new().dry_run(true);
+  applier::patch(&mut fs, TWO_FILES, &options, &mut events).unwrap();
+  assert_eq!(fs, two_file_fs(""));
+  assert!(fs.created_dirs.is_empty());
+  assert!(events.contains(&ApplyEvent::Modified {
+    path: PathBuf::from("src/main.rs"),
+    hunks: 1,
+  }));
+}
+
+#[test]
+fn apply_with_honors_reverse_and_fuzz() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three drifted
+"#;
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  let options = ApplyOptions::new().fuzz(1);
+
+  assert!(applier::apply(&patch, "one\ntwo\nthree\n").is_err());
+  assert_eq!(
+    applier::apply_with(&patch, "one\ntwo\nthree\n", &options).unwrap(),
+    "one\nTWO\nthree\n"
+  );
+  assert_eq!(
+    applier::apply_with(&patch, "one\nTWO\nthree\n", &options.reverse(true))
+      .unwrap(),
+    "one\ntwo\nthree\n"
+  );
+}
+
+#[test]
+fn whitespace_modes_relax_matching() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,3 @@
+ fn main() {
+-  let x = 1;
++  let x = 2;
+ }
+"#;
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  let apply = |source: &str, whitespace| {
+    let options = ApplyOptions::new().whitespace(whitespace);
+    applier::apply_with(&patch, source, &options).ok()
+  };
+  let trailing = "fn main() {  \n  let x = 1;\t\n}\n";
+  let changed = "fn  main()\t{\n    let x  =  1;\n}\n";
+  let removed = "fn main(){\nlet x=1;\n}\n";
+
+  for source in [trailing, changed, removed] {
+    assert_eq!(apply(source, Whitespace::Exact), None);
+  }
+  assert_eq!(
+    apply(trailing, Whitespace::IgnoreTrailing).as_deref(),
+    Some("fn main() {  \n  let x = 2;\n}\n")
+  );
+  assert_eq!(apply(changed, Whitespace::IgnoreTrailing), None);
+  assert_eq!(
+    apply(changed, Whitespace::IgnoreChanges).as_deref(),
+    Some("fn  main()\t{\n  let x = 2;\n}\n")
+  );
+  assert_eq!(apply(removed, Whitespace::IgnoreChanges), None);
+  assert_eq!(
+    apply(removed, Whitespace::IgnoreAll).as_deref(),
+    Some("fn main(){\n  let x = 2;\n}\n")
+  );
+
+  let fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    changed.to_string(),
+  )]));
+  assert_eq!(applier::validate(&fs, &patch).len(), 1);
+  let options = ApplyOptions::new().whitespace(Whitespace::IgnoreChanges);
+  assert!(applier::validate_with(&fs, &patch, &options).is_empty());
+}
+
+#[test]
+fn skip_applied_detects_post_image() {
+  let diff = r#"diff --git a/src/main.rs b/src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-old
++new
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("src/main.rs"),
+    "new\n".to_string(),
+  )]));
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  assert_eq!(
+    applier::validate(&fs, &patch),
+    vec![Issue::AlreadyApplied {
+      path: PathBuf::from("src/main.rs"),
+    }]
+  );
+  assert_eq!(
+    applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).unwrap_err(),
+    Error::AlreadyApplied {
+      path: "src/main.rs".to_string()
+    }
+  );
+
+  let options = ApplyOptions::new().skip_applied(true);
+  assert!(applier::validate_with(&fs, &patch, &options).is_empty());
+  let report = applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(
+    report
+      .files
+      .iter()
+      .map(|file| (file.path.to_str().unwrap(), &file.status))
+      .collect::<Vec<_>>(),
+    vec![
+      ("src/main.rs", &FileStatus::AlreadyApplied),
+      ("gone.txt", &FileStatus::AlreadyApplied),
+    ]
+  );
+  assert_eq!(fs.file("src/main.rs").unwrap(), "new\n");
+}
+
+#[test]
+fn auto_reverse_applies_reversed_patches() {
+  let mut fs = two_file_fs("");
+  fs.insert("src/main.rs", "new\n");
+  assert!(
+    applier::patch(&mut fs, TWO_FILES, &ApplyOptions::new(), &mut ()).is_err()
+  );
+
+  let mut fs = two_file_fs("");
+  fs.insert("src/main.rs", "new\n");
+  let options = ApplyOptions::new().auto_reverse(true);
+  let report = applier::patch(&mut fs, TWO_FILES, &options, &mut ()).unwrap();
+  assert_eq!(fs.file("src/main.rs").unwrap(), "old\n");
+  assert_eq!(fs.file("docs/guide.md").unwrap(), "new\n");
+  assert!(report.file(Path::new("src/main.rs")).unwrap().reversed);
+  assert!(!report.file(Path::new("docs/guide.md")).unwrap().reversed);
+}
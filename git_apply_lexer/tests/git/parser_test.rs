@@ -1,6 +1,7 @@
 use hit::error::Error;
 use hit::parser::Line;
 use hit::parser::Parser;
+use hit::parser::ParserOptions;
 use hit::parser::Patch;
 
 #[test]
@@ -32,9 +33,9 @@ index 1234567..abcdefg 100644
   assert_eq!(hunk.new_line, 1);
   assert_eq!(hunk.new_span, 2);
   assert_eq!(hunk.lines.len(), 3);
-  assert_eq!(hunk.lines[0], Line::Deletion("hello world"));
-  assert_eq!(hunk.lines[1], Line::Addition("Hello, world!"));
-  assert_eq!(hunk.lines[2], Line::Context("  context"));
+  assert_eq!(hunk.lines[0], Line::Deletion("hello world".into()));
+  assert_eq!(hunk.lines[1], Line::Addition("Hello, world!".into()));
+  assert_eq!(hunk.lines[2], Line::Context(" context".into()));
 }
 
 #[test]
@@ -335,7 +336,169 @@ fn parse_patch_without_file_header() {
   assert_eq!(hunk.new_line, 1);
   assert_eq!(hunk.new_span, 2);
   assert_eq!(hunk.lines.len(), 3);
-  assert_eq!(hunk.lines[0], Line::Deletion("hello world"));
-  assert_eq!(hunk.lines[1], Line::Addition("Hello, world!"));
-  assert_eq!(hunk.lines[2], Line::Context("  context"));
+  assert_eq!(hunk.lines[0], Line::Deletion("hello world".into()));
+  assert_eq!(hunk.lines[1], Line::Addition("Hello, world!".into()));
+  assert_eq!(hunk.lines[2], Line::Context(" context".into()));
+}
+
+#[test]
+fn patch_iterates_changed_lines_and_ranges() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,4 +1,4 @@
+ keep
+-old one
+-old two
++new one // TODO
++new two
+ keep
+@@ -10,1 +10,2 @@
+ ten
++// TODO: more
+"#;
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+
+  assert_eq!(
+    patch
+      .additions()
+      .filter(|text| text.contains("TODO"))
+      .count(),
+    2
+  );
+  assert_eq!(
+    patch.deletions().collect::<Vec<_>>(),
+    vec!["old one", "old two"]
+  );
+  assert_eq!(patch.hunks[0].changed_old_range(), Some(2..4));
+  assert_eq!(patch.hunks[0].changed_new_range(), Some(2..4));
+  assert_eq!(patch.hunks[1].changed_old_range(), Some(11..11));
+  assert_eq!(patch.hunks[1].changed_new_range(), Some(11..12));
+}
+
+#[test]
+fn lazy_parse_defers_hunk_lines() {
+  let diff = r#"diff --git a/file1.txt b/file1.txt
+--- a/file1.txt
++++ b/file1.txt
+@@ -1,2 +1,2 @@
+-old line 1
++new line 1
+ context
+\ No newline at end of file
+@@ -10 +10,0 @@
+-gone
+diff --git a/file2.txt b/file2.txt
+--- a/file2.txt
++++ b/file2.txt
+@@ -1 +1 @@
+-old line 2
++new line 2
+"#;
+  let lazy = Parser::new(diff)
+    .lazy()
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+
+  assert_eq!(lazy.len(), 2);
+  assert_eq!(lazy[0].header.old_file, "file1.txt");
+  assert!(lazy[0].header.hunks.is_empty());
+  assert_eq!(lazy[0].hunks.len(), 2);
+  assert_eq!(
+    lazy[0].hunks[0].body,
+    "-old line 1\n+new line 1\n context\n\\ No newline at end of file"
+  );
+  assert_eq!(lazy[0].hunks[1].body, "-gone");
+
+  let patches = Parser::new(diff)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  let materialized = lazy
+    .into_iter()
+    .map(|patch| patch.patch())
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(materialized, patches);
+}
+
+#[test]
+fn lazy_parse_reports_hunk_count_mismatch() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,1 @@
+-hello
++world
+"#;
+  assert!(Parser::new(diff).lazy().next().unwrap().is_err());
+}
+
+#[test]
+fn lazy_parse_rejects_context_diffs_once() {
+  let diff = "*** file.txt\n--- file.txt\n***************\n*** 1 ****\n! one\n--- 1 ----\n! ONE\n";
+  let mut lazy = Parser::new(diff).lazy();
+  assert!(matches!(lazy.next(), Some(Err(Error::Unsupported(_)))));
+  assert!(lazy.next().is_none());
+}
+
+#[test]
+fn parse_keeps_traditional_timestamps() {
+  let diff = "--- a/file.txt\t2024-01-01 10:00:00.000000000 +0000
++++ b/file.txt\t2024-01-02 11:30:00.000000000 +0000
+@@ -1,1 +1,1 @@
+-old
++new
+";
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+
+  assert_eq!(patch.old_file, "file.txt");
+  assert_eq!(patch.new_file, "file.txt");
+  assert_eq!(
+    patch.old_timestamp.as_deref(),
+    Some("2024-01-01 10:00:00.000000000 +0000")
+  );
+  assert_eq!(
+    patch.new_timestamp.as_deref(),
+    Some("2024-01-02 11:30:00.000000000 +0000")
+  );
+  assert!(patch.to_string().ends_with(diff));
+  assert_eq!(patch.to_buf().as_patch(), patch);
+}
+
+#[test]
+fn lenient_parse_skips_non_diff_content() {
+  let diff = "Here is the fix we discussed.
+---
+ file.txt | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,2 @@
+-old
++new
+ tail
+Some notes between files.
+diff --git a/other.txt b/other.txt
+--- a/other.txt
++++ b/other.txt
+@@ -1 +1 @@
+-a
++b
+-- 
+2.43.0
+";
+  assert!(Parser::new(diff).next().unwrap().is_err());
+
+  let lenient = ParserOptions { lenient: true };
+  let patches = Parser::new(diff)
+    .options(lenient)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches.len(), 2);
+  assert_eq!(patches[0].hunks[0].lines.len(), 3);
+  assert_eq!(patches[1].new_file, "other.txt");
+
+  let short = "junk\n--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n-a\n+b\n";
+  let result = Parser::new(short).options(lenient).next().unwrap();
+  assert!(matches!(result, Err(Error::Parse(_))));
 }
@@ -0,0 +1,73 @@
+use hit::builder::PatchBuilder;
+use hit::rebase::Conflict;
+
+#[test]
+fn rebase_shifts_hunks_after_earlier_changes() {
+  let base = PatchBuilder::modify("file.txt")
+    .hunk_at(2)
+    .context("b")
+    .add("inserted 1")
+    .add("inserted 2")
+    .context("c")
+    .build()
+    .unwrap();
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(10)
+    .context("j")
+    .delete("k")
+    .add("K")
+    .build()
+    .unwrap();
+
+  let rebased = patch.rebase_onto(&base).unwrap();
+  assert_eq!(rebased.hunks[0].old_line, 12);
+  assert_eq!(rebased.hunks[0].new_line, 12);
+  assert_eq!(rebased.hunks[0].lines, patch.hunks[0].lines);
+}
+
+#[test]
+fn rebase_ignores_other_files() {
+  let base = PatchBuilder::modify("other.txt")
+    .hunk_at(1)
+    .add("x")
+    .build()
+    .unwrap();
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .delete("c")
+    .build()
+    .unwrap();
+
+  assert_eq!(patch.rebase_onto(&base).unwrap(), patch);
+}
+
+#[test]
+fn rebase_reports_overlapping_hunks() {
+  let base = PatchBuilder::modify("file.txt")
+    .hunk_at(4)
+    .delete("d")
+    .add("D")
+    .build()
+    .unwrap();
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(3)
+    .context("c")
+    .context("d")
+    .add("after d")
+    .build()
+    .unwrap();
+
+  let conflict = patch.rebase_onto(&base).unwrap_err();
+  assert_eq!(
+    conflict,
+    Conflict {
+      file: "file.txt".to_string(),
+      hunk_index: 0,
+      line: 3,
+    }
+  );
+  assert_eq!(
+    conflict.to_string(),
+    "Conflict in file.txt: hunk #1 overlaps changes at line 3"
+  );
+}
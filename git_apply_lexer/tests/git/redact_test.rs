@@ -0,0 +1,57 @@
+use hit::parser::Parser;
+use hit::redact;
+use hit::redact::RedactOptions;
+
+const PATCH: &str = r#"diff --git a/src/secret.rs b/src/secret.rs
+--- a/src/secret.rs
++++ b/src/secret.rs
+@@ -1,3 +1,3 @@
+ fn key() -> &str {
+-  "hunter2"
++  "correct horse"
+ }
+"#;
+
+#[test]
+fn redact_replaces_content_and_keeps_structure() {
+  let redacted = redact::redact(PATCH, RedactOptions::new()).unwrap();
+  assert_eq!(
+    redacted,
+    r#"diff --git a/src/secret.rs b/src/secret.rs
+--- a/src/secret.rs
++++ b/src/secret.rs
+@@ -1,3 +1,3 @@
+ line 1
+-line 2
++line 3
+ line 4
+"#
+  );
+
+  let original = Parser::new(PATCH).next().unwrap().unwrap();
+  let parsed = Parser::new(&redacted).next().unwrap().unwrap();
+  assert_eq!(parsed.hunks.len(), original.hunks.len());
+  assert_eq!(parsed.hunks[0].lines.len(), original.hunks[0].lines.len());
+}
+
+#[test]
+fn redact_reuses_placeholders_and_hashes_paths() {
+  let diff = r#"diff --git a/docs/plan.md b/docs/plan.md
+--- a/docs/plan.md
++++ b/docs/plan.md
+@@ -1,2 +1,2 @@
+-same
++other
+ same
+"#;
+
+  let redacted =
+    redact::redact(diff, RedactOptions::new().hash_paths(true)).unwrap();
+  assert!(!redacted.contains("docs") && !redacted.contains("plan"));
+  assert!(redacted.contains(".md b/"));
+  assert!(redacted.contains("-line 1\n+line 2\n line 1\n"));
+  assert_eq!(
+    redacted,
+    redact::redact(diff, RedactOptions::new().hash_paths(true)).unwrap()
+  );
+}
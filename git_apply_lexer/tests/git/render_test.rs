@@ -0,0 +1,105 @@
+use hit::builder::PatchBuilder;
+use hit::parser::Parser;
+use hit::render;
+use hit::render::ColorScheme;
+
+#[test]
+fn pretty_plain_round_trips_through_parser() {
+  let patch = PatchBuilder::rename("old.txt", "new.txt")
+    .hunk_at(2)
+    .context("keep")
+    .delete("let x = 1;")
+    .add("let y = 1;")
+    .build()
+    .unwrap();
+
+  let text = render::pretty(&patch, ColorScheme::plain());
+  assert_eq!(
+    text,
+    "diff --git a/old.txt b/new.txt\n\
+     rename from old.txt\n\
+     rename to new.txt\n\
+     --- a/old.txt\n\
+     +++ b/new.txt\n\
+     @@ -2,2 +2,2 @@\n \
+     keep\n\
+     -let x = 1;\n\
+     +let y = 1;\n"
+  );
+  assert_eq!(Parser::new(&text).next().unwrap().unwrap(), patch);
+}
+
+#[test]
+fn pretty_colors_lines_and_highlights_changed_words() {
+  let patch = PatchBuilder::modify("file.txt")
+    .hunk_at(1)
+    .delete("let x = 1;")
+    .add("let y = 1;")
+    .build()
+    .unwrap();
+
+  let text = render::pretty(&patch, ColorScheme::default());
+  let lines: Vec<&str> = text.lines().collect();
+  assert_eq!(lines[0], "\x1b[1mdiff --git a/file.txt b/file.txt\x1b[0m");
+  assert_eq!(lines[3], "\x1b[36m@@ -1,1 +1,1 @@\x1b[0m");
+  assert_eq!(
+    lines[4],
+    "\x1b[31m-let \x1b[7;31mx\x1b[0m\x1b[31m = 1;\x1b[0m"
+  );
+  assert_eq!(
+    lines[5],
+    "\x1b[32m+let \x1b[7;32my\x1b[0m\x1b[32m = 1;\x1b[0m"
+  );
+}
+
+#[test]
+fn display_emits_canonical_unified_text() {
+  let diff = "diff --git a/run.sh b/bin/run.sh\n\
+     old mode 100644\n\
+     new mode 100755\n\
+     similarity index 90%\n\
+     rename from run.sh\n\
+     rename to bin/run.sh\n\
+     index 83db48f..bf269f4\n\
+     --- a/run.sh\n\
+     +++ b/bin/run.sh\n\
+     @@ -1,2 +1,2 @@\n \
+     #!/bin/sh\n\
+     -echo old\n\
+     +echo new\n\
+     \\ No newline at end of file\n";
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+
+  assert_eq!(patch.to_string(), diff);
+  assert_eq!(patch.hunks[0].to_string(), diff[diff.find("@@").unwrap()..]);
+  let lines: Vec<String> = patch.hunks[0]
+    .lines
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+  assert_eq!(
+    lines,
+    [
+      " #!/bin/sh",
+      "-echo old",
+      "+echo new",
+      "\\ No newline at end of file"
+    ]
+  );
+}
+
+#[test]
+fn quoted_paths_round_trip() {
+  let diff = "diff --git \"a/caf\\303\\251.txt\" \"b/caf\\303\\251.txt\"
+--- \"a/caf\\303\\251.txt\"
++++ \"b/caf\\303\\251.txt\"
+@@ -1,1 +1,1 @@
+-old
++new
+";
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+  assert_eq!(patch.old_file, "café.txt");
+  assert_eq!(patch.to_string(), diff);
+  assert_eq!(render::quote_path("plain name.txt"), "plain name.txt");
+  assert_eq!(render::quote_path("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+}
@@ -0,0 +1,82 @@
+use hit::applier;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::report::FileStatus;
+use hit::report::HunkOffset;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+const DIFF: &str = r#"diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/old.txt b/renamed.txt
+similarity index 100%
+rename from old.txt
+rename to renamed.txt
+diff --git a/edit.txt b/edit.txt
+--- a/edit.txt
++++ b/edit.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+diff --git a/docs/skip.md b/docs/skip.md
+--- a/docs/skip.md
++++ b/docs/skip.md
+@@ -1 +1 @@
+-a
++b
+"#;
+
+#[test]
+fn patch_returns_per_file_report() {
+  let mut fs = MockFileSystem::new(HashMap::from([
+    (PathBuf::from("gone.txt"), "bye\n".to_string()),
+    (PathBuf::from("old.txt"), "same\n".to_string()),
+    (
+      PathBuf::from("edit.txt"),
+      "zero\nzero\none\ntwo\nthree\n".to_string(),
+    ),
+    (PathBuf::from("docs/skip.md"), "a\n".to_string()),
+  ]));
+  let options = ApplyOptions::new().exclude("docs/*");
+
+  let report = applier::patch(&mut fs, DIFF, &options, &mut ()).unwrap();
+  let status = |path: &str| &report.file(Path::new(path)).unwrap().status;
+
+  assert_eq!(report.files.len(), 5);
+  assert_eq!(status("new.txt"), &FileStatus::Created);
+  assert_eq!(status("gone.txt"), &FileStatus::Deleted);
+  assert_eq!(
+    status("renamed.txt"),
+    &FileStatus::Renamed {
+      from: PathBuf::from("old.txt"),
+    }
+  );
+  assert_eq!(status("docs/skip.md"), &FileStatus::Skipped);
+
+  let edit = report.file(Path::new("edit.txt")).unwrap();
+  assert_eq!(edit.status, FileStatus::Modified);
+  assert_eq!(edit.hunks, 1);
+  assert_eq!(
+    edit.offsets,
+    vec![HunkOffset {
+      hunk: 1,
+      line: 3,
+      offset: 2,
+      fuzz: 0,
+    }]
+  );
+  assert!(!report.has_rejects());
+}
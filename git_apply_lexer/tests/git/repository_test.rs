@@ -0,0 +1,214 @@
+use hit::applier;
+use hit::applier::Issue;
+use hit::fs::FileSystem;
+use hit::fs::MockFileSystem;
+use hit::fs::OsFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use hit::repository::Eol;
+use hit::repository::Repository;
+use hit::repository::Revision;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn repository_fs(config: &str, attributes: &str) -> MockFileSystem {
+  MockFileSystem::new(HashMap::from([
+    (
+      PathBuf::from("/repo/.git/HEAD"),
+      "ref: refs/heads/main\n".to_string(),
+    ),
+    (PathBuf::from("/repo/.git/config"), config.to_string()),
+    (
+      PathBuf::from("/repo/.gitattributes"),
+      attributes.to_string(),
+    ),
+  ]))
+}
+
+#[test]
+fn discover_finds_root_and_prefix() {
+  let fs = repository_fs("", "");
+  let repository =
+    Repository::discover(&fs, Path::new("/repo/src/bin")).unwrap();
+  assert_eq!(repository.root, PathBuf::from("/repo"));
+  assert_eq!(repository.prefix, PathBuf::from("src/bin"));
+  assert_eq!(
+    repository.relative_path(Path::new("main.rs")),
+    PathBuf::from("src/bin/main.rs")
+  );
+
+  let fs = MockFileSystem::new(HashMap::from([
+    (
+      PathBuf::from("/work/.git"),
+      "gitdir: ../repo/.git/worktrees/work\n".to_string(),
+    ),
+    (
+      PathBuf::from("/work/../repo/.git/worktrees/work/commondir"),
+      "../..\n".to_string(),
+    ),
+  ]));
+  let repository = Repository::discover(&fs, Path::new("/work")).unwrap();
+  assert_eq!(repository.root, PathBuf::from("/work"));
+
+  assert_eq!(Repository::discover(&fs, Path::new("/elsewhere")), None);
+}
+
+#[test]
+fn eol_follows_attributes_and_config() {
+  let fs = repository_fs(
+    "[core]\n\tautocrlf = true\n",
+    "*.sh eol=lf\n*.png binary\ndocs/*.md -text\n",
+  );
+  let repository = Repository::discover(&fs, Path::new("/repo")).unwrap();
+  let eol = |path: &str| repository.eol(&fs, Path::new(path), "text\n");
+
+  assert_eq!(eol("/repo/build.sh"), Some(Eol::Lf));
+  assert_eq!(eol("/repo/logo.png"), None);
+  assert_eq!(eol("/repo/docs/guide.md"), None);
+  assert_eq!(eol("/repo/src/lib.rs"), Some(Eol::Crlf));
+
+  let fs = repository_fs("", "*.txt text eol=crlf\n");
+  let repository = Repository::discover(&fs, Path::new("/repo")).unwrap();
+  assert_eq!(
+    repository.eol(&fs, Path::new("/repo/a.txt"), ""),
+    Some(Eol::Crlf)
+  );
+  assert_eq!(repository.eol(&fs, Path::new("/repo/a.rs"), ""), None);
+}
+
+#[test]
+fn patch_writes_crlf_for_text_files() {
+  let diff = r#"diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1,2 @@
++one
++two
+"#;
+  let mut fs = repository_fs("", "*.txt eol=crlf\n");
+  let repository = Repository::discover(&fs, Path::new("/repo")).unwrap();
+  let options = ApplyOptions::new().root("/repo").repository(repository);
+
+  applier::patch(&mut fs, diff, &options, &mut ()).unwrap();
+  assert_eq!(
+    fs.read_to_string(Path::new("/repo/notes.txt")).unwrap(),
+    "one\r\ntwo\r\n"
+  );
+  assert_eq!(Eol::Lf.convert("one\r\ntwo\n"), "one\ntwo\n");
+}
+
+#[test]
+fn is_ignored_follows_gitignore_rules() {
+  let mut fs = repository_fs("", "");
+  fs.insert("/repo/.gitignore", "target/\n*.log\n!keep.log\n/dist\n");
+  fs.insert("/repo/src/.gitignore", "generated.rs\n");
+  fs.insert("/repo/.git/info/exclude", "scratch.txt\n");
+  let repository = Repository::discover(&fs, Path::new("/repo")).unwrap();
+  let ignored = |path: &str| repository.is_ignored(&fs, Path::new(path));
+
+  assert!(ignored("/repo/target/debug/hit"));
+  assert!(ignored("/repo/logs/today.log"));
+  assert!(!ignored("/repo/logs/keep.log"));
+  assert!(ignored("/repo/dist"));
+  assert!(!ignored("/repo/src/dist"));
+  assert!(ignored("/repo/src/generated.rs"));
+  assert!(!ignored("/repo/generated.rs"));
+  assert!(ignored("/repo/scratch.txt"));
+  assert!(!ignored("/repo/src/lib.rs"));
+}
+
+#[test]
+fn validate_flags_ignored_creations() {
+  let diff = r#"diff --git a/build.log b/build.log
+new file mode 100644
+--- /dev/null
++++ b/build.log
+@@ -0,0 +1 @@
++output
+"#;
+  let mut fs = repository_fs("", "");
+  fs.insert("/repo/.gitignore", "*.log\n");
+  let repository = Repository::discover(&fs, Path::new("/repo")).unwrap();
+  let options = ApplyOptions::new().root("/repo").repository(repository);
+  let patch = Parser::new(diff).next().unwrap().unwrap();
+
+  assert_eq!(
+    applier::validate_with(&fs, &patch, &options),
+    vec![Issue::Ignored {
+      path: PathBuf::from("/repo/build.log")
+    }]
+  );
+  assert!(applier::validate(&fs, &patch).is_empty());
+}
+
+#[test]
+fn resolve_against_repository_root() {
+  let fs = repository_fs("", "");
+  let repository = Repository::discover(&fs, Path::new("/repo/src")).unwrap();
+  let options = ApplyOptions::new().repository(repository);
+
+  assert_eq!(
+    options.resolve("src/lib.rs").unwrap(),
+    PathBuf::from("src/lib.rs")
+  );
+  let options = options.repository_root(true);
+  assert_eq!(
+    options.resolve("src/lib.rs").unwrap(),
+    PathBuf::from("/repo/src/lib.rs")
+  );
+  assert_eq!(
+    options.root("/elsewhere").resolve("src/lib.rs").unwrap(),
+    PathBuf::from("/elsewhere/src/lib.rs")
+  );
+}
+
+#[test]
+fn revision_reads_committed_blobs() {
+  let dir = tempfile::tempdir().unwrap();
+  let git = |args: &[&str]| {
+    let status = Command::new("git")
+      .arg("-C")
+      .arg(dir.path())
+      .args(["-c", "user.name=hit", "-c", "user.email=hit@example.com"])
+      .args(args)
+      .status()
+      .unwrap();
+    assert!(status.success());
+  };
+  git(&["init", "-q"]);
+  std::fs::write(dir.path().join("file.txt"), "old\n").unwrap();
+  git(&["add", "file.txt"]);
+  git(&["commit", "-q", "-m", "initial"]);
+  std::fs::write(dir.path().join("file.txt"), "new\n").unwrap();
+
+  let repository = Repository::discover(&OsFileSystem, dir.path()).unwrap();
+  assert!(Revision::resolve(repository.clone(), "missing").is_err());
+  let revision = Revision::resolve(repository, "HEAD").unwrap();
+  assert_eq!(
+    revision
+      .read_to_string(&dir.path().join("file.txt"))
+      .unwrap(),
+    "old\n"
+  );
+
+  let diff = "diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1 +1 @@
+-old
++newer
+";
+  let options = ApplyOptions::new().root(dir.path());
+  assert!(
+    applier::check(&revision, diff, &options)
+      .unwrap()
+      .is_empty()
+  );
+  assert_eq!(
+    applier::check(&OsFileSystem, diff, &options).unwrap().len(),
+    1
+  );
+}
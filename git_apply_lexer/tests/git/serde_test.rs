@@ -0,0 +1,49 @@
+#![cfg(feature = "serde")]
+
+use hit::applier;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Line;
+use hit::parser::Parser;
+use hit::parser::Patch;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIFF: &str = r#"diff --git a/file.txt b/file.txt
+index 1234567..89abcde 100644
+--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,2 @@
+ keep
+-old
++new
+\ No newline at end of file
+"#;
+
+#[test]
+fn patch_round_trips_through_json() {
+  let patch = Parser::new(DIFF).next().unwrap().unwrap();
+  let json = serde_json::to_string(&patch).unwrap();
+  let decoded: Patch = serde_json::from_str(&json).unwrap();
+  assert_eq!(decoded, patch);
+
+  let line: Line = serde_json::from_str(r#"{"Addition":"new"}"#).unwrap();
+  assert_eq!(line, Line::Addition("new".into()));
+}
+
+#[test]
+fn deserialized_patch_applies() {
+  let json = {
+    let patch = Parser::new(DIFF).next().unwrap().unwrap();
+    serde_json::to_vec(&patch).unwrap()
+  };
+  let patch: Patch = serde_json::from_slice(&json).unwrap();
+
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "keep\nold\n".to_string(),
+  )]));
+  applier::apply_patches(&mut fs, [patch], &ApplyOptions::new(), &mut ())
+    .unwrap();
+  assert_eq!(fs.file("file.txt").unwrap(), "keep\nnew");
+}
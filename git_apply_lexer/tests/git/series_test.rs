@@ -0,0 +1,104 @@
+use hit::applier;
+use hit::error::Error;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::parser::Parser;
+use hit::series;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const LOG: &str =
+  "commit 2222222222222222222222222222222222222222 (HEAD -> main)
+Author: Ada Lovelace <ada@example.com>
+Date:   Tue Jan 2 10:00:00 2024 +0000
+
+    Add a farewell
+
+    Say goodbye as well.
+
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1,2 @@
+ Hello
++Goodbye
+
+commit 1111111111111111111111111111111111111111
+Merge: aaaaaaa bbbbbbb
+Author: Charles Babbage <charles@example.com>
+Date:   Mon Jan 1 10:00:00 2024 +0000
+
+    Capitalize the greeting
+
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1 @@
+-hello
++Hello
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++note
+";
+
+#[test]
+fn series_attaches_commit_metadata() {
+  assert!(series::is_log(LOG));
+  let series = Parser::new(LOG).series().unwrap();
+  assert_eq!(series.commits.len(), 2);
+
+  let commit = &series.commits[0];
+  assert_eq!(commit.sha, "2222222222222222222222222222222222222222");
+  assert_eq!(commit.author, Some("Ada Lovelace <ada@example.com>"));
+  assert_eq!(commit.date, Some("Tue Jan 2 10:00:00 2024 +0000"));
+  assert_eq!(commit.message, "Add a farewell\n\nSay goodbye as well.");
+  assert_eq!(commit.patches.len(), 1);
+
+  let commit = &series.commits[1];
+  assert_eq!(commit.author, Some("Charles Babbage <charles@example.com>"));
+  assert_eq!(commit.message, "Capitalize the greeting");
+  assert_eq!(commit.patches.len(), 2);
+  assert_eq!(series.patches().count(), 3);
+}
+
+#[test]
+fn series_wraps_plain_diffs_in_one_commit() {
+  let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-a\n+b\n";
+  let series = Parser::new(diff).series().unwrap();
+  assert_eq!(series.commits.len(), 1);
+  assert_eq!(series.commits[0].sha, "");
+  assert_eq!(series.into_patches().count(), 1);
+}
+
+#[test]
+fn parser_skips_log_metadata() {
+  let patches = Parser::new(LOG)
+    .strip(1)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches.len(), 3);
+  assert_eq!(patches[2].new_file, "notes.txt");
+}
+
+#[test]
+fn apply_oldest_commit_from_log() {
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("greeting.txt"),
+    "hello\n".to_string(),
+  )]));
+  let series = Parser::new(LOG).series().unwrap();
+  for commit in series.commits.into_iter().rev() {
+    applier::apply_patches(
+      &mut fs,
+      commit.patches,
+      &ApplyOptions::new(),
+      &mut (),
+    )
+    .unwrap();
+  }
+  assert_eq!(fs.file("greeting.txt").unwrap(), "Hello\nGoodbye\n");
+  assert_eq!(fs.file("notes.txt").unwrap(), "note\n");
+}
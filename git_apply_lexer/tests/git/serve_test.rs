@@ -0,0 +1,176 @@
+#![cfg(feature = "cli")]
+
+use hit::fs::FileSystem;
+use hit::fs::MockFileSystem;
+use hit::serve;
+use serde_json::Value;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIFF: &str = "diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,2 @@
+ keep
+-old
++new
+";
+
+fn request(method: &str, params: Value) -> String {
+  json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })
+    .to_string()
+}
+
+fn mock() -> MockFileSystem {
+  let mut files = HashMap::new();
+  files.insert(PathBuf::from("file.txt"), "keep\nold\n".to_string());
+  MockFileSystem::new(files)
+}
+
+#[test]
+fn handle_parse_and_stat() {
+  let mut fs = mock();
+
+  let response =
+    serve::handle(&mut fs, &request("parse", json!({ "patch": DIFF })));
+  assert_eq!(response["id"], 1);
+  assert_eq!(response["result"][0]["newFile"], "file.txt");
+  assert_eq!(
+    response["result"][0]["hunks"][0]["lines"][1]["kind"],
+    "deletion"
+  );
+
+  let response =
+    serve::handle(&mut fs, &request("stat", json!({ "patch": DIFF })));
+  assert_eq!(
+    response["result"],
+    json!([{
+      "path": "file.txt",
+      "insertions": 1,
+      "deletions": 1,
+      "isBinary": false,
+    }])
+  );
+}
+
+#[test]
+fn handle_check_and_apply() {
+  let mut fs = mock();
+
+  let response =
+    serve::handle(&mut fs, &request("check", json!({ "patch": DIFF })));
+  assert_eq!(response["result"], json!({ "ok": true, "issues": [] }));
+
+  let response =
+    serve::handle(&mut fs, &request("apply", json!({ "patch": DIFF })));
+  assert_eq!(
+    response["result"]["events"],
+    json!([{ "kind": "modified", "path": "file.txt", "hunks": 1 }])
+  );
+  assert_eq!(
+    fs.read_to_string(&PathBuf::from("file.txt")).unwrap(),
+    "keep\nnew\n"
+  );
+
+  let response =
+    serve::handle(&mut fs, &request("check", json!({ "patch": DIFF })));
+  assert_eq!(response["result"]["ok"], false);
+  assert_eq!(response["result"]["issues"][0]["kind"], "alreadyApplied");
+
+  let response = serve::handle(
+    &mut fs,
+    &request("check", json!({ "patch": DIFF, "reverse": true })),
+  );
+  assert_eq!(response["result"]["ok"], true);
+}
+
+#[test]
+fn handle_reports_errors() {
+  let mut fs = mock();
+
+  let response = serve::handle(&mut fs, "{not json");
+  assert_eq!(response["error"]["code"], -32700);
+  assert_eq!(response["id"], Value::Null);
+
+  let response = serve::handle(&mut fs, &request("nope", json!({})));
+  assert_eq!(response["error"]["code"], -32601);
+
+  let response = serve::handle(&mut fs, &request("parse", json!({})));
+  assert_eq!(response["error"]["code"], -32602);
+
+  let response = serve::handle(
+    &mut fs,
+    &request("apply", json!({ "patch": "@@ -1 +1 @@" })),
+  );
+  assert_eq!(response["error"]["code"], -32000);
+}
+
+#[test]
+fn serve_answers_each_line() {
+  let mut fs = mock();
+  let input = format!(
+    "{}\n\n{}\n",
+    request("stat", json!({ "patch": DIFF })),
+    request("nope", json!({}))
+  );
+  let mut output = Vec::new();
+
+  serve::serve(&mut fs, input.as_bytes(), &mut output).unwrap();
+  let responses: Vec<Value> = String::from_utf8(output)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+  assert_eq!(responses.len(), 2);
+  assert!(responses[0].get("result").is_some());
+  assert!(responses[1].get("error").is_some());
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn respond_enforces_token_and_root() {
+  let mut files = HashMap::new();
+  files.insert(PathBuf::from("srv/file.txt"), "keep\nold\n".to_string());
+  let mut fs = MockFileSystem::new(files);
+  let config = serve::HttpConfig {
+    root: PathBuf::from("srv"),
+    token: Some("secret".to_string()),
+  };
+
+  let (status, _) =
+    serve::respond(&mut fs, &config, "POST", "/apply", None, DIFF);
+  assert_eq!(status, 401);
+
+  let auth = Some("Bearer secret");
+  let (status, _) = serve::respond(&mut fs, &config, "GET", "/apply", auth, "");
+  assert_eq!(status, 405);
+  let (status, _) = serve::respond(&mut fs, &config, "POST", "/nope", auth, "");
+  assert_eq!(status, 404);
+
+  let (status, body) =
+    serve::respond(&mut fs, &config, "POST", "/check", auth, DIFF);
+  assert_eq!((status, &body["ok"]), (200, &json!(true)));
+
+  let (status, _) =
+    serve::respond(&mut fs, &config, "POST", "/apply", auth, DIFF);
+  assert_eq!(status, 200);
+  assert_eq!(
+    fs.read_to_string(&PathBuf::from("srv/file.txt")).unwrap(),
+    "keep\nnew\n"
+  );
+
+  let (status, _) =
+    serve::respond(&mut fs, &config, "POST", "/apply?reverse=1", auth, DIFF);
+  assert_eq!(status, 200);
+  assert_eq!(
+    fs.read_to_string(&PathBuf::from("srv/file.txt")).unwrap(),
+    "keep\nold\n"
+  );
+
+  let escape = DIFF.replace("file.txt", "../file.txt");
+  let (status, body) =
+    serve::respond(&mut fs, &config, "POST", "/apply", auth, &escape);
+  assert_eq!(status, 422);
+  assert!(body["error"].as_str().unwrap().contains("../file.txt"));
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "signature")]
+
+use hit::error::Error;
+use hit::signature;
+
+const PUBLIC_KEY: &str =
+  "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==";
+
+#[test]
+fn verify_accepts_signed_text() {
+  signature::verify("test", PUBLIC_KEY, SIGNATURE).unwrap();
+
+  let key_file =
+    format!("untrusted comment: minisign public key\n{}\n", PUBLIC_KEY);
+  signature::verify("test", &key_file, SIGNATURE).unwrap();
+}
+
+#[test]
+fn verify_rejects_tampered_text() {
+  let result = signature::verify("tampered", PUBLIC_KEY, SIGNATURE);
+  assert!(matches!(result, Err(Error::Signature(_))));
+
+  let result = signature::verify("test", PUBLIC_KEY, "not a signature");
+  assert!(matches!(result, Err(Error::Signature(_))));
+}
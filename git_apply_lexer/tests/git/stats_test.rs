@@ -0,0 +1,253 @@
+use hit::error::Error;
+use hit::parser::Parser;
+use hit::parser::Patch;
+use hit::stats;
+use hit::stats::FileStat;
+use hit::stats::StatFormat;
+use hit::stats::StatSummary;
+
+fn parse(diff: &str) -> Vec<Patch<'_>> {
+  Parser::new(diff)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap()
+}
+
+#[test]
+fn stat_counts_insertions_and_deletions() {
+  let diff = r#"diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,3 @@
+-hello
++Hello
++world
+ context
+"#;
+  let patches = parse(diff);
+  let stat = patches[0].stat();
+
+  assert_eq!(stat.path, "file.txt");
+  assert_eq!(stat.insertions, 2);
+  assert_eq!(stat.deletions, 1);
+  assert!(!stat.is_binary);
+}
+
+#[test]
+fn render_diffstat_with_totals() {
+  let diff = r#"diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@
+-a
+-b
++c
++d
+diff --git a/README b/README
+--- a/README
++++ b/README
+@@ -1 +1,2 @@
+ title
++line
+"#;
+  let patches = parse(diff);
+
+  assert_eq!(
+    stats::render_diffstat(&patches, 80),
+    concat!(
+      " src/lib.rs | 4 ++--\n",
+      " README     | 1 +\n",
+      " 2 files changed, 3 insertions(+), 2 deletions(-)\n",
+    )
+  );
+}
+
+#[test]
+fn render_diffstat_scales_graph_to_width() {
+  let mut diff = String::from(
+    "diff --git a/big.txt b/big.txt\n--- a/big.txt\n+++ b/big.txt\n@@ -0,0 +1,100 @@\n",
+  );
+  for i in 0..100 {
+    diff.push_str(&format!("+line {}\n", i));
+  }
+  let patches = parse(&diff);
+
+  let rendered = stats::render_diffstat(&patches, 40);
+  let first = rendered.lines().next().unwrap();
+  assert_eq!(first.len(), 40);
+  assert!(first.starts_with(" big.txt | 100 +++"));
+}
+
+#[test]
+fn render_diffstat_binary_and_rename() {
+  let diff = r#"diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+diff --git a/old.txt b/new.txt
+similarity index 100%
+rename from old.txt
+rename to new.txt
+"#;
+  let patches = parse(diff);
+
+  assert_eq!(
+    stats::render_diffstat(&patches, 80),
+    concat!(
+      " logo.png           | Bin\n",
+      " old.txt => new.txt |   0\n",
+      " 2 files changed, 0 insertions(+), 0 deletions(-)\n",
+    )
+  );
+}
+
+#[test]
+fn lazy_stat_matches_stat() {
+  let diff = r#"diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,3 @@
+-hello
++Hello
++world
+ context
+-bye
+diff --git a/image.png b/image.png
+Binary files a/image.png and b/image.png differ
+"#;
+  let lazy = Parser::new(diff)
+    .lazy()
+    .map(|patch| patch.unwrap().stat())
+    .collect::<Vec<_>>();
+  let eager = parse(diff).iter().map(Patch::stat).collect::<Vec<_>>();
+  assert_eq!(lazy, eager);
+  assert_eq!(
+    stats::render_stats(&lazy, 80),
+    stats::render_diffstat(&parse(diff), 80)
+  );
+}
+
+#[test]
+fn parse_stat_lines_and_summary() {
+  assert_eq!(
+    stats::parse_stat_line("src/lib.rs | 4 ++--"),
+    Some(FileStat {
+      path: "src/lib.rs".to_string(),
+      insertions: 2,
+      deletions: 2,
+      is_binary: false,
+    })
+  );
+  assert!(stats::parse_stat_line("image.png | Bin 0 -> 12 bytes").is_some());
+  assert_eq!(stats::parse_stat_line("a | b"), None);
+  assert_eq!(
+    stats::parse_summary("2 files changed, 3 insertions(+), 2 deletions(-)"),
+    Some(StatSummary {
+      files: 2,
+      insertions: 3,
+      deletions: 2,
+    })
+  );
+  assert_eq!(stats::parse_summary("2 files moved"), None);
+}
+
+#[test]
+fn parser_records_diffstat_before_diff() {
+  let diff = r#" file.txt | 3 ++-
+ 1 file changed, 2 insertions(+), 1 deletion(-)
+
+diff --git a/file.txt b/file.txt
+--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,4 @@
+-hello
++Hello
++world
+ a.txt | 1 +
+ 1 file changed, 1 insertion(+)
+"#;
+  let mut parser = Parser::new(diff);
+  let patch = parser.next().unwrap().unwrap();
+  assert_eq!(patch.hunks[0].lines.len(), 5);
+  assert!(parser.next().is_none());
+
+  let diffstat = parser.diffstat();
+  assert_eq!(diffstat.files, vec![patch.stat()]);
+  assert_eq!(
+    diffstat.summary,
+    Some(StatSummary {
+      files: 1,
+      insertions: 2,
+      deletions: 1,
+    })
+  );
+}
+
+#[test]
+fn render_numstat_and_shortstat() {
+  let stats = vec![
+    FileStat {
+      path: "src/lib.rs".to_string(),
+      insertions: 3,
+      deletions: 1,
+      is_binary: false,
+    },
+    FileStat {
+      path: "logo.png".to_string(),
+      insertions: 0,
+      deletions: 0,
+      is_binary: true,
+    },
+  ];
+  assert_eq!(
+    stats::render(&stats, StatFormat::Numstat, 80),
+    "3\t1\tsrc/lib.rs\n-\t-\tlogo.png\n"
+  );
+  assert_eq!(
+    stats::render(&stats, StatFormat::Shortstat, 80),
+    " 2 files changed, 3 insertions(+), 1 deletion(-)\n"
+  );
+  assert_eq!(
+    StatSummary::of(&stats),
+    StatSummary {
+      files: 2,
+      insertions: 3,
+      deletions: 1,
+    }
+  );
+}
+
+#[test]
+fn series_stats_cover_every_commit() {
+  let log = "commit 1111111
+Author: A <a@example.com>
+
+    First
+
+diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1,2 @@
+ a
++b
+commit 2222222
+Author: A <a@example.com>
+
+    Second
+
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1,2 +1 @@
+-a
+-b
++c
+";
+  let series = Parser::new(log).series().unwrap();
+  assert_eq!(series.commits[1].stats()[0].deletions, 2);
+  assert_eq!(
+    series.summary(),
+    StatSummary {
+      files: 2,
+      insertions: 2,
+      deletions: 2,
+    }
+  );
+}
@@ -0,0 +1,119 @@
+use hit::buf::PatchBuf;
+use hit::error::Error;
+use hit::parser::Parser;
+use hit::parser::ParserOptions;
+use std::io::BufReader;
+use std::io::Read;
+
+fn streamed(text: &str) -> Vec<PatchBuf> {
+  Parser::from_reader(text.as_bytes())
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap()
+}
+
+fn parsed(text: &str) -> Vec<PatchBuf> {
+  Parser::new(text)
+    .map(|patch| patch.map(|patch| patch.to_buf()))
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap()
+}
+
+#[test]
+fn from_reader_matches_parser() {
+  let diff = "diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,3 @@
+ keep
+---- not a header
++++++ not a header either
+ keep
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-diff --git a/x b/x
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-old
++new
+\\ No newline at end of file
+";
+
+  let streamed = streamed(diff);
+  assert_eq!(streamed.len(), 3);
+  assert_eq!(streamed, parsed(diff));
+}
+
+#[test]
+fn from_reader_splits_plain_unified_diffs() {
+  let diff = "--- a/one.txt
++++ b/one.txt
+@@ -1 +1 @@
+-1
++one
+--- a/two.txt
++++ b/two.txt
+@@ -1 +1 @@
+-2
++two
+";
+
+  let streamed = streamed(diff);
+  assert_eq!(streamed.len(), 2);
+  assert_eq!(streamed[1].as_patch().new_file, "two.txt");
+  assert_eq!(streamed, parsed(diff));
+}
+
+#[test]
+fn from_reader_yields_before_reading_everything() {
+  let first = "diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-a
++b
+";
+  let rest =
+    "diff --git a/b.txt b/b.txt\n--- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-\u{0}";
+  let reader =
+    BufReader::new(first.as_bytes().chain(rest.as_bytes()).chain(&[0xff][..]));
+  let mut patches = Parser::from_reader(reader);
+
+  assert_eq!(
+    patches.next().unwrap().unwrap().as_patch().new_file,
+    "a.txt"
+  );
+  assert!(patches.next().unwrap().is_err());
+  assert!(patches.next().is_none());
+}
+
+#[test]
+fn from_read_honours_parser_options() {
+  let diff = "Some notes before the first file.
+diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-a
++b
+trailing prose the strict parser rejects
+";
+  let patches = Parser::from_read(diff.as_bytes())
+    .options(ParserOptions { lenient: true })
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches.len(), 1);
+  assert_eq!(patches[0].as_patch().new_file, "a.txt");
+
+  let normal = "1c1\n< a\n---\n> b\n";
+  let patches = Parser::from_read(normal.as_bytes())
+    .target("a.txt")
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(patches[0].as_patch().old_file, "a.txt");
+  assert_eq!(patches[0].as_patch().hunks.len(), 1);
+}
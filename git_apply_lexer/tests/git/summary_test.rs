@@ -0,0 +1,112 @@
+use hit::error::Error;
+use hit::parser::Parser;
+use hit::summary;
+use hit::summary::Change;
+use hit::summary::Status;
+
+const DIFF: &str = r#"diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/gone.sh b/gone.sh
+deleted file mode 100755
+--- a/gone.sh
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/src/old/lib.rs b/src/new/lib.rs
+similarity index 90%
+rename from src/old/lib.rs
+rename to src/new/lib.rs
+diff --git a/a.txt b/b.txt
+similarity index 100%
+copy from a.txt
+copy to b.txt
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+"#;
+
+#[test]
+fn changes_describe_structural_edits() {
+  let patches = Parser::new(DIFF)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(
+    summary::changes(&patches[0]),
+    vec![Change::Create {
+      path: "new.txt".to_string(),
+      mode: 0o100644,
+    }]
+  );
+  assert_eq!(
+    summary::changes(&patches[4]),
+    vec![Change::ModeChange {
+      path: "run.sh".to_string(),
+      old: 0o100644,
+      new: 0o100755,
+    }]
+  );
+  assert_eq!(
+    summary::render(&patches),
+    concat!(
+      " create mode 100644 new.txt\n",
+      " delete mode 100755 gone.sh\n",
+      " rename src/{old => new}/lib.rs (90%)\n",
+      " copy a.txt => b.txt (100%)\n",
+      " mode change 100644 => 100755 run.sh\n",
+    )
+  );
+}
+
+#[test]
+fn rename_path_factors_common_components() {
+  assert_eq!(
+    summary::rename_path("a/b/c.rs", "a/d/c.rs"),
+    "a/{b => d}/c.rs"
+  );
+  assert_eq!(summary::rename_path("a/c.rs", "a/d.rs"), "a/{c.rs => d.rs}");
+  assert_eq!(summary::rename_path("x/c.rs", "y/c.rs"), "{x => y}/c.rs");
+  assert_eq!(summary::rename_path("old", "new"), "old => new");
+}
+
+#[test]
+fn name_status_lists_paths_with_letters() {
+  let diff = format!(
+    "{}{}",
+    DIFF,
+    r#"diff --git a/link b/link
+old mode 100644
+new mode 120000
+diff --git a/edit.txt b/edit.txt
+--- a/edit.txt
++++ b/edit.txt
+@@ -1 +1 @@
+-a
++b
+"#
+  );
+  let patches = Parser::new(&diff)
+    .collect::<Result<Vec<_>, Error>>()
+    .unwrap();
+  assert_eq!(summary::name_status(&patches[1]).status, Status::Deleted);
+  assert_eq!(Status::TypeChanged.letter(), 'T');
+  assert_eq!(
+    summary::render_name_status(&patches),
+    concat!(
+      "A\tnew.txt\n",
+      "D\tgone.sh\n",
+      "R090\tsrc/old/lib.rs\tsrc/new/lib.rs\n",
+      "C100\ta.txt\tb.txt\n",
+      "M\trun.sh\n",
+      "T\tlink\n",
+      "M\tedit.txt\n",
+    )
+  );
+  assert_eq!(
+    summary::render_name_only(&patches[..3]),
+    "new.txt\ngone.sh\nsrc/new/lib.rs\n"
+  );
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "metrics")]
+
+use hit::applier;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::telemetry;
+use metrics::Counter;
+use metrics::CounterFn;
+use metrics::Gauge;
+use metrics::Histogram;
+use metrics::Key;
+use metrics::KeyName;
+use metrics::Metadata;
+use metrics::Recorder;
+use metrics::SharedString;
+use metrics::Unit;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+type Counts = Arc<Mutex<HashMap<String, u64>>>;
+
+struct Entry {
+  name: String,
+  counts: Counts,
+}
+
+impl CounterFn for Entry {
+  fn increment(&self, value: u64) {
+    *self
+      .counts
+      .lock()
+      .unwrap()
+      .entry(self.name.clone())
+      .or_default() += value;
+  }
+
+  fn absolute(&self, value: u64) {
+    self.counts.lock().unwrap().insert(self.name.clone(), value);
+  }
+}
+
+#[derive(Default)]
+struct CountingRecorder {
+  counts: Counts,
+}
+
+impl Recorder for CountingRecorder {
+  fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+  fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+  fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+  fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+    let name = key.labels().fold(key.name().to_string(), |name, label| {
+      format!("{}{{{}={}}}", name, label.key(), label.value())
+    });
+    Counter::from_arc(Arc::new(Entry {
+      name,
+      counts: self.counts.clone(),
+    }))
+  }
+
+  fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+    Gauge::noop()
+  }
+
+  fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+    Histogram::noop()
+  }
+}
+
+#[test]
+fn patch_reports_counters_to_the_recorder() {
+  let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,2 @@
+-one
++ONE
+ two
+@@ -4 +4 @@
+-four
++FOUR
+"#;
+  let mut fs = MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "one\ntwo\nthree\nfour\n".to_string(),
+  )]));
+  let recorder = CountingRecorder::default();
+
+  metrics::with_local_recorder(&recorder, || {
+    telemetry::describe();
+    applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).unwrap();
+    applier::patch(&mut fs, diff, &ApplyOptions::new(), &mut ()).unwrap_err();
+  });
+
+  let counts = recorder.counts.lock().unwrap();
+  assert_eq!(counts[telemetry::PATCHES_PARSED], 2);
+  assert_eq!(counts[telemetry::HUNKS_APPLIED], 2);
+  assert_eq!(counts[telemetry::BYTES_WRITTEN], 19);
+  assert_eq!(counts["hit_failures_total{kind=already_applied}"], 1);
+  assert!(!counts.contains_key(telemetry::HUNKS_FUZZED));
+}
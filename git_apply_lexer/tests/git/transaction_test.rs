@@ -0,0 +1,192 @@
+use hit::applier;
+use hit::error::Error;
+use hit::event::ApplyEvent;
+use hit::fs::FileSystem;
+use hit::fs::MockFileSystem;
+use hit::fs::OsFileSystem;
+use hit::options::ApplyOptions;
+use hit::transaction::Transaction;
+use std::collections::HashMap;
+use std::fs;
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+const SERIES: &str = r#"diff --git a/one.txt b/one.txt
+--- a/one.txt
++++ b/one.txt
+@@ -1 +1 @@
+-one
++ONE
+diff --git a/two.txt b/three.txt
+similarity index 100%
+rename from two.txt
+rename to three.txt
+diff --git a/four.txt b/four.txt
+--- a/four.txt
++++ b/four.txt
+@@ -1 +1 @@
+-missing
++FOUR
+"#;
+
+fn files() -> MockFileSystem {
+  MockFileSystem::new(HashMap::from([
+    (PathBuf::from("one.txt"), "one\n".to_string()),
+    (PathBuf::from("two.txt"), "two\n".to_string()),
+    (PathBuf::from("four.txt"), "four\n".to_string()),
+  ]))
+}
+
+#[test]
+fn atomic_patch_leaves_files_untouched_on_mismatch() {
+  let mut fs = files();
+  let mut events = Vec::new();
+  let options = ApplyOptions::new().atomic(true);
+
+  let error =
+    applier::patch(&mut fs, SERIES, &options, &mut events).unwrap_err();
+  assert!(matches!(error, Error::HunkMismatch { .. }));
+  assert_eq!(fs, files());
+  assert!(events.is_empty());
+
+  let mut fs = files();
+  applier::patch(&mut fs, SERIES, &ApplyOptions::new(), &mut ()).unwrap_err();
+  assert_eq!(fs.file("one.txt").unwrap(), "ONE\n");
+  assert!(fs.contains("three.txt"));
+}
+
+#[test]
+fn atomic_patch_commits_once_every_file_applies() {
+  let mut fs = files();
+  fs.write(Path::new("four.txt"), "missing\n").unwrap();
+  let mut events = Vec::new();
+  let options = ApplyOptions::new().atomic(true);
+
+  applier::patch(&mut fs, SERIES, &options, &mut events).unwrap();
+  assert_eq!(fs.file("one.txt").unwrap(), "ONE\n");
+  assert_eq!(fs.file("three.txt").unwrap(), "two\n");
+  assert!(!fs.contains("two.txt"));
+  assert_eq!(fs.file("four.txt").unwrap(), "FOUR\n");
+  assert!(events.contains(&ApplyEvent::Renamed {
+    from: PathBuf::from("two.txt"),
+    to: PathBuf::from("three.txt"),
+  }));
+}
+
+#[test]
+fn transaction_reads_staged_changes() {
+  let mut inner = files();
+  let mut transaction = Transaction::new(&mut inner);
+  transaction.write(Path::new("one.txt"), "staged\n").unwrap();
+  transaction
+    .rename(Path::new("one.txt"), Path::new("moved.txt"))
+    .unwrap();
+
+  assert!(transaction.read_to_string(Path::new("one.txt")).is_err());
+  assert_eq!(
+    transaction.read_to_string(Path::new("moved.txt")).unwrap(),
+    "staged\n"
+  );
+  transaction.commit().unwrap();
+  assert_eq!(inner.file("moved.txt").unwrap(), "staged\n");
+  assert!(!inner.contains("one.txt"));
+}
+
+#[test]
+fn transaction_rolls_back_when_commit_fails() {
+  let dir = tempfile::tempdir().unwrap();
+  let edited = dir.path().join("edited.txt");
+  fs::write(&edited, "before\n").unwrap();
+  fs::write(dir.path().join("blocked"), "not a directory\n").unwrap();
+  let diff = r#"diff --git a/edited.txt b/edited.txt
+--- a/edited.txt
++++ b/edited.txt
+@@ -1 +1 @@
+-before
++after
+diff --git a/blocked/new.txt b/blocked/new.txt
+new file mode 100644
+--- /dev/null
++++ b/blocked/new.txt
+@@ -0,0 +1 @@
++new
+"#;
+  let options = ApplyOptions::new().root(dir.path()).atomic(true);
+
+  applier::patch(&mut OsFileSystem, diff, &options, &mut ()).unwrap_err();
+  assert_eq!(fs::read_to_string(&edited).unwrap(), "before\n");
+}
+
+struct FailingFileSystem {
+  inner: MockFileSystem,
+  failing: PathBuf,
+}
+
+impl FileSystem for FailingFileSystem {
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    self.inner.read_to_string(path)
+  }
+
+  fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+    self.inner.read_bytes(path)
+  }
+
+  fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+    self.write_bytes(path, contents.as_bytes())
+  }
+
+  fn write_bytes(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+    if path == self.failing {
+      return Err(io::ErrorKind::PermissionDenied.into());
+    }
+    self.inner.write_bytes(path, contents)
+  }
+
+  fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+    self.inner.remove_file(path)
+  }
+
+  fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+    self.inner.create_dir_all(path)
+  }
+
+  #[cfg(unix)]
+  fn set_permissions(
+    &mut self,
+    path: &Path,
+    perm: Permissions,
+  ) -> io::Result<()> {
+    self.inner.set_permissions(path, perm)
+  }
+
+  #[cfg(unix)]
+  fn get_permissions(&self, path: &Path) -> io::Result<Permissions> {
+    self.inner.get_permissions(path)
+  }
+}
+
+#[test]
+fn transaction_restores_binary_files_in_memory() {
+  let blob = [0xff, 0x00, 0xfe];
+  let mut inner = MockFileSystem::default();
+  inner.insert_bytes("blob.bin", &blob);
+  let mut fs = FailingFileSystem {
+    inner,
+    failing: PathBuf::from("blocked.txt"),
+  };
+
+  let mut transaction = Transaction::new(&mut fs);
+  transaction
+    .write_bytes(Path::new("blob.bin"), &[0x80, 0x81])
+    .unwrap();
+  transaction
+    .write(Path::new("blocked.txt"), "new\n")
+    .unwrap();
+  transaction.commit().unwrap_err();
+
+  assert_eq!(fs.inner.file_bytes("blob.bin"), Some(&blob[..]));
+  assert!(!fs.inner.contains("blocked.txt"));
+}
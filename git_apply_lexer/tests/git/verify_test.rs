@@ -0,0 +1,54 @@
+use hit::verify;
+use hit::verify::Mismatch;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const PATCH: &str = r#"diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++hello
+"#;
+
+#[test]
+fn roundtrip_holds_for_applicable_patches() {
+  let files = HashMap::from([(
+    PathBuf::from("src/lib.rs"),
+    "one\ntwo\nthree\n".to_string(),
+  )]);
+
+  assert_eq!(verify::roundtrip(PATCH, &files).unwrap(), vec![]);
+  assert!(verify::roundtrip("@@ -1 +1 @@\n", &files).is_err());
+}
+
+#[test]
+fn roundtrip_reports_structured_mismatches() {
+  let files = HashMap::from([(
+    PathBuf::from("src/lib.rs"),
+    "one\nchanged\nthree\n".to_string(),
+  )]);
+
+  let mismatches = verify::roundtrip(PATCH, &files).unwrap();
+  assert_eq!(mismatches.len(), 1);
+  assert!(matches!(mismatches[0], Mismatch::Apply { index: 0, .. }));
+  assert!(mismatches[0].to_string().starts_with("patch #1: "));
+
+  let mismatches = verify::roundtrip(PATCH, &HashMap::new()).unwrap();
+  assert_eq!(
+    mismatches,
+    vec![Mismatch::MissingFile {
+      index: 0,
+      path: "src/lib.rs".to_string(),
+    }]
+  );
+}
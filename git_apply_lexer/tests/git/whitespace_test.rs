@@ -0,0 +1,110 @@
+use hit::applier;
+use hit::error::Error;
+use hit::event::ApplyEvent;
+use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
+use hit::options::WhitespacePolicy;
+use hit::parser::Parser;
+use hit::whitespace;
+use hit::whitespace::WhitespaceError;
+use hit::whitespace::WhitespaceIssue;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIRTY: &str = "--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,6 @@
+ one
+-two
++two\x20\x20
++ \tthree
++four
++
++\t
+";
+
+fn dirty_fs() -> MockFileSystem {
+  MockFileSystem::new(HashMap::from([(
+    PathBuf::from("file.txt"),
+    "one\ntwo\n".to_string(),
+  )]))
+}
+
+#[test]
+fn check_reports_added_line_errors() {
+  let patch = Parser::new(DIRTY).next().unwrap().unwrap();
+  assert_eq!(
+    whitespace::check(&patch),
+    vec![
+      WhitespaceIssue {
+        line: 2,
+        kind: WhitespaceError::TrailingSpace,
+      },
+      WhitespaceIssue {
+        line: 3,
+        kind: WhitespaceError::SpaceBeforeTab,
+      },
+      WhitespaceIssue {
+        line: 6,
+        kind: WhitespaceError::TrailingSpace,
+      },
+      WhitespaceIssue {
+        line: 5,
+        kind: WhitespaceError::BlankAtEof,
+      },
+      WhitespaceIssue {
+        line: 6,
+        kind: WhitespaceError::BlankAtEof,
+      },
+    ]
+  );
+}
+
+#[test]
+fn fix_line_strips_trailing_space_and_space_before_tab() {
+  assert_eq!(whitespace::fix_line("clean"), "clean");
+  assert_eq!(whitespace::fix_line("text \t "), "text");
+  assert_eq!(whitespace::fix_line("  \t \tbody"), "\t\tbody");
+  assert_eq!(whitespace::fix_line("\t  body\r"), "\t  body\r");
+  assert_eq!(whitespace::fix_line("body \r"), "body\r");
+}
+
+#[test]
+fn policies_warn_fix_or_error() {
+  let mut fs = dirty_fs();
+  let mut events = Vec::new();
+  let options = ApplyOptions::new().whitespace_policy(WhitespacePolicy::Warn);
+  applier::patch(&mut fs, DIRTY, &options, &mut events).unwrap();
+  assert_eq!(
+    fs.file("file.txt").unwrap(),
+    "one\ntwo  \n \tthree\nfour\n\n\t\n"
+  );
+  assert!(events.contains(&ApplyEvent::Whitespace {
+    path: PathBuf::from("file.txt"),
+    line: 2,
+    kind: WhitespaceError::TrailingSpace,
+    fixed: false,
+  }));
+
+  let mut fs = dirty_fs();
+  let mut events = Vec::new();
+  let options = ApplyOptions::new().whitespace_policy(WhitespacePolicy::Fix);
+  applier::patch(&mut fs, DIRTY, &options, &mut events).unwrap();
+  assert_eq!(fs.file("file.txt").unwrap(), "one\ntwo\n\tthree\nfour\n");
+  assert_eq!(
+    events
+      .iter()
+      .filter(|event| matches!(
+        event,
+        ApplyEvent::Whitespace { fixed: true, .. }
+      ))
+      .count(),
+    5
+  );
+
+  let mut fs = dirty_fs();
+  let options = ApplyOptions::new().whitespace_policy(WhitespacePolicy::Error);
+  let result = applier::patch(&mut fs, DIRTY, &options, &mut ());
+  assert!(matches!(result, Err(Error::Invalid(_))));
+  assert_eq!(fs, dirty_fs());
+}
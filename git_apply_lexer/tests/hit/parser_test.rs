@@ -1,6 +1,7 @@
 use hit::applier;
 use hit::fs::FileSystem;
 use hit::fs::MockFileSystem;
+use hit::options::ApplyOptions;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -20,7 +21,8 @@ fn parse_without_hunk_header() {
     source_content.to_string(),
   )]));
 
-  applier::patch(&mut fs, patch_content, false).unwrap();
+  applier::patch(&mut fs, patch_content, &ApplyOptions::default(), &mut ())
+    .unwrap();
 
   let new_content = fs.read_to_string(&PathBuf::from("file.txt")).unwrap();
   assert_eq!(new_content, expected_content);
@@ -39,6 +41,7 @@ fn parse_without_hunk_header_and_no_file_info() {
     source_content.to_string(),
   )]));
 
-  let result = applier::patch(&mut fs, patch_content, false);
+  let result =
+    applier::patch(&mut fs, patch_content, &ApplyOptions::default(), &mut ());
   assert!(result.is_err());
 }